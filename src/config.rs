@@ -0,0 +1,147 @@
+use std::cell::Cell;
+
+// Inputs at least this long are split across the rayon pool by default.
+const DEFAULT_MIN_PARALLEL_LEN: usize = 4096;
+
+thread_local! {
+    static CURRENT: Cell<YttriaConfig> = const { Cell::new(YttriaConfig::new()) };
+}
+
+// How the element-wise vector operations use rayon. Work is never split into pieces shorter
+// than `min_parallel_len`, so shorter inputs run serially on the calling thread. With
+// `num_threads` set, `install` runs on a dedicated pool of that size instead of the one that
+// is current, which keeps an application's own pool from being oversubscribed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YttriaConfig {
+    min_parallel_len: usize,
+    num_threads: Option<usize>,
+}
+
+impl Default for YttriaConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl YttriaConfig {
+    pub const fn new() -> Self {
+        Self {
+            min_parallel_len: DEFAULT_MIN_PARALLEL_LEN,
+            num_threads: None,
+        }
+    }
+
+    pub fn with_min_parallel_len(mut self, len: usize) -> Self {
+        self.min_parallel_len = len.max(1);
+        self
+    }
+
+    pub fn with_num_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "Thread count must be positive");
+        self.num_threads = Some(threads);
+        self
+    }
+
+    pub fn min_parallel_len(&self) -> usize {
+        self.min_parallel_len
+    }
+
+    pub fn num_threads(&self) -> Option<usize> {
+        self.num_threads
+    }
+
+    // The configuration in effect on this thread.
+    pub fn current() -> Self {
+        CURRENT.with(|c| c.get())
+    }
+
+    // Runs `f` with this configuration, restoring the previous one afterwards. A dedicated pool
+    // is built on every call, so wrap a whole workload rather than single operations.
+    pub fn install<R, F>(&self, f: F) -> R
+    where
+        R: Send,
+        F: FnOnce() -> R + Send,
+    {
+        let config = *self;
+        match self.num_threads {
+            None => config.scoped(f),
+            Some(threads) => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .start_handler(move |_| CURRENT.with(|c| c.set(config)))
+                .build()
+                .expect("Could not build thread pool")
+                .install(|| config.scoped(f)),
+        }
+    }
+
+    fn scoped<R, F: FnOnce() -> R>(self, f: F) -> R {
+        struct Restore(YttriaConfig);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.with(|c| c.set(self.0));
+            }
+        }
+
+        let _restore = Restore(CURRENT.with(|c| c.replace(self)));
+        f()
+    }
+}
+
+pub(crate) fn parallel_min_len() -> usize {
+    CURRENT.with(|c| c.get().min_parallel_len)
+}
+
+// Fastest of `runs` calls to `f`, for the benches.
+#[cfg(test)]
+pub(crate) fn best_of(runs: usize, mut f: impl FnMut()) -> std::time::Duration {
+    (0..runs)
+        .map(|_| {
+            let start = std::time::Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{YttriaVectorArithmetic, YttriaVectorUtils};
+
+    #[test]
+    fn test_high_threshold_runs_serially() {
+        let a = (0..50_000).map(|x| x as f64).collect::<Vec<_>>();
+        let caller = std::thread::current().id();
+
+        let config = YttriaConfig::new().with_min_parallel_len(usize::MAX);
+        let (serial, sum) = config.install(|| {
+            let serial = a.all(|_| std::thread::current().id() == caller);
+            (serial, a.add(&a))
+        });
+        assert!(serial);
+        assert_eq!(sum, a.multiply_const(2.0));
+
+        // The previous configuration is back once `install` returns.
+        assert_eq!(YttriaConfig::current(), YttriaConfig::new());
+    }
+
+    #[test]
+    fn test_dedicated_pool() {
+        let a = (0..50_000).map(|x| x as f64).collect::<Vec<_>>();
+        let config = YttriaConfig::new()
+            .with_min_parallel_len(1000)
+            .with_num_threads(2);
+
+        let (threads, current, sum) = config.install(|| {
+            (
+                rayon::current_num_threads(),
+                YttriaConfig::current(),
+                a.add(&a),
+            )
+        });
+        assert_eq!(threads, 2);
+        assert_eq!(current, config);
+        assert_eq!(sum, a.multiply_const(2.0));
+    }
+}
@@ -0,0 +1,290 @@
+use super::{firwin_kaiser, FilterSample};
+use crate::config::parallel_min_len;
+use num::FromPrimitive;
+use rayon::prelude::*;
+
+pub struct DecimateConfig {
+    attenuation: f64,
+    cutoff: f64,
+    taps: Option<Vec<f64>>,
+    compensate_delay: bool,
+    staged: bool,
+}
+
+impl Default for DecimateConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecimateConfig {
+    // By default the anti-alias filter keeps 80% of the output band, rejects everything above the
+    // output Nyquist frequency by 60 dB, compensates its group delay and splits large factors
+    // into stages.
+    pub fn new() -> Self {
+        Self {
+            attenuation: 60.0,
+            cutoff: 0.8,
+            taps: None,
+            compensate_delay: true,
+            staged: true,
+        }
+    }
+
+    pub fn with_attenuation(mut self, attenuation: f64) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    // Passband edge as a fraction of the output Nyquist frequency.
+    pub fn with_cutoff(mut self, cutoff: f64) -> Self {
+        assert!(
+            cutoff > 0.0 && cutoff < 1.0,
+            "Cutoff must lie strictly between 0 and the output Nyquist frequency"
+        );
+        self.cutoff = cutoff;
+        self
+    }
+
+    // Skips the filter design and decimates in a single stage with the given taps.
+    pub fn with_taps(mut self, taps: Vec<f64>) -> Self {
+        assert!(!taps.is_empty(), "Custom taps must not be empty");
+        self.taps = Some(taps);
+        self
+    }
+
+    // When enabled, the filter transient is trimmed so output sample `k` lines up with input
+    // sample `k * factor`.
+    pub fn with_delay_compensation(mut self, compensate_delay: bool) -> Self {
+        self.compensate_delay = compensate_delay;
+        self
+    }
+
+    pub fn with_staging(mut self, staged: bool) -> Self {
+        self.staged = staged;
+        self
+    }
+
+    fn stages(&self, factor: usize) -> Vec<(usize, Vec<f64>)> {
+        if let Some(taps) = &self.taps {
+            return vec![(factor, taps.clone())];
+        }
+
+        let factors = if self.staged {
+            prime_factors(factor)
+        } else {
+            vec![factor]
+        };
+
+        // Every stage passes the final band and rejects whatever would alias below the final
+        // output Nyquist frequency, so early stages get away with wide transition bands.
+        let mut remaining = factor;
+        factors
+            .into_iter()
+            .map(|m| {
+                let passband = self.cutoff / remaining as f64;
+                let stopband = 2.0 / m as f64 - 1.0 / remaining as f64;
+                remaining /= m;
+                (m, firwin_kaiser(passband, stopband, self.attenuation))
+            })
+            .collect()
+    }
+}
+
+// Largest factors first, so the narrowest filter runs at the lowest rate.
+fn prime_factors(mut n: usize) -> Vec<usize> {
+    let mut factors = Vec::new();
+    let mut p = 2;
+    while p * p <= n {
+        while n.is_multiple_of(p) {
+            factors.push(p);
+            n /= p;
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors.reverse();
+    factors
+}
+
+// Polyphase decimation, computing only the retained outputs: `out[k]` is the filter output at
+// input sample `k * factor + delay`, with tap phase `p` running over every `factor`th input.
+fn decimate_stage<S: FilterSample>(
+    input: &[S],
+    taps: &[f64],
+    factor: usize,
+    delay: usize,
+) -> Vec<S> {
+    let phases = (0..factor)
+        .map(|p| {
+            taps.iter()
+                .skip(p)
+                .step_by(factor)
+                .map(|&t| S::Real::from_f64(t).expect("Could not convert f64 into type"))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = vec![S::zero(); input.len().div_ceil(factor)];
+    out.par_iter_mut()
+        .with_min_len(parallel_min_len())
+        .enumerate()
+        .for_each(|(k, out)| {
+            let n = k * factor + delay;
+            let mut acc = S::zero();
+            for (p, phase) in phases.iter().enumerate() {
+                for (i, h) in phase.iter().enumerate() {
+                    let Some(idx) = n.checked_sub(p + i * factor) else {
+                        break;
+                    };
+                    if idx < input.len() {
+                        acc = acc + input[idx] * *h;
+                    }
+                }
+            }
+            *out = acc;
+        });
+    out
+}
+
+pub trait YttriaVectorDecimate<S: FilterSample> {
+    // `ceil(len / factor)` samples, however the factor is split into stages.
+    fn decimate_filtered_output_len(&self, factor: usize) -> usize;
+    fn decimate_filtered(&self, factor: usize, config: DecimateConfig) -> Vec<S>;
+}
+
+impl<S: FilterSample> YttriaVectorDecimate<S> for [S] {
+    fn decimate_filtered_output_len(&self, factor: usize) -> usize {
+        assert!(factor > 0, "Decimation factor must be at least 1");
+        self.len().div_ceil(factor)
+    }
+
+    fn decimate_filtered(&self, factor: usize, config: DecimateConfig) -> Vec<S> {
+        assert!(factor > 0, "Decimation factor must be at least 1");
+        if factor == 1 {
+            return self.to_vec();
+        }
+
+        let mut out = self.to_vec();
+        for (m, taps) in config.stages(factor) {
+            let delay = if config.compensate_delay {
+                (taps.len() - 1) / 2
+            } else {
+                0
+            };
+            out = decimate_stage(&out, &taps, m, delay);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::Complex;
+    use std::f64::consts::PI;
+
+    fn tone(len: usize, freq: f64, amplitude: f64) -> Vec<f64> {
+        (0..len)
+            .map(|i| amplitude * (PI * freq * i as f64).cos())
+            .collect()
+    }
+
+    fn rms_db(x: &[f64]) -> f64 {
+        10.0 * (x.iter().map(|x| x * x).sum::<f64>() / x.len() as f64).log10()
+    }
+
+    #[test]
+    fn test_prime_factors() {
+        assert_eq!(prime_factors(60), vec![5, 3, 2, 2]);
+        assert_eq!(prime_factors(97), vec![97]);
+        assert_eq!(prime_factors(1), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_decimate_rejects_aliases() {
+        let factor = 4;
+        let attenuation = 60.0;
+        let input = tone(16384, 0.35, 1.0);
+        let out =
+            input.decimate_filtered(factor, DecimateConfig::new().with_attenuation(attenuation));
+        assert_eq!(out.len(), input.len() / factor);
+
+        let middle = &out[200..(out.len() - 200)];
+        assert!(rms_db(middle) - rms_db(&input) < -attenuation);
+    }
+
+    #[test]
+    fn test_decimate_passes_passband() {
+        let factor = 4;
+        let input = tone(16384, 0.15, 1.0);
+        let out = input.decimate_filtered(factor, DecimateConfig::new());
+
+        let middle = &out[200..(out.len() - 200)];
+        assert!((rms_db(middle) - rms_db(&input)).abs() < 0.1);
+
+        // The complex path shares the same filter.
+        let complex = (0..16384)
+            .map(|i| Complex::from_polar(1.0, PI * 0.15 * i as f64))
+            .collect::<Vec<_>>();
+        let out = complex.decimate_filtered(factor, DecimateConfig::new());
+        for x in &out[200..(out.len() - 200)] {
+            assert!((20.0 * x.norm().log10()).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_decimate_delay_compensation() {
+        let factor = 4;
+        let mut impulse = vec![0.0; 1024];
+        impulse[400] = 1.0;
+
+        // Reference decimation of the impulse keeps it at sample 100, so the compensated output
+        // must correlate best against it with zero lag.
+        let argmax = |x: &[f64]| {
+            x.iter()
+                .enumerate()
+                .fold(
+                    (0, 0.0),
+                    |best, (i, v)| if v.abs() > best.1 { (i, v.abs()) } else { best },
+                )
+                .0
+        };
+
+        for staged in [true, false] {
+            let config = DecimateConfig::new().with_staging(staged);
+            let out = impulse.decimate_filtered(factor, config);
+            assert_eq!(argmax(&out), 400 / factor);
+
+            let config = DecimateConfig::new()
+                .with_staging(staged)
+                .with_delay_compensation(false);
+            let out = impulse.decimate_filtered(factor, config);
+            assert!(argmax(&out) > 400 / factor);
+        }
+    }
+
+    #[test]
+    fn test_decimate_staged_matches_single() {
+        let factor = 60;
+        let len = factor * 600;
+        let input = tone(len, 0.004, 1.0)
+            .iter()
+            .zip(tone(len, 0.009, 0.5))
+            .map(|(a, b)| a + b)
+            .collect::<Vec<_>>();
+
+        let staged = input.decimate_filtered(factor, DecimateConfig::new());
+        let single = input.decimate_filtered(factor, DecimateConfig::new().with_staging(false));
+        assert_eq!(staged.len(), single.len());
+
+        // Each filter contributes up to 60 dB of passband ripple on the 1.5 peak amplitude.
+        let budget = (prime_factors(factor).len() + 1) as f64 * 1e-3 * 1.5;
+        for k in 50..(staged.len() - 50) {
+            assert!((staged[k] - single[k]).abs() < budget);
+            assert!((staged[k] - input[k * factor]).abs() < budget);
+        }
+    }
+}
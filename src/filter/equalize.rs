@@ -0,0 +1,444 @@
+use std::fmt;
+
+use num::{Complex, Float, FromPrimitive, Zero};
+use rustfft::FftNum;
+
+use crate::utils::firwin2;
+use crate::vector::{FftContext, YttriaVectorCepstrum};
+use crate::windows::WindowKind;
+
+fn from_f64<T: FromPrimitive>(x: f64) -> T {
+    T::from_f64(x).expect("Could not convert f64 into type")
+}
+
+fn to_f64<T: Float>(x: T) -> f64 {
+    x.to_f64().expect("Could not convert type into f64")
+}
+
+// Welch settings for `estimate_response`: segments of `segment_len` samples, one response bin
+// each, overlapping by `overlap` and multiplied by `window`, with the averaged power then
+// smoothed over `smoothing` neighbouring bins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimateConfig {
+    segment_len: usize,
+    overlap: usize,
+    window: WindowKind,
+    smoothing: usize,
+}
+
+impl EstimateConfig {
+    // Hann segments overlapping by half, without smoothing.
+    pub fn new(segment_len: usize) -> Self {
+        Self {
+            segment_len,
+            overlap: segment_len / 2,
+            window: WindowKind::Hann,
+            smoothing: 1,
+        }
+    }
+
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    pub fn with_window(mut self, window: WindowKind) -> Self {
+        self.window = window;
+        self
+    }
+
+    // An odd number of bins, centred on each bin and wrapping around the band edges.
+    pub fn with_smoothing(mut self, bins: usize) -> Self {
+        self.smoothing = bins;
+        self
+    }
+}
+
+// Receiver frequency response from a calibration capture of a known stimulus, over
+// `segment_len` bins in FFT order, DC first. The capture's Welch power spectrum is divided by
+// the stimulus power `stimulus_spectrum`, given over the same bins at any scale, or taken as
+// flat when `None`. A power average carries no phase, so the response is its magnitude with
+// zero phase, and as the stimulus level is not known either it is normalised to unit mean
+// power across the bins.
+pub fn estimate_response<T>(
+    calibration_capture: &[Complex<T>],
+    stimulus_spectrum: Option<&[Complex<T>]>,
+    cfg: EstimateConfig,
+) -> Vec<Complex<T>>
+where
+    T: FftNum + Float + FromPrimitive,
+{
+    let EstimateConfig {
+        segment_len,
+        overlap,
+        window,
+        smoothing,
+    } = cfg;
+    assert!(segment_len > 0, "Segment length must be at least 1");
+    assert!(
+        overlap < segment_len,
+        "Overlap must be shorter than a segment"
+    );
+    assert!(
+        smoothing % 2 == 1 && smoothing <= segment_len,
+        "Smoothing must be an odd number of bins no wider than a segment"
+    );
+    assert!(
+        calibration_capture.len() >= segment_len,
+        "Capture of {} samples is shorter than a segment of {segment_len}",
+        calibration_capture.len()
+    );
+    if let Some(stimulus) = stimulus_spectrum {
+        assert!(
+            stimulus.len() == segment_len,
+            "Stimulus spectrum has {} bins, expected {segment_len}",
+            stimulus.len()
+        );
+    }
+
+    let window = window.generate::<T>(segment_len);
+    let hop = segment_len - overlap;
+    let segments = (calibration_capture.len() - segment_len) / hop + 1;
+    let mut context = FftContext::new();
+    let mut buffer = vec![Complex::<T>::zero(); segment_len];
+    let mut power = vec![0.0f64; segment_len];
+    for s in 0..segments {
+        let segment = &calibration_capture[s * hop..s * hop + segment_len];
+        for ((b, x), w) in buffer.iter_mut().zip(segment).zip(&window) {
+            *b = x * *w;
+        }
+        context.forward(&mut buffer);
+        for (power, b) in power.iter_mut().zip(&buffer) {
+            *power += to_f64(b.norm_sqr());
+        }
+    }
+
+    if let Some(stimulus) = stimulus_spectrum {
+        for (power, stimulus) in power.iter_mut().zip(stimulus) {
+            let stimulus = to_f64(stimulus.norm_sqr());
+            *power = if stimulus > 0.0 {
+                *power / stimulus
+            } else {
+                0.0
+            };
+        }
+    }
+
+    let half = smoothing as isize / 2;
+    let smoothed = (0..segment_len as isize)
+        .map(|k| {
+            (-half..=half)
+                .map(|offset| power[(k + offset).rem_euclid(segment_len as isize) as usize])
+                .sum::<f64>()
+                / smoothing as f64
+        })
+        .collect::<Vec<_>>();
+
+    let mean = smoothed.iter().sum::<f64>() / segment_len as f64;
+    smoothed
+        .iter()
+        .map(|power| {
+            let magnitude = if mean > 0.0 {
+                (power / mean).sqrt()
+            } else {
+                0.0
+            };
+            Complex::new(from_f64(magnitude), T::zero())
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InversePhase {
+    // Symmetric taps, delaying everything by `(numtaps - 1) / 2` samples.
+    Linear,
+    // The same magnitude with the least delay, through `minimum_phase`.
+    Minimum,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqualizerError {
+    EmptyResponse,
+    ZeroTaps,
+    // More taps than the response has bins would resolve detail the response does not hold.
+    TooManyTaps { numtaps: usize, max: usize },
+}
+
+impl fmt::Display for EqualizerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyResponse => write!(f, "response must not be empty"),
+            Self::ZeroTaps => write!(f, "equalizer needs at least one tap"),
+            Self::TooManyTaps { numtaps, max } => {
+                write!(f, "{numtaps} taps exceed the {max} the response resolves")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EqualizerError {}
+
+// Frequencies of the bins from DC up to Nyquist, as fractions of Nyquist. An odd length has no
+// bin on Nyquist itself, so the grid ends on an extra point there for `firwin2`.
+fn bin_freqs(len: usize) -> Vec<f64> {
+    let mut freqs = (0..=len / 2)
+        .map(|k| 2.0 * k as f64 / len as f64)
+        .collect::<Vec<_>>();
+    if !len.is_multiple_of(2) {
+        freqs.push(1.0);
+    }
+    freqs
+}
+
+// Real correction taps flattening `response`, a full band response in FFT order such as
+// `estimate_response` gives. Real taps have a magnitude even in frequency, so the response is
+// averaged with its mirror image first and only ripple common to both sides of DC is corrected.
+// The inverse is scaled so a bin at the response's RMS level passes at unity gain and clamped to
+// at most `max_boost_db` of gain, so a deep notch is only filled in that far instead of blowing
+// the filter up, while peaks are cut without limit. The taps come from `firwin2`, which
+// also holds an even length to zero gain at Nyquist.
+pub fn design_inverse_fir<T>(
+    response: &[Complex<T>],
+    numtaps: usize,
+    max_boost_db: T,
+    phase: InversePhase,
+) -> Result<Vec<T>, EqualizerError>
+where
+    T: Float + FromPrimitive,
+{
+    let len = response.len();
+    if len == 0 {
+        return Err(EqualizerError::EmptyResponse);
+    }
+    if numtaps == 0 {
+        return Err(EqualizerError::ZeroTaps);
+    }
+    if numtaps > len {
+        return Err(EqualizerError::TooManyTaps { numtaps, max: len });
+    }
+
+    let magnitude = response
+        .iter()
+        .map(|x| to_f64(x.norm()))
+        .collect::<Vec<_>>();
+    let rms = (magnitude.iter().map(|x| x * x).sum::<f64>() / len as f64).sqrt();
+    let max_gain = 10f64.powf(to_f64(max_boost_db) / 20.0);
+
+    let freqs = bin_freqs(len);
+    let mut gains = (0..=len / 2)
+        .map(|k| {
+            let even = 0.5 * (magnitude[k] + magnitude[(len - k) % len]);
+            if even * max_gain > rms {
+                rms / even
+            } else {
+                max_gain
+            }
+        })
+        .collect::<Vec<_>>();
+    if freqs.len() > gains.len() {
+        gains.push(*gains.last().expect("Response is not empty"));
+    }
+    if numtaps.is_multiple_of(2) {
+        *gains.last_mut().expect("Response is not empty") = 0.0;
+    }
+
+    let mut taps = firwin2(numtaps, &freqs, &gains, false);
+    if phase == InversePhase::Minimum {
+        taps = taps.minimum_phase();
+    }
+    Ok(taps.into_iter().map(from_f64).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::freqz;
+    use crate::functional::apply_fir;
+    use crate::random::complex_normal;
+    use crate::rng::{Xoshiro256PlusPlus, YttriaRng};
+    use crate::vector::YttriaVectorComplexFft;
+
+    // A delayed echo a third of the direct path ripples the response between +2.5 and -3.5 dB.
+    const RIPPLE: [f64; 5] = [1.0, 0.0, 0.0, 0.0, 1.0 / 3.0];
+
+    fn filter(taps: &[f64], signal: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        let re = signal.iter().map(|x| x.re).collect::<Vec<_>>();
+        let im = signal.iter().map(|x| x.im).collect::<Vec<_>>();
+        let (re, im) = (apply_fir(taps, &re).unwrap(), apply_fir(taps, &im).unwrap());
+        re.iter()
+            .zip(&im)
+            .map(|(re, im)| Complex::new(*re, *im))
+            .collect()
+    }
+
+    fn capture(taps: &[f64], len: usize, seed: u64) -> Vec<Complex<f64>> {
+        let noise = complex_normal::<f64>(len, &mut Xoshiro256PlusPlus::seed_from(seed));
+        filter(taps, &noise)
+    }
+
+    // dB magnitudes of the bins within the central 80% of the band.
+    fn inner_band_db(response: &[Complex<f64>]) -> Vec<f64> {
+        let len = response.len();
+        response
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| (*k).min(len - *k) * 10 < len * 4)
+            .map(|(_, x)| 20.0 * x.norm().log10())
+            .collect()
+    }
+
+    // Magnitude at Nyquist, where the taps alternate in sign.
+    fn nyquist_gain(taps: &[f64]) -> f64 {
+        taps.iter()
+            .enumerate()
+            .fold(
+                0.0f64,
+                |acc, (n, t)| if n % 2 == 0 { acc + t } else { acc - t },
+            )
+            .abs()
+    }
+
+    fn spread(db: &[f64]) -> (f64, f64) {
+        let mean = db.iter().sum::<f64>() / db.len() as f64;
+        let (low, high) = db.iter().fold((f64::MAX, f64::MIN), |(low, high), x| {
+            (low.min(*x), high.max(*x))
+        });
+        (low - mean, high - mean)
+    }
+
+    #[test]
+    fn test_estimate_flat_and_known_stimulus() {
+        let cfg = EstimateConfig::new(128).with_smoothing(3);
+        let response = estimate_response(&capture(&RIPPLE, 1 << 17, 1), None, cfg);
+        assert_eq!(response.len(), 128);
+
+        let (_, expected) = freqz(&RIPPLE, &[1.0], 64);
+        let rms = (expected.iter().map(|x| x.norm_sqr()).sum::<f64>() / 64.0).sqrt();
+        for k in 0..64 {
+            let expected = expected[k].norm() / rms;
+            let error_db = 20.0 * (response[k].norm() / expected).log10();
+            assert!(error_db.abs() < 0.3, "{k}: {error_db}");
+            assert_eq!(response[k].im, 0.0);
+        }
+
+        // A coloured stimulus divides out given its spectrum.
+        let colour = [1.0, 0.5, 0.25];
+        let stimulus = filter(&colour, &capture(&[1.0], 1 << 17, 2));
+        let through = filter(&RIPPLE, &stimulus);
+        let mut spectrum = vec![Complex::zero(); 128];
+        for (s, c) in spectrum.iter_mut().zip(colour) {
+            *s = Complex::new(c, 0.0);
+        }
+        let divided = estimate_response(&through, Some(&spectrum.fft()), cfg);
+        let coloured = estimate_response(&through, None, cfg);
+        let (low, high) = spread(&inner_band_db(&divided));
+        let (colour_low, _) = spread(&inner_band_db(&coloured));
+        assert!(low > -4.0 && high < 3.0, "{low} {high}");
+        assert!(colour_low < -6.0, "{colour_low}");
+        for k in 0..128 {
+            let error_db = 20.0 * (divided[k].norm() / response[k].norm()).log10();
+            assert!(error_db.abs() < 0.3, "{k}: {error_db}");
+        }
+    }
+
+    #[test]
+    fn test_flattens_ripple() {
+        let cfg = EstimateConfig::new(256).with_smoothing(5);
+        let response = estimate_response(&capture(&RIPPLE, 1 << 18, 3), None, cfg);
+        let (low, high) = spread(&inner_band_db(&response));
+        assert!(low < -3.0 && high > 2.0, "{low} {high}");
+
+        for phase in [InversePhase::Linear, InversePhase::Minimum] {
+            let taps = design_inverse_fir(&response, 129, 12.0, phase).unwrap();
+            let fresh = filter(&taps, &capture(&RIPPLE, 1 << 18, 4));
+            let corrected = estimate_response(&fresh, None, cfg);
+            let (low, high) = spread(&inner_band_db(&corrected));
+            assert!(low > -0.3 && high < 0.3, "{phase:?}: {low} {high}");
+
+            let peak = (0..taps.len())
+                .max_by(|a, b| taps[*a].abs().total_cmp(&taps[*b].abs()))
+                .unwrap();
+            match phase {
+                InversePhase::Linear => assert_eq!(peak, 64),
+                InversePhase::Minimum => assert!(peak < 8, "{peak}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_boost_clamped_at_notch() {
+        // Flat apart from a notch a tenth of the band wide at a quarter of Nyquist, on both sides
+        // of DC.
+        let len = 512;
+        let response = (0..len)
+            .map(|k: usize| {
+                let distance = k.min(len - k).abs_diff(len / 8);
+                Complex::new(if distance < len / 40 { 1e-4 } else { 1.0 }, 0.0)
+            })
+            .collect::<Vec<_>>();
+        let taps = design_inverse_fir(&response, 255, 6.0, InversePhase::Linear).unwrap();
+        let (_, h) = freqz(&taps, &[1.0], 256);
+        let db = h
+            .iter()
+            .map(|x| 20.0 * x.norm().log10())
+            .collect::<Vec<_>>();
+
+        let peak = db.iter().fold(f64::MIN, |acc, x| acc.max(*x));
+        assert!(peak < 6.5, "{peak}");
+        // The notch takes the whole boost, while the rest of the band sits at the RMS level the
+        // notch pulls down.
+        let rms_db = 10.0 * ((len - 46) as f64 / len as f64).log10();
+        assert!((db[64] - 6.0).abs() < 0.3, "{}", db[64]);
+        assert!((db[200] - rms_db).abs() < 0.2, "{}", db[200]);
+
+        // With a looser clamp the notch is filled in further, up to the new ceiling.
+        let loose = design_inverse_fir(&response, 255, 20.0, InversePhase::Linear).unwrap();
+        let (_, h) = freqz(&loose, &[1.0], 256);
+        assert!(20.0 * h[64].norm().log10() > 15.0);
+    }
+
+    #[test]
+    fn test_tap_count_errors() {
+        let response = vec![Complex::new(1.0f64, 0.0); 64];
+        assert_eq!(
+            design_inverse_fir(&response, 65, 6.0, InversePhase::Linear),
+            Err(EqualizerError::TooManyTaps {
+                numtaps: 65,
+                max: 64
+            })
+        );
+        assert_eq!(
+            design_inverse_fir(&response, 0, 6.0, InversePhase::Linear),
+            Err(EqualizerError::ZeroTaps)
+        );
+        assert_eq!(
+            design_inverse_fir::<f64>(&[], 1, 6.0, InversePhase::Linear),
+            Err(EqualizerError::EmptyResponse)
+        );
+
+        // A flat response needs no correction, an even length still nulling Nyquist.
+        let taps = design_inverse_fir(&response, 63, 6.0, InversePhase::Linear).unwrap();
+        let (_, h) = freqz(&taps, &[1.0], 32);
+        assert!(h.iter().all(|x| (x.norm() - 1.0).abs() < 0.02));
+        let taps = design_inverse_fir(&response, 64, 6.0, InversePhase::Linear).unwrap();
+        assert_eq!(taps.len(), 64);
+        let (_, h) = freqz(&taps, &[1.0], 32);
+        assert!(h[..28].iter().all(|x| (x.norm() - 1.0).abs() < 0.02));
+        assert!(nyquist_gain(&taps) < 1e-12, "{}", nyquist_gain(&taps));
+
+        // An odd length response accepts an odd tap count, and an even one is still nulled.
+        let odd = vec![Complex::new(1.0f64, 0.0); 63];
+        let taps = design_inverse_fir(&odd, 63, 6.0, InversePhase::Linear).unwrap();
+        let (_, h) = freqz(&taps, &[1.0], 32);
+        assert!(h.iter().all(|x| (x.norm() - 1.0).abs() < 0.02));
+        let taps = design_inverse_fir(&odd, 62, 6.0, InversePhase::Linear).unwrap();
+        assert!(nyquist_gain(&taps) < 1e-12, "{}", nyquist_gain(&taps));
+    }
+
+    #[test]
+    fn test_bin_freqs() {
+        assert_eq!(bin_freqs(1), [0.0, 1.0]);
+        assert_eq!(bin_freqs(4), [0.0, 0.5, 1.0]);
+        assert_eq!(bin_freqs(5), [0.0, 0.4, 0.8, 1.0]);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::windows;
+
+// Kaiser window shape parameter giving `attenuation` dB of stopband rejection.
+pub fn kaiser_beta(attenuation: f64) -> f64 {
+    if attenuation > 50.0 {
+        0.1102 * (attenuation - 8.7)
+    } else if attenuation > 21.0 {
+        0.5842 * (attenuation - 21.0).powf(0.4) + 0.07886 * (attenuation - 21.0)
+    } else {
+        0.0
+    }
+}
+
+// Number of taps and Kaiser beta needed for `attenuation` dB of ripple rejection across a
+// transition band `width` wide, with 1.0 being the Nyquist frequency.
+pub fn kaiserord(attenuation: f64, width: f64) -> (usize, f64) {
+    assert!(
+        attenuation >= 8.0,
+        "Kaiser window design requires at least 8 dB of attenuation"
+    );
+    assert!(
+        width > 0.0 && width <= 1.0,
+        "Transition width must lie within (0, 1]"
+    );
+
+    let numtaps = (attenuation - 7.95) / 2.285 / (std::f64::consts::PI * width) + 1.0;
+    (numtaps.ceil() as usize, kaiser_beta(attenuation))
+}
+
+// Windowed-sinc lowpass with its -6 dB point at `cutoff` (1.0 being the Nyquist frequency),
+// scaled to unity gain at DC.
+pub fn firwin(numtaps: usize, cutoff: f64, window: &[f64]) -> Vec<f64> {
+    assert!(
+        window.len() == numtaps,
+        "Window length {} does not match {numtaps} taps",
+        window.len()
+    );
+    assert!(
+        cutoff > 0.0 && cutoff < 1.0,
+        "Cutoff must lie strictly between 0 and the Nyquist frequency"
+    );
+
+    let alpha = (numtaps as f64 - 1.0) / 2.0;
+    let mut taps = window
+        .iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let x = cutoff * (i as f64 - alpha);
+            let sinc = if x == 0.0 {
+                1.0
+            } else {
+                (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x)
+            };
+            cutoff * sinc * w
+        })
+        .collect::<Vec<_>>();
+
+    let gain = taps.iter().sum::<f64>();
+    taps.iter_mut().for_each(|t| *t /= gain);
+    taps
+}
+
+// Kaiser windowed lowpass passing up to `passband` and rejecting from `stopband` onwards by
+// `attenuation` dB. The tap count is always odd so the group delay is a whole number of samples.
+pub fn firwin_kaiser(passband: f64, stopband: f64, attenuation: f64) -> Vec<f64> {
+    assert!(
+        passband < stopband,
+        "Passband edge must lie below the stopband edge"
+    );
+
+    let (numtaps, beta) = kaiserord(attenuation, stopband - passband);
+    let numtaps = numtaps | 1;
+    firwin(
+        numtaps,
+        (passband + stopband) / 2.0,
+        &windows::kaiser(numtaps, beta),
+    )
+}
+
+// Kaiser windowed type III Hilbert transformer: a 90 degree phase shift with a group delay of
+// `(numtaps - 1) / 2` samples. Accuracy degrades towards DC and Nyquist over a band that narrows
+// as the tap count grows.
+pub fn firwin_hilbert(numtaps: usize, beta: f64) -> Vec<f64> {
+    assert!(
+        numtaps % 2 == 1,
+        "A Hilbert transformer needs an odd number of taps"
+    );
+
+    let center = numtaps / 2;
+    windows::kaiser(numtaps, beta)
+        .iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let m = i as isize - center as isize;
+            if m % 2 == 0 {
+                0.0
+            } else {
+                2.0 / (std::f64::consts::PI * m as f64) * w
+            }
+        })
+        .collect()
+}
+
+// Root raised cosine pulse with the given roll-off, spanning `span` symbols at
+// `samples_per_symbol`, scaled to unit energy.
+pub fn rrc(rolloff: f64, span: usize, samples_per_symbol: usize) -> Vec<f64> {
+    assert!(
+        rolloff > 0.0 && rolloff <= 1.0,
+        "Roll-off must lie within (0, 1]"
+    );
+    assert!(
+        span > 0 && samples_per_symbol > 0,
+        "Span and samples per symbol must be positive"
+    );
+
+    let pi = std::f64::consts::PI;
+    let center = (span * samples_per_symbol) as f64 / 2.0;
+    let mut taps = (0..=(span * samples_per_symbol))
+        .map(|n| {
+            let t = (n as f64 - center) / samples_per_symbol as f64;
+            if t == 0.0 {
+                1.0 - rolloff + 4.0 * rolloff / pi
+            } else if (4.0 * rolloff * t).abs() == 1.0 {
+                rolloff / 2f64.sqrt()
+                    * ((1.0 + 2.0 / pi) * (pi / (4.0 * rolloff)).sin()
+                        + (1.0 - 2.0 / pi) * (pi / (4.0 * rolloff)).cos())
+            } else {
+                ((pi * t * (1.0 - rolloff)).sin()
+                    + 4.0 * rolloff * t * (pi * t * (1.0 + rolloff)).cos())
+                    / (pi * t * (1.0 - (4.0 * rolloff * t).powi(2)))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let energy = taps.iter().map(|t| t * t).sum::<f64>().sqrt();
+    taps.iter_mut().for_each(|t| *t /= energy);
+    taps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_firwin_kaiser_response() {
+        let taps = firwin_kaiser(0.2, 0.3, 60.0);
+        assert!(taps.len() % 2 == 1);
+
+        let response = |f: f64| {
+            let (re, im) = taps
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (n, t)| {
+                    let w = std::f64::consts::PI * f * n as f64;
+                    (re + t * w.cos(), im - t * w.sin())
+                });
+            (re * re + im * im).sqrt()
+        };
+
+        for i in 0..=100 {
+            let f = i as f64 / 100.0;
+            if f <= 0.2 {
+                assert!((response(f) - 1.0).abs() < 1.2e-3);
+            } else if f >= 0.3 {
+                assert!(20.0 * response(f).log10() < -59.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rrc_nyquist() {
+        // Two cascaded root raised cosines form a raised cosine, which is zero at every other
+        // symbol centre.
+        let sps = 8;
+        let taps = rrc(0.35, 16, sps);
+        assert!((taps.iter().map(|t| t * t).sum::<f64>() - 1.0).abs() < 1e-12);
+
+        let center = taps.len() - 1;
+        let cascade = (0..(2 * taps.len() - 1))
+            .map(|n| {
+                (0..taps.len())
+                    .filter(|k| n >= *k && n - k < taps.len())
+                    .map(|k| taps[k] * taps[n - k])
+                    .sum::<f64>()
+            })
+            .collect::<Vec<_>>();
+        for symbol in 1..8 {
+            assert!(cascade[center + symbol * sps].abs() < 5e-3);
+        }
+        assert!((cascade[center] - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,574 @@
+use super::FilterSample;
+use num::{Complex, Float, FromPrimitive, One, Zero};
+use std::f64::consts::PI;
+use std::fmt;
+
+// Second order section `(b0 + b1 z^-1 + b2 z^-2) / (1 + a1 z^-1 + a2 z^-2)`. First order
+// sections have `b2` and `a2` set to zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Biquad<T> {
+    pub b: [T; 3],
+    pub a: [T; 2],
+}
+
+impl<T: Float> Biquad<T> {
+    pub fn response(&self, z_inv: Complex<T>) -> Complex<T> {
+        let z_inv2 = z_inv * z_inv;
+        let num = z_inv2.scale(self.b[2]) + z_inv.scale(self.b[1]) + self.b[0];
+        let den = z_inv2.scale(self.a[1]) + z_inv.scale(self.a[0]) + T::one();
+        num / den
+    }
+
+    pub fn poles(&self) -> Vec<Complex<T>> {
+        let (a1, a2) = (self.a[0], self.a[1]);
+        if a2.is_zero() {
+            return vec![Complex::new(-a1, T::zero())];
+        }
+        let two = T::one() + T::one();
+        let disc = Complex::new(a1 * a1 - two * two * a2, T::zero()).sqrt();
+        vec![(disc - a1) / two, (-disc - a1) / two]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BiquadCascade<T> {
+    sections: Vec<Biquad<T>>,
+}
+
+impl<T: Float + FromPrimitive> BiquadCascade<T> {
+    pub fn new(sections: Vec<Biquad<T>>) -> Self {
+        Self { sections }
+    }
+
+    pub fn sections(&self) -> &[Biquad<T>] {
+        &self.sections
+    }
+
+    pub fn poles(&self) -> Vec<Complex<T>> {
+        self.sections.iter().flat_map(|s| s.poles()).collect()
+    }
+
+    // Complex response at each of `freqs`, in cycles per sample.
+    pub fn frequency_response(&self, freqs: &[T]) -> Vec<Complex<T>> {
+        let two_pi = T::from_f64(2.0 * PI).expect("Could not convert f64 into type");
+        freqs
+            .iter()
+            .map(|f| {
+                let z_inv = Complex::from_polar(T::one(), -two_pi * *f);
+                self.sections
+                    .iter()
+                    .fold(Complex::one(), |acc, s| acc * s.response(z_inv))
+            })
+            .collect()
+    }
+
+    // Runs `input` through the cascade from zero state, one transposed direct form II section
+    // after another.
+    pub fn filter<S: FilterSample<Real = T>>(&self, input: &[S]) -> Vec<S> {
+        let mut out = input.to_vec();
+        for s in &self.sections {
+            let (mut w1, mut w2) = (S::zero(), S::zero());
+            for x in out.iter_mut() {
+                let y = *x * s.b[0] + w1;
+                w1 = *x * s.b[1] + y * -s.a[0] + w2;
+                w2 = *x * s.b[2] + y * -s.a[1];
+                *x = y;
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IirBand {
+    Lowpass,
+    Highpass,
+    // The band runs from the design cutoff up to the given upper edge, in cycles per sample.
+    Bandpass(f64),
+    Bandstop(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IirDesignError {
+    ZeroOrder,
+    CutoffOutOfRange(f64),
+    InvalidRipple(f64),
+}
+
+impl fmt::Display for IirDesignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroOrder => write!(f, "filter order must be at least 1"),
+            Self::CutoffOutOfRange(cutoff) => write!(
+                f,
+                "cutoff {cutoff} must lie within (0, 0.5) cycles per sample"
+            ),
+            Self::InvalidRipple(ripple) => {
+                write!(f, "passband ripple {ripple} dB must be positive")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IirDesignError {}
+
+// Analog prototype as zeros, poles and gain, normalised to an edge at 1 rad/s.
+struct Zpk {
+    zeros: Vec<Complex<f64>>,
+    poles: Vec<Complex<f64>>,
+    gain: f64,
+}
+
+fn product(x: &[Complex<f64>], f: impl Fn(Complex<f64>) -> Complex<f64>) -> Complex<f64> {
+    x.iter().fold(Complex::one(), |acc, x| acc * f(*x))
+}
+
+// The bilinear transform runs at fs = 2, so prewarped edges are 4 tan(pi f).
+const BILINEAR_K: f64 = 4.0;
+
+fn prewarp(cutoff: f64) -> f64 {
+    BILINEAR_K * (PI * cutoff).tan()
+}
+
+impl Zpk {
+    fn transform(self, band: IirBand, cutoff: f64) -> Zpk {
+        let degree = self.poles.len() - self.zeros.len();
+        let w1 = prewarp(cutoff);
+        match band {
+            IirBand::Lowpass => Zpk {
+                zeros: self.zeros.iter().map(|z| z * w1).collect(),
+                poles: self.poles.iter().map(|p| p * w1).collect(),
+                gain: self.gain * w1.powi(degree as i32),
+            },
+            IirBand::Highpass => {
+                let ratio = product(&self.zeros, |z| -z) / product(&self.poles, |p| -p);
+                let mut zeros = self.zeros.iter().map(|z| w1 / z).collect::<Vec<_>>();
+                zeros.extend(vec![Complex::zero(); degree]);
+                Zpk {
+                    zeros,
+                    poles: self.poles.iter().map(|p| w1 / p).collect(),
+                    gain: self.gain * ratio.re,
+                }
+            }
+            IirBand::Bandpass(upper) | IirBand::Bandstop(upper) => {
+                let w2 = prewarp(upper);
+                let (bw, wo) = (w2 - w1, (w1 * w2).sqrt());
+                let split = |x: Vec<Complex<f64>>| {
+                    let roots = x
+                        .iter()
+                        .map(|x| (x * x - wo * wo).sqrt())
+                        .collect::<Vec<_>>();
+                    x.iter()
+                        .zip(&roots)
+                        .map(|(x, r)| x + r)
+                        .chain(x.iter().zip(&roots).map(|(x, r)| x - r))
+                        .collect::<Vec<_>>()
+                };
+
+                if let IirBand::Bandpass(_) = band {
+                    let mut zeros = split(self.zeros.iter().map(|z| z * bw / 2.0).collect());
+                    zeros.extend(vec![Complex::zero(); degree]);
+                    Zpk {
+                        zeros,
+                        poles: split(self.poles.iter().map(|p| p * bw / 2.0).collect()),
+                        gain: self.gain * bw.powi(degree as i32),
+                    }
+                } else {
+                    let ratio = product(&self.zeros, |z| -z) / product(&self.poles, |p| -p);
+                    let mut zeros = split(self.zeros.iter().map(|z| bw / 2.0 / z).collect());
+                    zeros.extend(vec![Complex::new(0.0, wo); degree]);
+                    zeros.extend(vec![Complex::new(0.0, -wo); degree]);
+                    Zpk {
+                        zeros,
+                        poles: split(self.poles.iter().map(|p| bw / 2.0 / p).collect()),
+                        gain: self.gain * ratio.re,
+                    }
+                }
+            }
+        }
+    }
+
+    fn bilinear(self) -> Zpk {
+        let degree = self.poles.len() - self.zeros.len();
+        let map = |s: &Complex<f64>| (BILINEAR_K + s) / (BILINEAR_K - s);
+        let ratio =
+            product(&self.zeros, |z| BILINEAR_K - z) / product(&self.poles, |p| BILINEAR_K - p);
+
+        let mut zeros = self.zeros.iter().map(map).collect::<Vec<_>>();
+        zeros.extend(vec![Complex::new(-1.0, 0.0); degree]);
+        Zpk {
+            zeros,
+            poles: self.poles.iter().map(map).collect(),
+            gain: self.gain * ratio.re,
+        }
+    }
+
+    // Pairs conjugate poles into sections, ordered so the section closest to the unit circle
+    // (the highest Q) runs last, and gives each the nearest remaining zeros. Every section is
+    // normalised to unit gain at `reference` cycles per sample, with the overall gain folded into
+    // the first section.
+    fn into_sections<T: Float + FromPrimitive>(self, reference: f64) -> BiquadCascade<T> {
+        let (pole_pairs, mut pole_reals) = split_conjugates(&self.poles);
+        let (mut zero_pairs, mut zero_reals) = split_conjugates(&self.zeros);
+
+        let mut groups = pole_pairs
+            .iter()
+            .map(|p| vec![*p, p.conj()])
+            .collect::<Vec<_>>();
+        pole_reals.sort_by(|a, b| b.norm().total_cmp(&a.norm()));
+        for pair in pole_reals.chunks(2) {
+            groups.push(pair.to_vec());
+        }
+        groups.sort_by(|a, b| a[0].norm().total_cmp(&b[0].norm()));
+
+        // Zeros are handed out from the highest Q section down, first order sections first.
+        let mut order = (0..groups.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            (groups[a].len(), -groups[a][0].norm())
+                .partial_cmp(&(groups[b].len(), -groups[b][0].norm()))
+                .unwrap()
+        });
+        let mut section_zeros = vec![Vec::new(); groups.len()];
+        for i in order {
+            let pole = groups[i][0];
+            let wanted = groups[i].len();
+            let nearest_real = |reals: &mut Vec<Complex<f64>>| {
+                let idx = (0..reals.len()).min_by(|&a, &b| {
+                    (reals[a] - pole)
+                        .norm()
+                        .total_cmp(&(reals[b] - pole).norm())
+                })?;
+                Some(reals.remove(idx))
+            };
+
+            let pair_idx = (0..zero_pairs.len()).min_by(|&a, &b| {
+                (zero_pairs[a] - pole)
+                    .norm()
+                    .total_cmp(&(zero_pairs[b] - pole).norm())
+            });
+            let real_dist = zero_reals
+                .iter()
+                .map(|z| (z - pole).norm())
+                .fold(f64::INFINITY, f64::min);
+            let zeros = match pair_idx {
+                Some(idx)
+                    if wanted == 2
+                        && ((zero_pairs[idx] - pole).norm() <= real_dist
+                            || zero_reals.len() < 2) =>
+                {
+                    let z = zero_pairs.remove(idx);
+                    vec![z, z.conj()]
+                }
+                _ => (0..wanted)
+                    .filter_map(|_| nearest_real(&mut zero_reals))
+                    .collect(),
+            };
+            section_zeros[i] = zeros;
+        }
+
+        let z_inv = Complex::from_polar(1.0, -2.0 * PI * reference);
+        let mut gain = self.gain;
+        let mut sections = groups
+            .iter()
+            .zip(&section_zeros)
+            .map(|(poles, zeros)| {
+                let b = real_poly(zeros);
+                let a = real_poly(poles);
+                let section = Biquad { b, a: [a[1], a[2]] };
+                let g = section.response(z_inv).norm();
+                gain *= g;
+                Biquad {
+                    b: b.map(|b| b / g),
+                    a: section.a,
+                }
+            })
+            .collect::<Vec<_>>();
+        if let Some(first) = sections.first_mut() {
+            first.b = first.b.map(|b| b * gain);
+        }
+
+        let cast = |x: f64| T::from_f64(x).expect("Could not convert f64 into type");
+        BiquadCascade::new(
+            sections
+                .iter()
+                .map(|s| Biquad {
+                    b: s.b.map(cast),
+                    a: s.a.map(cast),
+                })
+                .collect(),
+        )
+    }
+}
+
+// Upper half plane members of conjugate pairs, and the real roots.
+fn split_conjugates(roots: &[Complex<f64>]) -> (Vec<Complex<f64>>, Vec<Complex<f64>>) {
+    let tolerance = |x: &Complex<f64>| 1e-9 * x.norm().max(1.0);
+    let pairs = roots
+        .iter()
+        .filter(|x| x.im > tolerance(x))
+        .copied()
+        .collect::<Vec<_>>();
+    let reals = roots
+        .iter()
+        .filter(|x| x.im.abs() <= tolerance(x))
+        .map(|x| Complex::new(x.re, 0.0))
+        .collect::<Vec<_>>();
+    (pairs, reals)
+}
+
+// Coefficients of `prod (1 - r z^-1)` over up to two roots forming a real polynomial.
+fn real_poly(roots: &[Complex<f64>]) -> [f64; 3] {
+    match roots {
+        [] => [1.0, 0.0, 0.0],
+        [r] => [1.0, -r.re, 0.0],
+        [r1, r2] => [1.0, -(r1 + r2).re, (r1 * r2).re],
+        _ => unreachable!("Sections hold at most two roots"),
+    }
+}
+
+fn validate(order: usize, cutoff: f64, band: IirBand) -> Result<(), IirDesignError> {
+    if order == 0 {
+        return Err(IirDesignError::ZeroOrder);
+    }
+    for edge in [cutoff].into_iter().chain(match band {
+        IirBand::Bandpass(upper) | IirBand::Bandstop(upper) => Some(upper),
+        _ => None,
+    }) {
+        if !(edge > 0.0 && edge < 0.5) {
+            return Err(IirDesignError::CutoffOutOfRange(edge));
+        }
+    }
+    if let IirBand::Bandpass(upper) | IirBand::Bandstop(upper) = band {
+        if upper <= cutoff {
+            return Err(IirDesignError::CutoffOutOfRange(upper));
+        }
+    }
+    Ok(())
+}
+
+// Where each band is normalised: DC, Nyquist or the centre of the band.
+fn reference_frequency(cutoff: f64, band: IirBand) -> f64 {
+    match band {
+        IirBand::Lowpass | IirBand::Bandstop(_) => 0.0,
+        IirBand::Highpass => 0.5,
+        IirBand::Bandpass(upper) => {
+            (BILINEAR_K.recip() * (prewarp(cutoff) * prewarp(upper)).sqrt()).atan() / PI
+        }
+    }
+}
+
+fn design<T: Float + FromPrimitive>(
+    prototype: Zpk,
+    cutoff: f64,
+    band: IirBand,
+) -> BiquadCascade<T> {
+    prototype
+        .transform(band, cutoff)
+        .bilinear()
+        .into_sections(reference_frequency(cutoff, band))
+}
+
+// Butterworth design of `order` poles per band edge, -3 dB at `cutoff` cycles per sample.
+pub fn butterworth<T: Float + FromPrimitive>(
+    order: usize,
+    cutoff: f64,
+    band: IirBand,
+) -> Result<BiquadCascade<T>, IirDesignError> {
+    validate(order, cutoff, band)?;
+
+    let n = order as f64;
+    let poles = (0..order)
+        .map(|k| -Complex::from_polar(1.0, PI * (2.0 * k as f64 - n + 1.0) / (2.0 * n)))
+        .collect();
+    let prototype = Zpk {
+        zeros: Vec::new(),
+        poles,
+        gain: 1.0,
+    };
+    Ok(design(prototype, cutoff, band))
+}
+
+// Chebyshev type I design with `ripple_db` of passband ripple, the passband ending at `cutoff`
+// cycles per sample, where the response last falls to `-ripple_db`.
+pub fn chebyshev1<T: Float + FromPrimitive>(
+    order: usize,
+    ripple_db: f64,
+    cutoff: f64,
+    band: IirBand,
+) -> Result<BiquadCascade<T>, IirDesignError> {
+    validate(order, cutoff, band)?;
+    if ripple_db.is_nan() || ripple_db <= 0.0 {
+        return Err(IirDesignError::InvalidRipple(ripple_db));
+    }
+
+    let n = order as f64;
+    let eps = (10f64.powf(ripple_db / 10.0) - 1.0).sqrt();
+    let mu = (1.0 / eps).asinh() / n;
+    let poles = (0..order)
+        .map(|k| {
+            let theta = PI * (2.0 * k as f64 - n + 1.0) / (2.0 * n);
+            -Complex::new(mu, theta).sinh()
+        })
+        .collect::<Vec<_>>();
+    let mut gain = product(&poles, |p| -p).re;
+    if order.is_multiple_of(2) {
+        gain /= (1.0 + eps * eps).sqrt();
+    }
+    let prototype = Zpk {
+        zeros: Vec::new(),
+        poles,
+        gain,
+    };
+    Ok(design(prototype, cutoff, band))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Frequency the analog prototype sees after prewarping, for a band from `f1` (and `f2`).
+    fn prototype_frequency(f: f64, f1: f64, band: IirBand) -> f64 {
+        let w = (PI * f).tan();
+        let w1 = (PI * f1).tan();
+        match band {
+            IirBand::Lowpass => w / w1,
+            IirBand::Highpass => w1 / w,
+            IirBand::Bandpass(f2) | IirBand::Bandstop(f2) => {
+                let w2 = (PI * f2).tan();
+                let ratio = (w * w - w1 * w2) / (w * (w2 - w1));
+                if let IirBand::Bandpass(_) = band {
+                    ratio.abs()
+                } else {
+                    1.0 / ratio.abs()
+                }
+            }
+        }
+    }
+
+    // The bilinear designs have closed form magnitudes, which is also what every reference
+    // implementation of the same design produces.
+    fn butterworth_db(order: usize, f: f64, f1: f64, band: IirBand) -> f64 {
+        let x = prototype_frequency(f, f1, band);
+        -10.0 * (1.0 + x.powi(2 * order as i32)).log10()
+    }
+
+    fn chebyshev_db(order: usize, ripple: f64, f: f64, f1: f64, band: IirBand) -> f64 {
+        let x = prototype_frequency(f, f1, band);
+        let eps2 = 10f64.powf(ripple / 10.0) - 1.0;
+        let t = if x <= 1.0 {
+            (order as f64 * x.acos()).cos()
+        } else {
+            (order as f64 * x.acosh()).cosh()
+        };
+        -10.0 * (1.0 + eps2 * t * t).log10()
+    }
+
+    fn grid() -> Vec<f64> {
+        (1..1000).map(|i| i as f64 * 0.0005).collect()
+    }
+
+    fn response_db(cascade: &BiquadCascade<f64>, freqs: &[f64]) -> Vec<f64> {
+        cascade
+            .frequency_response(freqs)
+            .iter()
+            .map(|h| 20.0 * h.norm().log10())
+            .collect()
+    }
+
+    const BANDS: [IirBand; 4] = [
+        IirBand::Lowpass,
+        IirBand::Highpass,
+        IirBand::Bandpass(0.3),
+        IirBand::Bandstop(0.3),
+    ];
+
+    #[test]
+    fn test_butterworth_matches_closed_form() {
+        let freqs = grid();
+        for order in [4, 8] {
+            for band in BANDS {
+                let cascade = butterworth::<f64>(order, 0.1, band).unwrap();
+                for (f, db) in freqs.iter().zip(response_db(&cascade, &freqs)) {
+                    let expected = butterworth_db(order, *f, 0.1, band);
+                    if expected > -120.0 {
+                        assert!(
+                            (db - expected).abs() < 0.05,
+                            "order {order} {band:?} at {f}: {db} vs {expected}"
+                        );
+                    }
+                }
+                assert!(cascade.poles().iter().all(|p| p.norm() < 1.0));
+            }
+        }
+
+        // Odd orders need a first order section.
+        let cascade = butterworth::<f64>(5, 0.2, IirBand::Lowpass).unwrap();
+        assert_eq!(cascade.sections().len(), 3);
+        let h = cascade.frequency_response(&[0.2])[0];
+        assert!((20.0 * h.norm().log10() + 10.0 * 2f64.log10()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_chebyshev_ripple() {
+        let freqs = grid();
+        let ripple = 0.5;
+        for order in [4, 8] {
+            for band in BANDS {
+                let cascade = chebyshev1::<f64>(order, ripple, 0.1, band).unwrap();
+                for (f, db) in freqs.iter().zip(response_db(&cascade, &freqs)) {
+                    let expected = chebyshev_db(order, ripple, *f, 0.1, band);
+                    if expected > -120.0 {
+                        assert!(
+                            (db - expected).abs() < 0.05,
+                            "order {order} {band:?} at {f}: {db} vs {expected}"
+                        );
+                    }
+                }
+                assert!(cascade.poles().iter().all(|p| p.norm() < 1.0));
+            }
+
+            let cascade = chebyshev1::<f64>(order, ripple, 0.1, IirBand::Lowpass).unwrap();
+            let passband = (0..=200).map(|i| i as f64 * 0.0005).collect::<Vec<_>>();
+            let db = response_db(&cascade, &passband);
+            let max = db.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let min = db.iter().copied().fold(f64::INFINITY, f64::min);
+            assert!((max - min - ripple).abs() < 0.05, "{}", max - min);
+        }
+    }
+
+    #[test]
+    fn test_filter_matches_response() {
+        let cascade = butterworth::<f64>(6, 0.05, IirBand::Lowpass).unwrap();
+        let freq = 0.03;
+        let tone = (0..4000)
+            .map(|i| (2.0 * PI * freq * i as f64).cos())
+            .collect::<Vec<_>>();
+        let out = cascade.filter(&tone);
+        let gain = cascade.frequency_response(&[freq])[0];
+
+        for (i, y) in out.iter().enumerate().skip(2000) {
+            let expected = gain.norm() * (2.0 * PI * freq * i as f64 + gain.arg()).cos();
+            assert!((y - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_design_errors() {
+        assert_eq!(
+            butterworth::<f64>(0, 0.1, IirBand::Lowpass),
+            Err(IirDesignError::ZeroOrder)
+        );
+        assert_eq!(
+            butterworth::<f64>(2, 0.5, IirBand::Lowpass),
+            Err(IirDesignError::CutoffOutOfRange(0.5))
+        );
+        assert_eq!(
+            butterworth::<f64>(2, 0.1, IirBand::Bandpass(0.6)),
+            Err(IirDesignError::CutoffOutOfRange(0.6))
+        );
+        assert_eq!(
+            chebyshev1::<f64>(2, 0.0, 0.1, IirBand::Lowpass),
+            Err(IirDesignError::InvalidRipple(0.0))
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use num::{Float, FromPrimitive};
+use rustfft::FftNum;
+
+use super::freqz;
+use crate::windows::WindowKind;
+
+fn from_f64<T: FromPrimitive>(x: f64) -> T {
+    T::from_f64(x).expect("Could not convert f64 into type")
+}
+
+fn to_f64<T: Float>(x: T) -> f64 {
+    x.to_f64().expect("Could not convert type into f64")
+}
+
+// Windowed sinc interpolation kernel split into `phases` polyphase tables of `taps_per_phase`
+// taps, for evaluating a signal between its samples. The prototype is the sinc of `cutoff`
+// (1.0 being the input Nyquist frequency) sampled `phases` times per input sample across
+// `taps_per_phase` samples, and each phase is scaled to unity gain at DC. With a cutoff of 1.0
+// the sinc vanishes on every other input sample, so interpolating at whole sample positions
+// returns the samples unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SincKernel<T> {
+    taps_per_phase: usize,
+    phases: usize,
+    cutoff: f64,
+    prototype: Vec<T>,
+    // Phase `p` occupies `taps_per_phase` entries from `p * taps_per_phase`, ordered to line up
+    // with the input samples it weights.
+    tables: Vec<T>,
+}
+
+impl<T> SincKernel<T>
+where
+    T: Float + FromPrimitive,
+{
+    pub fn new(taps_per_phase: usize, phases: usize, cutoff: f64, window: WindowKind) -> Self {
+        assert!(
+            taps_per_phase >= 2 && taps_per_phase.is_multiple_of(2),
+            "Taps per phase must be even and at least 2, got {taps_per_phase}"
+        );
+        assert!(phases > 0, "A kernel needs at least one phase");
+        assert!(
+            cutoff > 0.0 && cutoff <= 1.0,
+            "Cutoff must lie within (0, 1], got {cutoff}"
+        );
+
+        // Prototype tap `m` sits `m / phases - taps_per_phase / 2` input samples from the centre.
+        // The window spans one tap more so it is symmetric about the centre tap; that last tap
+        // would belong to the next sample's phase zero and is dropped.
+        let len = taps_per_phase * phases;
+        let half = (taps_per_phase / 2) as f64;
+        let window = window.generate::<f64>(len + 1);
+        let mut prototype = (0..len)
+            .map(|m| {
+                let u = cutoff * (m as f64 / phases as f64 - half);
+                let sinc = if u == 0.0 {
+                    1.0
+                } else if u.fract() == 0.0 {
+                    0.0
+                } else {
+                    (std::f64::consts::PI * u).sin() / (std::f64::consts::PI * u)
+                };
+                cutoff * sinc * window[m]
+            })
+            .collect::<Vec<_>>();
+
+        for p in 0..phases {
+            let gain = prototype[p..].iter().step_by(phases).sum::<f64>();
+            prototype[p..]
+                .iter_mut()
+                .step_by(phases)
+                .for_each(|t| *t /= gain);
+        }
+
+        // Phase `p` interpolates at `p / phases` past input sample `i`, and its tap `j` weights
+        // sample `i + j + 1 - taps_per_phase / 2`.
+        let mut tables = Vec::with_capacity(len);
+        for p in 0..phases {
+            tables.extend(
+                (0..taps_per_phase).map(|j| prototype[(taps_per_phase - 1 - j) * phases + p]),
+            );
+        }
+
+        Self {
+            taps_per_phase,
+            phases,
+            cutoff,
+            prototype: prototype.into_iter().map(from_f64).collect(),
+            tables: tables.into_iter().map(from_f64).collect(),
+        }
+    }
+
+    pub fn taps_per_phase(&self) -> usize {
+        self.taps_per_phase
+    }
+
+    pub fn phases(&self) -> usize {
+        self.phases
+    }
+
+    pub fn cutoff(&self) -> f64 {
+        self.cutoff
+    }
+
+    // Taps interpolating `phase / phases` of a sample past input sample `i`, to be applied to
+    // the `taps_per_phase` samples from `i + 1 - taps_per_phase / 2`.
+    pub fn phase_taps(&self, phase: usize) -> &[T] {
+        assert!(
+            phase < self.phases,
+            "Phase {phase} out of range for {} phases",
+            self.phases
+        );
+        &self.tables[phase * self.taps_per_phase..(phase + 1) * self.taps_per_phase]
+    }
+
+    // The prototype lowpass at `phases` times the input rate, `taps_per_phase * phases` taps
+    // long. Its DC gain is `phases`, making it an interpolation filter for a zero-stuffed input.
+    pub fn impulse_response(&self) -> Vec<T> {
+        self.prototype.clone()
+    }
+
+    // Weighted sum of the input samples around `first` with the taps of `phase`, treating
+    // samples off either end as zero.
+    fn apply_phase(&self, samples: &[T], first: i64, phase: usize) -> T {
+        self.phase_taps(phase)
+            .iter()
+            .enumerate()
+            .fold(T::zero(), |acc, (j, tap)| {
+                let idx = first + j as i64;
+                if idx < 0 || idx >= samples.len() as i64 {
+                    acc
+                } else {
+                    acc + samples[idx as usize] * *tap
+                }
+            })
+    }
+
+    // Value of `samples` at fractional sample `position`, with samples off either end taken as
+    // zero. Positions between two phases blend their outputs linearly.
+    pub fn interpolate_at(&self, samples: &[T], position: T) -> T {
+        let index = position.floor();
+        let scaled = to_f64(position - index) * self.phases as f64;
+        let phase = (scaled.floor() as usize).min(self.phases - 1);
+        let blend = scaled - phase as f64;
+
+        let first = to_f64(index) as i64 + 1 - (self.taps_per_phase / 2) as i64;
+        let here = self.apply_phase(samples, first, phase);
+        if blend == 0.0 {
+            return here;
+        }
+        let next = if phase + 1 < self.phases {
+            self.apply_phase(samples, first, phase + 1)
+        } else {
+            self.apply_phase(samples, first + 1, 0)
+        };
+        here + (next - here) * from_f64(blend)
+    }
+}
+
+impl<T> SincKernel<T>
+where
+    T: FftNum + Float + FromPrimitive,
+{
+    // Magnitude response of the prototype in dB relative to its DC gain, at the `freqz` grid of
+    // `n_points` frequencies over [0, 1), 1.0 being the Nyquist frequency of the interpolated
+    // rate, `phases` times the input's.
+    pub fn frequency_response(&self, n_points: usize) -> Vec<T> {
+        let (_, response) = freqz(&self.prototype, &[T::one()], n_points);
+        let twenty = from_f64::<T>(20.0);
+        let dc = from_f64::<T>(self.phases as f64);
+        response
+            .iter()
+            .map(|h| twenty * (h.norm() / dc).log10())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::kaiser_beta;
+
+    #[test]
+    fn test_prototype_dc_gain() {
+        for (taps, phases, cutoff) in [(8, 1, 1.0), (16, 4, 0.8), (32, 7, 1.0)] {
+            let kernel = SincKernel::<f64>::new(taps, phases, cutoff, WindowKind::Kaiser(6.0));
+            let prototype = kernel.impulse_response();
+            assert_eq!(prototype.len(), taps * phases);
+            assert!((prototype.iter().sum::<f64>() - phases as f64).abs() < 1e-12);
+            for p in 0..phases {
+                assert_eq!(kernel.phase_taps(p).len(), taps);
+                assert!((kernel.phase_taps(p).iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stopband_attenuation() {
+        let (taps, phases, cutoff, attenuation) = (32, 16, 0.9, 80.0);
+        let kernel = SincKernel::<f64>::new(
+            taps,
+            phases,
+            cutoff,
+            WindowKind::Kaiser(kaiser_beta(attenuation)),
+        );
+
+        // The Kaiser transition band for this length, about the cutoff at the interpolated rate.
+        let len = (taps * phases) as f64;
+        let width = (attenuation - 7.95) / 2.285 / (std::f64::consts::PI * (len - 1.0));
+        let stopband = cutoff / phases as f64 + width / 2.0;
+
+        let n_points = 4096;
+        let response = kernel.frequency_response(n_points);
+        assert!(response[0].abs() < 1e-9);
+        for (i, level) in response.iter().enumerate() {
+            let f = i as f64 / n_points as f64;
+            if f > stopband {
+                assert!(*level < -attenuation + 2.0, "{f}: {level} dB");
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolation_condition() {
+        let samples = (0..50)
+            .map(|n| (0.37 * n as f64).sin() + 0.01 * n as f64)
+            .collect::<Vec<_>>();
+        for window in [WindowKind::Kaiser(8.0), WindowKind::Hann] {
+            let kernel = SincKernel::new(16, 8, 1.0, window);
+            for (n, x) in samples.iter().enumerate() {
+                assert_eq!(kernel.interpolate_at(&samples, n as f64), *x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolates_band_limited_tone() {
+        let f = 0.05;
+        let samples = (0..200)
+            .map(|n| (2.0 * std::f64::consts::PI * f * n as f64).cos())
+            .collect::<Vec<_>>();
+        let kernel = SincKernel::new(32, 64, 1.0, WindowKind::Kaiser(kaiser_beta(90.0)));
+        for position in [60.3, 99.5, 120.0 + 1.0 / 128.0, 140.98] {
+            let expected = (2.0 * std::f64::consts::PI * f * position).cos();
+            let value = kernel.interpolate_at(&samples, position);
+            assert!((value - expected).abs() < 1e-4, "{position}: {value}");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_odd_taps_per_phase() {
+        SincKernel::<f64>::new(7, 4, 1.0, WindowKind::Hann);
+    }
+}
@@ -0,0 +1,38 @@
+mod decimate;
+pub use decimate::{DecimateConfig, YttriaVectorDecimate};
+
+mod equalize;
+pub use equalize::{
+    design_inverse_fir, estimate_response, EqualizerError, EstimateConfig, InversePhase,
+};
+
+mod fir;
+pub use fir::{firwin, firwin_hilbert, firwin_kaiser, kaiser_beta, kaiserord, rrc};
+
+mod iir;
+pub use iir::{butterworth, chebyshev1, Biquad, BiquadCascade, IirBand, IirDesignError};
+
+mod kernel;
+pub use kernel::SincKernel;
+
+mod mask;
+pub use mask::{spectral_mask, spectral_mask_real, SpectralMaskError, YttriaVectorSpectralMask};
+
+mod remez;
+pub use remez::{remez, RemezError};
+
+mod resample;
+pub(crate) use resample::gcd;
+pub use resample::{PlanConfig, PlanError, ResamplePlan, ResampleStage, YttriaVectorResample};
+
+mod response;
+pub use response::{freqz, group_delay};
+
+mod sample;
+pub use sample::FilterSample;
+
+mod sparse;
+pub use sparse::{SparseFir, YttriaVectorSparse};
+
+mod streaming;
+pub(crate) use streaming::{DelayLine, StreamingFir};
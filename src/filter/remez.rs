@@ -0,0 +1,400 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+const GRID_DENSITY: usize = 16;
+const MAX_ITERATIONS: usize = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemezError {
+    // The extremal set was still moving after this many exchanges, typically for very long
+    // filters or for transitions so wide that much of the band is left unconstrained.
+    NoConvergence { iterations: usize },
+}
+
+impl fmt::Display for RemezError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoConvergence { iterations } => {
+                write!(
+                    f,
+                    "Remez exchange did not converge in {iterations} iterations"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RemezError {}
+
+// Parks-McClellan optimal equiripple design of a linear-phase (type I or II) FIR filter, following
+// Janovetz's implementation of the Remez exchange algorithm used by scipy.
+//
+// `bands` holds pairs of band edges, normalized like `firwin2` so that 1.0 is the Nyquist
+// frequency, e.g. `[0.0, 0.2, 0.3, 1.0]` for a lowpass. `desired` and `weights` give the gain and
+// the relative error weighting of every band.
+pub fn remez(
+    numtaps: usize,
+    bands: &[f64],
+    desired: &[f64],
+    weights: &[f64],
+) -> Result<Vec<f64>, RemezError> {
+    assert!(numtaps > 0, "At least one tap is required");
+    assert!(
+        bands.len().is_multiple_of(2) && !bands.is_empty(),
+        "Bands must be given as pairs of edges"
+    );
+    let numband = bands.len() / 2;
+    assert!(
+        desired.len() == numband && weights.len() == numband,
+        "Expected one desired gain and one weight for each of the {numband} bands"
+    );
+    assert!(
+        bands.windows(2).all(|x| x[0] <= x[1]) && bands[0] >= 0.0 && bands[bands.len() - 1] <= 1.0,
+        "Band edges must be monotonically increasing and lie within [0, 1]"
+    );
+
+    let r = numtaps / 2 + numtaps % 2;
+    let (grid, mut des, mut wt) = dense_grid(r, bands, desired, weights);
+    assert!(
+        grid.len() > r,
+        "The bands are too narrow for a {numtaps} tap filter"
+    );
+
+    // Even length filters have a fixed zero at Nyquist, which is factored out of the
+    // approximation problem.
+    if numtaps.is_multiple_of(2) {
+        for ((freq, des), wt) in grid.iter().zip(des.iter_mut()).zip(wt.iter_mut()) {
+            let c = (PI * freq).cos();
+            *des /= c;
+            *wt *= c;
+        }
+    }
+
+    let mut ext = (0..=r)
+        .map(|i| i * (grid.len() - 1) / r)
+        .collect::<Vec<_>>();
+    let mut err = vec![0.0; grid.len()];
+
+    let mut converged = false;
+    for _ in 0..MAX_ITERATIONS {
+        let parms = Parms::new(r, &ext, &grid, &des, &wt);
+        for (i, err) in err.iter_mut().enumerate() {
+            *err = wt[i] * (des[i] - parms.compute_a(grid[i]));
+        }
+        search(r, &mut ext, &err);
+
+        if is_done(&ext, &err) {
+            converged = true;
+            break;
+        }
+    }
+    if !converged {
+        return Err(RemezError::NoConvergence {
+            iterations: MAX_ITERATIONS,
+        });
+    }
+
+    let parms = Parms::new(r, &ext, &grid, &des, &wt);
+    let amplitudes = (0..=(numtaps / 2))
+        .map(|i| {
+            let c = if numtaps % 2 == 1 {
+                1.0
+            } else {
+                (PI * i as f64 / numtaps as f64).cos()
+            };
+            parms.compute_a(i as f64 / numtaps as f64) * c
+        })
+        .collect::<Vec<_>>();
+
+    Ok(frequency_sample(numtaps, &amplitudes))
+}
+
+// Grid frequencies are in cycles per sample, so the band edges are halved.
+fn dense_grid(
+    r: usize,
+    bands: &[f64],
+    desired: &[f64],
+    weights: &[f64],
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let delf = 0.5 / (GRID_DENSITY * r) as f64;
+
+    let mut grid = Vec::new();
+    let mut des = Vec::new();
+    let mut wt = Vec::new();
+
+    for band in 0..(bands.len() / 2) {
+        let mut lowf = bands[2 * band] / 2.0;
+        let highf = bands[2 * band + 1] / 2.0;
+        let k = ((highf - lowf) / delf + 0.5) as usize;
+
+        for _ in 0..k {
+            grid.push(lowf);
+            des.push(desired[band]);
+            wt.push(weights[band]);
+            lowf += delf;
+        }
+        if k > 0 {
+            *grid.last_mut().unwrap() = highf;
+        }
+    }
+
+    (grid, des, wt)
+}
+
+// Barycentric Lagrange interpolation parameters, Oppenheim & Schafer eqs 7.131 - 7.133b.
+struct Parms {
+    ad: Vec<f64>,
+    x: Vec<f64>,
+    y: Vec<f64>,
+}
+
+impl Parms {
+    fn new(r: usize, ext: &[usize], grid: &[f64], des: &[f64], wt: &[f64]) -> Self {
+        let x = ext
+            .iter()
+            .map(|&e| (2.0 * PI * grid[e]).cos())
+            .collect::<Vec<_>>();
+
+        // Products are taken in a strided order to keep the intermediate values well scaled.
+        let ld = (r - 1) / 15 + 1;
+        let ad = (0..=r)
+            .map(|i| {
+                let mut denom = 1.0;
+                for j in 0..ld {
+                    for k in (j..=r).step_by(ld) {
+                        if k != i {
+                            denom *= 2.0 * (x[i] - x[k]);
+                        }
+                    }
+                }
+                if denom.abs() < 0.00001 {
+                    denom = 0.00001;
+                }
+                1.0 / denom
+            })
+            .collect::<Vec<_>>();
+
+        let mut numer = 0.0;
+        let mut denom = 0.0;
+        let mut sign = 1.0;
+        for i in 0..=r {
+            numer += ad[i] * des[ext[i]];
+            denom += sign * ad[i] / wt[ext[i]];
+            sign = -sign;
+        }
+        let delta = numer / denom;
+
+        let mut sign = 1.0;
+        let y = (0..=r)
+            .map(|i| {
+                let y = des[ext[i]] - sign * delta / wt[ext[i]];
+                sign = -sign;
+                y
+            })
+            .collect();
+
+        Self { ad, x, y }
+    }
+
+    fn compute_a(&self, freq: f64) -> f64 {
+        let xc = (2.0 * PI * freq).cos();
+        let mut numer = 0.0;
+        let mut denom = 0.0;
+
+        for ((ad, x), y) in self.ad.iter().zip(&self.x).zip(&self.y) {
+            let c = xc - x;
+            if c.abs() < 1.0e-7 {
+                return *y;
+            }
+            let c = ad / c;
+            denom += c;
+            numer += c * y;
+        }
+
+        numer / denom
+    }
+}
+
+fn search(r: usize, ext: &mut [usize], err: &[f64]) {
+    let len = err.len();
+    let mut found = Vec::with_capacity(2 * r);
+
+    if (err[0] > 0.0 && err[0] > err[1]) || (err[0] < 0.0 && err[0] < err[1]) {
+        found.push(0);
+    }
+
+    for i in 1..(len - 1) {
+        if (err[i] >= err[i - 1] && err[i] > err[i + 1] && err[i] > 0.0)
+            || (err[i] <= err[i - 1] && err[i] < err[i + 1] && err[i] < 0.0)
+        {
+            found.push(i);
+        }
+    }
+
+    let j = len - 1;
+    if (err[j] > 0.0 && err[j] > err[j - 1]) || (err[j] < 0.0 && err[j] < err[j - 1]) {
+        found.push(j);
+    }
+
+    // Remove extra extremals, preferring to drop the smaller of any non-alternating pair.
+    let mut extra = found.len() as isize - (r as isize + 1);
+    while extra > 0 {
+        let mut up = err[found[0]] > 0.0;
+        let mut l = 0;
+        let mut alternating = true;
+
+        for j in 1..found.len() {
+            if err[found[j]].abs() < err[found[l]].abs() {
+                l = j;
+            }
+            if up && err[found[j]] < 0.0 {
+                up = false;
+            } else if !up && err[found[j]] > 0.0 {
+                up = true;
+            } else {
+                alternating = false;
+                l = if err[found[j]].abs() > err[found[j - 1]].abs() {
+                    j - 1
+                } else {
+                    j
+                };
+                break;
+            }
+        }
+
+        if alternating && extra == 1 {
+            l = if err[found[found.len() - 1]].abs() < err[found[0]].abs() {
+                found.len() - 1
+            } else {
+                0
+            };
+        }
+
+        found.remove(l);
+        extra -= 1;
+    }
+
+    let count = found.len().min(r + 1);
+    ext[..count].copy_from_slice(&found[..count]);
+}
+
+fn is_done(ext: &[usize], err: &[f64]) -> bool {
+    let mut min = err[ext[0]].abs();
+    let mut max = min;
+    for e in &ext[1..] {
+        let current = err[*e].abs();
+        min = min.min(current);
+        max = max.max(current);
+    }
+    (max - min) / max < 0.0001
+}
+
+fn frequency_sample(numtaps: usize, amplitudes: &[f64]) -> Vec<f64> {
+    let m = (numtaps as f64 - 1.0) / 2.0;
+    let upper = if numtaps % 2 == 1 {
+        m as usize
+    } else {
+        numtaps / 2 - 1
+    };
+
+    (0..numtaps)
+        .map(|n| {
+            let x = 2.0 * PI * (n as f64 - m) / numtaps as f64;
+            let mut val = amplitudes[0];
+            for (k, amplitude) in amplitudes.iter().enumerate().take(upper + 1).skip(1) {
+                val += 2.0 * amplitude * (x * k as f64).cos();
+            }
+            val / numtaps as f64
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::YttriaVectorComplexFft;
+    use num::Complex;
+
+    fn magnitude_response(taps: &[f64], len: usize) -> Vec<f64> {
+        let mut padded = vec![Complex::new(0.0, 0.0); len];
+        for (p, t) in padded.iter_mut().zip(taps) {
+            p.re = *t;
+        }
+        padded
+            .fft()
+            .iter()
+            .take(len / 2 + 1)
+            .map(|x| x.norm() * len as f64)
+            .collect()
+    }
+
+    fn design_and_check(numtaps: usize) {
+        let taps = remez(numtaps, &[0.0, 0.2, 0.3, 1.0], &[1.0, 0.0], &[1.0, 1.0]).unwrap();
+        assert_eq!(taps.len(), numtaps);
+        for (a, b) in taps.iter().zip(taps.iter().rev()) {
+            assert!((a - b).abs() < 1e-12);
+        }
+
+        let len = 8192;
+        let response = magnitude_response(&taps, len);
+        let freq = |k: usize| 2.0 * k as f64 / len as f64;
+
+        let passband = (0..response.len())
+            .filter(|&k| freq(k) <= 0.2)
+            .map(|k| (response[k] - 1.0).abs())
+            .collect::<Vec<_>>();
+        let stopband = (0..response.len())
+            .filter(|&k| freq(k) >= 0.3)
+            .map(|k| response[k])
+            .collect::<Vec<_>>();
+
+        let pass_ripple = passband.iter().cloned().fold(0.0, f64::max);
+        let stop_ripple = stopband.iter().cloned().fold(0.0, f64::max);
+
+        // Equal weights give equal ripple in both bands.
+        assert!(
+            (pass_ripple - stop_ripple).abs() / stop_ripple < 0.05,
+            "{numtaps} taps: passband {pass_ripple}, stopband {stop_ripple}"
+        );
+
+        // Every stopband lobe peaks at (roughly) the same level.
+        let lobes = (1..(stopband.len() - 1))
+            .filter(|&k| stopband[k] > stopband[k - 1] && stopband[k] > stopband[k + 1])
+            .map(|k| stopband[k])
+            .collect::<Vec<_>>();
+        assert!(lobes.len() > 5, "{numtaps} taps: {} lobes", lobes.len());
+        for lobe in lobes {
+            assert!(
+                lobe > 0.9 * stop_ripple,
+                "{numtaps} taps: {lobe} vs {stop_ripple}"
+            );
+        }
+
+        assert!(
+            20.0 * stop_ripple.log10() < -40.0,
+            "{numtaps} taps: stopband {stop_ripple}"
+        );
+    }
+
+    #[test]
+    fn test_remez_lowpass_odd() {
+        design_and_check(73);
+    }
+
+    #[test]
+    fn test_remez_lowpass_even() {
+        design_and_check(72);
+    }
+
+    #[test]
+    fn test_remez_no_convergence() {
+        // A transition most of the band wide leaves the error unconstrained over it, and the
+        // extremal set keeps moving.
+        assert_eq!(
+            remez(65, &[0.0, 0.2, 0.9, 1.0], &[1.0, 0.0], &[1.0, 1.0]),
+            Err(RemezError::NoConvergence {
+                iterations: MAX_ITERATIONS
+            })
+        );
+    }
+}
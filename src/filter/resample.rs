@@ -0,0 +1,643 @@
+use super::{firwin, firwin_kaiser, FilterSample, SincKernel};
+use crate::config::parallel_min_len;
+use crate::trace::{Trace, TraceSample};
+use crate::windows;
+use num::FromPrimitive;
+use rayon::prelude::*;
+use std::fmt;
+
+pub(crate) fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// Polyphase rational resampling: `out[k]` is the filter output at upsampled sample
+// `k * down + delay`, where only every `up`th tap lands on a nonzero input.
+fn resample_stage<S: FilterSample>(input: &[S], taps: &[f64], up: usize, down: usize) -> Vec<S> {
+    let taps = taps
+        .iter()
+        .map(|&t| S::Real::from_f64(t * up as f64).expect("Could not convert f64 into type"))
+        .collect::<Vec<_>>();
+    let delay = (taps.len() - 1) / 2;
+
+    let mut out = vec![S::zero(); (input.len() * up).div_ceil(down)];
+    out.par_iter_mut()
+        .with_min_len(parallel_min_len())
+        .enumerate()
+        .for_each(|(k, out)| {
+            let n = k * down + delay;
+            let mut acc = S::zero();
+            for j in ((n % up)..taps.len()).step_by(up) {
+                let Some(idx) = n.checked_sub(j).map(|m| m / up) else {
+                    break;
+                };
+                if idx < input.len() {
+                    acc = acc + input[idx] * taps[j];
+                }
+            }
+            *out = acc;
+        });
+    out
+}
+
+pub trait YttriaVectorResample<S: FilterSample> {
+    // Resamples by `up / down` with a Kaiser windowed lowpass at the lower of the two Nyquist
+    // frequencies, spanning ten input or output samples either side, whichever is longer. The
+    // output has `ceil(len * up / down)` samples and is aligned with the input.
+    fn resample_poly_output_len(&self, up: usize, down: usize) -> usize;
+    fn resample_poly(&self, up: usize, down: usize) -> Vec<S>;
+
+    // As `resample_poly`, with `taps` designed at the upsampled rate. An odd tap count keeps the
+    // output aligned with the input.
+    fn resample_poly_with_taps(&self, up: usize, down: usize, taps: &[f64]) -> Vec<S>;
+
+    // As `resample_poly`, with the phase tables of a prebuilt kernel, which must have `up`
+    // phases. Output `k` is `kernel.interpolate_at` the input position `k * down / up`, so the
+    // kernel inspected is exactly the one applied.
+    fn resample_poly_with_kernel(&self, up: usize, down: usize, kernel: &SincKernel<f64>)
+        -> Vec<S>;
+}
+
+impl<S: FilterSample> YttriaVectorResample<S> for [S] {
+    fn resample_poly_output_len(&self, up: usize, down: usize) -> usize {
+        assert!(up > 0 && down > 0, "Resampling factors must be at least 1");
+        (self.len() * up).div_ceil(down)
+    }
+
+    fn resample_poly(&self, up: usize, down: usize) -> Vec<S> {
+        assert!(up > 0 && down > 0, "Resampling factors must be at least 1");
+        let g = gcd(up as u64, down as u64) as usize;
+        let (up, down) = (up / g, down / g);
+        if up == 1 && down == 1 {
+            return self.to_vec();
+        }
+
+        let numtaps = 20 * up.max(down) + 1;
+        let taps = firwin(
+            numtaps,
+            1.0 / up.max(down) as f64,
+            &windows::kaiser(numtaps, 5.0),
+        );
+        self.resample_poly_with_taps(up, down, &taps)
+    }
+
+    fn resample_poly_with_taps(&self, up: usize, down: usize, taps: &[f64]) -> Vec<S> {
+        assert!(up > 0 && down > 0, "Resampling factors must be at least 1");
+        assert!(!taps.is_empty(), "Resampling taps must not be empty");
+        resample_stage(self, taps, up, down)
+    }
+
+    fn resample_poly_with_kernel(
+        &self,
+        up: usize,
+        down: usize,
+        kernel: &SincKernel<f64>,
+    ) -> Vec<S> {
+        assert!(up > 0 && down > 0, "Resampling factors must be at least 1");
+        assert!(
+            kernel.phases() == up,
+            "Kernel has {} phases but resampling up by {up}",
+            kernel.phases()
+        );
+
+        let tables = (0..up)
+            .map(|p| {
+                kernel
+                    .phase_taps(p)
+                    .iter()
+                    .map(|t| S::Real::from_f64(*t).expect("Could not convert f64 into type"))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let lead = kernel.taps_per_phase() / 2 - 1;
+
+        let mut out = vec![S::zero(); self.resample_poly_output_len(up, down)];
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .for_each(|(k, out)| {
+                let (i, p) = ((k * down) / up, (k * down) % up);
+                let mut acc = S::zero();
+                for (j, tap) in tables[p].iter().enumerate() {
+                    if let Some(x) = (i + j).checked_sub(lead).and_then(|idx| self.get(idx)) {
+                        acc = acc + *x * *tap;
+                    }
+                }
+                *out = acc;
+            });
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanError {
+    InvalidRate(f64),
+    // No ratio with factors up to the configured maximum is within the allowed error.
+    NoApproximation { max_factor: u64, max_error_ppm: f64 },
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRate(rate) => write!(f, "sample rate {rate} is not positive and finite"),
+            Self::NoApproximation {
+                max_factor,
+                max_error_ppm,
+            } => write!(
+                f,
+                "no ratio with factors up to {max_factor} is within {max_error_ppm} ppm"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+pub struct PlanConfig {
+    attenuation: f64,
+    passband: f64,
+    max_factor: u64,
+    max_stage_factor: u64,
+    max_error_ppm: f64,
+}
+
+impl Default for PlanConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlanConfig {
+    // By default the plan keeps 80% of the narrower band with 60 dB of alias and image
+    // rejection, only accepts exact ratios with factors up to 4096, and splits any factor above
+    // 16 across stages.
+    pub fn new() -> Self {
+        Self {
+            attenuation: 60.0,
+            passband: 0.8,
+            max_factor: 4096,
+            max_stage_factor: 16,
+            max_error_ppm: 0.0,
+        }
+    }
+
+    pub fn with_attenuation(mut self, attenuation: f64) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    // Passband edge as a fraction of the lower of the input and output Nyquist frequencies.
+    pub fn with_passband(mut self, passband: f64) -> Self {
+        assert!(
+            passband > 0.0 && passband < 1.0,
+            "Passband must lie strictly between 0 and the Nyquist frequency"
+        );
+        self.passband = passband;
+        self
+    }
+
+    // Largest overall up or down factor the plan may use.
+    pub fn with_max_factor(mut self, max_factor: u64) -> Self {
+        assert!(max_factor > 0, "Maximum factor must be at least 1");
+        self.max_factor = max_factor;
+        self
+    }
+
+    // Factors above this are split into cascaded stages where their prime factors allow.
+    pub fn with_max_stage_factor(mut self, max_stage_factor: u64) -> Self {
+        assert!(
+            max_stage_factor > 1,
+            "Maximum stage factor must be at least 2"
+        );
+        self.max_stage_factor = max_stage_factor;
+        self
+    }
+
+    // Allows the achieved output rate to differ from the requested one by up to this many parts
+    // per million, so awkward or irrational ratios can be approximated by small factors.
+    pub fn with_max_error_ppm(mut self, max_error_ppm: f64) -> Self {
+        assert!(max_error_ppm >= 0.0, "Maximum error must not be negative");
+        self.max_error_ppm = max_error_ppm;
+        self
+    }
+}
+
+// One rational stage of a plan. Cutoff and transition width are relative to the Nyquist
+// frequency of the upsampled rate the filter runs at. A stage built from a prebuilt kernel
+// applies that kernel's phase tables, with `taps` holding its prototype, and was designed to no
+// transition width or attenuation, which are NaN.
+#[derive(Debug, Clone)]
+pub struct ResampleStage {
+    pub up: usize,
+    pub down: usize,
+    pub cutoff: f64,
+    pub transition: f64,
+    pub attenuation: f64,
+    pub taps: Vec<f64>,
+    pub kernel: Option<SincKernel<f64>>,
+}
+
+impl ResampleStage {
+    fn run<S: FilterSample>(&self, signal: &[S]) -> Vec<S> {
+        match &self.kernel {
+            Some(kernel) => signal.resample_poly_with_kernel(self.up, self.down, kernel),
+            None => resample_stage(signal, &self.taps, self.up, self.down),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResamplePlan {
+    input_hz: f64,
+    output_hz: f64,
+    up: u64,
+    down: u64,
+    stages: Vec<ResampleStage>,
+}
+
+impl ResamplePlan {
+    pub fn from_rates(
+        input_hz: f64,
+        output_hz: f64,
+        config: PlanConfig,
+    ) -> Result<ResamplePlan, PlanError> {
+        for rate in [input_hz, output_hz] {
+            if !(rate.is_finite() && rate > 0.0) {
+                return Err(PlanError::InvalidRate(rate));
+            }
+        }
+
+        let (up, down) = exact_ratio(input_hz, output_hz)
+            .filter(|&(up, down)| up.max(down) <= config.max_factor)
+            .or_else(|| approximate_ratio(output_hz / input_hz, &config))
+            .ok_or(PlanError::NoApproximation {
+                max_factor: config.max_factor,
+                max_error_ppm: config.max_error_ppm,
+            })?;
+
+        // Ups are taken largest first and downs smallest first, so the per stage ratio never
+        // grows and no intermediate rate dips below the lower of the input and output rates.
+        let mut ups = stage_factors(up, config.max_stage_factor);
+        ups.sort_unstable_by(|a, b| b.cmp(a));
+        let mut downs = stage_factors(down, config.max_stage_factor);
+        downs.sort_unstable();
+
+        // Every stage passes the final band and only has to reject whatever would alias or
+        // image onto it, so stages running well above the final rates get wide transitions.
+        let band = input_hz.min(output_hz) / 2.0;
+        let mut rate = input_hz;
+        let stages = (0..ups.len().max(downs.len()))
+            .map(|i| {
+                let up = ups.get(i).copied().unwrap_or(1);
+                let down = downs.get(i).copied().unwrap_or(1);
+                let next = rate * up as f64 / down as f64;
+                let nyquist = rate * up as f64 / 2.0;
+                let passband = config.passband * band / nyquist;
+                let stopband = (rate.min(next) - band) / nyquist;
+                rate = next;
+                ResampleStage {
+                    up: up as usize,
+                    down: down as usize,
+                    cutoff: (passband + stopband) / 2.0,
+                    transition: stopband - passband,
+                    attenuation: config.attenuation,
+                    taps: firwin_kaiser(passband, stopband, config.attenuation),
+                    kernel: None,
+                }
+            })
+            .collect();
+
+        Ok(ResamplePlan {
+            input_hz,
+            output_hz,
+            up,
+            down,
+            stages,
+        })
+    }
+
+    // A single stage plan resampling by `kernel.phases() / down` through `kernel`, applied as
+    // `resample_poly_with_kernel` does, so the plan runs exactly the kernel that was inspected.
+    pub fn from_kernel(
+        input_hz: f64,
+        down: usize,
+        kernel: SincKernel<f64>,
+    ) -> Result<ResamplePlan, PlanError> {
+        if !(input_hz.is_finite() && input_hz > 0.0) {
+            return Err(PlanError::InvalidRate(input_hz));
+        }
+        assert!(down > 0, "Resampling factors must be at least 1");
+
+        let up = kernel.phases();
+        let stage = ResampleStage {
+            up,
+            down,
+            cutoff: kernel.cutoff() / up as f64,
+            transition: f64::NAN,
+            attenuation: f64::NAN,
+            taps: kernel.impulse_response(),
+            kernel: Some(kernel),
+        };
+        Ok(ResamplePlan {
+            input_hz,
+            output_hz: input_hz * up as f64 / down as f64,
+            up: up as u64,
+            down: down as u64,
+            stages: vec![stage],
+        })
+    }
+
+    pub fn up(&self) -> u64 {
+        self.up
+    }
+
+    pub fn down(&self) -> u64 {
+        self.down
+    }
+
+    pub fn stages(&self) -> &[ResampleStage] {
+        &self.stages
+    }
+
+    // Output rate the chosen ratio actually produces.
+    pub fn achieved_output_hz(&self) -> f64 {
+        self.input_hz * self.up as f64 / self.down as f64
+    }
+
+    // Relative error of the achieved output rate against the requested one, in parts per
+    // million.
+    pub fn error_ppm(&self) -> f64 {
+        (self.achieved_output_hz() - self.output_hz) / self.output_hz * 1e6
+    }
+
+    pub fn is_passthrough(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn execute<S: FilterSample>(&self, signal: &[S]) -> Vec<S> {
+        let mut out = signal.to_vec();
+        for stage in &self.stages {
+            out = stage.run(&out);
+        }
+        out
+    }
+
+    // As `execute`, recording the output of every stage in `trace`.
+    pub fn execute_with_trace<S: FilterSample + TraceSample>(
+        &self,
+        signal: &[S],
+        trace: &mut Trace,
+    ) -> Vec<S> {
+        let mut out = signal.to_vec();
+        for (i, stage) in self.stages.iter().enumerate() {
+            let name = format!("resample {i} ({}/{})", stage.up, stage.down);
+            out = trace.stage(&name, &out, |x| stage.run(x));
+        }
+        out
+    }
+}
+
+// Reduced `up / down` when both rates are whole numbers of hertz.
+fn exact_ratio(input_hz: f64, output_hz: f64) -> Option<(u64, u64)> {
+    let limit = (1u64 << 53) as f64;
+    if input_hz.fract() != 0.0 || output_hz.fract() != 0.0 || input_hz.max(output_hz) > limit {
+        return None;
+    }
+
+    let (input, output) = (input_hz as u64, output_hz as u64);
+    let g = gcd(input, output);
+    Some((output / g, input / g))
+}
+
+// First continued fraction convergent of `ratio` within the allowed error.
+fn approximate_ratio(ratio: f64, config: &PlanConfig) -> Option<(u64, u64)> {
+    if config.max_error_ppm == 0.0 {
+        return None;
+    }
+
+    let (mut h, mut h_prev) = (1u64, 0u64);
+    let (mut k, mut k_prev) = (0u64, 1u64);
+    let mut x = ratio;
+    for _ in 0..64 {
+        let a = x.floor();
+        if a > config.max_factor as f64 {
+            return None;
+        }
+        let a = a as u64;
+        (h, h_prev) = (a.checked_mul(h)?.checked_add(h_prev)?, h);
+        (k, k_prev) = (a.checked_mul(k)?.checked_add(k_prev)?, k);
+        if h.max(k) > config.max_factor {
+            return None;
+        }
+
+        let error = (h as f64 / k as f64 - ratio).abs() / ratio * 1e6;
+        if h > 0 && error <= config.max_error_ppm {
+            return Some((h, k));
+        }
+
+        let fract = x - a as f64;
+        if fract == 0.0 {
+            return None;
+        }
+        x = 1.0 / fract;
+    }
+    None
+}
+
+// Groups the prime factors of `n`, largest first, into products no larger than `max` where
+// possible. Primes above `max` get a stage of their own.
+fn stage_factors(mut n: u64, max: u64) -> Vec<u64> {
+    let mut primes = Vec::new();
+    let mut p = 2;
+    while p * p <= n {
+        while n.is_multiple_of(p) {
+            primes.push(p);
+            n /= p;
+        }
+        p += 1;
+    }
+    if n > 1 {
+        primes.push(n);
+    }
+
+    let mut out = Vec::new();
+    let mut chunk = 1;
+    for p in primes.into_iter().rev() {
+        if chunk > 1 && chunk * p > max {
+            out.push(chunk);
+            chunk = 1;
+        }
+        chunk *= p;
+    }
+    if chunk > 1 {
+        out.push(chunk);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn tone(len: usize, freq: f64) -> Vec<f64> {
+        (0..len)
+            .map(|i| (2.0 * PI * freq * i as f64).cos())
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_reduces_decimation() {
+        let plan = ResamplePlan::from_rates(2.4e6, 48e3, PlanConfig::new()).unwrap();
+        assert_eq!((plan.up(), plan.down()), (1, 50));
+        assert_eq!(plan.error_ppm(), 0.0);
+
+        let factors = plan
+            .stages()
+            .iter()
+            .map(|s| (s.up, s.down))
+            .collect::<Vec<_>>();
+        assert_eq!(factors, [(1, 5), (1, 10)]);
+        for stage in plan.stages() {
+            assert_eq!(stage.taps.len() % 2, 1);
+            assert!(stage.cutoff > 0.0 && stage.cutoff < 1.0);
+        }
+
+        let single =
+            ResamplePlan::from_rates(2.4e6, 48e3, PlanConfig::new().with_max_stage_factor(64))
+                .unwrap();
+        assert_eq!(single.stages().len(), 1);
+    }
+
+    #[test]
+    fn test_plan_audio_rates() {
+        let plan = ResamplePlan::from_rates(44100.0, 48000.0, PlanConfig::new()).unwrap();
+        assert_eq!((plan.up(), plan.down()), (160, 147));
+
+        let up = plan.stages().iter().map(|s| s.up).product::<usize>();
+        let down = plan.stages().iter().map(|s| s.down).product::<usize>();
+        assert_eq!((up, down), (160, 147));
+    }
+
+    #[test]
+    fn test_plan_approximates_irrational_ratio() {
+        let output = 1e6 * std::f64::consts::SQRT_2;
+        assert!(matches!(
+            ResamplePlan::from_rates(1e6, output, PlanConfig::new()),
+            Err(PlanError::NoApproximation { .. })
+        ));
+
+        let plan = ResamplePlan::from_rates(1e6, output, PlanConfig::new().with_max_error_ppm(1.0))
+            .unwrap();
+        assert_eq!((plan.up(), plan.down()), (1393, 985));
+        assert!(plan.error_ppm() != 0.0 && plan.error_ppm().abs() <= 1.0);
+    }
+
+    #[test]
+    fn test_execute_with_trace() {
+        let plan = ResamplePlan::from_rates(2.4e6, 48e3, PlanConfig::new()).unwrap();
+        let input = tone(5000, 0.001);
+        let mut trace = Trace::new(64);
+        let out = plan.execute_with_trace(&input, &mut trace);
+        assert_eq!(out, plan.execute(&input));
+
+        let lens = trace
+            .records()
+            .iter()
+            .map(|r| (r.input_len, r.output_len))
+            .collect::<Vec<_>>();
+        assert_eq!(lens, [(5000, 1000), (1000, 100)]);
+    }
+
+    #[test]
+    fn test_plan_passthrough() {
+        let plan = ResamplePlan::from_rates(48e3, 48e3, PlanConfig::new()).unwrap();
+        assert!(plan.is_passthrough());
+
+        let x = tone(100, 0.01);
+        assert_eq!(plan.execute(&x), x);
+
+        assert_eq!(
+            ResamplePlan::from_rates(0.0, 48e3, PlanConfig::new()).unwrap_err(),
+            PlanError::InvalidRate(0.0)
+        );
+    }
+
+    #[test]
+    fn test_execute_matches_resample_poly() {
+        // A tone well inside the passband comes out of both designs as the same tone at the new
+        // rate, up to the ripple of the two filters.
+        let freq = 0.05;
+        let input = tone(4000, freq);
+        for (input_hz, output_hz, up, down) in [(48e3, 16e3, 1, 3), (16e3, 40e3, 5, 2)] {
+            let plan = ResamplePlan::from_rates(input_hz, output_hz, PlanConfig::new()).unwrap();
+            let planned = plan.execute(&input);
+            let direct = input.resample_poly(up, down);
+            assert_eq!(planned.len(), (input.len() * up).div_ceil(down));
+            assert_eq!(planned.len(), direct.len());
+
+            let expected = tone(planned.len(), freq * down as f64 / up as f64);
+            for k in 100..(planned.len() - 100) {
+                assert!((planned[k] - expected[k]).abs() < 2e-3);
+                assert!((direct[k] - expected[k]).abs() < 2e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_kernel_matches_taps() {
+        // Hann tapers to zero at the kernel's edges, so the odd length prototype the taps path
+        // needs only adds a zero tap.
+        let input = tone(500, 0.07);
+        for (up, down) in [(3, 2), (4, 1), (2, 5)] {
+            let cutoff = (up as f64 / down as f64).min(1.0);
+            let kernel = SincKernel::new(16, up, cutoff, windows::WindowKind::Hann);
+            let mut taps = kernel.impulse_response();
+            taps.push(0.0);
+            taps.iter_mut().for_each(|t| *t /= up as f64);
+
+            let with_kernel = input.resample_poly_with_kernel(up, down, &kernel);
+            let with_taps = input.resample_poly_with_taps(up, down, &taps);
+            assert_eq!(with_kernel.len(), with_taps.len());
+            for (k, (a, b)) in with_kernel.iter().zip(&with_taps).enumerate() {
+                assert!((a - b).abs() < 1e-12, "{up}/{down} {k}");
+            }
+
+            for k in [0, 7, 100, with_kernel.len() - 1] {
+                let position = (k * down) as f64 / up as f64;
+                let expected = kernel.interpolate_at(&input, position);
+                assert!((with_kernel[k] - expected).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_plan_from_kernel() {
+        let input = tone(500, 0.07);
+        let kernel = SincKernel::new(16, 3, 1.0, windows::WindowKind::Hann);
+        let plan = ResamplePlan::from_kernel(32e3, 2, kernel.clone()).unwrap();
+        assert_eq!((plan.up(), plan.down()), (3, 2));
+        assert_eq!(plan.achieved_output_hz(), 48e3);
+        assert_eq!(plan.error_ppm(), 0.0);
+        assert_eq!(plan.stages().len(), 1);
+        assert_eq!(plan.stages()[0].kernel.as_ref(), Some(&kernel));
+        assert_eq!(
+            plan.execute(&input),
+            input.resample_poly_with_kernel(3, 2, &kernel)
+        );
+
+        assert_eq!(
+            ResamplePlan::from_kernel(-1.0, 2, kernel).unwrap_err(),
+            PlanError::InvalidRate(-1.0)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_kernel_phase_mismatch() {
+        let kernel = SincKernel::new(8, 3, 1.0, windows::WindowKind::Hann);
+        tone(10, 0.1).resample_poly_with_kernel(2, 1, &kernel);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::vector::YttriaVectorComplexFft;
+use num::{Complex, Float, FromPrimitive, Zero};
+use rustfft::FftNum;
+
+// Samples `coeffs` as a polynomial in z^-1 at `num_points` frequencies spaced evenly over [0, 1),
+// 1.0 being the Nyquist frequency. Coefficients beyond the FFT length wrap around, which keeps
+// the sampled spectrum exact. The `1 / len` scaling of `fft` is undone, so the values are the
+// polynomial's own.
+fn sample_polynomial<T>(coeffs: &[Complex<T>], num_points: usize) -> Vec<Complex<T>>
+where
+    T: FftNum + Float,
+{
+    let len = 2 * num_points;
+    let mut padded = vec![Complex::<T>::zero(); len];
+    for (i, c) in coeffs.iter().enumerate() {
+        padded[i % len] = padded[i % len] + c;
+    }
+    let mut spectrum = padded.fft();
+    spectrum.truncate(num_points);
+    let scale = T::from(len).expect("Could not convert length to type");
+    spectrum.iter_mut().for_each(|x| *x = x.scale(scale));
+    spectrum
+}
+
+fn frequency_grid<T: Float + FromPrimitive>(num_points: usize) -> Vec<T> {
+    (0..num_points)
+        .map(|i| {
+            T::from_f64(i as f64 / num_points as f64).expect("Could not convert f64 into type")
+        })
+        .collect()
+}
+
+fn to_complex<T: Float>(coeffs: &[T]) -> Vec<Complex<T>> {
+    coeffs.iter().map(|c| Complex::new(*c, T::zero())).collect()
+}
+
+// Complex frequency response of the filter `b / a` at `num_points` frequencies spaced evenly over
+// [0, 1), with 1.0 being the Nyquist frequency.
+pub fn freqz<T>(b: &[T], a: &[T], num_points: usize) -> (Vec<T>, Vec<Complex<T>>)
+where
+    T: FftNum + Float + FromPrimitive,
+{
+    assert!(
+        !b.is_empty() && !a.is_empty(),
+        "Filter coefficients must not be empty"
+    );
+    assert!(num_points > 0, "At least one frequency point is required");
+
+    let num = sample_polynomial(&to_complex(b), num_points);
+    let den = sample_polynomial(&to_complex(a), num_points);
+    let response = num.iter().zip(&den).map(|(num, den)| num / den).collect();
+
+    (frequency_grid(num_points), response)
+}
+
+// Group delay in samples of the filter `b / a`, as the negative derivative of its phase
+// response, at `num_points` frequencies spaced evenly over [0, 1) with 1.0 being the Nyquist
+// frequency. Frequencies where the response vanishes report a delay of zero.
+pub fn group_delay<T>(b: &[T], a: &[T], num_points: usize) -> (Vec<T>, Vec<T>)
+where
+    T: FftNum + Float + FromPrimitive,
+{
+    assert!(
+        !b.is_empty() && !a.is_empty(),
+        "Filter coefficients must not be empty"
+    );
+    assert!(num_points > 0, "At least one frequency point is required");
+
+    // c = b * reversed(a), after which the delay is that of c, offset by the order of a.
+    let mut c = vec![Complex::<T>::zero(); b.len() + a.len() - 1];
+    for (i, bi) in b.iter().enumerate() {
+        for (j, aj) in a.iter().rev().enumerate() {
+            c[i + j].re = c[i + j].re + *bi * *aj;
+        }
+    }
+    let cr = c
+        .iter()
+        .enumerate()
+        .map(|(n, c)| c * T::from_usize(n).expect("Could not convert index to type"))
+        .collect::<Vec<_>>();
+
+    let num = sample_polynomial(&cr, num_points);
+    let den = sample_polynomial(&c, num_points);
+    let offset = T::from_usize(a.len() - 1).expect("Could not convert order to type");
+
+    let delay = num
+        .iter()
+        .zip(&den)
+        .map(|(num, den)| {
+            if den.norm()
+                < T::epsilon() * T::from_f64(10.0).expect("Could not convert f64 into type")
+            {
+                T::zero()
+            } else {
+                (num / den).re - offset
+            }
+        })
+        .collect();
+
+    (frequency_grid(num_points), delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::remez;
+
+    #[test]
+    fn test_freqz_moving_average() {
+        let b = [0.2; 5];
+        let (freqs, response) = freqz(&b, &[1.0], 10);
+
+        assert!((response[0].norm() - 1.0).abs() < 1e-12);
+        for (i, (f, h)) in freqs.iter().zip(&response).enumerate() {
+            // Nulls of a length 5 moving average sit at multiples of 2/5 of Nyquist.
+            if i == 4 || i == 8 {
+                assert!((f - 0.4 * (i / 4) as f64).abs() < 1e-12);
+                assert!(h.norm() < 1e-12);
+            } else {
+                assert!(h.norm() > 0.1);
+            }
+        }
+
+        let (_, response) = freqz(&[1.0], &[1.0, -0.5], 4);
+        assert!((response[0] - Complex::new(2.0, 0.0)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_group_delay_linear_phase() {
+        let taps = remez(41, &[0.0, 0.3, 0.4, 1.0], &[1.0, 0.0], &[1.0, 1.0]).unwrap();
+        let (freqs, delay) = group_delay(&taps, &[1.0], 512);
+
+        assert_eq!(freqs.len(), 512);
+        for (f, d) in freqs.iter().zip(&delay) {
+            // Zeros of the stopband response sit right on the unit circle.
+            if *f < 0.35 {
+                assert!((d - 20.0).abs() < 1e-6, "{f}: {d}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_group_delay_small_gain() {
+        // A tiny but nonzero gain is not a null, however many points the response is sampled at.
+        for num_points in [4, 64, 4096] {
+            let (_, delay) = group_delay(&[0.0, 1e-13], &[1.0], num_points);
+            assert!(delay.iter().all(|d| (d - 1.0).abs() < 1e-9), "{num_points}");
+        }
+
+        // Zeros of 1 + z^-2 at half Nyquist do report zero.
+        let (_, delay) = group_delay(&[1.0, 0.0, 1.0], &[1.0], 8);
+        assert_eq!(delay[4], 0.0);
+        assert!((delay[0] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_group_delay_one_pole() {
+        // H(z) = 1 / (1 - p z^-1) has a delay of p / (1 - p) at DC.
+        let p = 0.5;
+        let (_, delay) = group_delay(&[1.0], &[1.0, -p], 64);
+        assert!((delay[0] - p / (1.0 - p)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,44 @@
+use num::{Complex, Float, FromPrimitive, Zero};
+use std::ops::{Add, Mul};
+
+// Sample types that can be run through a real valued FIR filter.
+pub trait FilterSample:
+    Copy + Send + Sync + Zero + Add<Output = Self> + Mul<Self::Real, Output = Self>
+{
+    type Real: Float + FromPrimitive + Send + Sync;
+
+    // Absolute value for real samples, the envelope for complex ones.
+    fn magnitude(&self) -> Self::Real;
+}
+
+impl FilterSample for f32 {
+    type Real = f32;
+
+    fn magnitude(&self) -> f32 {
+        self.abs()
+    }
+}
+
+impl FilterSample for f64 {
+    type Real = f64;
+
+    fn magnitude(&self) -> f64 {
+        self.abs()
+    }
+}
+
+impl FilterSample for Complex<f32> {
+    type Real = f32;
+
+    fn magnitude(&self) -> f32 {
+        self.norm()
+    }
+}
+
+impl FilterSample for Complex<f64> {
+    type Real = f64;
+
+    fn magnitude(&self) -> f64 {
+        self.norm()
+    }
+}
@@ -0,0 +1,252 @@
+use num::{Complex, Float};
+use rayon::prelude::*;
+
+use crate::config::parallel_min_len;
+use crate::vector::convolve_output_len;
+
+// Sorts the taps by delay and sums any that share one.
+fn merge_taps<T: Float>(taps: &[(usize, T)]) -> Vec<(usize, T)> {
+    let mut merged = taps.to_vec();
+    merged.sort_by_key(|(delay, _)| *delay);
+    merged.dedup_by(|next, kept| {
+        if next.0 == kept.0 {
+            kept.1 = kept.1 + next.1;
+            true
+        } else {
+            false
+        }
+    });
+    merged
+}
+
+// FIR filter stored as its nonzero taps, for channel models and comb filters whose impulse
+// response is a handful of echoes across a long span. Each output sample costs one product per
+// tap however far apart they are. The delay line carries the last `max_delay` samples between
+// blocks, so filtering a signal in pieces gives the same output as filtering it whole.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseFir<T> {
+    // Sorted by delay, each delay once.
+    taps: Vec<(usize, T)>,
+    history: Vec<Complex<T>>,
+}
+
+impl<T> SparseFir<T>
+where
+    T: Float + Send + Sync,
+{
+    // Taps as `(delay, coefficient)` pairs in any order. Coefficients sharing a delay are
+    // summed.
+    pub fn new(taps: &[(usize, T)]) -> Self {
+        let taps = merge_taps(taps);
+        let max_delay = taps.last().map_or(0, |(delay, _)| *delay);
+        Self {
+            taps,
+            history: vec![Complex::new(T::zero(), T::zero()); max_delay],
+        }
+    }
+
+    // Keeps the taps of a dense impulse response whose magnitude exceeds `threshold`. How many
+    // survived is `num_taps`.
+    pub fn from_dense(taps: &[T], threshold: T) -> Self {
+        let kept = taps
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.abs() > threshold)
+            .map(|(delay, t)| (delay, *t))
+            .collect::<Vec<_>>();
+        Self::new(&kept)
+    }
+
+    pub fn taps(&self) -> &[(usize, T)] {
+        &self.taps
+    }
+
+    pub fn num_taps(&self) -> usize {
+        self.taps.len()
+    }
+
+    pub fn max_delay(&self) -> usize {
+        self.history.len()
+    }
+
+    // Clears the delay line, as if the filter had only ever seen zeros.
+    pub fn reset(&mut self) {
+        self.history
+            .iter_mut()
+            .for_each(|x| *x = Complex::new(T::zero(), T::zero()));
+    }
+
+    pub fn process_block(&mut self, input: &[Complex<T>]) -> Vec<Complex<T>> {
+        let max_delay = self.history.len();
+        self.history.extend_from_slice(input);
+
+        let (taps, history) = (&self.taps, &self.history);
+        let out = (0..input.len())
+            .into_par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|n| {
+                taps.iter()
+                    .fold(Complex::new(T::zero(), T::zero()), |acc, (delay, c)| {
+                        acc + history[n + max_delay - delay].scale(*c)
+                    })
+            })
+            .collect();
+
+        self.history.drain(..input.len());
+        out
+    }
+}
+
+pub trait YttriaVectorSparse<T> {
+    // Full linear convolution with the sparse taps `(delay, coefficient)`, as long as `convolve`
+    // with the dense vector the taps describe: `max_delay` samples more than `self`, or empty
+    // without taps. Coefficients sharing a delay are summed.
+    fn convolve_sparse(&self, taps: &[(usize, T)]) -> Vec<T>;
+}
+
+impl<T> YttriaVectorSparse<T> for [T]
+where
+    T: Float + Send + Sync,
+{
+    fn convolve_sparse(&self, taps: &[(usize, T)]) -> Vec<T> {
+        let taps = merge_taps(taps);
+        let span = taps.last().map_or(0, |(delay, _)| delay + 1);
+        let len = convolve_output_len(self.len(), span);
+
+        (0..len)
+            .into_par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|n| {
+                taps.iter()
+                    .filter(|(delay, _)| *delay <= n && n - delay < self.len())
+                    .fold(T::zero(), |acc, (delay, c)| acc + self[n - delay] * *c)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::YttriaConfig;
+    use crate::rng::gaussian_noise;
+    use crate::vector::YttriaVectorArithmetic;
+
+    fn dense(taps: &[(usize, f64)]) -> Vec<f64> {
+        let span = taps.iter().map(|(delay, _)| delay + 1).max().unwrap_or(0);
+        let mut out = vec![0.0; span];
+        for (delay, c) in taps {
+            out[*delay] += c;
+        }
+        out
+    }
+
+    #[test]
+    fn test_matches_dense_convolution() {
+        let taps = [(0, 0.9), (7, -0.4), (31, 0.25), (100, 0.1)];
+        let x = gaussian_noise(500, 1);
+        let sparse = x.convolve_sparse(&taps);
+        let expected = x.convolve(&dense(&taps));
+        assert_eq!(sparse.len(), expected.len());
+        for (a, b) in sparse.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-12);
+        }
+
+        // The streaming filter gives the first `len` samples of the full convolution.
+        let complex = x
+            .iter()
+            .zip(gaussian_noise(500, 2))
+            .map(|(re, im)| Complex::new(*re, im))
+            .collect::<Vec<_>>();
+        let out = SparseFir::new(&taps).process_block(&complex);
+        let re = x.convolve(&dense(&taps));
+        let im = gaussian_noise(500, 2).convolve(&dense(&taps));
+        for (n, y) in out.iter().enumerate() {
+            assert!((y - Complex::new(re[n], im[n])).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_streaming_equals_batch() {
+        let taps = [(3, 1.0), (40, -0.5), (41, 0.25)];
+        let input = gaussian_noise(1000, 3)
+            .iter()
+            .zip(gaussian_noise(1000, 4))
+            .map(|(re, im)| Complex::new(*re, im))
+            .collect::<Vec<_>>();
+
+        let whole = SparseFir::new(&taps).process_block(&input);
+        let mut filter = SparseFir::new(&taps);
+        let mut pieces = Vec::new();
+        for block in [
+            &input[..1],
+            &input[1..17],
+            &input[17..17],
+            &input[17..600],
+            &input[600..],
+        ] {
+            pieces.extend(filter.process_block(block));
+        }
+        assert_eq!(pieces, whole);
+
+        filter.reset();
+        assert_eq!(filter.process_block(&input), whole);
+    }
+
+    #[test]
+    fn test_duplicate_delays_merge() {
+        let filter = SparseFir::new(&[(5, 0.5), (2, 1.0), (5, 0.25), (2, -0.5)]);
+        assert_eq!(filter.taps(), [(2, 0.5), (5, 0.75)]);
+        assert_eq!(filter.max_delay(), 5);
+
+        let x = gaussian_noise(64, 5);
+        assert_eq!(
+            x.convolve_sparse(&[(5, 0.5), (2, 1.0), (5, 0.25), (2, -0.5)]),
+            x.convolve_sparse(&[(2, 0.5), (5, 0.75)])
+        );
+    }
+
+    #[test]
+    fn test_from_dense() {
+        let filter = SparseFir::from_dense(&[0.0, 1e-9, 0.8, 0.0, -0.3, 1e-7, 0.0], 1e-6);
+        assert_eq!(filter.num_taps(), 2);
+        assert_eq!(filter.taps(), [(2, 0.8), (4, -0.3)]);
+
+        let empty = SparseFir::from_dense(&[1e-9; 8], 1e-6);
+        assert_eq!(empty.num_taps(), 0);
+        assert!(gaussian_noise(10, 6)
+            .convolve_sparse(empty.taps())
+            .is_empty());
+    }
+
+    // Run with `cargo test --release -- --ignored bench_sparse_multipath --nocapture`. Both paths
+    // run on one thread, so the margin is the products skipped rather than the thread count.
+    #[test]
+    #[ignore]
+    fn bench_sparse_multipath() {
+        // Three paths across a 50k sample span.
+        let taps = [(0, 1.0), (12_345, 0.5), (49_999, 0.2)];
+        let x = gaussian_noise(100_000, 7);
+
+        let ((sparse, sparse_time), (expected, dense_time)) =
+            YttriaConfig::new().with_num_threads(1).install(|| {
+                let timed = |f: &dyn Fn() -> Vec<f64>| {
+                    let start = std::time::Instant::now();
+                    (f(), start.elapsed())
+                };
+                (
+                    timed(&|| x.convolve_sparse(&taps)),
+                    timed(&|| x.convolve(&dense(&taps))),
+                )
+            });
+        println!("sparse: {sparse_time:?}, dense: {dense_time:?}");
+        assert!(
+            sparse_time * 100 < dense_time,
+            "{sparse_time:?} {dense_time:?}"
+        );
+
+        for (a, b) in sparse.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use super::FilterSample;
+use num::{FromPrimitive, Zero};
+
+// FIR filter that keeps its delay line between blocks, so filtering a signal in pieces gives the
+// same output as filtering it whole.
+pub(crate) struct StreamingFir<S: FilterSample> {
+    taps: Vec<S::Real>,
+    history: Vec<S>,
+}
+
+impl<S: FilterSample> StreamingFir<S> {
+    pub(crate) fn new(taps: &[f64]) -> Self {
+        assert!(!taps.is_empty(), "Filter taps must not be empty");
+        Self {
+            taps: taps
+                .iter()
+                .map(|&t| S::Real::from_f64(t).expect("Could not convert f64 into type"))
+                .collect(),
+            history: vec![S::zero(); taps.len() - 1],
+        }
+    }
+
+    pub(crate) fn delay(&self) -> usize {
+        (self.taps.len() - 1) / 2
+    }
+
+    pub(crate) fn process(&mut self, input: &[S]) -> Vec<S> {
+        let order = self.history.len();
+        self.history.extend_from_slice(input);
+
+        let out = (0..input.len())
+            .map(|n| {
+                self.taps
+                    .iter()
+                    .zip(self.history[n..(n + order + 1)].iter().rev())
+                    .fold(S::zero(), |acc, (h, x)| acc + *x * *h)
+            })
+            .collect();
+
+        self.history.drain(..input.len());
+        out
+    }
+}
+
+// Pure delay of `len` samples carried across blocks.
+pub(crate) struct DelayLine<S> {
+    history: Vec<S>,
+}
+
+impl<S: Copy + Zero> DelayLine<S> {
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            history: vec![S::zero(); len],
+        }
+    }
+
+    pub(crate) fn process(&mut self, input: &[S]) -> Vec<S> {
+        self.history.extend_from_slice(input);
+        self.history.drain(..input.len()).collect()
+    }
+}
@@ -0,0 +1,445 @@
+// Pure counterparts of a few core operations, for wrapping in outer optimizers and numerical
+// differentiation. Every function here returns a new allocation or an error rather than
+// mutating or panicking, reads no global state such as `YttriaConfig`, and runs its sums
+// serially in a fixed order, so the same inputs give bit-identical outputs whatever the thread
+// pool looks like.
+use std::fmt;
+
+use num::{Complex, Float, FromPrimitive, Zero};
+use rustfft::FftNum;
+
+use crate::windows::WindowKind;
+use crate::FftContext;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FunctionalError {
+    // The named input has no elements, or a requested length is zero.
+    Empty(&'static str),
+    LengthMismatch { left: usize, right: usize },
+    // The named parameter is outside its valid range.
+    InvalidParameter(&'static str),
+}
+
+impl fmt::Display for FunctionalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty(what) => write!(f, "{what} must not be empty"),
+            Self::LengthMismatch { left, right } => {
+                write!(f, "lengths differ: {left} and {right}")
+            }
+            Self::InvalidParameter(what) => write!(f, "{what} is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for FunctionalError {}
+
+fn from_f64<T: FromPrimitive>(x: f64) -> T {
+    T::from_f64(x).expect("Could not convert f64 into type")
+}
+
+fn non_empty<T>(x: &[T], what: &'static str) -> Result<(), FunctionalError> {
+    if x.is_empty() {
+        Err(FunctionalError::Empty(what))
+    } else {
+        Ok(())
+    }
+}
+
+// Magnitude response of the FIR filter `taps` at `n` frequencies spaced evenly over [0, 1),
+// 1.0 being the Nyquist frequency, the grid `freqz` uses. Each point is summed directly rather
+// than through an FFT, so any `Float` works, dual numbers included.
+#[must_use = "the response is returned, not stored"]
+pub fn fir_response<T>(taps: &[T], n: usize) -> Result<Vec<T>, FunctionalError>
+where
+    T: Float + FromPrimitive,
+{
+    non_empty(taps, "taps")?;
+    if n == 0 {
+        return Err(FunctionalError::Empty("frequency grid"));
+    }
+
+    Ok((0..n)
+        .map(|k| {
+            let omega = std::f64::consts::PI * k as f64 / n as f64;
+            let h = taps
+                .iter()
+                .enumerate()
+                .fold(Complex::<T>::zero(), |acc, (i, tap)| {
+                    let (sin, cos) = (omega * i as f64).sin_cos();
+                    acc + Complex::new(*tap * from_f64(cos), -*tap * from_f64(sin))
+                });
+            h.norm()
+        })
+        .collect())
+}
+
+// Causal FIR filtering with zero initial state, `out[n] = sum taps[i] * signal[n - i]`, giving
+// as many samples as `signal`.
+#[must_use = "the filtered signal is returned, not written back"]
+pub fn apply_fir<T>(taps: &[T], signal: &[T]) -> Result<Vec<T>, FunctionalError>
+where
+    T: Float,
+{
+    non_empty(taps, "taps")?;
+
+    Ok((0..signal.len())
+        .map(|n| {
+            taps.iter()
+                .zip(signal[..=n].iter().rev())
+                .fold(T::zero(), |acc, (tap, x)| acc + *tap * *x)
+        })
+        .collect())
+}
+
+// `kind` generated at length `n`, with its parameter checked instead of asserted.
+#[must_use = "the window is returned, not stored"]
+pub fn window<T>(kind: WindowKind, n: usize) -> Result<Vec<T>, FunctionalError>
+where
+    T: Float + FromPrimitive,
+{
+    if n == 0 {
+        return Err(FunctionalError::Empty("window"));
+    }
+    match kind {
+        WindowKind::Kaiser(beta) if !(beta.is_finite() && beta >= 0.0) => {
+            Err(FunctionalError::InvalidParameter("Kaiser beta"))
+        }
+        WindowKind::Poisson(tau) | WindowKind::HannPoisson(tau)
+            if !(tau.is_finite() && tau > 0.0) =>
+        {
+            Err(FunctionalError::InvalidParameter("Poisson decay"))
+        }
+        _ => {
+            // Windows normalised over `n - 1` samples have no value at a length of one.
+            let window = kind.generate::<T>(n);
+            if window.iter().all(|w| w.is_finite()) {
+                Ok(window)
+            } else {
+                Err(FunctionalError::InvalidParameter("window length"))
+            }
+        }
+    }
+}
+
+// Welch power spectral density settings: segments of `segment_len` samples overlapping by
+// `overlap`, each multiplied by `window`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PsdConfig {
+    segment_len: usize,
+    overlap: usize,
+    window: WindowKind,
+}
+
+impl PsdConfig {
+    // Hann segments overlapping by half, as is usual for Welch's method.
+    pub fn new(segment_len: usize) -> Self {
+        Self {
+            segment_len,
+            overlap: segment_len / 2,
+            window: WindowKind::Hann,
+        }
+    }
+
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    pub fn with_window(mut self, window: WindowKind) -> Self {
+        self.window = window;
+        self
+    }
+}
+
+// One sided Welch estimate of the power spectral density of a real signal, in power per
+// cycle per sample over the `segment_len / 2 + 1` bins from DC to Nyquist. Segments are
+// averaged in order; white noise of variance `s` reads `2 * s` away from DC and Nyquist.
+#[must_use = "the spectrum is returned, not stored"]
+pub fn psd<T>(signal: &[T], config: PsdConfig) -> Result<Vec<T>, FunctionalError>
+where
+    T: FftNum + Float + FromPrimitive,
+{
+    let PsdConfig {
+        segment_len,
+        overlap,
+        window: kind,
+    } = config;
+    if segment_len < 2 {
+        return Err(FunctionalError::InvalidParameter("segment length"));
+    }
+    if overlap >= segment_len {
+        return Err(FunctionalError::InvalidParameter("overlap"));
+    }
+    if signal.len() < segment_len {
+        return Err(FunctionalError::LengthMismatch {
+            left: signal.len(),
+            right: segment_len,
+        });
+    }
+
+    let window = window::<T>(kind, segment_len)?;
+    let energy = window.iter().fold(T::zero(), |acc, w| acc + *w * *w);
+    // A two sample Hann window, say, is all zeros.
+    if energy == T::zero() {
+        return Err(FunctionalError::InvalidParameter("window energy"));
+    }
+    let hop = segment_len - overlap;
+    let segments = (signal.len() - segment_len) / hop + 1;
+    let bins = segment_len / 2 + 1;
+
+    let mut context = FftContext::new();
+    let mut buffer = vec![Complex::<T>::zero(); segment_len];
+    let mut out = vec![T::zero(); bins];
+    for s in 0..segments {
+        let segment = &signal[s * hop..s * hop + segment_len];
+        for ((b, x), w) in buffer.iter_mut().zip(segment).zip(&window) {
+            *b = Complex::new(*x * *w, T::zero());
+        }
+        context.forward(&mut buffer);
+        for (out, b) in out.iter_mut().zip(&buffer) {
+            *out = *out + b.norm_sqr();
+        }
+    }
+
+    // Every bin but DC, and Nyquist for even lengths, also stands for its negative frequency.
+    let scale = energy * from_f64(segments as f64);
+    let two = T::one() + T::one();
+    for (k, out) in out.iter_mut().enumerate() {
+        let paired = k != 0 && 2 * k != segment_len;
+        *out = *out / scale * if paired { two } else { T::one() };
+    }
+    Ok(out)
+}
+
+// RMS error vector magnitude of `rx` against `reference`, as a fraction of the reference's RMS
+// magnitude.
+#[must_use = "the EVM is returned, not stored"]
+pub fn evm<T>(rx: &[Complex<T>], reference: &[Complex<T>]) -> Result<T, FunctionalError>
+where
+    T: Float,
+{
+    non_empty(reference, "reference")?;
+    if rx.len() != reference.len() {
+        return Err(FunctionalError::LengthMismatch {
+            left: rx.len(),
+            right: reference.len(),
+        });
+    }
+
+    let (error, power) =
+        rx.iter()
+            .zip(reference)
+            .fold((T::zero(), T::zero()), |(error, power), (rx, reference)| {
+                (
+                    error + (rx - reference).norm_sqr(),
+                    power + reference.norm_sqr(),
+                )
+            });
+    if power == T::zero() {
+        return Err(FunctionalError::InvalidParameter("reference power"));
+    }
+    Ok((error / power).sqrt())
+}
+
+// Central difference estimate of the gradient of `f` at `x0`, stepping each coordinate by
+// `eps` either side. The error falls as `eps^2` until rounding in `f` takes over, so around
+// the cube root of the machine epsilon, scaled to `x0`, is a good step.
+#[must_use = "the gradient is returned, not stored"]
+pub fn finite_difference_gradient<T, F>(f: F, x0: &[T], eps: T) -> Result<Vec<T>, FunctionalError>
+where
+    T: Float,
+    F: Fn(&[T]) -> T,
+{
+    if !(eps > T::zero() && eps.is_finite()) {
+        return Err(FunctionalError::InvalidParameter("step"));
+    }
+
+    let two = T::one() + T::one();
+    let mut x = x0.to_vec();
+    Ok((0..x0.len())
+        .map(|i| {
+            x[i] = x0[i] + eps;
+            let up = f(&x);
+            x[i] = x0[i] - eps;
+            let down = f(&x);
+            x[i] = x0[i];
+            (up - down) / (two * eps)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{firwin, freqz};
+    use crate::rng::gaussian_noise;
+
+    #[test]
+    fn test_deterministic_across_threads() {
+        let signal = gaussian_noise(20_000, 1);
+        let rx = signal
+            .chunks_exact(2)
+            .map(|x| Complex::new(x[0], x[1]))
+            .collect::<Vec<_>>();
+        let reference = rx.iter().map(|x| x * 0.9).collect::<Vec<_>>();
+        let taps = gaussian_noise(31, 2);
+
+        let run = || {
+            (
+                fir_response(&taps, 512).unwrap(),
+                apply_fir(&taps, &signal).unwrap(),
+                window::<f64>(WindowKind::Kaiser(7.0), 1001).unwrap(),
+                psd(&signal, PsdConfig::new(256)).unwrap(),
+                evm(&rx, &reference).unwrap(),
+                finite_difference_gradient(|x| x.iter().map(|x| x * x).sum(), &taps, 1e-5).unwrap(),
+            )
+        };
+        let results = [1, 2, 3, 8]
+            .iter()
+            .map(|&threads| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .unwrap()
+                    .install(run)
+            })
+            .collect::<Vec<_>>();
+
+        let bits = |x: &[f64]| x.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+        for result in &results[1..] {
+            assert_eq!(bits(&result.0), bits(&results[0].0));
+            assert_eq!(bits(&result.1), bits(&results[0].1));
+            assert_eq!(bits(&result.2), bits(&results[0].2));
+            assert_eq!(bits(&result.3), bits(&results[0].3));
+            assert_eq!(result.4.to_bits(), results[0].4.to_bits());
+            assert_eq!(bits(&result.5), bits(&results[0].5));
+        }
+    }
+
+    #[test]
+    fn test_matches_crate_paths() {
+        let taps = firwin(21, 0.3, &WindowKind::Hamming.generate(21));
+        let (_, response) = freqz(&taps, &[1.0], 128);
+        for (r, h) in fir_response(&taps, 128).unwrap().iter().zip(&response) {
+            assert!((r - h.norm()).abs() < 1e-12);
+        }
+
+        let signal = gaussian_noise(300, 3);
+        let full = crate::vector::YttriaVectorArithmetic::convolve(&signal[..], &taps);
+        assert_eq!(apply_fir(&taps, &signal).unwrap().len(), signal.len());
+        for (a, b) in apply_fir(&taps, &signal).unwrap().iter().zip(&full) {
+            assert!((a - b).abs() < 1e-12);
+        }
+
+        // Unit variance white noise has a one sided density of two.
+        let spectrum = psd(&gaussian_noise(1 << 18, 4), PsdConfig::new(128)).unwrap();
+        let mean = spectrum[1..64].iter().sum::<f64>() / 63.0;
+        assert!((mean - 2.0).abs() < 0.02, "{mean}");
+
+        let reference = [Complex::new(1.0, 0.0), Complex::new(0.0, -1.0)];
+        let rx = [Complex::new(1.1, 0.0), Complex::new(0.0, -0.9)];
+        assert!((evm(&rx, &reference).unwrap() - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            fir_response::<f64>(&[], 8),
+            Err(FunctionalError::Empty("taps"))
+        );
+        assert_eq!(
+            window::<f64>(WindowKind::Poisson(-1.0), 8),
+            Err(FunctionalError::InvalidParameter("Poisson decay"))
+        );
+        assert_eq!(
+            psd(&[0.0; 100], PsdConfig::new(64).with_overlap(64)),
+            Err(FunctionalError::InvalidParameter("overlap"))
+        );
+        assert_eq!(
+            psd(&[0.0; 10], PsdConfig::new(64)),
+            Err(FunctionalError::LengthMismatch {
+                left: 10,
+                right: 64
+            })
+        );
+        // Degenerate windows give errors rather than NaN.
+        assert_eq!(
+            window::<f64>(WindowKind::Hann, 1),
+            Err(FunctionalError::InvalidParameter("window length"))
+        );
+        assert_eq!(window::<f64>(WindowKind::Rectangular, 1), Ok(vec![1.0]));
+        assert_eq!(
+            psd(&[1.0; 10], PsdConfig::new(2)),
+            Err(FunctionalError::InvalidParameter("window energy"))
+        );
+        assert!(psd(
+            &[1.0; 10],
+            PsdConfig::new(2).with_window(WindowKind::Rectangular)
+        )
+        .is_ok());
+        for eps in [0.0, -1e-6, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                finite_difference_gradient(|x| x[0], &[1.0], eps),
+                Err(FunctionalError::InvalidParameter("step"))
+            );
+        }
+
+        let one = [Complex::new(1.0, 0.0)];
+        assert_eq!(
+            evm(&one, &[Complex::zero()]),
+            Err(FunctionalError::InvalidParameter("reference power"))
+        );
+        assert_eq!(
+            evm(&one, &[one[0], one[0]]),
+            Err(FunctionalError::LengthMismatch { left: 1, right: 2 })
+        );
+    }
+
+    #[test]
+    fn test_gradient_of_quadratic() {
+        // f(x) = sum a_i x_i^2 + b_i x_i, whose gradient is 2 a_i x_i + b_i.
+        let a = [1.0, -2.0, 0.5, 3.0, 0.25];
+        let b = [0.3, 0.0, -1.0, 2.0, 5.0];
+        let f = |x: &[f64]| {
+            x.iter()
+                .zip(a.iter().zip(&b))
+                .map(|(x, (a, b))| a * x * x + b * x)
+                .sum::<f64>()
+        };
+        let x0 = [0.7, -1.3, 2.0, 0.0, -0.4];
+        let gradient = finite_difference_gradient(f, &x0, 1e-5).unwrap();
+        for ((g, x), (a, b)) in gradient.iter().zip(&x0).zip(a.iter().zip(&b)) {
+            assert!((g - (2.0 * a * x + b)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_fit_fir_taps() {
+        // Gradient descent on 8 taps, starting from a pass-through, towards the magnitude
+        // response of a windowed sinc lowpass. The squared error falls by over 100 times.
+        let n = 64;
+        let target = fir_response(&firwin(8, 0.4, &WindowKind::Hann.generate(8)), n).unwrap();
+        let loss = |taps: &[f64]| {
+            fir_response(taps, n)
+                .unwrap()
+                .iter()
+                .zip(&target)
+                .map(|(r, t)| (r - t) * (r - t))
+                .sum::<f64>()
+                / n as f64
+        };
+
+        let mut taps = vec![0.0; 8];
+        taps[3] = 1.0;
+        let initial = loss(&taps);
+        for _ in 0..500 {
+            let gradient = finite_difference_gradient(loss, &taps, 1e-6).unwrap();
+            for (t, g) in taps.iter_mut().zip(&gradient) {
+                *t -= 0.1 * g;
+            }
+        }
+        let fitted = loss(&taps);
+        assert!(fitted < initial / 100.0, "{initial} -> {fitted}");
+    }
+}
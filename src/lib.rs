@@ -1,7 +1,37 @@
+mod config;
+pub use config::YttriaConfig;
+mod matrix;
+pub use matrix::Matrix;
+mod samples;
+pub use samples::{Samples, SamplesError, SamplesView};
+mod split_complex;
+pub use split_complex::{SplitComplex, SplitComplexError};
 mod unit;
 mod vector;
+pub use vector::{
+    bits_from_run_lengths_quantized, complex_from_interleaved, complex_from_interleaved_bytes,
+    complex_from_interleaved_mut, convolve_output_len, db_power_sum, from_bytes, from_polar,
+    from_run_lengths, log_bin_edges, merge_axes, select_where, unpack_radix_u128, AxisError,
+    ComplexInterp, Endianness, FftContext, FitQuality, GainCurveError, InterpKind, LineCodeError,
+    ManchesterConvention, MonotonicPolicy, NoiseFloorConfig, NoiseFloorMethod, OutOfRange,
+    OverflowError, PlainSample, PowerCcdf, QuantizedRun, RebinError, RebinMode, RegridError,
+    Scrambler, TailPadding, TransposeError,
+};
+#[cfg(feature = "half")]
+pub use vector::{narrow_from_f32, HalfSample};
+pub mod filter;
+pub mod functional;
+pub mod modulation;
+pub mod random;
+pub mod signal;
+pub mod special;
+pub mod stream;
+pub mod trace;
 pub mod windows;
 
 pub mod prelude;
 mod utils;
+
+mod rng;
+pub use rng::{Xoshiro256PlusPlus, YttriaRng};
 pub use utils::*;
@@ -0,0 +1,171 @@
+use std::ops::{Index, IndexMut};
+
+use num::Zero;
+
+use crate::vector::YttriaVectorTranspose;
+
+// Dense row-major matrix, stored as one flat vector so rows are contiguous slices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<T> {
+    rows: usize,
+    cols: usize,
+    data: Vec<T>,
+}
+
+impl<T> Matrix<T> {
+    pub fn from_vec(rows: usize, cols: usize, data: Vec<T>) -> Self {
+        assert!(
+            data.len() == rows * cols,
+            "A {rows}x{cols} matrix needs {} elements but got {}",
+            rows * cols,
+            data.len()
+        );
+        Self { rows, cols, data }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        assert!(
+            row < self.rows,
+            "Row {row} out of range for {} rows",
+            self.rows
+        );
+        &self.data[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> &mut [T] {
+        assert!(
+            row < self.rows,
+            "Row {row} out of range for {} rows",
+            self.rows
+        );
+        &mut self.data[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn from_rows<R: AsRef<[T]>>(rows: &[R]) -> Self {
+        let cols = rows.first().map(|r| r.as_ref().len()).unwrap_or(0);
+        let mut data = Vec::with_capacity(rows.len() * cols);
+        for (idx, row) in rows.iter().enumerate() {
+            let row = row.as_ref();
+            assert!(
+                row.len() == cols,
+                "Row {idx} has length {} but expected {cols}",
+                row.len()
+            );
+            data.extend_from_slice(row);
+        }
+        Self::from_vec(rows.len(), cols, data)
+    }
+}
+
+impl<T: Zero + Clone> Matrix<T> {
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Self::from_vec(rows, cols, vec![T::zero(); rows * cols])
+    }
+}
+
+impl<T: Zero + Copy + Send + Sync> Matrix<T> {
+    // The shape is known to match, so the blocked transpose cannot fail here.
+    pub fn transpose(&self) -> Self {
+        let mut out = Self::zeros(self.cols, self.rows);
+        self.data
+            .transpose_into(self.rows, self.cols, &mut out.data)
+            .expect("Matrix shape matches its data");
+        out
+    }
+
+    pub fn column(&self, col: usize) -> Vec<T> {
+        let mut out = vec![T::zero(); self.rows];
+        self.data
+            .copy_column_into(self.rows, self.cols, col, &mut out)
+            .unwrap_or_else(|err| panic!("{err}"));
+        out
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(
+            col < self.cols,
+            "Column {col} out of range for {} columns",
+            self.cols
+        );
+        &self.row(row)[col]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(
+            col < self.cols,
+            "Column {col} out of range for {} columns",
+            self.cols
+        );
+        &mut self.row_mut(row)[col]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let mut m = Matrix::from_rows(&[[1, 2, 3], [4, 5, 6]]);
+        assert_eq!((m.rows(), m.cols()), (2, 3));
+        assert_eq!(m.as_slice(), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(m.row(1), [4, 5, 6]);
+        assert_eq!(m[(0, 2)], 3);
+
+        m[(1, 0)] = 9;
+        m.row_mut(0).fill(0);
+        assert_eq!(m.into_vec(), [0, 0, 0, 9, 5, 6]);
+        assert_eq!(Matrix::<f64>::zeros(3, 2).as_slice(), [0.0; 6]);
+    }
+
+    #[test]
+    fn test_transpose() {
+        let m = Matrix::from_rows(&[[1, 2, 3], [4, 5, 6]]);
+        let t = m.transpose();
+        assert_eq!((t.rows(), t.cols()), (3, 2));
+        assert_eq!(t.as_slice(), [1, 4, 2, 5, 3, 6]);
+        assert_eq!(t.transpose(), m);
+        assert_eq!(m.column(1), [2, 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ragged_rows() {
+        Matrix::from_rows(&[vec![1, 2], vec![3]]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_column_out_of_range() {
+        let m = Matrix::from_rows(&[[1, 2], [3, 4]]);
+        let _ = m[(0, 2)];
+    }
+}
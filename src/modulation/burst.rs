@@ -0,0 +1,276 @@
+use super::Constellation;
+use crate::filter::rrc;
+use crate::rng::YttriaRng;
+use crate::vector::{YttriaVectorArithmetic, YttriaVectorImpairments};
+use num::{Complex, Float, FromPrimitive, Zero};
+
+// Span of the root raised cosine shaping filter, in symbols.
+pub const RRC_SPAN: usize = 8;
+
+// Everything needed to check a receiver against a burst from `BurstBuilder`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroundTruth<T> {
+    pub bits: Vec<u8>,
+    // Preamble followed by the data symbols, before shaping.
+    pub symbols: Vec<Complex<T>>,
+    pub preamble_len: usize,
+    pub samples_per_symbol: usize,
+    pub rolloff: Option<T>,
+    // Shaped burst before any impairment.
+    pub clean: Vec<Complex<T>>,
+    pub delay: Option<T>,
+    pub cfo: Option<T>,
+    pub phase: Option<T>,
+    pub iq_imbalance: Option<(T, T)>,
+    pub snr_db: Option<T>,
+}
+
+// Assembles known-truth IQ bursts: bits are mapped onto the constellation, the preamble symbols
+// are prepended, the result is optionally shaped and then impaired in a fixed order of delay,
+// CFO, phase, IQ imbalance and finally AWGN, so the SNR refers to the signal as received. All
+// randomness comes from the generator passed to `build`, so a burst is replayed by building
+// again from a generator with the same seed.
+#[derive(Debug, Clone)]
+pub struct BurstBuilder<T> {
+    constellation: Constellation<T>,
+    bits: Vec<u8>,
+    preamble: Vec<Complex<T>>,
+    shaping: Option<(T, usize)>,
+    delay: Option<T>,
+    cfo: Option<T>,
+    phase: Option<T>,
+    iq_imbalance: Option<(T, T)>,
+    snr_db: Option<T>,
+}
+
+impl<T> BurstBuilder<T>
+where
+    T: Float + FromPrimitive + Send + Sync,
+{
+    pub fn new(constellation: Constellation<T>) -> Self {
+        Self {
+            constellation,
+            bits: Vec::new(),
+            preamble: Vec::new(),
+            shaping: None,
+            delay: None,
+            cfo: None,
+            phase: None,
+            iq_imbalance: None,
+            snr_db: None,
+        }
+    }
+
+    pub fn bits(mut self, bits: &[u8]) -> Self {
+        self.bits = bits.to_vec();
+        self
+    }
+
+    pub fn preamble(mut self, preamble: &[Complex<T>]) -> Self {
+        self.preamble = preamble.to_vec();
+        self
+    }
+
+    pub fn shaping_rrc(mut self, rolloff: T, samples_per_symbol: usize) -> Self {
+        self.shaping = Some((rolloff, samples_per_symbol));
+        self
+    }
+
+    pub fn delay_frac(mut self, delay: T) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    pub fn cfo(mut self, cfo: T) -> Self {
+        self.cfo = Some(cfo);
+        self
+    }
+
+    pub fn phase(mut self, phase: T) -> Self {
+        self.phase = Some(phase);
+        self
+    }
+
+    pub fn iq_imbalance(mut self, gain_db: T, phase: T) -> Self {
+        self.iq_imbalance = Some((gain_db, phase));
+        self
+    }
+
+    pub fn awgn_snr_db(mut self, snr_db: T) -> Self {
+        self.snr_db = Some(snr_db);
+        self
+    }
+
+    pub fn build(&self, rng: &mut impl YttriaRng) -> (Vec<Complex<T>>, GroundTruth<T>) {
+        let mut symbols = self.preamble.clone();
+        symbols.extend(self.constellation.map(&self.bits));
+
+        let (clean, samples_per_symbol) = match self.shaping {
+            Some((rolloff, sps)) => {
+                let taps = rrc(
+                    rolloff.to_f64().expect("Could not convert type into f64"),
+                    RRC_SPAN,
+                    sps,
+                )
+                .iter()
+                .map(|t| {
+                    Complex::new(
+                        T::from_f64(*t).expect("Could not convert f64 into type"),
+                        T::zero(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+                let mut upsampled = vec![Complex::<T>::zero(); symbols.len() * sps];
+                for (i, s) in symbols.iter().enumerate() {
+                    upsampled[i * sps] = *s;
+                }
+                (upsampled.convolve(&taps), sps)
+            }
+            None => (symbols.clone(), 1),
+        };
+
+        let mut burst = clean.clone();
+        if let Some(delay) = self.delay {
+            burst = burst.fractional_delay(delay);
+        }
+        if let Some(cfo) = self.cfo {
+            burst = burst.apply_cfo(cfo);
+        }
+        if let Some(phase) = self.phase {
+            burst = burst.apply_phase(phase);
+        }
+        if let Some((gain_db, phase)) = self.iq_imbalance {
+            burst = burst.apply_iq_imbalance(gain_db, phase);
+        }
+        if let Some(snr_db) = self.snr_db {
+            burst = burst.add_awgn(snr_db, rng);
+        }
+
+        let truth = GroundTruth {
+            bits: self.bits.clone(),
+            symbols,
+            preamble_len: self.preamble.len(),
+            samples_per_symbol,
+            rolloff: self.shaping.map(|(rolloff, _)| rolloff),
+            clean,
+            delay: self.delay,
+            cfo: self.cfo,
+            phase: self.phase,
+            iq_imbalance: self.iq_imbalance,
+            snr_db: self.snr_db,
+        };
+
+        (burst, truth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Xoshiro256PlusPlus;
+
+    fn rng(seed: u64) -> Xoshiro256PlusPlus {
+        Xoshiro256PlusPlus::seed_from(seed)
+    }
+
+    fn bits(len: usize) -> Vec<u8> {
+        let mut rng = Xoshiro256PlusPlus::seed_from(3);
+        (0..len).map(|_| (rng.next_u64() & 1) as u8).collect()
+    }
+
+    fn preamble() -> Vec<Complex<f64>> {
+        [1.0, -1.0, 1.0, 1.0, -1.0, -1.0, 1.0]
+            .iter()
+            .map(|x| Complex::new(*x, 0.0))
+            .collect()
+    }
+
+    fn shaped() -> BurstBuilder<f64> {
+        BurstBuilder::new(Constellation::qpsk())
+            .bits(&bits(200))
+            .shaping_rrc(0.35, 8)
+            .preamble(&preamble())
+    }
+
+    #[test]
+    fn test_burst_clean_matches_shaping() {
+        let (burst, truth) = shaped().build(&mut rng(0));
+        assert_eq!(burst, truth.clean);
+
+        let qpsk = Constellation::qpsk();
+        let mut symbols = preamble();
+        symbols.extend(qpsk.map(&bits(200)));
+        assert_eq!(truth.symbols, symbols);
+
+        let taps = rrc(0.35, RRC_SPAN, 8)
+            .iter()
+            .map(|t| Complex::new(*t, 0.0))
+            .collect::<Vec<_>>();
+        let mut upsampled = vec![Complex::zero(); symbols.len() * 8];
+        for (i, s) in symbols.iter().enumerate() {
+            upsampled[i * 8] = *s;
+        }
+        assert_eq!(burst, upsampled.convolve(&taps));
+
+        let (unshaped, truth) = BurstBuilder::new(Constellation::qpsk())
+            .bits(&bits(200))
+            .build(&mut rng(0));
+        assert_eq!(unshaped, qpsk.map(&bits(200)));
+        assert_eq!(truth.samples_per_symbol, 1);
+    }
+
+    #[test]
+    fn test_burst_stages_match_primitives() {
+        let (_, truth) = shaped().build(&mut rng(0));
+        let clean = truth.clean;
+
+        assert_eq!(
+            shaped().delay_frac(2.7).build(&mut rng(0)).0,
+            clean.fractional_delay(2.7)
+        );
+        assert_eq!(
+            shaped().cfo(1e-4).build(&mut rng(0)).0,
+            clean.apply_cfo(1e-4)
+        );
+        assert_eq!(
+            shaped().phase(0.3).build(&mut rng(0)).0,
+            clean.apply_phase(0.3)
+        );
+        assert_eq!(
+            shaped().iq_imbalance(0.5, 0.05).build(&mut rng(0)).0,
+            clean.apply_iq_imbalance(0.5, 0.05)
+        );
+        assert_eq!(
+            shaped().awgn_snr_db(15.0).build(&mut rng(42)).0,
+            clean.add_awgn(15.0, &mut rng(42))
+        );
+    }
+
+    #[test]
+    fn test_burst_truth_and_determinism() {
+        let builder = shaped()
+            .cfo(1e-4)
+            .phase(0.3)
+            .delay_frac(2.7)
+            .iq_imbalance(0.5, 0.05)
+            .awgn_snr_db(15.0);
+
+        let (first, truth) = builder.build(&mut rng(42));
+        let (second, _) = builder.build(&mut rng(42));
+        assert_eq!(first, second);
+
+        assert_eq!(truth.bits, bits(200));
+        assert_eq!(truth.preamble_len, 7);
+        assert_eq!(truth.samples_per_symbol, 8);
+        assert_eq!(truth.rolloff, Some(0.35));
+        assert_eq!(truth.cfo, Some(1e-4));
+        assert_eq!(truth.phase, Some(0.3));
+        assert_eq!(truth.delay, Some(2.7));
+        assert_eq!(truth.iq_imbalance, Some((0.5, 0.05)));
+        assert_eq!(truth.snr_db, Some(15.0));
+
+        let (other, _) = builder.build(&mut rng(43));
+        assert_ne!(first, other);
+    }
+}
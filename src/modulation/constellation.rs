@@ -0,0 +1,84 @@
+use crate::vector::{TailPadding, YttriaVectorBitwise};
+use num::{Complex, Float, FromPrimitive};
+
+// Maps groups of bits, most significant first, onto constellation points by index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constellation<T> {
+    points: Vec<Complex<T>>,
+    bits_per_symbol: usize,
+}
+
+impl<T> Constellation<T>
+where
+    T: Float + FromPrimitive,
+{
+    pub fn new(points: Vec<Complex<T>>) -> Self {
+        assert!(
+            points.len() >= 2 && points.len().is_power_of_two() && points.len() <= 256,
+            "Constellation size must be a power of two between 2 and 256, got {}",
+            points.len()
+        );
+
+        let bits_per_symbol = points.len().trailing_zeros() as usize;
+        Self {
+            points,
+            bits_per_symbol,
+        }
+    }
+
+    pub fn bpsk() -> Self {
+        Self::new(vec![
+            Complex::new(T::one(), T::zero()),
+            Complex::new(-T::one(), T::zero()),
+        ])
+    }
+
+    // Gray coded with unit average power: the first bit selects the sign of the in-phase
+    // component and the second the quadrature.
+    pub fn qpsk() -> Self {
+        let a =
+            T::from_f64(std::f64::consts::FRAC_1_SQRT_2).expect("Could not convert f64 into type");
+        Self::new(vec![
+            Complex::new(a, a),
+            Complex::new(a, -a),
+            Complex::new(-a, a),
+            Complex::new(-a, -a),
+        ])
+    }
+
+    pub fn points(&self) -> &[Complex<T>] {
+        &self.points
+    }
+
+    pub fn bits_per_symbol(&self) -> usize {
+        self.bits_per_symbol
+    }
+
+    // A trailing partial symbol is completed with zero bits.
+    pub fn map(&self, bits: &[u8]) -> Vec<Complex<T>> {
+        bits.bits_to_symbols(self.bits_per_symbol, TailPadding::Zeros)
+            .iter()
+            .map(|s| self.points[*s as usize])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qpsk_gray_mapping() {
+        let qpsk = Constellation::<f64>::qpsk();
+        let symbols = qpsk.map(&[0, 0, 0, 1, 1, 0, 1, 1, 1]);
+        assert_eq!(symbols.len(), 5);
+        assert_eq!(symbols[..4], qpsk.points()[..]);
+        assert_eq!(symbols[4], qpsk.points()[2]);
+
+        // Neighbouring points differ by a single bit.
+        for (a, b) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+            let distance = (qpsk.points()[a] - qpsk.points()[b]).norm();
+            assert!((distance - 2f64.sqrt()).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use num::{Complex, Float, FromPrimitive};
+
+use crate::filter::{firwin, StreamingFir};
+use crate::windows::kaiser;
+
+fn from_f64<T: FromPrimitive>(x: f64) -> T {
+    T::from_f64(x).expect("Could not convert f64 into type")
+}
+
+fn to_f64<T: Float>(x: T) -> f64 {
+    x.to_f64().expect("Could not convert type into f64")
+}
+
+// Gardner loop gains, per unit of timing error on full scale symbols. The proportional gain
+// corrects a symbol centre within a few transitions and the integral gain pulls the symbol period
+// in from rate errors of up to about 10%.
+const TIMING_PROPORTIONAL: f64 = 0.1;
+const TIMING_INTEGRAL: f64 = 0.02;
+// The symbol period estimate stays within this fraction of the nominal period.
+const MAX_RATE_ERROR: f64 = 0.25;
+const LOWPASS_BETA: f64 = 5.0;
+
+// Noncoherent FSK demodulator giving one soft frequency estimate per symbol. A phase difference
+// discriminator measures the instantaneous frequency, scaled by `deviation` so the outermost tones
+// read +-1 and the inner 4-FSK tones +-1/3, then an optional lowpass smooths it and it is sampled
+// at the symbol centres.
+//
+// Symbol timing comes from a Gardner detector on the discriminator output driving a proportional
+// plus integral loop, so the strobes follow both timing phase and symbol rate errors. The first
+// symbol is assumed to start at the first input sample. Rate errors up to about 10% are pulled in
+// within a few dozen symbols, but while they are the strobes may gain or drop a symbol, so frame
+// alignment belongs after the loop, on a preamble or sync word. The loop needs transitions to
+// steer, and coasts on its rate estimate through runs of one symbol. `with_timing_phase` fixes
+// the timing instead.
+pub struct FskDemodulator<T> {
+    samples_per_symbol: T,
+    deviation: T,
+    lowpass: Option<StreamingFir<f64>>,
+    timing_phase: Option<T>,
+    previous: Option<Complex<f64>>,
+    // Discriminator output not yet consumed, starting at absolute sample `base`.
+    buffer: Vec<f64>,
+    base: usize,
+    strobe: Option<f64>,
+    period: f64,
+    last_symbol: Option<f64>,
+}
+
+impl<T: Float + FromPrimitive> FskDemodulator<T> {
+    // `samples_per_symbol` may be fractional, and `deviation` is the frequency offset of the
+    // outermost tones in cycles per sample.
+    pub fn new(samples_per_symbol: T, deviation: T) -> Self {
+        assert!(
+            samples_per_symbol >= from_f64(2.0),
+            "Need at least two samples per symbol"
+        );
+        assert!(
+            deviation > T::zero() && deviation < from_f64(0.5),
+            "Deviation must lie within (0, 0.5) cycles per sample"
+        );
+
+        Self {
+            samples_per_symbol,
+            deviation,
+            lowpass: None,
+            timing_phase: None,
+            previous: None,
+            buffer: Vec::new(),
+            base: 0,
+            strobe: None,
+            period: to_f64(samples_per_symbol),
+            last_symbol: None,
+        }
+    }
+
+    // Lowpass after the discriminator with its -6 dB point at `cutoff` cycles per sample, about
+    // three quarters of the symbol rate being a good start. Its taps span four symbols.
+    pub fn with_lowpass(mut self, cutoff: T) -> Self {
+        let numtaps = 2 * (2.0 * to_f64(self.samples_per_symbol)).round() as usize + 1;
+        self.lowpass = Some(StreamingFir::new(&firwin(
+            numtaps,
+            2.0 * to_f64(cutoff),
+            &kaiser(numtaps, LOWPASS_BETA),
+        )));
+        self
+    }
+
+    // Takes symbol timing from outside: the first symbol starts `phase` samples into the input
+    // and symbols follow at exactly `samples_per_symbol`.
+    pub fn with_timing_phase(mut self, phase: T) -> Self {
+        assert!(phase >= T::zero(), "Timing phase must not be negative");
+        self.timing_phase = Some(phase);
+        self
+    }
+
+    // Current symbol period estimate in samples.
+    pub fn samples_per_symbol_estimate(&self) -> T {
+        from_f64(self.period)
+    }
+
+    fn interpolate(&self, t: f64) -> f64 {
+        let i = t.floor() as usize - self.base;
+        let frac = t - t.floor();
+        self.buffer[i] * (1.0 - frac) + self.buffer[i + 1] * frac
+    }
+
+    pub fn process(&mut self, input: &[Complex<T>]) -> Vec<T> {
+        let scale = 1.0 / (2.0 * std::f64::consts::PI * to_f64(self.deviation));
+        let frequency = input
+            .iter()
+            .map(|x| {
+                let x = Complex::new(to_f64(x.re), to_f64(x.im));
+                let previous = self.previous.replace(x).unwrap_or(x);
+                (x * previous.conj()).arg() * scale
+            })
+            .collect::<Vec<_>>();
+        let filtered = match self.lowpass.as_mut() {
+            Some(lowpass) => lowpass.process(&frequency),
+            None => frequency,
+        };
+        self.buffer.extend(filtered);
+
+        // Discriminator sample `n` measures the frequency between input samples `n - 1` and
+        // `n`, so the centre of a symbol sits half a sample later than in the input.
+        let delay = self.lowpass.as_ref().map_or(0, |lowpass| lowpass.delay());
+        let nominal = to_f64(self.samples_per_symbol);
+        let phase = self.timing_phase.map_or(0.0, to_f64);
+        let mut strobe = *self
+            .strobe
+            .get_or_insert(phase + nominal / 2.0 + 0.5 + delay as f64);
+
+        let mut out = Vec::new();
+        while (strobe.floor() as usize + 1) < self.base + self.buffer.len() {
+            let symbol = self.interpolate(strobe);
+            let mut next = strobe + self.period;
+            if let (None, Some(last)) = (self.timing_phase, self.last_symbol) {
+                let error = self.interpolate(strobe - self.period / 2.0) * (last - symbol);
+                next += TIMING_PROPORTIONAL * nominal * error;
+                self.period = (self.period + TIMING_INTEGRAL * nominal * error).clamp(
+                    nominal * (1.0 - MAX_RATE_ERROR),
+                    nominal * (1.0 + MAX_RATE_ERROR),
+                );
+            }
+            self.last_symbol = Some(symbol);
+            out.push(from_f64(symbol));
+            strobe = next;
+        }
+        self.strobe = Some(strobe);
+
+        // Keep what the next midpoint and strobe will interpolate from. Before the first strobe,
+        // which the lowpass delay and timing phase may put beyond everything buffered so far,
+        // nothing is dropped.
+        let keep = ((strobe - self.period).floor().max(0.0) as usize)
+            .max(self.base)
+            .min(self.base + self.buffer.len());
+        self.buffer.drain(..keep - self.base);
+        self.base = keep;
+        out
+    }
+
+    // Hard decisions for 2-FSK or 4-FSK. The level means are found by k-means from the soft
+    // values themselves, starting evenly spread between their extremes, so a frequency offset or
+    // deviation error moves the thresholds with them and rare levels are still found. Each symbol
+    // is the Gray code of its level counted from the lowest tone, so neighbouring levels differ
+    // in one bit and 2-FSK gives 1 for the high tone.
+    pub fn slice_symbols(&self, soft: &[T], levels: usize) -> Vec<u8> {
+        assert!(
+            levels == 2 || levels == 4,
+            "Only 2-FSK and 4-FSK are supported, got {levels} levels"
+        );
+        let soft = soft.iter().map(|x| to_f64(*x)).collect::<Vec<_>>();
+        let level = |x: f64, thresholds: &[f64]| thresholds.iter().filter(|t| x > **t).count();
+        let thresholds = |centres: &[f64]| {
+            centres
+                .windows(2)
+                .map(|c| (c[0] + c[1]) / 2.0)
+                .collect::<Vec<_>>()
+        };
+
+        let (low, high) = soft
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), x| {
+                (low.min(*x), high.max(*x))
+            });
+        let mut centres = (0..levels)
+            .map(|k| low + (high - low) * k as f64 / (levels - 1) as f64)
+            .collect::<Vec<_>>();
+        for _ in 0..100 {
+            let bounds = thresholds(&centres);
+            let mut sums = vec![(0.0, 0usize); levels];
+            for x in &soft {
+                let sum = &mut sums[level(*x, &bounds)];
+                *sum = (sum.0 + x, sum.1 + 1);
+            }
+            let updated = sums
+                .iter()
+                .zip(&centres)
+                .map(|((sum, count), centre)| {
+                    if *count == 0 {
+                        *centre
+                    } else {
+                        sum / *count as f64
+                    }
+                })
+                .collect::<Vec<_>>();
+            if updated == centres {
+                break;
+            }
+            centres = updated;
+        }
+
+        let bounds = thresholds(&centres);
+        soft.iter()
+            .map(|x| {
+                let level = level(*x, &bounds) as u8;
+                level ^ (level >> 1)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{Xoshiro256PlusPlus, YttriaRng};
+    use crate::vector::YttriaVectorImpairments;
+
+    // Continuous phase FSK at `samples_per_symbol`, which need not be whole, with the levels
+    // evenly spread over +-`deviation`.
+    fn cpfsk(
+        levels: &[usize],
+        count: usize,
+        samples_per_symbol: f64,
+        deviation: f64,
+    ) -> Vec<Complex<f64>> {
+        let len = (levels.len() as f64 * samples_per_symbol) as usize;
+        let mut phase = 0.0f64;
+        (0..len)
+            .map(|n| {
+                let level = levels[(n as f64 / samples_per_symbol) as usize];
+                let x = Complex::from_polar(1.0, 2.0 * std::f64::consts::PI * phase);
+                phase = (phase + deviation * (2.0 * level as f64 / (count - 1) as f64 - 1.0))
+                    .rem_euclid(1.0);
+                x
+            })
+            .collect()
+    }
+
+    fn gray(level: usize) -> u8 {
+        (level ^ (level >> 1)) as u8
+    }
+
+    fn random_levels(len: usize, count: usize, rng: &mut impl YttriaRng) -> Vec<usize> {
+        (0..len)
+            .map(|_| (rng.next_u64() % count as u64) as usize)
+            .collect()
+    }
+
+    fn errors(decoded: &[u8], expected: &[u8], skip: usize) -> usize {
+        let len = decoded.len().min(expected.len()) - 2;
+        (skip..len).filter(|k| decoded[*k] != expected[*k]).count()
+    }
+
+    #[test]
+    fn test_2fsk_at_20db() {
+        let mut rng = Xoshiro256PlusPlus::seed_from(1);
+        let levels = random_levels(2000, 2, &mut rng);
+        let signal = cpfsk(&levels, 2, 8.0, 0.0625).add_awgn(20.0, &mut rng);
+
+        let new = || FskDemodulator::new(8.0, 0.0625).with_lowpass(0.09);
+        let mut demodulator = new();
+        let soft = demodulator.process(&signal);
+        assert!(soft.len() >= levels.len() - 3);
+        let bits = demodulator.slice_symbols(&soft, 2);
+        let expected = levels.iter().map(|l| gray(*l)).collect::<Vec<_>>();
+        assert_eq!(errors(&bits, &expected, 0), 0);
+
+        // Soft values sit near the nominal levels.
+        let mean_high = soft
+            .iter()
+            .zip(&levels)
+            .filter(|(_, l)| **l == 1)
+            .map(|(s, _)| *s)
+            .sum::<f64>()
+            / levels.iter().filter(|l| **l == 1).count() as f64;
+        assert!((mean_high - 1.0).abs() < 0.1, "{mean_high}");
+
+        let mut streamed = new();
+        let chunks = signal
+            .chunks(333)
+            .flat_map(|block| streamed.process(block))
+            .collect::<Vec<_>>();
+        assert_eq!(chunks, soft);
+    }
+
+    #[test]
+    fn test_streaming_before_first_strobe() {
+        // Chunks shorter than the lowpass delay, or than an external timing phase, arrive before
+        // the first strobe has anything to sample.
+        let mut rng = Xoshiro256PlusPlus::seed_from(6);
+        let levels = random_levels(200, 2, &mut rng);
+        let signal = cpfsk(&levels, 2, 8.0, 0.0625).add_awgn(20.0, &mut rng);
+
+        let demodulators: [fn() -> FskDemodulator<f64>; 3] = [
+            || FskDemodulator::new(8.0, 0.0625).with_lowpass(0.09),
+            || FskDemodulator::new(8.0, 0.0625).with_timing_phase(40.0),
+            || {
+                FskDemodulator::new(8.0, 0.0625)
+                    .with_lowpass(0.09)
+                    .with_timing_phase(40.0)
+            },
+        ];
+        for new in demodulators {
+            let whole = new().process(&signal);
+            assert!(whole.len() > 190);
+            for chunk in [1, 5, 7] {
+                let mut streamed = new();
+                let soft = signal
+                    .chunks(chunk)
+                    .flat_map(|block| streamed.process(block))
+                    .collect::<Vec<_>>();
+                assert_eq!(soft, whole, "{chunk}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_4fsk_gray_levels() {
+        let mut rng = Xoshiro256PlusPlus::seed_from(2);
+        let levels = random_levels(2000, 4, &mut rng);
+        let signal = cpfsk(&levels, 4, 10.0, 0.075).add_awgn(25.0, &mut rng);
+
+        let mut demodulator = FskDemodulator::new(10.0, 0.075).with_lowpass(0.075);
+        let soft = demodulator.process(&signal);
+        let symbols = demodulator.slice_symbols(&soft, 4);
+        let expected = levels.iter().map(|l| gray(*l)).collect::<Vec<_>>();
+        assert_eq!(errors(&symbols, &expected, 0), 0);
+        assert_eq!(
+            demodulator.slice_symbols(&[-1.0, -1.0 / 3.0, 1.0 / 3.0, 1.0], 4),
+            [0b00, 0b01, 0b11, 0b10]
+        );
+    }
+
+    #[test]
+    fn test_symbol_rate_error() {
+        for actual in [7.2, 8.8] {
+            let mut rng = Xoshiro256PlusPlus::seed_from(3);
+            let levels = random_levels(3000, 2, &mut rng);
+            let signal = cpfsk(&levels, 2, actual, 0.0625).add_awgn(20.0, &mut rng);
+
+            let mut demodulator = FskDemodulator::new(8.0, 0.0625).with_lowpass(0.09);
+            let soft = demodulator.process(&signal);
+            let bits = demodulator.slice_symbols(&soft, 2);
+            let expected = levels.iter().map(|l| gray(*l)).collect::<Vec<_>>();
+            // Pulling in may slip a symbol, after which every decision is right.
+            let slipped = (0..=2)
+                .map(|slip| {
+                    errors(&bits[slip..], &expected, 64).min(errors(&bits, &expected[slip..], 64))
+                })
+                .min();
+            assert_eq!(slipped, Some(0), "{actual}");
+            let period = demodulator.samples_per_symbol_estimate();
+            assert!((period - actual).abs() < 0.2, "{actual}: {period}");
+
+            // Fixed timing at the nominal rate slips within a few symbols.
+            let mut fixed = FskDemodulator::new(8.0, 0.0625)
+                .with_lowpass(0.09)
+                .with_timing_phase(0.0);
+            let soft = fixed.process(&signal);
+            let bits = fixed.slice_symbols(&soft, 2);
+            assert!(errors(&bits, &expected, 64) > 100, "{actual}");
+        }
+    }
+
+    #[test]
+    fn test_external_timing_phase() {
+        let mut rng = Xoshiro256PlusPlus::seed_from(4);
+        let levels = random_levels(500, 2, &mut rng);
+        let mut signal = vec![Complex::new(1.0, 0.0); 13];
+        signal.extend(cpfsk(&levels, 2, 8.0, 0.0625));
+
+        let mut demodulator = FskDemodulator::new(8.0, 0.0625).with_timing_phase(13.0);
+        let soft = demodulator.process(&signal);
+        for (s, l) in soft.iter().zip(&levels) {
+            assert!((s - (2.0 * *l as f64 - 1.0)).abs() < 1e-9, "{s} {l}");
+        }
+    }
+
+    #[test]
+    fn test_unbalanced_thresholds() {
+        // A rare low tone, with the levels offset and compressed by a frequency error and a
+        // deviation mismatch. Midpoints of the nominal levels would misread most of them.
+        let mut rng = Xoshiro256PlusPlus::seed_from(5);
+        let levels = (0..5000)
+            .map(|_| usize::from(rng.next_f64() > 0.03))
+            .collect::<Vec<_>>();
+        let soft = levels
+            .iter()
+            .map(|l| 0.6 + 0.5 * (2.0 * *l as f64 - 1.0) + 0.05 * rng.next_gaussian())
+            .collect::<Vec<_>>();
+        let demodulator = FskDemodulator::new(8.0, 0.0625);
+        let bits = demodulator.slice_symbols(&soft, 2);
+        assert!(levels.iter().zip(&bits).all(|(l, b)| gray(*l) == *b));
+
+        let levels = (0..5000)
+            .map(|_| match rng.next_f64() {
+                x if x < 0.02 => 0,
+                x if x < 0.9 => 1,
+                x if x < 0.95 => 2,
+                _ => 3,
+            })
+            .collect::<Vec<_>>();
+        let soft = levels
+            .iter()
+            .map(|l| 0.8 * (2.0 * *l as f64 / 3.0 - 1.0) - 0.1 + 0.03 * rng.next_gaussian())
+            .collect::<Vec<_>>();
+        let symbols = demodulator.slice_symbols(&soft, 4);
+        assert!(levels.iter().zip(&symbols).all(|(l, s)| gray(*l) == *s));
+    }
+}
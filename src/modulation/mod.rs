@@ -0,0 +1,14 @@
+mod ber;
+pub use ber::{ber_bpsk, ber_qam, ber_qpsk};
+
+mod burst;
+pub use burst::{BurstBuilder, GroundTruth, RRC_SPAN};
+
+mod constellation;
+pub use constellation::Constellation;
+
+mod fsk;
+pub use fsk::FskDemodulator;
+
+mod ssb;
+pub use ssb::{Sideband, SsbDemodulator, SsbModulator, YttriaVectorSsb};
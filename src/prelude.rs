@@ -1,5 +1,16 @@
+pub use super::filter::{
+    YttriaVectorDecimate, YttriaVectorResample, YttriaVectorSparse, YttriaVectorSpectralMask,
+};
+pub use super::modulation::YttriaVectorSsb;
+pub use super::rng::YttriaRng;
+pub use super::signal::{YttriaVectorAmbiguity, YttriaVectorImd, YttriaVectorThreshold};
 pub use super::unit::YttriaUnitSqrt;
+#[cfg(feature = "half")]
+pub use super::vector::YttriaVectorHalf;
 pub use super::vector::{
-    YttriaVectorArithmetic, YttriaVectorBitwise, YttriaVectorComplex, YttriaVectorComplexFft,
-    YttriaVectorStatistics, YttriaVectorUtils,
+    YttriaVectorArithmetic, YttriaVectorBatch, YttriaVectorBatchMut, YttriaVectorBitwise,
+    YttriaVectorBoolBits, YttriaVectorBytes, YttriaVectorCepstrum, YttriaVectorCompare,
+    YttriaVectorComplex, YttriaVectorComplexFft, YttriaVectorFloat, YttriaVectorGainCurve,
+    YttriaVectorImpairments, YttriaVectorInteger, YttriaVectorRealFft, YttriaVectorRegrid,
+    YttriaVectorSpectrum, YttriaVectorStatistics, YttriaVectorTranspose, YttriaVectorUtils,
 };
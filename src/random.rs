@@ -0,0 +1,130 @@
+use num::{Complex, Float, FromPrimitive};
+
+use crate::rng::YttriaRng;
+
+fn from_f64<T: FromPrimitive>(x: f64) -> T {
+    T::from_f64(x).expect("Could not convert f64 into type")
+}
+
+fn to_f64<T: Float>(x: T) -> f64 {
+    x.to_f64().expect("Could not convert type into f64")
+}
+
+// Random test vectors drawn from `rng`, so the same seed always gives the same vector. Samples
+// are generated in f64 and converted.
+
+// Uniform on [low, high), or all `low` when the two are equal. A draw that rounds up to `high`
+// on conversion, as the top of the range does in f32, is drawn again.
+pub fn uniform<T: Float + FromPrimitive>(
+    n: usize,
+    low: T,
+    high: T,
+    rng: &mut impl YttriaRng,
+) -> Vec<T> {
+    assert!(low <= high, "Lower bound must not exceed the upper bound");
+    let (low_f64, span) = (to_f64(low), to_f64(high) - to_f64(low));
+    (0..n)
+        .map(|_| loop {
+            let x = from_f64(low_f64 + span * rng.next_f64());
+            if x < high || low == high {
+                break x;
+            }
+        })
+        .collect()
+}
+
+pub fn normal<T: Float + FromPrimitive>(
+    n: usize,
+    mean: T,
+    std: T,
+    rng: &mut impl YttriaRng,
+) -> Vec<T> {
+    assert!(std >= T::zero(), "Standard deviation must not be negative");
+    let (mean, std) = (to_f64(mean), to_f64(std));
+    (0..n)
+        .map(|_| from_f64(mean + std * rng.next_gaussian()))
+        .collect()
+}
+
+// Circular complex Gaussian noise of unit power, half of it in each of the real and imaginary
+// parts.
+pub fn complex_normal<T: Float + FromPrimitive>(
+    n: usize,
+    rng: &mut impl YttriaRng,
+) -> Vec<Complex<T>> {
+    let sigma = std::f64::consts::FRAC_1_SQRT_2;
+    (0..n)
+        .map(|_| {
+            let re = sigma * rng.next_gaussian();
+            let im = sigma * rng.next_gaussian();
+            Complex::new(from_f64(re), from_f64(im))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Xoshiro256PlusPlus;
+
+    fn moments(x: &[f64]) -> (f64, f64) {
+        let n = x.len() as f64;
+        let mean = x.iter().sum::<f64>() / n;
+        let variance = x.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_uniform_moments() {
+        let x = uniform(1_000_000, -2.0, 6.0, &mut Xoshiro256PlusPlus::seed_from(1));
+        assert!(x.iter().all(|x| (-2.0..6.0).contains(x)));
+        let (mean, variance) = moments(&x);
+        // Standard errors are about 2.3e-3 for the mean and 4.8e-3 for the variance.
+        assert!((mean - 2.0).abs() < 0.012, "{mean}");
+        assert!((variance - 64.0 / 12.0).abs() < 0.024, "{variance}");
+    }
+
+    #[test]
+    fn test_uniform_f32_excludes_high() {
+        // Four f32 values wide, so most draws land between two of them and round.
+        let (low, high) = (1.0f32, 1.0 + 4.0 * f32::EPSILON);
+        let x = uniform(10_000, low, high, &mut Xoshiro256PlusPlus::seed_from(4));
+        assert!(x.iter().all(|x| (low..high).contains(x)));
+        for k in 0..4 {
+            assert!(x.contains(&(low + k as f32 * f32::EPSILON)), "{k}");
+        }
+
+        assert_eq!(
+            uniform(3, 2.0f32, 2.0, &mut Xoshiro256PlusPlus::seed_from(4)),
+            [2.0; 3]
+        );
+    }
+
+    #[test]
+    fn test_normal_moments() {
+        let x = normal(1_000_000, 3.0, 0.5, &mut Xoshiro256PlusPlus::seed_from(2));
+        let (mean, variance) = moments(&x);
+        assert!((mean - 3.0).abs() < 2.5e-3, "{mean}");
+        assert!((variance - 0.25).abs() < 1.8e-3, "{variance}");
+
+        let x = normal(1000, 0.0f32, 1.0, &mut Xoshiro256PlusPlus::seed_from(3));
+        assert_eq!(
+            x,
+            normal(1000, 0.0, 1.0, &mut Xoshiro256PlusPlus::seed_from(3))
+        );
+    }
+
+    #[test]
+    fn test_complex_normal_moments() {
+        let x = complex_normal::<f64>(1_000_000, &mut Xoshiro256PlusPlus::seed_from(4));
+        let re = x.iter().map(|x| x.re).collect::<Vec<_>>();
+        let im = x.iter().map(|x| x.im).collect::<Vec<_>>();
+        for part in [re, im] {
+            let (mean, variance) = moments(&part);
+            assert!(mean.abs() < 3.6e-3, "{mean}");
+            assert!((variance - 0.5).abs() < 3.6e-3, "{variance}");
+        }
+        let correlation = x.iter().map(|x| x.re * x.im).sum::<f64>() / x.len() as f64;
+        assert!(correlation.abs() < 2.5e-3, "{correlation}");
+    }
+}
@@ -0,0 +1,138 @@
+// Randomness for every randomized API in the crate. Functions that consume random numbers take
+// `&mut impl YttriaRng` rather than seeding themselves, and nothing reads from the operating
+// system, so a simulation is reproduced by passing a generator built from the same seed. Types
+// that draw numbers as they run, like a dithered `Nco`, own the generator they are given.
+//
+// Stability: for a given seed, `Xoshiro256PlusPlus::seed_from` and every provided method below
+// produce the same sequence in all releases sharing a major version.
+pub trait YttriaRng {
+    fn seed_from(seed: u64) -> Self
+    where
+        Self: Sized;
+
+    fn next_u64(&mut self) -> u64;
+
+    // Uniform on [0, 1) with 53 bits of precision.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    // Standard normal via Box-Muller, using the cosine branch of one pair of uniforms.
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    // Uniform on [0, 1) with 24 bits of precision, one `next_u64` per sample.
+    fn fill_f32_slice_uniform(&mut self, out: &mut [f32]) {
+        for x in out.iter_mut() {
+            *x = (self.next_u64() >> 40) as f32 * (1.0 / (1u32 << 24) as f32);
+        }
+    }
+
+    // Standard normal via Box-Muller in f64, keeping both the cosine and the sine branch, so
+    // each pair of samples consumes two `next_u64`. An odd final sample uses the cosine branch
+    // alone, like `next_gaussian`.
+    fn fill_f32_slice_gaussian(&mut self, out: &mut [f32]) {
+        for pair in out.chunks_mut(2) {
+            let u1 = 1.0 - self.next_f64();
+            let u2 = self.next_f64();
+            let radius = (-2.0 * u1.ln()).sqrt();
+            let (sin, cos) = (2.0 * std::f64::consts::PI * u2).sin_cos();
+            pair[0] = (radius * cos) as f32;
+            if let Some(x) = pair.get_mut(1) {
+                *x = (radius * sin) as f32;
+            }
+        }
+    }
+}
+
+// xoshiro256++ by Blackman and Vigna, seeded through splitmix64. Small and fast, though not
+// suitable for cryptography.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xoshiro256PlusPlus {
+    state: [u64; 4],
+}
+
+impl YttriaRng for Xoshiro256PlusPlus {
+    fn seed_from(seed: u64) -> Self {
+        // splitmix64 expansion of the seed, as recommended by the xoshiro authors.
+        let mut x = seed;
+        let mut state = [0u64; 4];
+        for s in state.iter_mut() {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *s = z ^ (z >> 31);
+        }
+        Self { state }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+}
+
+// Unit variance Gaussian samples from a seeded generator, the noise most tests start from.
+#[cfg(test)]
+pub(crate) fn gaussian_noise(len: usize, seed: u64) -> Vec<f64> {
+    let mut rng = Xoshiro256PlusPlus::seed_from(seed);
+    (0..len).map(|_| rng.next_gaussian()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaussian_moments() {
+        let mut out = vec![0f32; 10_000_000];
+        Xoshiro256PlusPlus::seed_from(1).fill_f32_slice_gaussian(&mut out);
+
+        let n = out.len() as f64;
+        let mean = out.iter().map(|x| *x as f64).sum::<f64>() / n;
+        let central = |p: i32| out.iter().map(|x| (*x as f64 - mean).powi(p)).sum::<f64>() / n;
+        let variance = central(2);
+        let kurtosis = central(4) / (variance * variance);
+
+        // Standard errors over 1e7 samples are sqrt(1 / n) = 3.2e-4 for the mean,
+        // sqrt(2 / n) = 4.5e-4 for the variance and sqrt(24 / n) = 1.55e-3 for the kurtosis; the
+        // bounds allow five of them.
+        assert!(mean.abs() < 1.6e-3, "{mean}");
+        assert!((variance - 1.0).abs() < 2.3e-3, "{variance}");
+        assert!((kurtosis - 3.0).abs() < 7.7e-3, "{kurtosis}");
+    }
+
+    #[test]
+    fn test_uniform_range_and_mean() {
+        let mut out = vec![0f32; 1_000_000];
+        Xoshiro256PlusPlus::seed_from(2).fill_f32_slice_uniform(&mut out);
+        assert!(out.iter().all(|x| (0.0..1.0).contains(x)));
+
+        let mean = out.iter().map(|x| *x as f64).sum::<f64>() / out.len() as f64;
+        assert!((mean - 0.5).abs() < 1.5e-3, "{mean}");
+    }
+
+    #[test]
+    fn test_odd_gaussian_fill() {
+        let mut odd = [0f32; 5];
+        let mut even = [0f32; 6];
+        Xoshiro256PlusPlus::seed_from(3).fill_f32_slice_gaussian(&mut odd);
+        Xoshiro256PlusPlus::seed_from(3).fill_f32_slice_gaussian(&mut even);
+        assert_eq!(odd, even[..5]);
+        assert!(odd.iter().all(|x| x.is_finite()));
+    }
+}
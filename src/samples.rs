@@ -0,0 +1,401 @@
+use std::fmt;
+use std::ops::{Add, Deref, DerefMut, Mul, Sub};
+
+use crate::filter::{
+    DecimateConfig, FilterSample, PlanConfig, PlanError, ResamplePlan, YttriaVectorDecimate,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplesError {
+    RateMismatch {
+        left_hz: f64,
+        right_hz: f64,
+    },
+    CenterMismatch {
+        left_hz: Option<f64>,
+        right_hz: Option<f64>,
+    },
+    LengthMismatch {
+        left: usize,
+        right: usize,
+    },
+}
+
+impl fmt::Display for SamplesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateMismatch { left_hz, right_hz } => {
+                write!(f, "sample rates differ: {left_hz} Hz and {right_hz} Hz")
+            }
+            Self::CenterMismatch { left_hz, right_hz } => {
+                write!(f, "center frequencies differ: {left_hz:?} and {right_hz:?}")
+            }
+            Self::LengthMismatch { left, right } => {
+                write!(f, "lengths differ: {left} and {right} samples")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SamplesError {}
+
+// Samples tagged with their rate, and for complex baseband the RF frequency they are centred on,
+// so processing that changes the rate keeps the two together. Derefs to the plain slice, so every
+// vector trait still applies; those return bare vectors, which `map` wraps back up for methods
+// that keep the rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Samples<T> {
+    data: Vec<T>,
+    sample_rate_hz: f64,
+    center_hz: Option<f64>,
+}
+
+impl<T> Deref for Samples<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.data
+    }
+}
+
+impl<T> DerefMut for Samples<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+}
+
+impl<T> Samples<T> {
+    pub fn new(data: Vec<T>, sample_rate_hz: f64) -> Self {
+        assert!(
+            sample_rate_hz.is_finite() && sample_rate_hz > 0.0,
+            "Sample rate {sample_rate_hz} is not positive and finite"
+        );
+        Self {
+            data,
+            sample_rate_hz,
+            center_hz: None,
+        }
+    }
+
+    pub fn with_center_hz(mut self, center_hz: f64) -> Self {
+        self.center_hz = Some(center_hz);
+        self
+    }
+
+    pub fn sample_rate_hz(&self) -> f64 {
+        self.sample_rate_hz
+    }
+
+    pub fn center_hz(&self) -> Option<f64> {
+        self.center_hz
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    // Wraps the result of rate preserving processing with this metadata.
+    pub fn map<U>(&self, f: impl FnOnce(&[T]) -> Vec<U>) -> Samples<U> {
+        Samples {
+            data: f(&self.data),
+            sample_rate_hz: self.sample_rate_hz,
+            center_hz: self.center_hz,
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.data.len() as f64 / self.sample_rate_hz
+    }
+
+    // Time of each sample in seconds, the first being at zero.
+    pub fn time_axis(&self) -> Vec<f64> {
+        (0..self.data.len())
+            .map(|n| n as f64 / self.sample_rate_hz)
+            .collect()
+    }
+
+    // Frequency of each bin of an FFT as long as the samples, in the unshifted order `fft`
+    // returns, offset by the center frequency when there is one.
+    pub fn freq_axis(&self) -> Vec<f64> {
+        let len = self.data.len();
+        let center = self.center_hz.unwrap_or(0.0);
+        (0..len)
+            .map(|k| {
+                let bin = if k < len.div_ceil(2) {
+                    k as f64
+                } else {
+                    k as f64 - len as f64
+                };
+                center + bin * self.sample_rate_hz / len as f64
+            })
+            .collect()
+    }
+
+    fn check_compatible<U>(&self, other: &Samples<U>) -> Result<(), SamplesError> {
+        if self.sample_rate_hz != other.sample_rate_hz {
+            return Err(SamplesError::RateMismatch {
+                left_hz: self.sample_rate_hz,
+                right_hz: other.sample_rate_hz,
+            });
+        }
+        if self.center_hz != other.center_hz {
+            return Err(SamplesError::CenterMismatch {
+                left_hz: self.center_hz,
+                right_hz: other.center_hz,
+            });
+        }
+        if self.data.len() != other.data.len() {
+            return Err(SamplesError::LengthMismatch {
+                left: self.data.len(),
+                right: other.data.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn zip_with(
+        &self,
+        other: &Samples<T>,
+        f: impl Fn(T, T) -> T,
+    ) -> Result<Samples<T>, SamplesError>
+    where
+        T: Copy,
+    {
+        self.check_compatible(other)?;
+        Ok(self.map(|x| x.iter().zip(&other.data).map(|(a, b)| f(*a, *b)).collect()))
+    }
+}
+
+impl<T> Samples<T> {
+    // The samples at times `start_s <= t < end_s`, borrowed along with the metadata. A sample
+    // within a billionth of a sample period of either boundary counts as on it, so boundaries
+    // computed in floating point do not lose or gain a sample to rounding. The slice's own time
+    // axis starts again from zero.
+    pub fn slice_time(&self, start_s: f64, end_s: f64) -> SamplesView<'_, T> {
+        assert!(
+            start_s <= end_s,
+            "Slice start {start_s} s is after its end {end_s} s"
+        );
+        let index = |t: f64| {
+            let n = (t * self.sample_rate_hz - 1e-9).ceil().max(0.0);
+            (n as usize).min(self.data.len())
+        };
+        SamplesView {
+            data: &self.data[index(start_s)..index(end_s)],
+            sample_rate_hz: self.sample_rate_hz,
+            center_hz: self.center_hz,
+        }
+    }
+}
+
+// A borrowed stretch of `Samples`, keeping their rate and center frequency without copying the
+// data. Derefs to the plain slice like `Samples` does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplesView<'a, T> {
+    data: &'a [T],
+    sample_rate_hz: f64,
+    center_hz: Option<f64>,
+}
+
+impl<T> Deref for SamplesView<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.data
+    }
+}
+
+impl<'a, T> SamplesView<'a, T> {
+    pub fn sample_rate_hz(&self) -> f64 {
+        self.sample_rate_hz
+    }
+
+    pub fn center_hz(&self) -> Option<f64> {
+        self.center_hz
+    }
+
+    pub fn as_slice(&self) -> &'a [T] {
+        self.data
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.data.len() as f64 / self.sample_rate_hz
+    }
+
+    // Wraps the result of rate preserving processing with this metadata, as `Samples::map`.
+    pub fn map<U>(&self, f: impl FnOnce(&[T]) -> Vec<U>) -> Samples<U> {
+        Samples {
+            data: f(self.data),
+            sample_rate_hz: self.sample_rate_hz,
+            center_hz: self.center_hz,
+        }
+    }
+
+    pub fn to_samples(&self) -> Samples<T>
+    where
+        T: Clone,
+    {
+        self.map(<[T]>::to_vec)
+    }
+}
+
+// Element-wise arithmetic between two signals, which must share their rate, center frequency and
+// length.
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>> Samples<T> {
+    pub fn add_samples(&self, other: &Samples<T>) -> Result<Samples<T>, SamplesError> {
+        self.zip_with(other, |a, b| a + b)
+    }
+
+    pub fn subtract_samples(&self, other: &Samples<T>) -> Result<Samples<T>, SamplesError> {
+        self.zip_with(other, |a, b| a - b)
+    }
+
+    pub fn multiply_samples(&self, other: &Samples<T>) -> Result<Samples<T>, SamplesError> {
+        self.zip_with(other, |a, b| a * b)
+    }
+}
+
+impl<S: FilterSample> Samples<S> {
+    // `YttriaVectorDecimate::decimate_filtered`, dividing the rate by `factor`.
+    pub fn decimate_filtered(&self, factor: usize, config: DecimateConfig) -> Samples<S> {
+        let mut out = self.map(|x| x.decimate_filtered(factor, config));
+        out.sample_rate_hz /= factor as f64;
+        out
+    }
+
+    // Resamples through a `ResamplePlan` to `rate_hz`, or as close to it as `config` allows; the
+    // result carries the rate actually achieved.
+    pub fn resample_to(&self, rate_hz: f64, config: PlanConfig) -> Result<Samples<S>, PlanError> {
+        let plan = ResamplePlan::from_rates(self.sample_rate_hz, rate_hz, config)?;
+        let mut out = self.map(|x| plan.execute(x));
+        out.sample_rate_hz = plan.achieved_output_hz();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{
+        YttriaVectorArithmetic, YttriaVectorComplex, YttriaVectorComplexFft, YttriaVectorStatistics,
+    };
+    use num::Complex;
+    use std::f64::consts::PI;
+
+    fn tone(rate_hz: f64, freq_hz: f64, len: usize) -> Samples<Complex<f64>> {
+        let data = (0..len)
+            .map(|n| Complex::from_polar(1.0, 2.0 * PI * freq_hz * n as f64 / rate_hz))
+            .collect();
+        Samples::new(data, rate_hz)
+    }
+
+    fn peak_hz(x: &Samples<Complex<f64>>) -> f64 {
+        let spectrum = x.map(|x| x.fft().magnitude());
+        let peak = spectrum
+            .iter()
+            .enumerate()
+            .fold(0, |best, (k, m)| if *m > spectrum[best] { k } else { best });
+        spectrum.freq_axis()[peak]
+    }
+
+    #[test]
+    fn test_decimate_fft_frequencies() {
+        // A -12.5 kHz tone sampled at 1 MHz around 100 MHz, decimated by 8 to 125 kHz.
+        let x = tone(1e6, -12_500.0, 80_000).with_center_hz(100e6);
+        assert_eq!(peak_hz(&x), 100e6 - 12_500.0);
+
+        let decimated = x.decimate_filtered(8, DecimateConfig::new());
+        assert_eq!(decimated.sample_rate_hz(), 125e3);
+        assert_eq!(decimated.center_hz(), Some(100e6));
+        assert_eq!(decimated.len(), 10_000);
+        assert_eq!(decimated.duration(), x.duration());
+        assert_eq!(peak_hz(&decimated), 100e6 - 12_500.0);
+
+        let resampled = decimated.resample_to(100e3, PlanConfig::new()).unwrap();
+        assert_eq!(resampled.sample_rate_hz(), 100e3);
+        assert_eq!(resampled.len(), 8000);
+        assert_eq!(peak_hz(&resampled), 100e6 - 12_500.0);
+    }
+
+    #[test]
+    fn test_axes() {
+        let x = Samples::new(vec![0.0; 5], 10.0).with_center_hz(1000.0);
+        assert_eq!(x.time_axis(), [0.0, 0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(x.freq_axis(), [1000.0, 1002.0, 1004.0, 996.0, 998.0]);
+        let even = Samples::new(vec![0.0; 4], 8.0);
+        assert_eq!(even.freq_axis(), [0.0, 2.0, -4.0, -2.0]);
+    }
+
+    #[test]
+    fn test_slice_time_boundaries() {
+        let x = Samples::new((0..100).collect::<Vec<i32>>(), 10.0);
+        // Samples at exactly the start are kept and at exactly the end dropped, even when the
+        // boundaries carry rounding error.
+        assert_eq!(*x.slice_time(0.3, 0.5), [3, 4]);
+        assert_eq!(*x.slice_time(0.1 * 3.0, 0.7 - 0.2), [3, 4]);
+        assert_eq!(*x.slice_time(0.25, 0.51), [3, 4, 5]);
+        assert!(x.slice_time(0.42, 0.48).is_empty());
+        assert_eq!(x.slice_time(-1.0, 0.2).len(), 2);
+        assert_eq!(x.slice_time(9.5, 20.0).len(), 5);
+        assert!(x.slice_time(20.0, 30.0).is_empty());
+        assert_eq!(x.slice_time(0.0, 10.0).to_samples(), x);
+        assert_eq!(x.slice_time(1.0, 2.0).sample_rate_hz(), 10.0);
+
+        // The slice borrows the samples rather than copying them.
+        let centred = x.clone().with_center_hz(5.0);
+        let view = centred.slice_time(0.3, 0.5);
+        assert_eq!(view.as_ptr(), centred[3..].as_ptr());
+        assert_eq!((view.center_hz(), view.duration()), (Some(5.0), 0.2));
+        assert_eq!(*view.map(|x| x.to_vec()), [3, 4]);
+    }
+
+    #[test]
+    fn test_binary_ops_check_metadata() {
+        let a = Samples::new(vec![1.0, 2.0, 3.0], 48e3);
+        let b = Samples::new(vec![0.5, 0.5, 0.5], 48e3);
+        assert_eq!(*a.add_samples(&b).unwrap(), [1.5, 2.5, 3.5]);
+        assert_eq!(*a.subtract_samples(&b).unwrap(), [0.5, 1.5, 2.5]);
+        assert_eq!(*a.multiply_samples(&b).unwrap(), [0.5, 1.0, 1.5]);
+
+        let other_rate = Samples::new(vec![0.5; 3], 44.1e3);
+        assert_eq!(
+            a.add_samples(&other_rate),
+            Err(SamplesError::RateMismatch {
+                left_hz: 48e3,
+                right_hz: 44.1e3
+            })
+        );
+        assert_eq!(
+            a.multiply_samples(&b.clone().with_center_hz(1e6)),
+            Err(SamplesError::CenterMismatch {
+                left_hz: None,
+                right_hz: Some(1e6)
+            })
+        );
+        assert_eq!(
+            a.add_samples(&Samples::new(vec![0.5; 4], 48e3)),
+            Err(SamplesError::LengthMismatch { left: 3, right: 4 })
+        );
+    }
+
+    #[test]
+    fn test_deref_interop() {
+        let mut x = Samples::new(vec![1.0, 2.0, 3.0, 4.0], 8e3);
+        // Slice traits apply directly, in place through `DerefMut`.
+        assert_eq!(x.mean(), 2.5);
+        x.multiply_const_inplace(2.0);
+        assert_eq!(x.as_slice(), [2.0, 4.0, 6.0, 8.0]);
+        let scaled = x.map(|x| x.add_const(1.0));
+        assert_eq!((scaled.sample_rate_hz(), scaled[0]), (8e3, 3.0));
+
+        fn takes_slice(x: &[f64]) -> f64 {
+            x.iter().sum()
+        }
+        assert_eq!(takes_slice(&x), 20.0);
+        assert_eq!(x.into_vec(), [2.0, 4.0, 6.0, 8.0]);
+    }
+}
@@ -0,0 +1,390 @@
+use num::{Complex, Float, FromPrimitive, Zero};
+use rayon::prelude::*;
+use rustfft::{Fft, FftNum, FftPlanner};
+
+use crate::matrix::Matrix;
+use crate::vector::{assert_output_len, FftContext};
+
+// Delay by Doppler grid of a cross-ambiguity surface. Rows are delays `0..=max_delay` samples
+// and columns `doppler_bins` Doppler shifts centred on zero, spaced `1 / fft_len()` cycles per
+// sample with `fft_len()` the whole number closest to `doppler_bins / doppler_span`, so the
+// columns span `doppler_span` as closely as a whole transform length allows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmbiguityGrid<T> {
+    max_delay: usize,
+    doppler_bins: usize,
+    doppler_span: T,
+    fft_len: usize,
+}
+
+impl<T: Float> AmbiguityGrid<T> {
+    pub fn new(max_delay: usize, doppler_bins: usize, doppler_span: T) -> Self {
+        assert!(doppler_bins > 0, "Need at least one Doppler bin");
+        assert!(
+            doppler_span > T::zero() && doppler_span <= T::one(),
+            "Doppler span must lie within (0, 1] cycles per sample"
+        );
+        let span = doppler_span
+            .to_f64()
+            .expect("Could not convert type into f64");
+        let fft_len = ((doppler_bins as f64 / span).round() as usize).max(doppler_bins);
+
+        Self {
+            max_delay,
+            doppler_bins,
+            doppler_span,
+            fft_len,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.max_delay + 1
+    }
+
+    pub fn cols(&self) -> usize {
+        self.doppler_bins
+    }
+
+    pub fn doppler_span(&self) -> T {
+        self.doppler_span
+    }
+
+    pub fn fft_len(&self) -> usize {
+        self.fft_len
+    }
+
+    // Doppler shift of each column in cycles per sample.
+    pub fn doppler_axis(&self) -> Vec<T>
+    where
+        T: FromPrimitive,
+    {
+        (0..self.doppler_bins)
+            .map(|k| {
+                let bin = k as f64 - (self.doppler_bins / 2) as f64;
+                T::from_f64(bin / self.fft_len as f64).expect("Could not convert f64 into type")
+            })
+            .collect()
+    }
+
+    fn row_scratch_len(&self, fft: &dyn Fft<T>) -> usize
+    where
+        T: FftNum,
+    {
+        self.fft_len + fft.get_inplace_scratch_len()
+    }
+
+    // Scratch `cross_ambiguity_into` needs for `rows` delays at once.
+    pub fn scratch_len(&self, rows: usize) -> usize
+    where
+        T: FftNum,
+    {
+        let fft = FftPlanner::<T>::new().plan_fft_forward(self.fft_len);
+        rows * self.row_scratch_len(fft.as_ref())
+    }
+}
+
+// Magnitudes of the delays `first..first + out.len() / cols` into `out`, one row per delay and
+// one parallel task per row.
+fn fill_rows<T>(
+    surveillance: &[Complex<T>],
+    reference: &[Complex<T>],
+    grid: &AmbiguityGrid<T>,
+    first: usize,
+    out: &mut [T],
+    scratch: &mut [Complex<T>],
+    fft: &dyn Fft<T>,
+) where
+    T: FftNum + Float,
+{
+    let (cols, fft_len) = (grid.doppler_bins, grid.fft_len);
+    out.par_chunks_mut(cols)
+        .zip(scratch.par_chunks_mut(grid.row_scratch_len(fft)))
+        .enumerate()
+        .for_each(|(row, (out, scratch))| {
+            let delay = first + row;
+            let (buffer, scratch) = scratch.split_at_mut(fft_len);
+            buffer.fill(Complex::zero());
+            // Folding the lag product modulo the transform length samples its DTFT exactly at
+            // multiples of `1 / fft_len`, however long the integration.
+            let products = surveillance.iter().skip(delay).zip(reference);
+            for (n, (s, r)) in products.enumerate() {
+                let bin = &mut buffer[(n + delay) % fft_len];
+                *bin = *bin + s * r.conj();
+            }
+            fft.process_with_scratch(buffer, scratch);
+
+            for (k, out) in out.iter_mut().enumerate() {
+                let bin = (k + fft_len - cols / 2) % fft_len;
+                *out = buffer[bin].norm();
+            }
+        });
+}
+
+// Cross-ambiguity function of a surveillance signal against a reference,
+// `|sum_n s[n] conj(r[n - delay]) exp(-2 pi i f n)|` over an `AmbiguityGrid`, with the reference
+// taken as zero before its first sample. Each delay multiplies the signals and transforms the
+// product across time, costing `len + fft_len log(fft_len)` per row, and the rows are spread over
+// threads. A target echo delayed by `d` and shifted by `f` peaks at row `d` in the column nearest
+// `f`, its coherent gain growing with the integrated samples against noise that grows with their
+// square root, so doubling the length gains 3 dB.
+pub trait YttriaVectorAmbiguity<T: FftNum> {
+    // `rows x cols` magnitudes, row-major.
+    fn cross_ambiguity(
+        &self,
+        reference: &[Complex<T>],
+        max_delay: usize,
+        doppler_bins: usize,
+        doppler_span_norm: T,
+    ) -> Matrix<T>;
+    // Allocation free, with scratch of at least `grid.scratch_len(grid.rows())`.
+    fn cross_ambiguity_into(
+        &self,
+        reference: &[Complex<T>],
+        grid: &AmbiguityGrid<T>,
+        out: &mut [T],
+        scratch: &mut [Complex<T>],
+        context: &mut FftContext<T>,
+    );
+    // Computes `stripe_delays` delays at a time, handing each stripe to `f` along with its first
+    // delay, so only one stripe of the surface is ever held.
+    fn cross_ambiguity_chunked<F>(
+        &self,
+        reference: &[Complex<T>],
+        grid: &AmbiguityGrid<T>,
+        stripe_delays: usize,
+        f: F,
+    ) where
+        F: FnMut(usize, &Matrix<T>);
+}
+
+fn assert_same_len<T>(surveillance: &[T], reference: &[T]) {
+    assert!(
+        surveillance.len() == reference.len(),
+        "Reference has {} samples but the surveillance signal {}",
+        reference.len(),
+        surveillance.len()
+    );
+}
+
+impl<T> YttriaVectorAmbiguity<T> for [Complex<T>]
+where
+    T: FftNum + Float,
+{
+    fn cross_ambiguity(
+        &self,
+        reference: &[Complex<T>],
+        max_delay: usize,
+        doppler_bins: usize,
+        doppler_span_norm: T,
+    ) -> Matrix<T> {
+        let grid = AmbiguityGrid::new(max_delay, doppler_bins, doppler_span_norm);
+        let mut out = vec![T::zero(); grid.rows() * grid.cols()];
+        let mut scratch = vec![Complex::zero(); grid.scratch_len(grid.rows())];
+        self.cross_ambiguity_into(
+            reference,
+            &grid,
+            &mut out,
+            &mut scratch,
+            &mut FftContext::new(),
+        );
+        Matrix::from_vec(grid.rows(), grid.cols(), out)
+    }
+
+    fn cross_ambiguity_into(
+        &self,
+        reference: &[Complex<T>],
+        grid: &AmbiguityGrid<T>,
+        out: &mut [T],
+        scratch: &mut [Complex<T>],
+        context: &mut FftContext<T>,
+    ) {
+        assert_same_len(self, reference);
+        assert_output_len("cross_ambiguity_into", grid.rows() * grid.cols(), out.len());
+        let fft = context.plan_forward(grid.fft_len);
+        let needed = grid.rows() * grid.row_scratch_len(fft.as_ref());
+        assert!(
+            scratch.len() >= needed,
+            "cross_ambiguity_into needs at least {needed} scratch elements, got {}",
+            scratch.len()
+        );
+        fill_rows(self, reference, grid, 0, out, scratch, fft.as_ref());
+    }
+
+    fn cross_ambiguity_chunked<F>(
+        &self,
+        reference: &[Complex<T>],
+        grid: &AmbiguityGrid<T>,
+        stripe_delays: usize,
+        mut f: F,
+    ) where
+        F: FnMut(usize, &Matrix<T>),
+    {
+        assert_same_len(self, reference);
+        assert!(stripe_delays > 0, "Stripes need at least one delay");
+        let fft = FftContext::new().plan_forward(grid.fft_len);
+        let stripe_delays = stripe_delays.min(grid.rows());
+        let mut scratch = vec![Complex::zero(); stripe_delays * grid.row_scratch_len(fft.as_ref())];
+        let mut stripe = Matrix::zeros(stripe_delays, grid.cols());
+
+        for first in (0..grid.rows()).step_by(stripe_delays) {
+            let rows = stripe_delays.min(grid.rows() - first);
+            if rows < stripe.rows() {
+                stripe = Matrix::zeros(rows, grid.cols());
+            }
+            fill_rows(
+                self,
+                reference,
+                grid,
+                first,
+                stripe.as_mut_slice(),
+                &mut scratch,
+                fft.as_ref(),
+            );
+            f(first, &stripe);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::complex_normal;
+    use crate::rng::{Xoshiro256PlusPlus, YttriaRng};
+    use std::f64::consts::PI;
+
+    // Unit modulus reference with random phases, so the lag product at the target is a pure tone.
+    fn reference(len: usize, seed: u64) -> Vec<Complex<f64>> {
+        let mut rng = Xoshiro256PlusPlus::seed_from(seed);
+        (0..len)
+            .map(|_| Complex::from_polar(1.0, 2.0 * PI * rng.next_f64()))
+            .collect()
+    }
+
+    // `reference` delayed by `delay` samples and shifted by `doppler` cycles per sample.
+    fn echo(reference: &[Complex<f64>], delay: usize, doppler: f64) -> Vec<Complex<f64>> {
+        (0..reference.len())
+            .map(|n| {
+                let x = n
+                    .checked_sub(delay)
+                    .map_or(Complex::zero(), |m| reference[m]);
+                x * Complex::from_polar(1.0, 2.0 * PI * doppler * n as f64)
+            })
+            .collect()
+    }
+
+    fn argmax(caf: &Matrix<f64>) -> (usize, usize) {
+        let i = (0..caf.as_slice().len())
+            .max_by(|a, b| caf.as_slice()[*a].total_cmp(&caf.as_slice()[*b]))
+            .unwrap();
+        (i / caf.cols(), i % caf.cols())
+    }
+
+    #[test]
+    fn test_peak_and_doppler_spread() {
+        let (len, delay, doppler) = (4096, 37, 0.0123);
+        let r = reference(len, 1);
+        let s = echo(&r, delay, doppler);
+        let grid = AmbiguityGrid::new(64, 128, 0.1);
+        assert_eq!(grid.fft_len(), 1280);
+        let axis = grid.doppler_axis();
+
+        let caf = s.cross_ambiguity(&r, 64, 128, 0.1);
+        let (row, col) = argmax(&caf);
+        let nearest = (0..axis.len())
+            .min_by(|a, b| {
+                (axis[*a] - doppler)
+                    .abs()
+                    .total_cmp(&(axis[*b] - doppler).abs())
+            })
+            .unwrap();
+        assert_eq!((row, col), (delay, nearest));
+
+        // Integrating `len - delay` samples of a pure tone gives a Dirichlet kernel in Doppler.
+        let integrated = (len - delay) as f64;
+        for (k, f) in axis.iter().enumerate() {
+            let x = PI * (f - doppler);
+            let expected = ((integrated * x).sin() / x.sin()).abs();
+            assert!(
+                (caf[(delay, k)] - expected).abs() < 1e-6 * integrated,
+                "{k}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_direct_computation() {
+        let mut rng = Xoshiro256PlusPlus::seed_from(2);
+        let s = complex_normal::<f64>(40, &mut rng);
+        let r = complex_normal::<f64>(40, &mut rng);
+        let grid = AmbiguityGrid::new(5, 8, 0.5);
+        let caf = s.cross_ambiguity(&r, 5, 8, 0.5);
+        assert_eq!((caf.rows(), caf.cols()), (6, 8));
+
+        for delay in 0..=5 {
+            for (k, f) in grid.doppler_axis().iter().enumerate() {
+                let direct = (delay..40)
+                    .map(|n| {
+                        s[n] * r[n - delay].conj()
+                            * Complex::from_polar(1.0, -2.0 * PI * f * n as f64)
+                    })
+                    .sum::<Complex<f64>>()
+                    .norm();
+                assert!((caf[(delay, k)] - direct).abs() < 1e-9, "{delay} {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_integration_gain() {
+        // A weak echo 10 dB below the noise; the peak to mean floor ratio doubles with the
+        // integration length.
+        let gain_db = |len: usize| {
+            (0..8)
+                .map(|seed| {
+                    let r = reference(len, 10 + seed);
+                    let noise =
+                        complex_normal::<f64>(len, &mut Xoshiro256PlusPlus::seed_from(seed));
+                    let s = echo(&r, 20, 0.0)
+                        .iter()
+                        .zip(&noise)
+                        .map(|(s, n)| s * 0.1f64.sqrt() + n)
+                        .collect::<Vec<_>>();
+                    let caf = s.cross_ambiguity(&r, 40, 64, 0.05);
+                    let peak = caf[(20, 32)].powi(2);
+                    let floor = (0..caf.rows())
+                        .filter(|row| row.abs_diff(20) > 1)
+                        .flat_map(|row| caf.row(row).iter().map(|x| x * x))
+                        .sum::<f64>()
+                        / (caf.cols() * (caf.rows() - 3)) as f64;
+                    10.0 * (peak / floor).log10()
+                })
+                .sum::<f64>()
+                / 8.0
+        };
+        let (short, long) = (gain_db(2048), gain_db(4096));
+        assert!(((long - short) - 3.01).abs() < 0.5, "{short} {long}");
+        // Against the floor the peak sits close to the integration length times the 0.1 SNR.
+        assert!((short - 10.0 * (204.8f64).log10()).abs() < 1.0, "{short}");
+    }
+
+    #[test]
+    fn test_chunked_matches_one_shot() {
+        let mut rng = Xoshiro256PlusPlus::seed_from(3);
+        let s = complex_normal::<f32>(1000, &mut rng);
+        let r = complex_normal::<f32>(1000, &mut rng);
+        let grid = AmbiguityGrid::new(10, 16, 0.2f32);
+        let whole = s.cross_ambiguity(&r, 10, 16, 0.2);
+
+        let mut out = vec![0.0; grid.rows() * grid.cols()];
+        let mut scratch = vec![Complex::zero(); grid.scratch_len(grid.rows())];
+        s.cross_ambiguity_into(&r, &grid, &mut out, &mut scratch, &mut FftContext::new());
+        assert_eq!(out, whole.as_slice());
+
+        let mut stripes = Vec::new();
+        s.cross_ambiguity_chunked(&r, &grid, 3, |first, stripe| {
+            assert_eq!(first, stripes.len() / grid.cols());
+            stripes.extend_from_slice(stripe.as_slice());
+        });
+        assert_eq!(stripes, whole.as_slice());
+    }
+}
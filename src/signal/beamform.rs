@@ -0,0 +1,333 @@
+use num::{Complex, Float, FromPrimitive, Zero};
+use rayon::prelude::*;
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::vector::assert_output_len;
+use crate::Matrix;
+
+// Samples per task. Every channel's block stays in cache while all the beams are formed from it.
+const CHUNK_LEN: usize = 2048;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeamformError {
+    NoChannels,
+    // Channel `channel` holds `len` samples where the first holds `expected`.
+    ChannelLength {
+        channel: usize,
+        len: usize,
+        expected: usize,
+    },
+    // `weights` weights were given for `channels` channels.
+    WeightCount {
+        weights: usize,
+        channels: usize,
+    },
+}
+
+impl fmt::Display for BeamformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoChannels => write!(f, "beamforming needs at least one channel"),
+            Self::ChannelLength {
+                channel,
+                len,
+                expected,
+            } => write!(
+                f,
+                "channel {channel} has {len} samples but the first has {expected}"
+            ),
+            Self::WeightCount { weights, channels } => {
+                write!(f, "{weights} weights given for {channels} channels")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BeamformError {}
+
+// Common channel length, once every channel has been checked against it.
+fn channel_len<T, C: AsRef<[T]>>(channels: &[C], weights: usize) -> Result<usize, BeamformError> {
+    let expected = channels
+        .first()
+        .ok_or(BeamformError::NoChannels)?
+        .as_ref()
+        .len();
+    if let Some((channel, len)) = channels
+        .iter()
+        .map(|c| c.as_ref().len())
+        .enumerate()
+        .find(|(_, len)| *len != expected)
+    {
+        return Err(BeamformError::ChannelLength {
+            channel,
+            len,
+            expected,
+        });
+    }
+    if weights != channels.len() {
+        return Err(BeamformError::WeightCount {
+            weights,
+            channels: channels.len(),
+        });
+    }
+    Ok(expected)
+}
+
+// Weighted sum of the channels over the samples from `start`, four channels per pass over `out`.
+fn accumulate<T, C>(channels: &[C], weights: &[Complex<T>], start: usize, out: &mut [Complex<T>])
+where
+    T: Float,
+    C: AsRef<[Complex<T>]>,
+{
+    let range = start..start + out.len();
+    out.fill(Complex::zero());
+
+    let mut groups = channels.chunks_exact(4);
+    let mut group_weights = weights.chunks_exact(4);
+    for (c, w) in (&mut groups).zip(&mut group_weights) {
+        let samples = c[0].as_ref()[range.clone()]
+            .iter()
+            .zip(&c[1].as_ref()[range.clone()])
+            .zip(&c[2].as_ref()[range.clone()])
+            .zip(&c[3].as_ref()[range.clone()]);
+        for (out, (((x0, x1), x2), x3)) in out.iter_mut().zip(samples) {
+            *out = *out + w[0] * x0 + w[1] * x1 + w[2] * x2 + w[3] * x3;
+        }
+    }
+    for (c, w) in groups.remainder().iter().zip(group_weights.remainder()) {
+        for (out, x) in out.iter_mut().zip(&c.as_ref()[range.clone()]) {
+            *out = *out + *w * x;
+        }
+    }
+}
+
+// Coherent sum of equal length channels, each multiplied by its complex weight.
+pub fn beamform_into<T, C>(
+    channels: &[C],
+    weights: &[Complex<T>],
+    out: &mut [Complex<T>],
+) -> Result<(), BeamformError>
+where
+    T: Float + Send + Sync,
+    C: AsRef<[Complex<T>]> + Sync,
+{
+    let len = channel_len(channels, weights.len())?;
+    assert_output_len("beamform_into", len, out.len());
+
+    out.par_chunks_mut(CHUNK_LEN)
+        .enumerate()
+        .for_each(|(chunk, out)| accumulate(channels, weights, chunk * CHUNK_LEN, out));
+    Ok(())
+}
+
+pub fn beamform<T, C>(
+    channels: &[C],
+    weights: &[Complex<T>],
+) -> Result<Vec<Complex<T>>, BeamformError>
+where
+    T: Float + Send + Sync,
+    C: AsRef<[Complex<T>]> + Sync,
+{
+    let len = channel_len(channels, weights.len())?;
+    let mut out = vec![Complex::zero(); len];
+    beamform_into(channels, weights, &mut out)?;
+    Ok(out)
+}
+
+// One beam per row of `weights`, which has a column per channel. All the beams are formed from
+// each block of samples before moving on to the next, so the channels are read only once.
+pub fn beamform_multi<T, C>(
+    channels: &[C],
+    weights: &Matrix<Complex<T>>,
+) -> Result<Vec<Vec<Complex<T>>>, BeamformError>
+where
+    T: Float + Send + Sync,
+    C: AsRef<[Complex<T>]> + Sync,
+{
+    let len = channel_len(channels, weights.cols())?;
+    let mut beams = vec![vec![Complex::zero(); len]; weights.rows()];
+
+    let mut blocks = (0..len.div_ceil(CHUNK_LEN))
+        .map(|_| Vec::with_capacity(beams.len()))
+        .collect::<Vec<_>>();
+    for beam in beams.iter_mut() {
+        for (block, out) in blocks.iter_mut().zip(beam.chunks_mut(CHUNK_LEN)) {
+            block.push(out);
+        }
+    }
+    blocks
+        .into_par_iter()
+        .enumerate()
+        .for_each(|(chunk, block)| {
+            for (beam, out) in block.into_iter().enumerate() {
+                accumulate(channels, weights.row(beam), chunk * CHUNK_LEN, out);
+            }
+        });
+    Ok(beams)
+}
+
+// Unit magnitude weights steering a uniform linear array towards `angle` radians from broadside,
+// with elements `spacing` wavelengths apart. Element `n` of a plane wave from `angle` leads
+// element 0 by `2 pi n spacing sin(angle)`, which the weights undo so the beam gain there is
+// `n_elements`.
+pub fn steering_weights<T>(n_elements: usize, spacing: T, angle: T) -> Vec<Complex<T>>
+where
+    T: Float + FromPrimitive,
+{
+    let step =
+        -T::from_f64(2.0 * PI).expect("Could not convert f64 into type") * spacing * angle.sin();
+    (0..n_elements)
+        .map(|n| {
+            Complex::from_polar(
+                T::one(),
+                step * T::from_usize(n).expect("Could not convert usize into type"),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::YttriaVectorArithmetic;
+
+    fn tone(len: usize, phase: f64) -> Vec<Complex<f64>> {
+        (0..len)
+            .map(|n| Complex::from_polar(1.0, 0.05 * n as f64 + phase))
+            .collect()
+    }
+
+    // Plane wave from `angle` radians across a uniform linear array.
+    fn plane_wave(
+        n_elements: usize,
+        spacing: f64,
+        angle: f64,
+        len: usize,
+    ) -> Vec<Vec<Complex<f64>>> {
+        (0..n_elements)
+            .map(|n| tone(len, 2.0 * PI * n as f64 * spacing * angle.sin()))
+            .collect()
+    }
+
+    fn power(x: &[Complex<f64>]) -> f64 {
+        x.iter().map(|x| x.norm_sqr()).sum::<f64>() / x.len() as f64
+    }
+
+    #[test]
+    fn test_coherent_sum_and_null() {
+        let x = tone(10_000, 0.3);
+        let one = Complex::new(1.0, 0.0);
+        let sum = beamform(&[&x[..], &x[..]], &[one, one]).unwrap();
+        for (s, x) in sum.iter().zip(&x) {
+            assert!((s - x * 2.0).norm() < 1e-12);
+        }
+
+        let null = beamform(&[&x[..], &x[..]], &[one, -one]).unwrap();
+        assert!(10.0 * (power(&null) / power(&x)).log10() < -60.0);
+    }
+
+    #[test]
+    fn test_matches_reference_sum() {
+        // Channel counts either side of the unrolled group size, and a length that leaves a
+        // partial block.
+        let len = 2 * CHUNK_LEN + 77;
+        for n in [1, 3, 4, 5, 9] {
+            let channels = (0..n)
+                .map(|c| tone(len, c as f64).multiply_const(Complex::new(1.0 + c as f64, 0.0)))
+                .collect::<Vec<_>>();
+            let weights = (0..n)
+                .map(|c| Complex::from_polar(0.5 + c as f64 / 4.0, -0.7 * c as f64))
+                .collect::<Vec<_>>();
+            let out = beamform(&channels, &weights).unwrap();
+            for (t, out) in out.iter().enumerate() {
+                let expected = channels
+                    .iter()
+                    .zip(&weights)
+                    .fold(Complex::zero(), |acc, (c, w)| acc + w * c[t]);
+                assert!((out - expected).norm() < 1e-12, "{n} {t}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_beam_matches_single() {
+        let (n, spacing) = (7, 0.5);
+        let channels = plane_wave(n, spacing, 0.4, 3 * CHUNK_LEN + 5);
+        let angles = [-1.0, -0.2, 0.0, 0.4, 1.2];
+        let rows = angles
+            .iter()
+            .map(|a| steering_weights(n, spacing, *a))
+            .collect::<Vec<_>>();
+        let beams = beamform_multi(&channels, &Matrix::from_rows(&rows)).unwrap();
+        assert_eq!(beams.len(), angles.len());
+        for (beam, weights) in beams.iter().zip(&rows) {
+            assert_eq!(*beam, beamform(&channels, weights).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_steering_peak() {
+        let (n, spacing) = (16, 0.5);
+        let target = 20f64.to_radians();
+        let weights = steering_weights(n, spacing, target);
+
+        let response = (-1600..=1600)
+            .map(|a| {
+                let angle = (a as f64 / 20.0).to_radians();
+                let beam = beamform(&plane_wave(n, spacing, angle, 64), &weights).unwrap();
+                (angle, power(&beam))
+            })
+            .collect::<Vec<_>>();
+        let peak = response
+            .iter()
+            .fold(response[0], |acc, r| if r.1 > acc.1 { *r } else { acc });
+        assert!(
+            (peak.0 - target).abs() < 0.1f64.to_radians(),
+            "{}",
+            peak.0.to_degrees()
+        );
+        assert!((peak.1 - (n * n) as f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_dimension_errors() {
+        let x = tone(100, 0.0);
+        let short = tone(99, 0.0);
+        let one = Complex::new(1.0, 0.0);
+        assert_eq!(
+            beamform(&[&x[..], &short[..]], &[one, one]),
+            Err(BeamformError::ChannelLength {
+                channel: 1,
+                len: 99,
+                expected: 100
+            })
+        );
+        assert_eq!(
+            beamform(&[&x[..], &x[..]], &[one]),
+            Err(BeamformError::WeightCount {
+                weights: 1,
+                channels: 2
+            })
+        );
+        assert_eq!(
+            beamform::<f64, &[Complex<f64>]>(&[], &[]),
+            Err(BeamformError::NoChannels)
+        );
+        assert_eq!(
+            beamform_multi(&[&x[..]], &Matrix::zeros(3, 2)),
+            Err(BeamformError::WeightCount {
+                weights: 2,
+                channels: 1
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_output_length() {
+        let x = tone(100, 0.0);
+        let mut out = vec![Complex::zero(); 99];
+        let _ = beamform_into(&[&x[..]], &[Complex::new(1.0, 0.0)], &mut out);
+    }
+}
@@ -0,0 +1,360 @@
+use num::{Complex, Zero};
+use rayon::prelude::*;
+
+use crate::filter::{firwin_kaiser, StreamingFir};
+use crate::windows::WindowKind;
+use crate::FftContext;
+
+// How frame power spectra are combined into the reported spectrum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Averaging {
+    Linear,
+    // Each frame moves the average this fraction of the way towards its own spectrum.
+    Exponential(f64),
+    PeakHold,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    frame_len: usize,
+    hop: usize,
+    window: WindowKind,
+    averaging: Averaging,
+    decimation: Option<usize>,
+    batch_frames: usize,
+    peak_threshold_db: f64,
+    max_peaks: usize,
+}
+
+impl CaptureConfig {
+    // By default frames overlap by half, are Hann windowed and averaged linearly, and peaks down
+    // to 40 dB below the strongest are reported, at most 16 of them. Frames are transformed 64 at
+    // a time, which bounds memory use at a few dozen frames whatever the capture length.
+    pub fn new(frame_len: usize) -> Self {
+        assert!(frame_len > 1, "Frames need at least two samples");
+        Self {
+            frame_len,
+            hop: frame_len / 2,
+            window: WindowKind::Hann,
+            averaging: Averaging::Linear,
+            decimation: None,
+            batch_frames: 64,
+            peak_threshold_db: 40.0,
+            max_peaks: 16,
+        }
+    }
+
+    pub fn with_hop(mut self, hop: usize) -> Self {
+        assert!(
+            hop > 0 && hop <= self.frame_len,
+            "Hop of {hop} samples must lie within the {} sample frame",
+            self.frame_len
+        );
+        self.hop = hop;
+        self
+    }
+
+    pub fn with_window(mut self, window: WindowKind) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn with_averaging(mut self, averaging: Averaging) -> Self {
+        if let Averaging::Exponential(alpha) = averaging {
+            assert!(
+                alpha > 0.0 && alpha <= 1.0,
+                "Exponential averaging factor must lie within (0, 1]"
+            );
+        }
+        self.averaging = averaging;
+        self
+    }
+
+    // Lowpass filters and keeps every `factor`th sample before framing. Frequencies in the
+    // report are then in cycles per decimated sample.
+    pub fn with_decimation(mut self, factor: usize) -> Self {
+        assert!(factor > 0, "Decimation factor must be positive");
+        self.decimation = (factor > 1).then_some(factor);
+        self
+    }
+
+    // Frames transformed in parallel at a time.
+    pub fn with_batch_frames(mut self, frames: usize) -> Self {
+        assert!(frames > 0, "Batch must hold at least one frame");
+        self.batch_frames = frames;
+        self
+    }
+
+    // Peaks are reported down to `below_max_db` under the strongest bin.
+    pub fn with_peaks(mut self, below_max_db: f64, max_peaks: usize) -> Self {
+        self.peak_threshold_db = below_max_db;
+        self.max_peaks = max_peaks;
+        self
+    }
+}
+
+// Statistics of the input samples, before any decimation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CaptureStats {
+    pub mean: Complex<f64>,
+    pub mean_power: f64,
+    pub peak_magnitude: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectralPeak {
+    pub bin: usize,
+    // Refined by a parabola through the peak bin and its neighbours in dB, in cycles per sample
+    // within [-0.5, 0.5).
+    pub frequency: f64,
+    pub power_db: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureReport {
+    // Average power per bin in unshifted FFT order, scaled by the window energy so a white
+    // input of unit power reads one in every bin. Empty when no full frame was seen.
+    pub spectrum: Vec<f64>,
+    pub frames: usize,
+    pub samples: usize,
+    pub stats: CaptureStats,
+    // Strongest first.
+    pub peaks: Vec<SpectralPeak>,
+}
+
+struct Accumulator {
+    averaging: Averaging,
+    spectrum: Vec<f64>,
+    frames: usize,
+}
+
+impl Accumulator {
+    fn add(&mut self, power: &[f64]) {
+        if self.frames == 0 {
+            self.spectrum.extend_from_slice(power);
+        } else {
+            for (avg, p) in self.spectrum.iter_mut().zip(power) {
+                *avg = match self.averaging {
+                    Averaging::Linear => *avg + p,
+                    Averaging::Exponential(alpha) => *avg + alpha * (p - *avg),
+                    Averaging::PeakHold => avg.max(*p),
+                };
+            }
+        }
+        self.frames += 1;
+    }
+
+    fn finish(mut self) -> (Vec<f64>, usize) {
+        if self.averaging == Averaging::Linear && self.frames > 0 {
+            let frames = self.frames as f64;
+            self.spectrum.iter_mut().for_each(|x| *x /= frames);
+        }
+        (self.spectrum, self.frames)
+    }
+}
+
+fn find_peaks(spectrum: &[f64], below_max_db: f64, max_peaks: usize) -> Vec<SpectralPeak> {
+    let n = spectrum.len();
+    let strongest = spectrum.iter().cloned().fold(0.0, f64::max);
+    if n < 3 || strongest <= 0.0 {
+        return Vec::new();
+    }
+
+    let db = |k: usize| 10.0 * spectrum[k % n].max(f64::MIN_POSITIVE).log10();
+    let threshold = 10.0 * strongest.log10() - below_max_db;
+    let mut peaks = (0..n)
+        .filter(|&k| {
+            let (left, right) = (spectrum[(k + n - 1) % n], spectrum[(k + 1) % n]);
+            spectrum[k] > left && spectrum[k] >= right && db(k) >= threshold
+        })
+        .map(|k| {
+            let (a, b, c) = (db(k + n - 1), db(k), db(k + 1));
+            let curvature = a - 2.0 * b + c;
+            let offset = if curvature < 0.0 {
+                (a - c) / (2.0 * curvature)
+            } else {
+                0.0
+            };
+            let frequency = (k as f64 + offset) / n as f64;
+            SpectralPeak {
+                bin: k,
+                frequency: if frequency >= 0.5 {
+                    frequency - 1.0
+                } else {
+                    frequency
+                },
+                power_db: b - (a - c) * offset / 4.0,
+            }
+        })
+        .collect::<Vec<_>>();
+    // Ties keep bin order, so the list is the same however the spectrum was computed.
+    peaks.sort_by(|a, b| b.power_db.total_cmp(&a.power_db));
+    peaks.truncate(max_peaks);
+    peaks
+}
+
+// Averaged power spectrum, statistics and peaks of a capture delivered in chunks of any size.
+// Frames are transformed in parallel but combined strictly in order, so the report is identical
+// however the capture is chunked and however many threads run. A trailing partial frame only
+// counts towards the statistics.
+pub fn process_capture<I>(source: I, config: CaptureConfig) -> CaptureReport
+where
+    I: IntoIterator<Item = Vec<Complex<f32>>>,
+{
+    let frame_len = config.frame_len;
+    let hop = config.hop;
+    let window = config.window.generate::<f64>(frame_len);
+    let scale = window.iter().map(|w| w * w).sum::<f64>().recip();
+
+    let mut decimator = config.decimation.map(|factor| {
+        let taps = firwin_kaiser(0.8 / factor as f64, 1.0 / factor as f64, 60.0);
+        (StreamingFir::<Complex<f32>>::new(&taps), factor, 0usize)
+    });
+    let mut accumulator = Accumulator {
+        averaging: config.averaging,
+        spectrum: Vec::with_capacity(frame_len),
+        frames: 0,
+    };
+    let (mut sum, mut sum_power, mut peak_magnitude) = (Complex::<f64>::zero(), 0.0, 0.0f64);
+    let mut samples = 0;
+
+    let batch_len = frame_len + (config.batch_frames - 1) * hop;
+    let mut pending: Vec<Complex<f32>> = Vec::with_capacity(batch_len + hop);
+    let transform = |pending: &mut Vec<Complex<f32>>, accumulator: &mut Accumulator| {
+        if pending.len() < frame_len {
+            return;
+        }
+        let frames = (pending.len() - frame_len) / hop + 1;
+        let data = pending.as_slice();
+        let powers = (0..frames)
+            .into_par_iter()
+            .map_init(
+                || (FftContext::<f64>::new(), vec![Complex::zero(); frame_len]),
+                |(context, buffer), f| {
+                    let frame = &data[(f * hop)..(f * hop + frame_len)];
+                    for ((out, x), w) in buffer.iter_mut().zip(frame).zip(&window) {
+                        *out = Complex::new(x.re as f64 * w, x.im as f64 * w);
+                    }
+                    context.forward(buffer);
+                    buffer
+                        .iter()
+                        .map(|x| x.norm_sqr() * scale)
+                        .collect::<Vec<_>>()
+                },
+            )
+            .collect::<Vec<_>>();
+        for power in &powers {
+            accumulator.add(power);
+        }
+        pending.drain(..(frames * hop));
+    };
+
+    for chunk in source {
+        for x in &chunk {
+            let x = Complex::new(x.re as f64, x.im as f64);
+            sum += x;
+            sum_power += x.norm_sqr();
+            peak_magnitude = peak_magnitude.max(x.norm());
+        }
+        samples += chunk.len();
+
+        // Feed the chunk through in pieces so `pending` never holds more than one batch.
+        for piece in chunk.chunks(batch_len.max(1)) {
+            match &mut decimator {
+                Some((filter, factor, phase)) => {
+                    let filtered = filter.process(piece);
+                    let start = (*factor - *phase) % *factor;
+                    pending.extend(filtered.iter().skip(start).step_by(*factor));
+                    *phase = (*phase + piece.len()) % *factor;
+                }
+                None => pending.extend_from_slice(piece),
+            }
+            if pending.len() >= batch_len {
+                transform(&mut pending, &mut accumulator);
+            }
+        }
+    }
+    transform(&mut pending, &mut accumulator);
+
+    let (spectrum, frames) = accumulator.finish();
+    let count = samples.max(1) as f64;
+    CaptureReport {
+        peaks: find_peaks(&spectrum, config.peak_threshold_db, config.max_peaks),
+        spectrum,
+        frames,
+        samples,
+        stats: CaptureStats {
+            mean: sum / count,
+            mean_power: sum_power / count,
+            peak_magnitude,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn tone(len: usize, freq: f64, amplitude: f64) -> Vec<Complex<f32>> {
+        (0..len)
+            .map(|i| {
+                let x = Complex::from_polar(amplitude, 2.0 * PI * freq * i as f64);
+                Complex::new(x.re as f32, x.im as f32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_single_tone() {
+        let report = process_capture([tone(10_000, 0.1, 2.0)], CaptureConfig::new(256));
+        assert_eq!(report.samples, 10_000);
+        assert_eq!(report.frames, (10_000 - 256) / 128 + 1);
+        assert_eq!(report.spectrum.len(), 256);
+        assert!((report.stats.mean_power - 4.0).abs() < 1e-5);
+        assert!((report.stats.peak_magnitude - 2.0).abs() < 1e-5);
+        assert!(report.stats.mean.norm() < 1e-2);
+
+        let peak = report.peaks[0];
+        assert!((peak.frequency - 0.1).abs() < 0.01, "{peak:?}");
+        assert_eq!(peak.bin, (0.1f64 * 256.0).round() as usize);
+    }
+
+    #[test]
+    fn test_averaging_modes() {
+        // A tone present only in the second half of the capture.
+        let mut capture = vec![Complex::zero(); 4096];
+        capture.extend(tone(4096, -0.25, 1.0));
+        let at = |averaging| {
+            let config = CaptureConfig::new(64)
+                .with_hop(64)
+                .with_averaging(averaging);
+            process_capture([capture.clone()], config).spectrum[48]
+        };
+
+        let linear = at(Averaging::Linear);
+        let peak = at(Averaging::PeakHold);
+        let exponential = at(Averaging::Exponential(0.5));
+        assert!((linear - peak / 2.0).abs() < 1e-4 * peak);
+        assert!((exponential - peak).abs() < 1e-4 * peak);
+    }
+
+    #[test]
+    fn test_decimation() {
+        // 0.3 aliases onto -0.1 when keeping every second sample without a filter.
+        let mut capture = tone(20_000, 0.05, 1.0);
+        for (x, y) in capture.iter_mut().zip(tone(20_000, 0.3, 1.0)) {
+            *x += y;
+        }
+        let decimated = process_capture(
+            capture.chunks(777).map(|c| c.to_vec()),
+            CaptureConfig::new(256).with_decimation(2),
+        );
+        assert_eq!(decimated.samples, 20_000);
+        assert!((decimated.peaks[0].frequency - 0.1).abs() < 0.01);
+        assert!(decimated
+            .peaks
+            .iter()
+            .all(|p| (p.frequency + 0.1).abs() > 0.02));
+    }
+}
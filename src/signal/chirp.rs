@@ -0,0 +1,18 @@
+use num::{Complex, Float, FromPrimitive};
+
+// Unit magnitude linear chirp whose instantaneous frequency starts at `start` and rises by `rate`
+// every sample, both in cycles per sample. The phase is accumulated in f64 and wrapped to a
+// single cycle, so long chirps keep their precision in f32.
+pub fn chirp<T: Float + FromPrimitive>(len: usize, start: f64, rate: f64) -> Vec<Complex<T>> {
+    (0..len)
+        .map(|n| {
+            let n = n as f64;
+            let cycles = (start * n + rate * n * n / 2.0).rem_euclid(1.0);
+            Complex::from_polar(
+                T::one(),
+                T::from_f64(2.0 * std::f64::consts::PI * cycles)
+                    .expect("Could not convert f64 into type"),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,213 @@
+use super::chirp;
+use crate::FftContext;
+use num::{Complex, Float, FromPrimitive, Zero};
+use rustfft::FftNum;
+
+// Demodulates chirp spread spectrum symbols of 2^SF samples by multiplying each window with the
+// conjugate reference chirp and picking the strongest FFT bin. `slope` is the sweep of the
+// reference chirp across one symbol as a fraction of the sample rate, so 1.0 sweeps the full band
+// like LoRa and negative values demodulate down-chirps. Peak magnitudes are normalized so a unit
+// amplitude symbol reports 1.0.
+pub struct Dechirper<T: Float + FftNum> {
+    up_chirp: Vec<Complex<T>>,
+    down_chirp: Vec<Complex<T>>,
+    context: FftContext<T>,
+    buffer: Vec<Complex<T>>,
+    pending: Vec<Complex<T>>,
+}
+
+impl<T> Dechirper<T>
+where
+    T: Float + FftNum + FromPrimitive,
+{
+    pub fn new(spreading_factor: u32, slope: f64, context: FftContext<T>) -> Self {
+        assert!(
+            (1..=16).contains(&spreading_factor),
+            "Spreading factor must lie within 1..=16"
+        );
+        assert!(slope != 0.0, "Chirp slope must not be zero");
+
+        let len = 1usize << spreading_factor;
+        let up_chirp = chirp(len, -slope / 2.0, slope / len as f64);
+        let down_chirp = up_chirp.iter().map(|x| x.conj()).collect();
+
+        Self {
+            up_chirp,
+            down_chirp,
+            context,
+            buffer: vec![Complex::zero(); len],
+            pending: Vec::with_capacity(len),
+        }
+    }
+
+    pub fn symbol_len(&self) -> usize {
+        self.up_chirp.len()
+    }
+
+    pub fn up_chirp(&self) -> &[Complex<T>] {
+        &self.up_chirp
+    }
+
+    pub fn down_chirp(&self) -> &[Complex<T>] {
+        &self.down_chirp
+    }
+
+    // Detected symbol value and peak magnitude of a single window of `symbol_len` samples.
+    pub fn process_symbol(&mut self, samples: &[Complex<T>]) -> (usize, T) {
+        assert!(
+            samples.len() == self.symbol_len(),
+            "Expected {} samples per symbol, got {}",
+            self.symbol_len(),
+            samples.len()
+        );
+
+        for ((out, x), reference) in self.buffer.iter_mut().zip(samples).zip(&self.down_chirp) {
+            *out = x * reference;
+        }
+        self.context.forward(&mut self.buffer);
+
+        let (bin, peak) = self
+            .buffer
+            .iter()
+            .enumerate()
+            .fold((0, T::zero()), |best, (i, x)| {
+                let mag = x.norm();
+                if mag > best.1 {
+                    (i, mag)
+                } else {
+                    best
+                }
+            });
+
+        let len = T::from_usize(self.symbol_len()).expect("Could not convert length to type");
+        (bin, peak / len)
+    }
+
+    // Demodulates every complete window, carrying a partial trailing window over to the next call
+    // so symbol boundaries stay aligned across arbitrarily sized chunks.
+    pub fn process_stream(&mut self, samples: &[Complex<T>]) -> Vec<(usize, T)> {
+        let len = self.symbol_len();
+        let mut out = Vec::new();
+        let mut samples = samples;
+
+        if !self.pending.is_empty() {
+            let needed = (len - self.pending.len()).min(samples.len());
+            self.pending.extend_from_slice(&samples[..needed]);
+            samples = &samples[needed..];
+
+            if self.pending.len() < len {
+                return out;
+            }
+            let window = std::mem::take(&mut self.pending);
+            out.push(self.process_symbol(&window));
+            self.pending = window;
+            self.pending.clear();
+        }
+
+        let mut windows = samples.chunks_exact(len);
+        for window in &mut windows {
+            out.push(self.process_symbol(window));
+        }
+        self.pending.extend_from_slice(windows.remainder());
+
+        out
+    }
+
+    // Drops any partially received window.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{Xoshiro256PlusPlus, YttriaRng};
+    use std::f64::consts::PI;
+
+    // LoRa symbol `value` of the full-band up-chirp, sampled `offset` samples late. The frequency
+    // wraps back to the bottom of the band after the cyclic shift.
+    fn symbol(sf: u32, value: usize, offset: f64) -> Vec<Complex<f64>> {
+        let len = (1usize << sf) as f64;
+        (0..(1usize << sf))
+            .map(|n| {
+                let t = (n as f64 + offset + value as f64).rem_euclid(len);
+                Complex::from_polar(1.0, 2.0 * PI * (t * t / (2.0 * len) - t / 2.0))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dechirp_noisy_symbols() {
+        let sf = 7;
+        let mut rng = Xoshiro256PlusPlus::seed_from(5);
+        let values = (0..64)
+            .map(|_| (rng.next_u64() % (1 << sf)) as usize)
+            .collect::<Vec<_>>();
+
+        // 5 dB SNR on unit amplitude symbols.
+        let sigma = (10f64.powf(-5.0 / 10.0) / 2.0).sqrt();
+        let samples = values
+            .iter()
+            .flat_map(|&v| symbol(sf, v, 0.0))
+            .map(|x| x + Complex::new(rng.next_gaussian(), rng.next_gaussian()) * sigma)
+            .collect::<Vec<_>>();
+
+        // Feed the stream in uneven chunks to exercise the window alignment.
+        let mut dechirper = Dechirper::new(sf, 1.0, FftContext::new());
+        let mut detected = Vec::new();
+        for chunk in samples.chunks(77) {
+            detected.extend(dechirper.process_stream(chunk));
+        }
+
+        assert_eq!(detected.len(), values.len());
+        for ((bin, peak), value) in detected.iter().zip(&values) {
+            assert_eq!(bin, value);
+            assert!(*peak > 0.8);
+        }
+    }
+
+    #[test]
+    fn test_dechirp_timing_offset() {
+        let sf = 8;
+        let len = 1 << sf;
+        let mut dechirper = Dechirper::new(sf, 1.0, FftContext::new());
+
+        for value in [0, 17, 100, 200, 255] {
+            let (aligned_bin, aligned_peak) = dechirper.process_symbol(&symbol(sf, value, 0.0));
+            assert_eq!(aligned_bin, value);
+            assert!((aligned_peak - 1.0).abs() < 1e-9);
+
+            // Half a sample of timing error moves the energy half a bin and splits it across the
+            // frequency wrap, costing magnitude but leaving the peak next to the true value.
+            let (bin, peak) = dechirper.process_symbol(&symbol(sf, value, 0.5));
+            let error = (bin + len - value) % len;
+            assert!(error <= 1 || error == len - 1, "{value}: {bin}");
+            assert!(peak < 0.9 * aligned_peak && peak > 0.3, "{value}: {peak}");
+        }
+    }
+
+    #[test]
+    fn test_reference_chirps() {
+        let sf = 6;
+        let len = 1 << sf;
+        let dechirper = Dechirper::<f64>::new(sf, 1.0, FftContext::new());
+
+        for (up, down) in dechirper.up_chirp().iter().zip(dechirper.down_chirp()) {
+            assert!((up.norm() - 1.0).abs() < 1e-12);
+            assert!((down.norm() - 1.0).abs() < 1e-12);
+        }
+
+        // The unwrapped phase difference is the instantaneous frequency, ramping linearly across
+        // the band for the up-chirp and mirrored for the down-chirp.
+        let up = dechirper.up_chirp();
+        let down = dechirper.down_chirp();
+        for n in 0..(len - 1) {
+            let expected = -0.5 + (n as f64 + 0.5) / len as f64;
+            let up_freq = (up[n + 1] * up[n].conj()).arg() / (2.0 * PI);
+            let down_freq = (down[n + 1] * down[n].conj()).arg() / (2.0 * PI);
+            assert!((up_freq - expected).abs() < 1e-9);
+            assert!((down_freq + expected).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use num::{Complex, Float, FromPrimitive, Zero};
+use rustfft::FftNum;
+use std::collections::VecDeque;
+
+use crate::windows::hann;
+use crate::FftContext;
+
+// Streaming spectral subtraction noise reducer. Frames of `frame_len` samples, one every `hop`,
+// are windowed with a square root periodic Hann window, which is used again on resynthesis so
+// overlap-add reconstructs the input exactly when the gain is one. Each bin's power is reduced by
+// `oversubtraction` times the noise power estimate, with the gain held at or above `floor`, and
+// the original phase is kept. Output is the input delayed by `delay()` samples.
+//
+// The noise estimate is the average power spectrum of every frame passed to
+// `update_noise_estimate`, and, with a VAD threshold set, of every processed frame whose mean
+// power falls below it. Without either it stays zero and the input passes unchanged.
+pub struct SpectralSubtractor<T: Float + FftNum> {
+    frame_len: usize,
+    hop: usize,
+    oversubtraction: T,
+    floor: T,
+    smoothing: Option<T>,
+    vad_threshold: Option<T>,
+    window: Vec<T>,
+    synthesis_scale: T,
+    noise: Vec<T>,
+    noise_frames: usize,
+    gain: Vec<T>,
+    context: FftContext<T>,
+    buffer: Vec<Complex<T>>,
+    history: VecDeque<T>,
+    pending: usize,
+    overlap: Vec<T>,
+    output: VecDeque<T>,
+}
+
+fn from_f64<T: FromPrimitive>(x: f64) -> T {
+    T::from_f64(x).expect("Could not convert f64 into type")
+}
+
+impl<T> SpectralSubtractor<T>
+where
+    T: Float + FftNum + FromPrimitive,
+{
+    // `hop` must divide `frame_len` and be at most half of it. Oversubtraction defaults to 2 and
+    // the floor to 0.05, with gain smoothing and VAD off.
+    pub fn new(frame_len: usize, hop: usize) -> Self {
+        assert!(
+            hop > 0 && 2 * hop <= frame_len && frame_len.is_multiple_of(hop),
+            "Hop of {hop} samples must divide the {frame_len} sample frame at least twice"
+        );
+
+        let window = hann::<T>(frame_len + 1)[..frame_len]
+            .iter()
+            .map(|w| w.sqrt())
+            .collect::<Vec<_>>();
+        let energy = window.iter().fold(T::zero(), |acc, w| acc + *w * *w);
+        let len = T::from_usize(frame_len).expect("Could not convert usize into type");
+        let hop_len = T::from_usize(hop).expect("Could not convert usize into type");
+
+        Self {
+            frame_len,
+            hop,
+            oversubtraction: from_f64(2.0),
+            floor: from_f64(0.05),
+            smoothing: None,
+            vad_threshold: None,
+            window,
+            // Undoes the unnormalized inverse FFT and the overlap of the squared window.
+            synthesis_scale: hop_len / (energy * len),
+            noise: vec![T::zero(); frame_len],
+            noise_frames: 0,
+            gain: vec![T::one(); frame_len],
+            context: FftContext::new(),
+            buffer: vec![Complex::zero(); frame_len],
+            history: VecDeque::from(vec![T::zero(); frame_len]),
+            pending: 0,
+            overlap: vec![T::zero(); frame_len],
+            output: VecDeque::from(vec![T::zero(); hop]),
+        }
+    }
+
+    pub fn with_oversubtraction(mut self, factor: T) -> Self {
+        assert!(factor >= T::zero(), "Oversubtraction must not be negative");
+        self.oversubtraction = factor;
+        self
+    }
+
+    // Smallest gain applied to any bin, as an amplitude ratio.
+    pub fn with_floor(mut self, floor: T) -> Self {
+        assert!(
+            floor >= T::zero() && floor <= T::one(),
+            "Spectral floor must lie within [0, 1]"
+        );
+        self.floor = floor;
+        self
+    }
+
+    // Each bin's gain moves this fraction of the way back towards its previous value, which
+    // suppresses the isolated short-lived peaks heard as musical noise.
+    pub fn with_gain_smoothing(mut self, smoothing: T) -> Self {
+        assert!(
+            smoothing >= T::zero() && smoothing < T::one(),
+            "Gain smoothing must lie within [0, 1)"
+        );
+        self.smoothing = Some(smoothing);
+        self
+    }
+
+    // Frames whose mean power per sample is below `power` update the noise estimate.
+    pub fn with_vad_threshold(mut self, power: T) -> Self {
+        self.vad_threshold = Some(power);
+        self
+    }
+
+    pub fn delay(&self) -> usize {
+        self.frame_len
+    }
+
+    // Noise power per FFT bin of a windowed frame.
+    pub fn noise_estimate(&self) -> &[T] {
+        &self.noise
+    }
+
+    pub fn update_noise_estimate(&mut self, frame: &[T]) {
+        assert!(
+            frame.len() == self.frame_len,
+            "Noise frame has {} samples, expected {}",
+            frame.len(),
+            self.frame_len
+        );
+        for ((out, x), w) in self.buffer.iter_mut().zip(frame).zip(&self.window) {
+            *out = Complex::new(*x * *w, T::zero());
+        }
+        self.context.forward(&mut self.buffer);
+        self.accumulate_noise();
+    }
+
+    fn accumulate_noise(&mut self) {
+        self.noise_frames += 1;
+        let count = T::from_usize(self.noise_frames).expect("Could not convert usize into type");
+        for (noise, x) in self.noise.iter_mut().zip(&self.buffer) {
+            *noise = *noise + (x.norm_sqr() - *noise) / count;
+        }
+    }
+
+    fn process_frame(&mut self) {
+        for ((out, x), w) in self.buffer.iter_mut().zip(&self.history).zip(&self.window) {
+            *out = Complex::new(*x * *w, T::zero());
+        }
+
+        if let Some(threshold) = self.vad_threshold {
+            let len = T::from_usize(self.frame_len).expect("Could not convert usize into type");
+            let power = self.history.iter().fold(T::zero(), |acc, x| acc + *x * *x) / len;
+            self.context.forward(&mut self.buffer);
+            if power < threshold {
+                self.accumulate_noise();
+            }
+        } else {
+            self.context.forward(&mut self.buffer);
+        }
+
+        let floor = self.floor * self.floor;
+        for ((x, noise), gain) in self.buffer.iter_mut().zip(&self.noise).zip(&mut self.gain) {
+            let power = x.norm_sqr();
+            let target = if power > T::zero() {
+                (T::one() - self.oversubtraction * *noise / power)
+                    .max(floor)
+                    .min(T::one())
+                    .sqrt()
+            } else {
+                T::one()
+            };
+            *gain = match self.smoothing {
+                Some(smoothing) => smoothing * *gain + (T::one() - smoothing) * target,
+                None => target,
+            };
+            *x = *x * *gain;
+        }
+        self.context.inverse(&mut self.buffer);
+
+        for ((overlap, x), w) in self.overlap.iter_mut().zip(&self.buffer).zip(&self.window) {
+            *overlap = *overlap + x.re * *w * self.synthesis_scale;
+        }
+        self.output.extend(self.overlap.drain(..self.hop));
+        self.overlap
+            .extend(std::iter::repeat_n(T::zero(), self.hop));
+    }
+
+    pub fn process(&mut self, input: &[T]) -> Vec<T> {
+        let mut out = Vec::with_capacity(input.len());
+        for x in input {
+            self.history.pop_front();
+            self.history.push_back(*x);
+            self.pending += 1;
+            if self.pending == self.hop {
+                self.pending = 0;
+                self.process_frame();
+            }
+            out.push(
+                self.output
+                    .pop_front()
+                    .expect("Output queue is never empty"),
+            );
+        }
+        out
+    }
+
+    // Pushes zeros through to drain the `delay()` samples still held.
+    pub fn flush(&mut self) -> Vec<T> {
+        self.process(&vec![T::zero(); self.frame_len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::gaussian_noise;
+    use std::f64::consts::PI;
+
+    const FRAME: usize = 512;
+    const HOP: usize = 128;
+
+    // Harmonics of a slowly gliding pitch under a syllable-rate envelope.
+    fn speechlike(len: usize) -> Vec<f64> {
+        let mut phase = 0.0;
+        (0..len)
+            .map(|i| {
+                let t = i as f64;
+                phase += 2.0 * PI * (0.012 + 0.001 * (2.0 * PI * t / 20_000.0).sin());
+                let envelope = 0.6 + 0.4 * (2.0 * PI * t / 3000.0).sin();
+                envelope
+                    * (1..=5)
+                        .map(|h| (h as f64 * phase).sin() / h as f64)
+                        .sum::<f64>()
+            })
+            .collect()
+    }
+
+    fn power(x: &[f64]) -> f64 {
+        x.iter().map(|x| x * x).sum::<f64>() / x.len() as f64
+    }
+
+    fn snr_db(reference: &[f64], signal: &[f64]) -> f64 {
+        let error = reference
+            .iter()
+            .zip(signal)
+            .map(|(a, b)| a - b)
+            .collect::<Vec<_>>();
+        10.0 * (power(reference) / power(&error)).log10()
+    }
+
+    fn noise(len: usize, power: f64, seed: u64) -> Vec<f64> {
+        gaussian_noise(len, seed)
+            .iter()
+            .map(|x| power.sqrt() * x)
+            .collect()
+    }
+
+    fn trained(noise_power: f64) -> SpectralSubtractor<f64> {
+        let mut denoiser = SpectralSubtractor::new(FRAME, HOP).with_gain_smoothing(0.5);
+        let training = noise(64 * FRAME, noise_power, 1);
+        for frame in training.chunks(FRAME) {
+            denoiser.update_noise_estimate(frame);
+        }
+        denoiser
+    }
+
+    #[test]
+    fn test_improves_snr() {
+        let len = 60_000;
+        let clean = speechlike(len);
+        let noise_power = power(&clean) / 10f64.powf(0.5);
+        let noisy = clean
+            .iter()
+            .zip(noise(len, noise_power, 2))
+            .map(|(s, n)| s + n)
+            .collect::<Vec<_>>();
+
+        let mut denoiser = trained(noise_power);
+        let out = denoiser.process(&noisy);
+
+        let skip = 2 * FRAME;
+        let before = snr_db(&clean[skip..], &noisy[skip..]);
+        let after = snr_db(&clean[skip..len - FRAME], &out[skip + FRAME..]);
+        assert!((before - 5.0).abs() < 0.2, "{before}");
+        assert!(after - before >= 6.0, "{before} dB -> {after} dB");
+    }
+
+    #[test]
+    fn test_clean_signal_passes() {
+        let len = 30_000;
+        let clean = speechlike(len);
+        let mut denoiser = trained(power(&clean) / 1000.0);
+        let out = denoiser.process(&clean);
+
+        let skip = 2 * FRAME;
+        let (reference, out) = (&clean[skip..len - FRAME], &out[skip + FRAME..]);
+        assert!(snr_db(reference, out) > 20.0);
+        assert!((10.0 * (power(out) / power(reference)).log10()).abs() < 1.0);
+
+        // With no noise estimate the input comes back exactly, only delayed.
+        let mut passthrough = SpectralSubtractor::new(FRAME, HOP);
+        let out = passthrough.process(&clean);
+        for (a, b) in clean.iter().zip(&out[FRAME..]) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_batch() {
+        let input = speechlike(10_000)
+            .iter()
+            .zip(noise(10_000, 0.1, 3))
+            .map(|(s, n)| s + n)
+            .collect::<Vec<_>>();
+
+        let mut batch = trained(0.1).with_vad_threshold(0.05);
+        let mut expected = batch.process(&input);
+        expected.extend(batch.flush());
+
+        let mut streaming = trained(0.1).with_vad_threshold(0.05);
+        let mut out = Vec::new();
+        let mut pos = 0;
+        for size in [1, 7, 50, 513, 2, 1000].iter().cycle() {
+            let end = (pos + size).min(input.len());
+            out.extend(streaming.process(&input[pos..end]));
+            pos = end;
+            if pos == input.len() {
+                break;
+            }
+        }
+        out.extend(streaming.flush());
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_noise_estimate_freezes() {
+        let mut denoiser = trained(0.1);
+        let estimate = denoiser.noise_estimate().to_vec();
+        denoiser.process(&noise(20_000, 0.01, 4));
+        assert_eq!(denoiser.noise_estimate(), estimate);
+
+        // With a VAD threshold the quiet frames keep it moving, and louder ones do not.
+        let mut denoiser = trained(0.1).with_vad_threshold(0.05);
+        denoiser.process(&noise(20_000, 0.01, 4));
+        let quiet = denoiser.noise_estimate().to_vec();
+        assert_ne!(quiet, estimate);
+        denoiser.process(&noise(20_000, 1.0, 5));
+        assert_eq!(denoiser.noise_estimate(), quiet);
+    }
+}
@@ -0,0 +1,235 @@
+use num::{Float, FromPrimitive, One, Zero};
+use std::collections::VecDeque;
+
+use crate::filter::FilterSample;
+
+// Streaming peak limiter. Samples leave `lookahead` samples after they arrive, so the gain can
+// start falling before a peak reaches the output. The smoothed gain follows the smallest gain any
+// sample in the lookahead window needs, with separate attack and release time constants, and is
+// finally capped by the gain the outgoing sample itself needs, so the output magnitude never
+// exceeds the ceiling whatever the smoothing does. Complex samples are limited on their
+// envelope, keeping their phase.
+pub struct LookaheadLimiter<S: FilterSample> {
+    ceiling: S::Real,
+    lookahead: usize,
+    attack: S::Real,
+    release: S::Real,
+    delay: VecDeque<S>,
+    // Monotonic queue of (sample index, required gain) for the sliding minimum.
+    window: VecDeque<(usize, S::Real)>,
+    input_count: usize,
+    gain: S::Real,
+    max_reduction: S::Real,
+}
+
+fn coefficient<T: Float + FromPrimitive>(samples: usize) -> T {
+    let samples = T::from_usize(samples.max(1)).expect("Could not convert usize into type");
+    T::one() - (-samples.recip()).exp()
+}
+
+impl<S: FilterSample> LookaheadLimiter<S> {
+    // By default the attack time constant is a quarter of the lookahead and the release time
+    // constant ten times the lookahead.
+    pub fn new(lookahead: usize, ceiling: S::Real) -> Self {
+        assert!(ceiling > S::Real::zero(), "Ceiling must be positive");
+
+        Self {
+            ceiling,
+            lookahead,
+            attack: coefficient(lookahead / 4),
+            release: coefficient(10 * lookahead),
+            delay: VecDeque::from(vec![S::zero(); lookahead]),
+            window: VecDeque::new(),
+            input_count: 0,
+            gain: S::Real::one(),
+            max_reduction: S::Real::one(),
+        }
+    }
+
+    // Time constants in samples.
+    pub fn with_attack(mut self, samples: usize) -> Self {
+        self.attack = coefficient(samples);
+        self
+    }
+
+    pub fn with_release(mut self, samples: usize) -> Self {
+        self.release = coefficient(samples);
+        self
+    }
+
+    pub fn delay(&self) -> usize {
+        self.lookahead
+    }
+
+    // Gain applied to the most recent output sample, in dB below unity.
+    pub fn gain_reduction_db(&self) -> S::Real {
+        -to_db(self.gain)
+    }
+
+    // Largest reduction applied so far, in dB below unity.
+    pub fn max_gain_reduction_db(&self) -> S::Real {
+        -to_db(self.max_reduction)
+    }
+
+    fn required_gain(&self, x: &S) -> S::Real {
+        let magnitude = x.magnitude();
+        if magnitude > self.ceiling {
+            self.ceiling / magnitude
+        } else {
+            S::Real::one()
+        }
+    }
+
+    fn step(&mut self, x: S) -> S {
+        let required = self.required_gain(&x);
+        while self.window.back().is_some_and(|(_, g)| *g >= required) {
+            self.window.pop_back();
+        }
+        self.window.push_back((self.input_count, required));
+        while self
+            .window
+            .front()
+            .is_some_and(|(i, _)| i + self.lookahead < self.input_count)
+        {
+            self.window.pop_front();
+        }
+        self.input_count += 1;
+
+        let target = self.window.front().map_or(S::Real::one(), |(_, g)| *g);
+        let coefficient = if target < self.gain {
+            self.attack
+        } else {
+            self.release
+        };
+        self.gain = self.gain + (target - self.gain) * coefficient;
+
+        self.delay.push_back(x);
+        let out = self.delay.pop_front().expect("Delay line is never empty");
+        let gain = self.gain.min(self.required_gain(&out));
+        if gain < self.max_reduction {
+            self.max_reduction = gain;
+        }
+
+        let mut out = out * gain;
+        // Guard against the product rounding a hair above the ceiling.
+        let magnitude = out.magnitude();
+        if magnitude > self.ceiling {
+            out = out * (self.ceiling / magnitude);
+        }
+        out
+    }
+
+    // Output is the input `delay()` samples earlier, limited.
+    pub fn process_into(&mut self, input: &[S], out: &mut [S]) {
+        assert!(
+            input.len() == out.len(),
+            "Input and output lengths differ: {} != {}",
+            input.len(),
+            out.len()
+        );
+        for (x, out) in input.iter().zip(out.iter_mut()) {
+            *out = self.step(*x);
+        }
+    }
+
+    pub fn process(&mut self, input: &[S]) -> Vec<S> {
+        let mut out = vec![S::zero(); input.len()];
+        self.process_into(input, &mut out);
+        out
+    }
+
+    // Pushes zeros through to drain the `delay()` samples still held.
+    pub fn flush(&mut self) -> Vec<S> {
+        self.process(&vec![S::zero(); self.lookahead])
+    }
+}
+
+fn to_db<T: Float + FromPrimitive>(gain: T) -> T {
+    T::from_f64(20.0).expect("Could not convert f64 into type") * gain.log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::Complex;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_never_exceeds_ceiling() {
+        // Impulses of growing height right after each other and spaced both shorter and longer
+        // than the lookahead.
+        let ceiling = 0.5;
+        let mut input = vec![0.0; 4000];
+        let mut pos = 0;
+        for k in 1..200 {
+            input[pos] = if k % 2 == 0 { 1.0 } else { -1.0 } * (k as f64 * 0.37);
+            pos += 1 + (k * 7) % 41;
+            if pos >= input.len() {
+                break;
+            }
+        }
+        for (i, x) in input.iter_mut().enumerate().skip(3000).take(100) {
+            *x = 100.0 * (0.3 * i as f64).sin();
+        }
+
+        for lookahead in [0, 1, 16, 64] {
+            let mut limiter = LookaheadLimiter::new(lookahead, ceiling).with_attack(3);
+            let mut out = limiter.process(&input);
+            out.extend(limiter.flush());
+            let peak = out.iter().fold(0.0f64, |acc, x| acc.max(x.abs()));
+            assert!(peak <= ceiling + f64::EPSILON * ceiling, "{peak}");
+            assert!(limiter.max_gain_reduction_db() > 40.0);
+        }
+
+        let iq = (0..2000)
+            .map(|i| Complex::from_polar(if i % 97 == 0 { 30.0 } else { 0.2 }, 0.1 * i as f64))
+            .collect::<Vec<_>>();
+        let mut limiter = LookaheadLimiter::new(8, ceiling);
+        let out = limiter.process(&iq);
+        assert!(out
+            .iter()
+            .all(|x| x.norm() <= ceiling + f64::EPSILON * ceiling));
+        // Phase survives the envelope gain.
+        for (x, y) in iq.iter().zip(&out[8..]) {
+            assert!((x.arg() - y.arg()).abs() < 1e-9 || y.norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_transparent_below_ceiling() {
+        let input = (0..1000)
+            .map(|i| 0.9 * (2.0 * PI * 0.01 * i as f64).sin())
+            .collect::<Vec<_>>();
+        let mut limiter = LookaheadLimiter::new(32, 1.0);
+        let out = limiter.process(&input);
+
+        assert!(out[..32].iter().all(|x| *x == 0.0));
+        assert_eq!(out[32..], input[..(1000 - 32)]);
+        assert_eq!(limiter.gain_reduction_db(), 0.0);
+        assert_eq!(limiter.max_gain_reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn test_streaming_matches_batch() {
+        let input = (0..3000)
+            .map(|i| (1.0 + 3.0 * (i as f64 * 0.004).sin().abs()) * (i as f64 * 0.2).sin())
+            .collect::<Vec<_>>();
+
+        let mut batch = LookaheadLimiter::new(24, 1.0);
+        let expected = batch.process(&input);
+
+        let mut streaming = LookaheadLimiter::new(24, 1.0);
+        let mut out = Vec::new();
+        let mut pos = 0;
+        for size in [1, 7, 50, 513, 2, 1000].iter().cycle() {
+            let end = (pos + size).min(input.len());
+            out.extend(streaming.process(&input[pos..end]));
+            pos = end;
+            if pos == input.len() {
+                break;
+            }
+        }
+        assert_eq!(out, expected);
+        assert_eq!(streaming.gain_reduction_db(), batch.gain_reduction_db());
+    }
+}
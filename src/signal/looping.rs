@@ -0,0 +1,238 @@
+use num::{Complex, Float, FromPrimitive, One, Zero};
+
+// Replays a reference waveform indefinitely, keeping exact sample continuity across `fill` calls
+// of any size. Optionally the last `crossfade` samples of every repetition are blended into the
+// start of the next with a raised-cosine fade, and a per-loop complex gain and a frequency offset
+// can be applied progressively to simulate slow fading across repetitions.
+pub struct LoopingSource<T> {
+    reference: Vec<Complex<T>>,
+    crossfade: usize,
+    position: usize,
+    loops: usize,
+    gain: Complex<T>,
+    gain_step: Complex<T>,
+    phase: T,
+    phase_step: T,
+}
+
+impl<T> LoopingSource<T>
+where
+    T: Float + FromPrimitive,
+{
+    pub fn new(reference: Vec<Complex<T>>) -> Self {
+        assert!(
+            !reference.is_empty(),
+            "Reference waveform must not be empty"
+        );
+
+        Self {
+            reference,
+            crossfade: 0,
+            position: 0,
+            loops: 0,
+            gain: Complex::one(),
+            gain_step: Complex::one(),
+            phase: T::zero(),
+            phase_step: T::zero(),
+        }
+    }
+
+    // Each repetition after the first starts with an `overlap` sample raised-cosine blend from the
+    // tail of the waveform into its head, so the effective loop period becomes
+    // `reference.len() - overlap`.
+    pub fn with_crossfade(mut self, overlap: usize) -> Self {
+        assert!(
+            2 * overlap <= self.reference.len(),
+            "Crossfade of {overlap} samples is longer than half the waveform ({})",
+            self.reference.len()
+        );
+        self.crossfade = overlap;
+        self.set_loop_gain(self.loop_gain());
+        self
+    }
+
+    // The gain is spread evenly over the samples of a loop, so after every full repetition the
+    // output has been multiplied by exactly `gain` without any step at the seam.
+    pub fn with_loop_gain(mut self, gain: Complex<T>) -> Self {
+        self.set_loop_gain(gain);
+        self
+    }
+
+    // Frequency offset in cycles per sample, applied as a continuous rotation of the output.
+    pub fn with_frequency_offset(mut self, norm_freq: T) -> Self {
+        self.phase_step = T::from_f64(2.0 * std::f64::consts::PI).unwrap() * norm_freq;
+        self
+    }
+
+    pub fn period(&self) -> usize {
+        self.reference.len() - self.crossfade
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn loops(&self) -> usize {
+        self.loops
+    }
+
+    pub fn fill(&mut self, out: &mut [Complex<T>]) {
+        let pi = T::from_f64(std::f64::consts::PI).unwrap();
+        let period = self.period();
+
+        for out in out.iter_mut() {
+            let sample = self.sample_at(self.position);
+
+            *out = sample * self.gain * Complex::from_polar(T::one(), self.phase);
+
+            self.gain = self.gain * self.gain_step;
+            self.phase = self.phase + self.phase_step;
+            if self.phase > pi {
+                self.phase = self.phase - pi - pi;
+            } else if self.phase < -pi {
+                self.phase = self.phase + pi + pi;
+            }
+
+            self.position += 1;
+            if self.position == period {
+                self.position = 0;
+                self.loops += 1;
+            }
+        }
+    }
+
+    fn sample_at(&self, position: usize) -> Complex<T> {
+        if self.loops == 0 || position >= self.crossfade {
+            return self.reference[position];
+        }
+
+        let pi = T::from_f64(std::f64::consts::PI).unwrap();
+        let half = T::from_f64(0.5).unwrap();
+        let fraction =
+            T::from_usize(position + 1).unwrap() / T::from_usize(self.crossfade + 1).unwrap();
+        let weight = half - half * (pi * fraction).cos();
+
+        self.reference[position].scale(weight)
+            + self.reference[self.period() + position].scale(T::one() - weight)
+    }
+
+    fn loop_gain(&self) -> Complex<T> {
+        let period = T::from_usize(self.period()).unwrap();
+        if self.gain_step.is_one() {
+            Complex::one()
+        } else {
+            self.gain_step.powf(period)
+        }
+    }
+
+    fn set_loop_gain(&mut self, gain: Complex<T>) {
+        self.gain_step = if gain.is_one() {
+            Complex::one()
+        } else if gain.is_zero() {
+            Complex::zero()
+        } else {
+            gain.powf(T::one() / T::from_usize(self.period()).unwrap())
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{YttriaVectorArithmetic, YttriaVectorComplex, YttriaVectorUtils};
+
+    fn tone(len: usize, freq: f64) -> Vec<Complex<f64>> {
+        (0..len)
+            .map(|i| Complex::from_polar(1.0, 2.0 * std::f64::consts::PI * freq * i as f64))
+            .collect()
+    }
+
+    fn max_jump(x: &[Complex<f64>]) -> f64 {
+        x.iter()
+            .zip(&x[1..])
+            .map(|(a, b)| (b - a).norm())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_fills_match_tiling() {
+        let reference = tone(37, 0.013);
+        let mut source = LoopingSource::new(reference.clone());
+
+        let mut out = Vec::new();
+        for size in (1..40).cycle().take(60) {
+            let mut block = vec![Complex::zero(); size];
+            source.fill(&mut block);
+            out.extend(block);
+        }
+
+        let tiled = reference.tile(out.len() / reference.len() + 1);
+        assert_eq!(out, tiled[..out.len()]);
+        assert_eq!(source.position(), out.len() % reference.len());
+    }
+
+    #[test]
+    fn test_crossfade_smooths_seam() {
+        // 10.5 cycles, so naive tiling has a half-cycle phase jump at the seam. With a 56 sample
+        // crossfade the tail leads the head by a tenth of a cycle, so the fade slightly slows
+        // the tone rather than speeding it up.
+        let reference = tone(420, 0.025);
+        let within = max_jump(&reference);
+        assert!(max_jump(&reference.tile(2)) > 1.9);
+
+        let mut source = LoopingSource::new(reference).with_crossfade(56);
+        let mut out = vec![Complex::zero(); 3 * source.period() + 100];
+        source.fill(&mut out);
+
+        assert!(
+            max_jump(&out) <= within * 1.01,
+            "{} > {within}",
+            max_jump(&out)
+        );
+    }
+
+    #[test]
+    fn test_frequency_offset_is_continuous() {
+        let reference = tone(50, 0.1);
+        let offset = 0.0031;
+        let mut source = LoopingSource::new(reference.clone()).with_frequency_offset(offset);
+
+        let mut out = Vec::new();
+        for size in [7, 50, 1, 123, 64, 5] {
+            let mut block = vec![Complex::zero(); size];
+            source.fill(&mut block);
+            out.extend(block);
+        }
+
+        let expected_step = 2.0 * std::f64::consts::PI * offset;
+        let demodulated = out.multiply(&reference.tile(8)[..out.len()].conj());
+        let phase = demodulated.iter().map(|x| x.arg()).collect::<Vec<_>>();
+        for step in phase.angle_unwrap(None).diff() {
+            assert!((step - expected_step).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_loop_gain_compounds_per_loop() {
+        let reference = vec![Complex::new(1.0f64, 0.0); 10];
+        let mut source = LoopingSource::new(reference).with_loop_gain(Complex::new(0.5, 0.0));
+
+        let mut out = vec![Complex::zero(); 31];
+        source.fill(&mut out);
+
+        assert!((out[10].re - 0.5).abs() < 1e-12);
+        assert!((out[20].re - 0.25).abs() < 1e-12);
+        assert!((out[30].re - 0.125).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_zero_length_fill() {
+        let mut source = LoopingSource::new(tone(16, 0.1));
+        let mut out = vec![Complex::zero(); 5];
+        source.fill(&mut out);
+
+        source.fill(&mut []);
+        assert_eq!(source.position(), 5);
+        assert_eq!(source.loops(), 0);
+    }
+}
@@ -0,0 +1,37 @@
+mod ambiguity;
+pub use ambiguity::{AmbiguityGrid, YttriaVectorAmbiguity};
+
+mod beamform;
+pub use beamform::{beamform, beamform_into, beamform_multi, steering_weights, BeamformError};
+
+mod capture;
+pub use capture::{
+    process_capture, Averaging, CaptureConfig, CaptureReport, CaptureStats, SpectralPeak,
+};
+
+mod chirp;
+pub use chirp::chirp;
+
+mod dechirp;
+pub use dechirp::Dechirper;
+
+mod looping;
+pub use looping::LoopingSource;
+
+mod limiter;
+pub use limiter::LookaheadLimiter;
+
+mod multitone;
+pub use multitone::{multitone, ImdProduct, ImdReport, YttriaVectorImd};
+
+mod threshold;
+pub use threshold::{ThresholdDetector, ThresholdEvent, YttriaVectorThreshold};
+
+mod nco;
+pub use nco::{Nco, PhaseWidth};
+
+mod pll;
+pub use pll::Pll;
+
+mod denoise;
+pub use denoise::SpectralSubtractor;
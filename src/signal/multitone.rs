@@ -0,0 +1,309 @@
+use num::{Float, FromPrimitive};
+
+use crate::vector::{NoiseFloorConfig, YttriaVectorRealFft, YttriaVectorSpectrum};
+use crate::windows::kaiser;
+
+fn from_f64<T: FromPrimitive>(x: f64) -> T {
+    T::from_f64(x).expect("Could not convert f64 into type")
+}
+
+fn to_f64<T: Float>(x: T) -> f64 {
+    x.to_f64().expect("Could not convert type into f64")
+}
+
+// Schroeder's low crest factor phases, `-2 pi sum_{l < k} (k - l) p_l` with `p_l` tone `l`'s
+// share of the total power. With equal amplitudes this is Newman's quadratic phase law. The
+// phases assume the tones sit on an evenly spaced grid, listed in frequency order.
+fn schroeder_phases(amplitudes: &[f64]) -> Vec<f64> {
+    let total = amplitudes.iter().map(|a| a * a).sum::<f64>();
+    (0..amplitudes.len())
+        .map(|k| {
+            let spread = amplitudes[..k]
+                .iter()
+                .enumerate()
+                .map(|(l, a)| (k - l) as f64 * a * a / total)
+                .sum::<f64>();
+            -2.0 * std::f64::consts::PI * spread
+        })
+        .collect()
+}
+
+// Sum of cosines at `freqs` cycles per sample with the given amplitudes and starting phases in
+// radians. Without phases the tones get Schroeder phases, which keep the crest factor of many
+// evenly spaced tones close to that of a single one instead of letting them all peak together.
+// The phase of each tone is accumulated in f64 and wrapped to a single cycle.
+pub fn multitone<T: Float + FromPrimitive>(
+    len: usize,
+    freqs: &[T],
+    amplitudes: &[T],
+    phases: Option<&[T]>,
+) -> Vec<T> {
+    assert!(
+        amplitudes.len() == freqs.len(),
+        "{} amplitudes given for {} tones",
+        amplitudes.len(),
+        freqs.len()
+    );
+    let amplitudes = amplitudes.iter().map(|a| to_f64(*a)).collect::<Vec<_>>();
+    let phases = match phases {
+        Some(phases) => {
+            assert!(
+                phases.len() == freqs.len(),
+                "{} phases given for {} tones",
+                phases.len(),
+                freqs.len()
+            );
+            phases.iter().map(|p| to_f64(*p)).collect()
+        }
+        None => schroeder_phases(&amplitudes),
+    };
+    let tones = freqs
+        .iter()
+        .zip(&amplitudes)
+        .zip(&phases)
+        .map(|((f, a), p)| (to_f64(*f), *a, *p))
+        .collect::<Vec<_>>();
+
+    (0..len)
+        .map(|n| {
+            let sum = tones
+                .iter()
+                .map(|(f, a, p)| {
+                    let cycles = (f * n as f64).rem_euclid(1.0);
+                    a * (2.0 * std::f64::consts::PI * cycles + p).cos()
+                })
+                .sum::<f64>();
+            from_f64(sum)
+        })
+        .collect()
+}
+
+// Shape of the analysis window. Its sidelobes sit below -130 dB, and the main lobe is under five
+// bins either side of a tone, so `IMD_HALF_WIDTH` bins about the nearest bin hold all of a
+// tone's power wherever it falls.
+const IMD_BETA: f64 = 14.0;
+const IMD_HALF_WIDTH: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImdProduct {
+    // Folded into [0, 0.5] cycles per sample.
+    pub frequency: f64,
+    // Relative to the mean level of the two fundamentals.
+    pub level_dbc: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImdReport {
+    // Mean power of each fundamental in dB, so a tone of unit amplitude reads -3.01 dB.
+    pub f1_power_db: f64,
+    pub f2_power_db: f64,
+    // Second order products at f2 - f1 and f1 + f2.
+    pub imd2_difference: ImdProduct,
+    pub imd2_sum: ImdProduct,
+    // Third order products at 2 f1 - f2 and 2 f2 - f1.
+    pub imd3_low: ImdProduct,
+    pub imd3_high: ImdProduct,
+    // The stronger of the two third order products.
+    pub imd3_dbc: f64,
+    // Output referred third order intercept, `P - imd3_dbc / 2` with `P` the mean fundamental
+    // level in dB. Subtract the gain of the device for the input referred IIP3.
+    pub oip3_db: f64,
+    // Level of the background noise integrated over the same bandwidth as each product, so a
+    // product at or below it is not resolved from the noise.
+    pub noise_floor_dbc: f64,
+}
+
+pub trait YttriaVectorImd<T> {
+    // Two tone intermodulation measurement of a real signal driven by tones at `f1` and `f2`
+    // cycles per sample. The whole vector is analysed with a Kaiser window, and each tone or
+    // product is measured by its power in the 13 bins about its folded frequency. Panics unless
+    // both tones lie within (0, 0.5) and at least 13 bins apart; products that fold onto a
+    // fundamental or onto each other are measured together.
+    fn measure_imd(&self, f1: T, f2: T) -> ImdReport;
+}
+
+// Aliases a frequency in cycles per sample into [0, 0.5].
+fn fold(f: f64) -> f64 {
+    let f = f.rem_euclid(1.0);
+    if f > 0.5 {
+        1.0 - f
+    } else {
+        f
+    }
+}
+
+impl<T> YttriaVectorImd<T> for [T]
+where
+    T: Float,
+{
+    fn measure_imd(&self, f1: T, f2: T) -> ImdReport {
+        let (f1, f2) = (to_f64(f1), to_f64(f2));
+        let len = self.len();
+        for f in [f1, f2] {
+            assert!(
+                f > 0.0 && f < 0.5,
+                "Tone frequencies must lie within (0, 0.5), got {f}"
+            );
+        }
+        let min_spacing = (2 * IMD_HALF_WIDTH + 1) as f64 / len as f64;
+        assert!(
+            (f2 - f1).abs() >= min_spacing,
+            "Tones {f1} and {f2} fall within {} bins of each other",
+            2 * IMD_HALF_WIDTH + 1
+        );
+
+        let window = kaiser(len, IMD_BETA);
+        let windowed = self
+            .iter()
+            .zip(&window)
+            .map(|(x, w)| to_f64(*x) * w)
+            .collect::<Vec<_>>();
+        let power = windowed
+            .rfft()
+            .iter()
+            .map(|x| x.norm_sqr())
+            .collect::<Vec<_>>();
+        // Half of a tone's energy lands in the non-negative bins, which with the unnormalized
+        // transform hold `len * sum(w^2) / 4` per unit of squared amplitude.
+        let scale = 2.0 / (len as f64 * window.iter().map(|w| w * w).sum::<f64>());
+
+        let band_power = |f: f64| {
+            let centre = (fold(f) * len as f64).round() as usize;
+            let first = centre.saturating_sub(IMD_HALF_WIDTH);
+            let last = (centre + IMD_HALF_WIDTH).min(power.len() - 1);
+            power[first..=last].iter().sum::<f64>() * scale
+        };
+        let db = |p: f64| 10.0 * p.log10();
+
+        let (f1_power_db, f2_power_db) = (db(band_power(f1)), db(band_power(f2)));
+        let reference = (f1_power_db + f2_power_db) / 2.0;
+        let product = |f: f64| ImdProduct {
+            frequency: fold(f),
+            level_dbc: db(band_power(f)) - reference,
+        };
+        let (imd3_low, imd3_high) = (product(2.0 * f1 - f2), product(2.0 * f2 - f1));
+        let imd3_dbc = imd3_low.level_dbc.max(imd3_high.level_dbc);
+
+        let floor = power.noise_floor_estimate(NoiseFloorConfig::new());
+        let noise_floor_dbc = db(floor * (2 * IMD_HALF_WIDTH + 1) as f64 * scale) - reference;
+
+        ImdReport {
+            f1_power_db,
+            f2_power_db,
+            imd2_difference: product(f2 - f1),
+            imd2_sum: product(f1 + f2),
+            imd3_low,
+            imd3_high,
+            imd3_dbc,
+            oip3_db: reference - imd3_dbc / 2.0,
+            noise_floor_dbc,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{Xoshiro256PlusPlus, YttriaRng};
+    use crate::vector::YttriaVectorFloat;
+
+    fn amplitude(dbc: f64) -> f64 {
+        10f64.powf(dbc / 20.0)
+    }
+
+    #[test]
+    fn test_injected_products() {
+        let (f1, f2) = (0.1003, 0.1371);
+        let freqs = [f1, f2, f2 - f1, f1 + f2, 2.0 * f1 - f2, 2.0 * f2 - f1];
+        let levels = [-30.0, -45.0, -60.0, -72.0];
+        let amplitudes = [0.5, 0.5]
+            .into_iter()
+            .chain(levels.iter().map(|l| 0.5 * amplitude(*l)))
+            .collect::<Vec<_>>();
+        let phases = [0.0, 1.3, -0.4, 2.2, 0.9, -2.7];
+        let signal = multitone(8192, &freqs, &amplitudes, Some(&phases));
+
+        let report = signal.measure_imd(f1, f2);
+        let tone = 10.0 * (0.125f64).log10();
+        assert!((report.f1_power_db - tone).abs() < 0.2, "{report:?}");
+        assert!((report.f2_power_db - tone).abs() < 0.2, "{report:?}");
+        let products = [
+            report.imd2_difference,
+            report.imd2_sum,
+            report.imd3_low,
+            report.imd3_high,
+        ];
+        for ((product, level), f) in products.iter().zip(levels).zip(&freqs[2..]) {
+            assert!((product.level_dbc - level).abs() < 0.2, "{product:?}");
+            assert!((product.frequency - f).abs() < 1e-12);
+        }
+        assert!((report.imd3_dbc + 60.0).abs() < 0.2);
+        assert!((report.oip3_db - (tone + 30.0)).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_folded_product() {
+        // 2 f2 - f1 = 0.53 aliases to 0.47, and f1 + f2 = 0.79 to 0.21.
+        let (f1, f2) = (0.35, 0.44);
+        let signal = multitone(
+            4096,
+            &[f1, f2, 0.47],
+            &[1.0, 1.0, amplitude(-50.0)],
+            Some(&[0.0, 0.5, 1.0]),
+        );
+        let report = signal.measure_imd(f1, f2);
+        assert!((report.imd3_high.frequency - 0.47).abs() < 1e-12);
+        assert!(
+            (report.imd3_high.level_dbc + 50.0).abs() < 0.2,
+            "{report:?}"
+        );
+        assert!((report.imd2_sum.frequency - 0.21).abs() < 1e-12);
+        assert!(report.imd2_sum.level_dbc < -120.0, "{report:?}");
+    }
+
+    #[test]
+    fn test_clean_two_tone_at_noise_floor() {
+        let (f1, f2) = (0.211, 0.2473);
+        let mut signal = multitone(16384, &[f1, f2], &[0.5, 0.5], None);
+        let mut rng = Xoshiro256PlusPlus::seed_from(1);
+        signal
+            .iter_mut()
+            .for_each(|x| *x += 1e-5 * rng.next_gaussian());
+
+        let report = signal.measure_imd(f1, f2);
+        assert!(report.noise_floor_dbc < -80.0, "{report:?}");
+        for product in [
+            report.imd2_difference,
+            report.imd2_sum,
+            report.imd3_low,
+            report.imd3_high,
+        ] {
+            assert!(
+                product.level_dbc < report.noise_floor_dbc + 6.0,
+                "{product:?} {report:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_schroeder_crest_factor() {
+        let freqs = (1..=32).map(|k| k as f64 / 256.0).collect::<Vec<_>>();
+        let amplitudes = vec![1.0; freqs.len()];
+        let zero_phase = multitone(256, &freqs, &amplitudes, Some(&vec![0.0; freqs.len()]));
+        let schroeder = multitone(256, &freqs, &amplitudes, None);
+
+        // Zero phases line every tone up at the first sample: a peak of 32 over an RMS of 4.
+        assert!((zero_phase.crest_factor() - 8.0).abs() < 1e-9);
+        assert!(
+            schroeder.crest_factor() < 2.5,
+            "{}",
+            schroeder.crest_factor()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tones_too_close() {
+        multitone(1024, &[0.1, 0.105], &[1.0, 1.0], None).measure_imd(0.1, 0.105);
+    }
+}
@@ -0,0 +1,131 @@
+use num::{Float, FromPrimitive};
+use std::f64::consts::PI;
+
+// Type 2 phase locked loop: a proportional plus integral loop filter drives a numerically
+// controlled oscillator, so a constant frequency offset is tracked with zero steady state phase
+// error. `bandwidth` is the natural frequency of the loop in radians per sample, typically around
+// 2 * pi / 100 or less, and a damping of 1 / sqrt(2) trades settling time against overshoot.
+pub struct Pll<T> {
+    alpha: T,
+    beta: T,
+    phase: T,
+    frequency: T,
+}
+
+fn from_f64<T: FromPrimitive>(x: f64) -> T {
+    T::from_f64(x).expect("Could not convert f64 into type")
+}
+
+impl<T: Float + FromPrimitive> Pll<T> {
+    pub fn new(bandwidth: T, damping: T) -> Self {
+        assert!(bandwidth > T::zero(), "Loop bandwidth must be positive");
+        assert!(damping > T::zero(), "Damping must be positive");
+
+        let two = from_f64::<T>(2.0);
+        let four = from_f64::<T>(4.0);
+        let denominator = T::one() + two * damping * bandwidth + bandwidth * bandwidth;
+        Self {
+            alpha: four * damping * bandwidth / denominator,
+            beta: four * bandwidth * bandwidth / denominator,
+            phase: T::zero(),
+            frequency: T::zero(),
+        }
+    }
+
+    // Starts the oscillator at `frequency` radians per sample, to shorten acquisition of a roughly
+    // known offset.
+    pub fn with_frequency(mut self, frequency: T) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    // Current phase estimate, within [-pi, pi).
+    pub fn phase(&self) -> T {
+        self.phase
+    }
+
+    // Current frequency estimate in radians per sample.
+    pub fn frequency(&self) -> T {
+        self.frequency
+    }
+
+    // Takes the detector output for the current sample, positive when the input leads the
+    // estimate, and returns the phase estimate for the next sample.
+    pub fn advance(&mut self, phase_error: T) -> T {
+        self.frequency = self.frequency + self.beta * phase_error;
+        let phase = self.phase + self.frequency + self.alpha * phase_error;
+
+        let pi = from_f64::<T>(PI);
+        let turn = from_f64::<T>(2.0 * PI);
+        self.phase = phase - turn * ((phase + pi) / turn).floor();
+        self.phase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::YttriaVectorComplex;
+    use num::Complex;
+
+    #[test]
+    fn test_locks_to_frequency_offset() {
+        let offset = 0.02;
+        let mut pll = Pll::new(2.0 * PI / 200.0, 1.0 / 2f64.sqrt());
+        let mut errors = Vec::new();
+        for n in 0..5000 {
+            let input = Complex::from_polar(1.0, offset * n as f64 + 1.0);
+            let error = (input * Complex::from_polar(1.0, -pll.phase())).arg();
+            errors.push(error);
+            pll.advance(error);
+        }
+
+        assert!(errors[0].abs() > 0.5);
+        assert!(
+            errors[4000..].iter().all(|e| e.abs() < 1e-9),
+            "{}",
+            errors[4999]
+        );
+        assert!((pll.frequency() - offset).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_costas_loop() {
+        // BPSK with a carrier offset, recovered through the Costas detector up to the inherent
+        // half turn ambiguity.
+        let offset = -0.01;
+        let symbols = (0..6000)
+            .map(|n: u64| {
+                if (n.wrapping_mul(2_654_435_761) >> 7) & 1 == 0 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            })
+            .collect::<Vec<f64>>();
+        let mut pll = Pll::new(2.0 * PI / 300.0, 1.0 / 2f64.sqrt());
+        let mut corrected = Vec::new();
+        for (n, s) in symbols.iter().enumerate() {
+            let input = Complex::from_polar(*s, offset * n as f64 + 0.4);
+            let derotated = [input * Complex::from_polar(1.0, -pll.phase())];
+            pll.advance(derotated.phase_error_bpsk()[0]);
+            corrected.push(derotated[0]);
+        }
+
+        assert!((pll.frequency() - offset).abs() < 1e-6);
+        let sign = (corrected[5000].re * symbols[5000]).signum();
+        for (x, s) in corrected[5000..].iter().zip(&symbols[5000..]) {
+            assert!((x.re * sign - s).abs() < 1e-3 && x.im.abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_phase_wraps() {
+        let mut pll = Pll::new(0.01, 1.0).with_frequency(3.0);
+        for _ in 0..100 {
+            let phase = pll.advance(0.0);
+            assert!((-PI..PI).contains(&phase));
+        }
+        assert_eq!(pll.frequency(), 3.0);
+    }
+}
@@ -0,0 +1,312 @@
+use num::Float;
+use rayon::prelude::*;
+
+use crate::config::parallel_min_len;
+
+// A run of samples above threshold. `start` is the first sample at or above the rise threshold
+// and `end` the first sample after it below the fall threshold, so the event covers
+// `start..end`; `end` is `None` while the event is still active at the end of the input. The
+// peak is the largest sample in the event, the earliest one on ties.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThresholdEvent<T> {
+    pub start: usize,
+    pub end: Option<usize>,
+    pub peak: T,
+    pub peak_index: usize,
+}
+
+// Hysteresis detector on a metric such as correlation magnitude or power in dB. An event starts
+// on a sample `>= rise` and lasts while samples stay `>= fall`, so both thresholds are inclusive
+// and a NaN ends an event without starting one. Events shorter than `min_duration` samples are
+// dropped. Blocks are indexed as one continuous stream, so an event spanning blocks is reported
+// once, with absolute indices, by the block it ends in.
+pub struct ThresholdDetector<T> {
+    rise: T,
+    fall: T,
+    min_duration: usize,
+    position: usize,
+    active: Option<ThresholdEvent<T>>,
+}
+
+impl<T: Float> ThresholdDetector<T> {
+    pub fn new(rise: T, fall: T, min_duration: usize) -> Self {
+        assert!(
+            rise >= fall,
+            "Rise threshold must not be below the fall threshold"
+        );
+
+        Self {
+            rise,
+            fall,
+            min_duration,
+            position: 0,
+            active: None,
+        }
+    }
+
+    // Number of samples processed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // Events that ended within `block`.
+    pub fn process(&mut self, block: &[T]) -> Vec<ThresholdEvent<T>> {
+        let mut events = Vec::new();
+        for x in block.iter().copied() {
+            let index = self.position;
+            self.position += 1;
+
+            if let Some(event) = self.active.as_mut() {
+                if x >= self.fall {
+                    if x > event.peak {
+                        event.peak = x;
+                        event.peak_index = index;
+                    }
+                } else {
+                    let mut event = self.active.take().expect("Event is active");
+                    if index - event.start >= self.min_duration {
+                        event.end = Some(index);
+                        events.push(event);
+                    }
+                }
+            } else if x >= self.rise {
+                self.active = Some(ThresholdEvent {
+                    start: index,
+                    end: None,
+                    peak: x,
+                    peak_index: index,
+                });
+            }
+        }
+        events
+    }
+
+    // The event still in progress, once it has lasted `min_duration` samples.
+    pub fn open_event(&self) -> Option<ThresholdEvent<T>> {
+        self.active
+            .filter(|event| self.position - event.start >= self.min_duration)
+    }
+
+    pub fn reset(&mut self) {
+        self.position = 0;
+        self.active = None;
+    }
+}
+
+pub trait YttriaVectorThreshold<T> {
+    // Every event in the vector, see `ThresholdDetector`, followed by the one still active at
+    // the end if any.
+    fn threshold_events(&self, rise: T, fall: T, min_duration: usize) -> Vec<ThresholdEvent<T>>;
+    // Cell averaging CFAR on power or magnitude cells. Each cell is compared against `scale`
+    // times the mean of up to `train` training cells either side, skipping `guard` cells next to
+    // it so a target's own spread does not raise its threshold. Towards the edges the training
+    // windows are cut short rather than wrapped, and a cell without any training cells is never
+    // flagged.
+    fn cfar_ca(&self, guard: usize, train: usize, scale: T) -> Vec<bool>
+    where
+        T: Send + Sync;
+}
+
+impl<T> YttriaVectorThreshold<T> for [T]
+where
+    T: Float,
+{
+    fn threshold_events(&self, rise: T, fall: T, min_duration: usize) -> Vec<ThresholdEvent<T>> {
+        let mut detector = ThresholdDetector::new(rise, fall, min_duration);
+        let mut events = detector.process(self);
+        events.extend(detector.open_event());
+        events
+    }
+
+    fn cfar_ca(&self, guard: usize, train: usize, scale: T) -> Vec<bool>
+    where
+        T: Send + Sync,
+    {
+        let len = self.len();
+        let scale = scale.to_f64().unwrap();
+
+        (0..len)
+            .into_par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|i| {
+                let left = (i.saturating_sub(guard + train), i.saturating_sub(guard));
+                let right = ((i + guard + 1).min(len), (i + guard + train + 1).min(len));
+                let count = (left.1 - left.0) + (right.1 - right.0);
+                if count == 0 {
+                    return false;
+                }
+                // Each window is summed on its own, in f64 whatever `T` is, so a NaN or a huge
+                // cell only affects the cells it trains rather than every window after it.
+                let sum = self[left.0..left.1]
+                    .iter()
+                    .chain(&self[right.0..right.1])
+                    .map(|x| x.to_f64().unwrap())
+                    .sum::<f64>();
+                self[i].to_f64().unwrap() > scale * sum / count as f64
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{gaussian_noise, Xoshiro256PlusPlus, YttriaRng};
+
+    fn event(
+        start: usize,
+        end: Option<usize>,
+        peak: f64,
+        peak_index: usize,
+    ) -> ThresholdEvent<f64> {
+        ThresholdEvent {
+            start,
+            end,
+            peak,
+            peak_index,
+        }
+    }
+
+    #[test]
+    fn test_hysteresis_and_min_duration() {
+        let metric = [
+            0.0, 5.0, 3.0, 1.5, 1.0, 0.5, // Dips to the fall threshold without ending.
+            4.0, 0.0, // Too short.
+            2.5, 4.5, 6.0, 0.0, // Peak on the last sample.
+            2.9, 2.9, 2.9, 0.0, // Never reaches rise.
+        ];
+        let events = metric.threshold_events(3.0, 1.0, 2);
+        assert_eq!(
+            events,
+            [event(1, Some(5), 5.0, 1), event(9, Some(11), 6.0, 10)]
+        );
+
+        let events = metric.threshold_events(3.0, 1.0, 0);
+        assert_eq!(events[1], event(6, Some(7), 4.0, 6));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn test_threshold_equality() {
+        // Both thresholds are inclusive: exactly `rise` starts and exactly `fall` sustains.
+        let events = [3.0, 1.0, 1.0, 0.999, 3.0].threshold_events(3.0, 1.0, 1);
+        assert_eq!(events, [event(0, Some(3), 3.0, 0), event(4, None, 3.0, 4)]);
+
+        // Equal thresholds make a plain comparator.
+        let events = [0.0, 2.0, 2.0, 1.0].threshold_events(2.0, 2.0, 1);
+        assert_eq!(events, [event(1, Some(3), 2.0, 1)]);
+
+        let events = [5.0, f64::NAN, 5.0].threshold_events(3.0, 1.0, 1);
+        assert_eq!(events, [event(0, Some(1), 5.0, 0), event(2, None, 5.0, 2)]);
+    }
+
+    #[test]
+    fn test_open_events_at_boundaries() {
+        // Active from the first sample to the last.
+        let events = [4.0, 7.0, 2.0].threshold_events(3.0, 1.0, 3);
+        assert_eq!(events, [event(0, None, 7.0, 1)]);
+
+        // Still within `min_duration` at the end, so not yet an event.
+        let events = [0.0, 4.0, 2.0].threshold_events(3.0, 1.0, 3);
+        assert!(events.is_empty());
+
+        assert!([0.0f64; 0].threshold_events(3.0, 1.0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_peak_at_event_edges() {
+        let events = [9.0, 4.0, 0.0, 4.0, 5.0, 9.0, 0.0].threshold_events(3.0, 1.0, 1);
+        assert_eq!(
+            events,
+            [event(0, Some(2), 9.0, 0), event(3, Some(6), 9.0, 5)]
+        );
+
+        // Ties go to the earliest sample, and an open event peaks on the last sample.
+        let events = [7.0, 7.0, 0.0, 4.0, 8.0].threshold_events(3.0, 1.0, 1);
+        assert_eq!(events, [event(0, Some(2), 7.0, 0), event(3, None, 8.0, 4)]);
+    }
+
+    #[test]
+    fn test_streaming_matches_batch() {
+        let metric = gaussian_noise(5000, 1)
+            .iter()
+            .enumerate()
+            .map(|(n, x)| 2.0 * (n as f64 * 0.01).sin() + x)
+            .collect::<Vec<_>>();
+        let batch = metric.threshold_events(1.5, 0.5, 8);
+        assert!(batch.len() > 5);
+
+        for chunk in [1, 7, 100, 4999] {
+            let mut detector = ThresholdDetector::new(1.5, 0.5, 8);
+            let mut streamed = metric
+                .chunks(chunk)
+                .flat_map(|block| detector.process(block))
+                .collect::<Vec<_>>();
+            streamed.extend(detector.open_event());
+            assert_eq!(streamed, batch, "{chunk}");
+            assert_eq!(detector.position(), metric.len());
+        }
+    }
+
+    #[test]
+    fn test_cfar_target_in_noise() {
+        // Exponentially distributed noise power, as from a square law detector, with a target
+        // spread over three cells and another against the edge.
+        let mut rng = Xoshiro256PlusPlus::seed_from(2);
+        let mut power = (0..4096)
+            .map(|_| -(1.0 - rng.next_f64()).ln())
+            .collect::<Vec<f64>>();
+        power[2000] += 80.0;
+        power[1999] += 40.0;
+        power[2001] += 40.0;
+        power[1] += 80.0;
+
+        // With 32 training cells a threshold of 20 times the mean sits well above the noise.
+        let detections = power.cfar_ca(2, 16, 20.0);
+        let flagged = (0..power.len())
+            .filter(|i| detections[*i])
+            .collect::<Vec<_>>();
+        assert_eq!(flagged, [1, 1999, 2000, 2001]);
+
+        // Without guard cells the target's shoulders feed the training windows and raise its own
+        // threshold.
+        let spread = power.cfar_ca(0, 2, 20.0);
+        assert!(!spread[1999] && !spread[2001]);
+    }
+
+    #[test]
+    fn test_cfar_edges() {
+        // Only the cell either side trains, so the ends see one training cell.
+        let cells = [1.0, 10.0, 1.0, 1.0, 5.0];
+        assert_eq!(cells.cfar_ca(0, 1, 2.0), [false, true, false, false, true]);
+        // Guard cells cover everything, leaving nothing to train on.
+        assert_eq!(cells.cfar_ca(5, 3, 2.0), [false; 5]);
+        assert!([0.0f32; 0].cfar_ca(1, 1, 2.0).is_empty());
+    }
+
+    #[test]
+    fn test_cfar_bad_cells_stay_local() {
+        let mut rng = Xoshiro256PlusPlus::seed_from(3);
+        let noise = (0..400)
+            .map(|_| -(1.0 - rng.next_f64()).ln())
+            .collect::<Vec<f64>>();
+        let mut target = noise.clone();
+        target[300] += 80.0;
+        let clean = target.cfar_ca(2, 16, 20.0);
+        assert!(clean[300]);
+
+        // A NaN or a strong return at cell 10 changes nothing beyond the cells it trains.
+        for bad in [f64::NAN, 1e17] {
+            let mut power = target.clone();
+            power[10] = bad;
+            let detections = power.cfar_ca(2, 16, 20.0);
+            assert_eq!(detections[29..], clean[29..], "{bad}");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rise_below_fall() {
+        ThresholdDetector::new(1.0, 2.0, 0);
+    }
+}
@@ -0,0 +1,305 @@
+use std::fmt;
+
+use num::{Complex, Float, Zero};
+use rayon::prelude::*;
+use rustfft::FftNum;
+
+use crate::config::parallel_min_len;
+use crate::vector::{assert_output_len, YttriaVectorComplexFft};
+
+// Samples per task: an even share for each thread, but no fewer than the crate's parallel
+// threshold. The inner loops then run over long plain slices, which vectorize.
+fn chunk_len(len: usize) -> usize {
+    len.div_ceil(rayon::current_num_threads())
+        .max(parallel_min_len())
+        .max(1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitComplexError {
+    pub re: usize,
+    pub im: usize,
+}
+
+impl fmt::Display for SplitComplexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "real and imaginary parts differ in length: {} and {}",
+            self.re, self.im
+        )
+    }
+}
+
+impl std::error::Error for SplitComplexError {}
+
+// Planar complex samples, with the real and imaginary parts in separate vectors of equal
+// length. Kernels then work on runs of plain `T`, which vectorize where the interleaved
+// `Complex<T>` layout needs shuffles. The layouts differ, so moving between the two always
+// copies; `complex_from_interleaved` is the zero-copy view for interleaved data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitComplex<T> {
+    re: Vec<T>,
+    im: Vec<T>,
+}
+
+impl<T> SplitComplex<T> {
+    pub fn new(re: Vec<T>, im: Vec<T>) -> Result<Self, SplitComplexError> {
+        if re.len() != im.len() {
+            return Err(SplitComplexError {
+                re: re.len(),
+                im: im.len(),
+            });
+        }
+        Ok(Self { re, im })
+    }
+
+    pub fn len(&self) -> usize {
+        self.re.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.re.is_empty()
+    }
+
+    pub fn re(&self) -> &[T] {
+        &self.re
+    }
+
+    pub fn im(&self) -> &[T] {
+        &self.im
+    }
+
+    // Both parts at once, so they can be written together without breaking the equal lengths.
+    pub fn parts_mut(&mut self) -> (&mut [T], &mut [T]) {
+        (&mut self.re, &mut self.im)
+    }
+
+    pub fn into_parts(self) -> (Vec<T>, Vec<T>) {
+        (self.re, self.im)
+    }
+}
+
+impl<T: Zero + Clone> SplitComplex<T> {
+    pub fn zeros(len: usize) -> Self {
+        Self {
+            re: vec![T::zero(); len],
+            im: vec![T::zero(); len],
+        }
+    }
+}
+
+impl<T> SplitComplex<T>
+where
+    T: Float + Send + Sync,
+{
+    // Copies the interleaved samples apart.
+    pub fn from_interleaved(samples: &[Complex<T>]) -> Self {
+        let mut out = Self::zeros(samples.len());
+        let chunk = chunk_len(samples.len());
+        out.re
+            .par_chunks_mut(chunk)
+            .zip(out.im.par_chunks_mut(chunk))
+            .zip(samples.par_chunks(chunk))
+            .for_each(|((re, im), samples)| {
+                for ((re, im), x) in re.iter_mut().zip(im.iter_mut()).zip(samples) {
+                    *re = x.re;
+                    *im = x.im;
+                }
+            });
+        out
+    }
+
+    pub fn to_interleaved(&self) -> Vec<Complex<T>> {
+        let mut out = vec![Complex::zero(); self.len()];
+        let chunk = chunk_len(self.len());
+        out.par_chunks_mut(chunk)
+            .zip(self.re.par_chunks(chunk))
+            .zip(self.im.par_chunks(chunk))
+            .for_each(|((out, re), im)| {
+                for ((out, re), im) in out.iter_mut().zip(re).zip(im) {
+                    *out = Complex::new(*re, *im);
+                }
+            });
+        out
+    }
+
+    // Element-wise product, `(ac - bd) + j(ad + bc)` over the planar parts.
+    pub fn multiply_into(&self, other: &Self, out: &mut Self) {
+        assert!(
+            other.len() == self.len(),
+            "Cannot multiply {} samples by {}",
+            self.len(),
+            other.len()
+        );
+        assert_output_len("multiply_into", self.len(), out.len());
+        let chunk = chunk_len(self.len());
+
+        out.re
+            .par_chunks_mut(chunk)
+            .zip(out.im.par_chunks_mut(chunk))
+            .zip(self.re.par_chunks(chunk).zip(self.im.par_chunks(chunk)))
+            .zip(other.re.par_chunks(chunk).zip(other.im.par_chunks(chunk)))
+            .for_each(|(((out_re, out_im), (a, b)), (c, d))| {
+                let n = out_re.len();
+                let (out_im, a, b, c, d) = (&mut out_im[..n], &a[..n], &b[..n], &c[..n], &d[..n]);
+                for i in 0..n {
+                    out_re[i] = a[i] * c[i] - b[i] * d[i];
+                    out_im[i] = a[i] * d[i] + b[i] * c[i];
+                }
+            });
+    }
+
+    pub fn multiply(&self, other: &Self) -> Self {
+        let mut out = Self::zeros(self.len());
+        self.multiply_into(other, &mut out);
+        out
+    }
+
+    pub fn conj(&self) -> Self {
+        let mut out = self.clone();
+        out.conj_inplace();
+        out
+    }
+
+    pub fn conj_inplace(&mut self) {
+        let chunk = chunk_len(self.len());
+        self.im.par_chunks_mut(chunk).for_each(|im| {
+            for x in im {
+                *x = -*x;
+            }
+        });
+    }
+
+    // Scales each sample by the matching real gain.
+    pub fn multiply_real(&self, gains: &[T]) -> Self {
+        assert!(
+            gains.len() == self.len(),
+            "Cannot scale {} samples by {} gains",
+            self.len(),
+            gains.len()
+        );
+
+        let mut out = Self::zeros(self.len());
+        let chunk = chunk_len(self.len());
+        out.re
+            .par_chunks_mut(chunk)
+            .zip(out.im.par_chunks_mut(chunk))
+            .zip(self.re.par_chunks(chunk).zip(self.im.par_chunks(chunk)))
+            .zip(gains.par_chunks(chunk))
+            .for_each(|(((out_re, out_im), (re, im)), gains)| {
+                let n = out_re.len();
+                let (out_im, re, im, gains) = (&mut out_im[..n], &re[..n], &im[..n], &gains[..n]);
+                for i in 0..n {
+                    out_re[i] = re[i] * gains[i];
+                    out_im[i] = im[i] * gains[i];
+                }
+            });
+        out
+    }
+
+    pub fn norm_sqr_into(&self, out: &mut [T]) {
+        assert_output_len("norm_sqr_into", self.len(), out.len());
+        let chunk = chunk_len(self.len());
+        out.par_chunks_mut(chunk)
+            .zip(self.re.par_chunks(chunk).zip(self.im.par_chunks(chunk)))
+            .for_each(|(out, (re, im))| {
+                for ((out, re), im) in out.iter_mut().zip(re).zip(im) {
+                    *out = *re * *re + *im * *im;
+                }
+            });
+    }
+
+    pub fn norm_sqr(&self) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.norm_sqr_into(&mut out);
+        out
+    }
+}
+
+impl<T> SplitComplex<T>
+where
+    T: FftNum + Float + Send + Sync,
+{
+    // `fft` of the interleaved samples, including its division by the length. rustfft works on
+    // interleaved data, so this copies there and back.
+    pub fn fft(&self) -> Self {
+        Self::from_interleaved(&self.to_interleaved().fft())
+    }
+}
+
+impl<T> From<&[Complex<T>]> for SplitComplex<T>
+where
+    T: Float + Send + Sync,
+{
+    fn from(samples: &[Complex<T>]) -> Self {
+        Self::from_interleaved(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::gaussian_noise;
+    use crate::vector::{YttriaVectorArithmetic, YttriaVectorComplex};
+
+    fn random(len: usize, seed: u64) -> Vec<Complex<f64>> {
+        gaussian_noise(2 * len, seed)
+            .chunks_exact(2)
+            .map(|x| Complex::new(x[0], x[1]))
+            .collect()
+    }
+
+    fn assert_close(split: &SplitComplex<f64>, interleaved: &[Complex<f64>]) {
+        assert_eq!(split.len(), interleaved.len());
+        for (s, x) in split.to_interleaved().iter().zip(interleaved) {
+            assert!((s - x).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Lengths either side of the default parallel threshold.
+        for len in [0, 1, 4096, 3 * 4096 + 17] {
+            let x = random(len, len as u64);
+            let split = SplitComplex::from(&x[..]);
+            assert_eq!(split.re(), x.real());
+            assert_eq!(split.im(), x.imag());
+            assert_eq!(split.to_interleaved(), x);
+
+            let (re, im) = split.into_parts();
+            assert_eq!(SplitComplex::new(re, im).unwrap().to_interleaved(), x);
+        }
+    }
+
+    #[test]
+    fn test_matches_interleaved() {
+        let len = 2 * 4096 + 5;
+        let (a, b) = (random(len, 1), random(len, 2));
+        let gains = random(len, 3).real();
+        let (split_a, split_b) = (SplitComplex::from(&a[..]), SplitComplex::from(&b[..]));
+
+        assert_close(&split_a.multiply(&split_b), &a.multiply(&b));
+        assert_close(&split_a.conj(), &a.conj());
+        let scaled = a.iter().zip(&gains).map(|(x, g)| x * g).collect::<Vec<_>>();
+        assert_close(&split_a.multiply_real(&gains), &scaled);
+        for (s, x) in split_a.norm_sqr().iter().zip(&a) {
+            assert!((s - x.norm_sqr()).abs() < 1e-12);
+        }
+        assert_close(&split_a.fft(), &a.fft());
+    }
+
+    #[test]
+    fn test_length_mismatch() {
+        assert_eq!(
+            SplitComplex::new(vec![1.0, 2.0], vec![3.0]),
+            Err(SplitComplexError { re: 2, im: 1 })
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_multiply_length_mismatch() {
+        SplitComplex::<f64>::zeros(3).multiply(&SplitComplex::zeros(2));
+    }
+}
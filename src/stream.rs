@@ -0,0 +1,479 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::filter::gcd;
+
+// Moving a cursor to `requested` would put it more than `max_skew` samples ahead of the slowest
+// cursor sharing its source, which would have to buffer the difference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkewError {
+    pub requested: u64,
+    pub max_skew: u64,
+}
+
+impl fmt::Display for SkewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "advancing to sample {} exceeds the maximum skew of {} samples",
+            self.requested, self.max_skew
+        )
+    }
+}
+
+impl std::error::Error for SkewError {}
+
+// A run of input samples starting at absolute input index `start`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamBlock<S> {
+    pub start: u64,
+    pub samples: Vec<S>,
+}
+
+impl<S> StreamBlock<S> {
+    pub fn end(&self) -> u64 {
+        self.start + self.samples.len() as u64
+    }
+}
+
+// The block source shared by every cursor teed from one stream, with the blocks some cursor
+// still has to read.
+struct Source<S> {
+    blocks: Box<dyn Iterator<Item = Vec<S>>>,
+    // Contiguous, oldest first; blocks every cursor has passed are dropped.
+    buffered: VecDeque<StreamBlock<S>>,
+    end: u64,
+    // Next index of each cursor, `None` once the cursor is dropped.
+    positions: Vec<Option<u64>>,
+    max_skew: u64,
+}
+
+impl<S> Source<S> {
+    fn trim(&mut self) {
+        let Some(slowest) = self.positions.iter().filter_map(|p| *p).min() else {
+            self.buffered.clear();
+            return;
+        };
+        while self
+            .buffered
+            .front()
+            .is_some_and(|block| block.end() <= slowest)
+        {
+            self.buffered.pop_front();
+        }
+    }
+}
+
+impl<S: Clone> Source<S> {
+    // Furthest cursor `id` may go, `max_skew` past the slowest of the others.
+    fn skew_limit(&self, id: usize) -> u64 {
+        self.positions
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != id)
+            .filter_map(|(_, position)| *position)
+            .min()
+            .map_or(u64::MAX, |slowest| slowest.saturating_add(self.max_skew))
+    }
+
+    fn check_skew(&self, id: usize, requested: u64) -> Result<(), SkewError> {
+        if requested > self.skew_limit(id) {
+            Err(SkewError {
+                requested,
+                max_skew: self.max_skew,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    // Reads blocks until sample `index` has been read, returning false if the source ends
+    // first. Empty blocks are skipped.
+    fn read_past(&mut self, index: u64) -> bool {
+        while self.end <= index {
+            match self.blocks.next() {
+                Some(samples) if samples.is_empty() => {}
+                Some(samples) => {
+                    let block = StreamBlock {
+                        start: self.end,
+                        samples,
+                    };
+                    self.end = block.end();
+                    self.buffered.push_back(block);
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn next_block(&mut self, id: usize) -> Result<Option<StreamBlock<S>>, SkewError> {
+        let position = self.positions[id].expect("Cursor is live");
+        if !self.read_past(position) {
+            return Ok(None);
+        }
+        let block = self
+            .buffered
+            .iter()
+            .find(|block| block.end() > position)
+            .expect("Buffer holds every sample a live cursor has yet to read");
+        // A block reaching past the skew bound is cut short at it, so blocks longer than the
+        // bound still get through.
+        let end = block.end().min(self.skew_limit(id));
+        if end <= position {
+            return Err(SkewError {
+                requested: position + 1,
+                max_skew: self.max_skew,
+            });
+        }
+
+        let samples =
+            block.samples[(position - block.start) as usize..(end - block.start) as usize].to_vec();
+        self.positions[id] = Some(end);
+        self.trim();
+        Ok(Some(StreamBlock {
+            start: position,
+            samples,
+        }))
+    }
+
+    fn skip_to(&mut self, id: usize, index: u64) -> Result<bool, SkewError> {
+        let position = self.positions[id].expect("Cursor is live");
+        if index <= position {
+            return Ok(true);
+        }
+        self.check_skew(id, index)?;
+        if !self.read_past(index - 1) {
+            self.positions[id] = Some(self.end);
+            self.trim();
+            return Ok(false);
+        }
+        self.positions[id] = Some(index);
+        self.trim();
+        Ok(true)
+    }
+}
+
+// Reads a stream of sample blocks while counting the absolute input index of every sample, so
+// branches of a multi-rate graph fed from the same stream can be related sample for sample.
+// `tee` splits the stream between two cursors that each see every block, buffering only the
+// samples between the slower and the faster of them.
+//
+// A branch that changes the rate registers the factor on its cursor: output `k` of the branch
+// is then input sample `k * factor`, counting from the start of the stream. Cursors are not
+// `Send`; a graph pulls its blocks from one thread.
+pub struct StreamCursor<S> {
+    source: Rc<RefCell<Source<S>>>,
+    id: usize,
+    factor: u64,
+}
+
+impl<S: Clone + 'static> StreamCursor<S> {
+    pub fn new<I>(blocks: I) -> Self
+    where
+        I: IntoIterator<Item = Vec<S>>,
+        I::IntoIter: 'static,
+    {
+        Self {
+            source: Rc::new(RefCell::new(Source {
+                blocks: Box::new(blocks.into_iter()),
+                buffered: VecDeque::new(),
+                end: 0,
+                positions: vec![Some(0)],
+                max_skew: u64::MAX,
+            })),
+            id: 0,
+            factor: 1,
+        }
+    }
+
+    // Two cursors at this one's position over the same blocks. No cursor of the source gets more
+    // than `max_skew` samples ahead of the slowest: blocks are cut short at the bound, and reads
+    // that cannot move at all, or skips past it, fail with `SkewError` and leave the cursor where
+    // it was, so it can retry once the others have caught up. A dropped cursor no longer holds
+    // the others back.
+    pub fn tee(self, max_skew: usize) -> (Self, Self) {
+        let other = {
+            let mut source = self.source.borrow_mut();
+            source.max_skew = max_skew as u64;
+            let position = source.positions[self.id];
+            source.positions.push(position);
+            Self {
+                source: Rc::clone(&self.source),
+                id: source.positions.len() - 1,
+                factor: self.factor,
+            }
+        };
+        (self, other)
+    }
+}
+
+impl<S: Clone> StreamCursor<S> {
+    // Absolute input index of the next sample this cursor will return.
+    pub fn position(&self) -> u64 {
+        self.source.borrow().positions[self.id].expect("Cursor is live")
+    }
+
+    // The rest of the current block, or `None` at the end of the stream. The block stops short
+    // where it would take the cursor more than the skew bound ahead of the slowest, and only a
+    // cursor already at the bound gets `SkewError`.
+    pub fn next_block(&mut self) -> Result<Option<StreamBlock<S>>, SkewError> {
+        self.source.borrow_mut().next_block(self.id)
+    }
+
+    // Discards the samples before `index`. Returns false if the stream ended first, leaving the
+    // cursor at its end.
+    pub fn skip_to(&mut self, index: u64) -> Result<bool, SkewError> {
+        self.source.borrow_mut().skip_to(self.id, index)
+    }
+
+    // Records that the branch reading this cursor keeps one sample in `factor`, those whose
+    // index is a multiple of it, on top of any factor already registered.
+    pub fn register_rate_change(&mut self, factor: usize) {
+        assert!(factor > 0, "Rate change factor must be positive");
+        self.factor *= factor as u64;
+    }
+
+    pub fn rate_factor(&self) -> u64 {
+        self.factor
+    }
+
+    // Input index of the branch's output sample `output`.
+    pub fn input_index(&self, output: u64) -> u64 {
+        output * self.factor
+    }
+}
+
+impl<S> Drop for StreamCursor<S> {
+    fn drop(&mut self) {
+        if let Ok(mut source) = self.source.try_borrow_mut() {
+            source.positions[self.id] = None;
+            source.trim();
+        }
+    }
+}
+
+// Moves the cursor that is behind forward, dropping samples, until both resume at the same
+// absolute index: the first at or after the leading cursor's position that is a multiple of both
+// rate factors, so each rate-changing branch keeps its output phase. Returns that index, or
+// `None` if the stream ended before either cursor reached it.
+pub fn align<S: Clone>(
+    a: &mut StreamCursor<S>,
+    b: &mut StreamCursor<S>,
+) -> Result<Option<u64>, SkewError> {
+    let lcm = a.factor / gcd(a.factor, b.factor) * b.factor;
+    let target = a.position().max(b.position()).div_ceil(lcm) * lcm;
+
+    // The cursor behind moves first, so neither gets further ahead of the other than the
+    // grids require.
+    let (behind, ahead) = if a.position() <= b.position() {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    if behind.skip_to(target)? && ahead.skip_to(target)? {
+        Ok(Some(target))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Blocks of consecutive sample indices with lengths cycling through `sizes`.
+    fn counting(total: u64, sizes: &'static [usize]) -> impl Iterator<Item = Vec<u64>> {
+        let mut start = 0;
+        sizes.iter().cycle().map_while(move |size| {
+            if start >= total {
+                return None;
+            }
+            let end = (start + *size as u64).min(total);
+            let block = (start..end).collect::<Vec<_>>();
+            start = end;
+            Some(block)
+        })
+    }
+
+    // Keeps the samples of `block` on the cursor's grid, numbered by their output index.
+    fn decimate(cursor: &StreamCursor<f64>, block: &StreamBlock<f64>) -> Vec<(u64, f64)> {
+        let factor = cursor.rate_factor();
+        (block.start..block.end())
+            .zip(&block.samples)
+            .filter(|(index, _)| index.is_multiple_of(factor))
+            .map(|(index, x)| (index / factor, *x))
+            .collect()
+    }
+
+    #[test]
+    fn test_impulse_indices_agree_across_rates() {
+        let impulse_at = 123_450;
+        let blocks = (0..200)
+            .map(|b| {
+                (0..1013)
+                    .map(|i| if b * 1013 + i == impulse_at { 1.0 } else { 0.0 })
+                    .collect::<Vec<f64>>()
+            })
+            .collect::<Vec<_>>();
+
+        let (mut full, mut tenth) = StreamCursor::new(blocks).tee(1 << 20);
+        tenth.register_rate_change(2);
+        tenth.register_rate_change(5);
+        let (mut found_full, mut found_tenth) = (None, None);
+        loop {
+            let (a, b) = (full.next_block().unwrap(), tenth.next_block().unwrap());
+            let (Some(a), Some(b)) = (a, b) else { break };
+            for (k, x) in decimate(&full, &a) {
+                if x == 1.0 {
+                    found_full = Some(full.input_index(k));
+                }
+            }
+            for (k, x) in decimate(&tenth, &b) {
+                if x == 1.0 {
+                    found_tenth = Some(tenth.input_index(k));
+                }
+            }
+        }
+        assert_eq!(found_full, Some(impulse_at as u64));
+        assert_eq!(found_tenth, Some(impulse_at as u64));
+        assert_eq!(full.position(), 200 * 1013);
+    }
+
+    #[test]
+    fn test_skew_bound() {
+        let (mut fast, mut slow) = StreamCursor::new(counting(10_000, &[100])).tee(250);
+        assert_eq!(fast.next_block().unwrap().unwrap().start, 0);
+        assert_eq!(fast.next_block().unwrap().unwrap().start, 100);
+        // The third block is cut short at the bound, after which the cursor cannot move.
+        let partial = fast.next_block().unwrap().unwrap();
+        assert_eq!((partial.start, partial.end()), (200, 250));
+        assert_eq!(
+            fast.next_block(),
+            Err(SkewError {
+                requested: 251,
+                max_skew: 250
+            })
+        );
+        assert_eq!(fast.position(), 250);
+        assert_eq!(
+            fast.skip_to(251),
+            Err(SkewError {
+                requested: 251,
+                max_skew: 250
+            })
+        );
+
+        // Once the slow cursor catches up the failed read goes through, with the rest of the
+        // block.
+        slow.next_block().unwrap();
+        let rest = fast.next_block().unwrap().unwrap();
+        assert_eq!((rest.start, rest.end()), (250, 300));
+
+        // A dropped cursor stops holding the other back.
+        drop(slow);
+        while fast.next_block().unwrap().is_some() {}
+        assert_eq!(fast.position(), 10_000);
+    }
+
+    #[test]
+    fn test_blocks_longer_than_skew() {
+        let (a, b) = StreamCursor::new(vec![vec![0u8; 200]; 3]).tee(100);
+        let mut reads = [Vec::new(), Vec::new()];
+        let mut cursors = [a, b];
+        loop {
+            let mut progressed = false;
+            // Cursors alternate, each running as far as the bound lets it.
+            for which in 0..2 {
+                while let Ok(Some(block)) = cursors[which].next_block() {
+                    assert!(block.end() <= cursors[1 - which].position() + 100);
+                    reads[which].push((block.start, block.end()));
+                    progressed = true;
+                }
+            }
+            if !progressed {
+                break;
+            }
+        }
+        assert_eq!((cursors[0].position(), cursors[1].position()), (600, 600));
+        for reads in &reads {
+            assert!(reads.windows(2).all(|pair| pair[0].1 == pair[1].0));
+        }
+        assert_eq!(
+            reads[0],
+            [
+                (0, 100),
+                (100, 200),
+                (200, 300),
+                (300, 400),
+                (400, 500),
+                (500, 600)
+            ]
+        );
+        assert_eq!(reads[1], [(0, 200), (200, 400), (400, 600)]);
+    }
+
+    #[test]
+    fn test_align_after_pause() {
+        let (mut running, mut paused) = StreamCursor::new(counting(100_000, &[97, 13])).tee(5_000);
+        paused.register_rate_change(4);
+        paused.next_block().unwrap();
+        for _ in 0..30 {
+            running.next_block().unwrap();
+        }
+        let ahead = running.position();
+        assert_eq!(ahead, 15 * 110);
+
+        let target = align(&mut running, &mut paused).unwrap().unwrap();
+        assert_eq!(target, ahead.div_ceil(4) * 4);
+        assert_eq!((running.position(), paused.position()), (target, target));
+        let (a, b) = (running.next_block().unwrap(), paused.next_block().unwrap());
+        assert_eq!(a, b);
+        assert_eq!(a.unwrap().samples[0], target);
+
+        // Both grids have to line up: 6 and 4 meet every 12 samples.
+        let (mut a, mut b) = StreamCursor::new(counting(100, &[10])).tee(100);
+        a.register_rate_change(6);
+        b.register_rate_change(4);
+        b.skip_to(13).unwrap();
+        assert_eq!(align(&mut a, &mut b), Ok(Some(24)));
+        assert_eq!(
+            a.next_block().unwrap().unwrap().samples,
+            [24, 25, 26, 27, 28, 29]
+        );
+    }
+
+    #[test]
+    fn test_align_at_end() {
+        let (mut a, mut b) = StreamCursor::new(counting(50, &[20])).tee(100);
+        b.register_rate_change(64);
+        a.skip_to(30).unwrap();
+        assert_eq!(align(&mut a, &mut b), Ok(None));
+    }
+
+    #[test]
+    fn test_bookkeeping_over_millions_of_samples() {
+        let total = 3_000_017;
+        let (a, b) = StreamCursor::new(counting(total, &[1, 4093, 0, 7, 65_536, 255])).tee(1 << 17);
+        let mut cursors = [a, b];
+        let mut next = [0, 0];
+        for turn in 0.. {
+            // Uneven paces, so the first cursor keeps running into the skew bound.
+            let which = usize::from(turn % 5 >= 3);
+            match cursors[which].next_block() {
+                Ok(Some(block)) => {
+                    assert_eq!(block.start, next[which]);
+                    assert_eq!(block.samples[0], block.start);
+                    assert_eq!(*block.samples.last().unwrap(), block.end() - 1);
+                    next[which] = block.end();
+                }
+                Ok(None) if next == [total, total] => break,
+                Ok(None) | Err(_) => {}
+            }
+        }
+        assert_eq!(cursors[0].position(), total);
+        assert_eq!(cursors[1].position(), total);
+    }
+}
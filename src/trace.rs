@@ -0,0 +1,292 @@
+use num::Complex;
+use std::fmt;
+
+use crate::vector::YttriaVectorStatistics;
+
+// Samples a trace can summarise. Complex samples are summarised by their magnitude.
+pub trait TraceSample: Copy {
+    fn trace_value(&self) -> f64;
+}
+
+impl TraceSample for f32 {
+    fn trace_value(&self) -> f64 {
+        *self as f64
+    }
+}
+
+impl TraceSample for f64 {
+    fn trace_value(&self) -> f64 {
+        *self
+    }
+}
+
+impl TraceSample for Complex<f32> {
+    fn trace_value(&self) -> f64 {
+        self.norm() as f64
+    }
+}
+
+impl TraceSample for Complex<f64> {
+    fn trace_value(&self) -> f64 {
+        self.norm()
+    }
+}
+
+// Summary of one stage's output. Min and max skip NaNs, while a single NaN turns the mean and
+// RMS into NaN; all four are NaN for an empty output. `samples` holds at most the
+// trace's `max_samples` values, taken at an even stride through the output.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StageRecord {
+    pub name: String,
+    pub input_len: usize,
+    pub output_len: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub rms: f64,
+    pub nan_count: usize,
+    pub samples: Vec<f64>,
+}
+
+// Opt-in record of what each stage of a pipeline produced. A disabled trace ignores every
+// stage without looking at the data, so pipelines can take a `&mut Trace` unconditionally.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trace {
+    #[default]
+    Disabled,
+    Enabled {
+        max_samples: usize,
+        records: Vec<StageRecord>,
+    },
+}
+
+impl Trace {
+    // Records every stage, keeping up to `max_samples` samples of each output.
+    pub fn new(max_samples: usize) -> Self {
+        Self::Enabled {
+            max_samples,
+            records: Vec::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        matches!(self, Self::Enabled { .. })
+    }
+
+    pub fn records(&self) -> &[StageRecord] {
+        match self {
+            Self::Disabled => &[],
+            Self::Enabled { records, .. } => records,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        if let Self::Enabled { records, .. } = self {
+            records.clear();
+        }
+    }
+
+    pub fn record<S: TraceSample>(&mut self, name: &str, input_len: usize, output: &[S]) {
+        let Self::Enabled {
+            max_samples,
+            records,
+        } = self
+        else {
+            return;
+        };
+
+        let values = output.iter().map(|x| x.trace_value()).collect::<Vec<_>>();
+        let nan_count = values.iter().filter(|x| x.is_nan()).count();
+        let (min, max, mean, rms) = if values.is_empty() {
+            (f64::NAN, f64::NAN, f64::NAN, f64::NAN)
+        } else {
+            let finite = values
+                .iter()
+                .copied()
+                .filter(|x| !x.is_nan())
+                .collect::<Vec<_>>();
+            let (min, max) = if finite.is_empty() {
+                (f64::NAN, f64::NAN)
+            } else {
+                finite.extremes()
+            };
+            let power = values.iter().map(|x| x * x).collect::<Vec<_>>().mean();
+            (min, max, values.mean(), power.sqrt())
+        };
+
+        let samples = if *max_samples == 0 {
+            Vec::new()
+        } else {
+            let stride = values.len().div_ceil(*max_samples).max(1);
+            values.iter().step_by(stride).copied().collect()
+        };
+
+        records.push(StageRecord {
+            name: name.to_string(),
+            input_len,
+            output_len: output.len(),
+            min,
+            max,
+            mean,
+            rms,
+            nan_count,
+            samples,
+        });
+    }
+
+    // Runs `f` on `input` and records its output under `name`.
+    pub fn stage<S, U, F>(&mut self, name: &str, input: &[S], f: F) -> Vec<U>
+    where
+        U: TraceSample,
+        F: FnOnce(&[S]) -> Vec<U>,
+    {
+        let out = f(input);
+        self.record(name, input.len(), &out);
+        out
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self::Enabled { records, .. } = self else {
+            return writeln!(f, "trace disabled");
+        };
+
+        let width = records
+            .iter()
+            .map(|r| r.name.len())
+            .chain([5])
+            .max()
+            .unwrap_or(5);
+        writeln!(
+            f,
+            "{:<width$} {:>10} {:>10} {:>12} {:>12} {:>12} {:>12} {:>8}",
+            "stage", "in", "out", "min", "max", "mean", "rms", "nan"
+        )?;
+        for r in records {
+            writeln!(
+                f,
+                "{:<width$} {:>10} {:>10} {:>12.5e} {:>12.5e} {:>12.5e} {:>12.5e} {:>8}",
+                r.name, r.input_len, r.output_len, r.min, r.max, r.mean, r.rms, r.nan_count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{best_of, YttriaConfig};
+    use crate::vector::YttriaVectorArithmetic;
+
+    fn pipeline(trace: &mut Trace, input: &[f64], poison: bool) -> Vec<f64> {
+        let scaled = trace.stage("scale", input, |x| x.multiply_const(2.0));
+        let spoiled = trace.stage("offset", &scaled, |x| {
+            let mut out = x.add_const(1.0);
+            if poison {
+                out[3] = f64::NAN;
+            }
+            out
+        });
+        trace.stage("repeat", &spoiled, |x| x.repeat(2))
+    }
+
+    #[test]
+    fn test_records_each_stage() {
+        let input = (0..100).map(|x| x as f64).collect::<Vec<_>>();
+        let mut trace = Trace::new(1000);
+        let out = pipeline(&mut trace, &input, false);
+
+        let records = trace.records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(),
+            ["scale", "offset", "repeat"]
+        );
+        assert_eq!((records[0].input_len, records[0].output_len), (100, 100));
+        assert_eq!((records[2].input_len, records[2].output_len), (100, 200));
+
+        assert_eq!((records[0].min, records[0].max), (0.0, 198.0));
+        assert!((records[0].mean - 99.0).abs() < 1e-12);
+        assert_eq!((records[1].min, records[1].max), (1.0, 199.0));
+        assert!((records[1].mean - 100.0).abs() < 1e-12);
+
+        let rms = (out.iter().map(|x| x * x).sum::<f64>() / out.len() as f64).sqrt();
+        assert!((records[2].rms - rms).abs() < 1e-9);
+        assert_eq!(records[2].samples, out);
+        assert!(records.iter().all(|r| r.nan_count == 0));
+
+        let table = trace.to_string();
+        assert_eq!(table.lines().count(), 4);
+        assert!(table.lines().nth(2).unwrap().starts_with("offset"));
+    }
+
+    #[test]
+    fn test_sample_cap() {
+        let input = vec![1.0; 1001];
+        for cap in [1, 7, 100, 1000, 2000] {
+            let mut trace = Trace::new(cap);
+            trace.record("ones", input.len(), &input);
+            let samples = &trace.records()[0].samples;
+            assert!(samples.len() <= cap);
+            assert!(samples.len() >= cap.min(input.len()) / 2);
+        }
+
+        let mut trace = Trace::new(0);
+        trace.record("ones", input.len(), &input);
+        assert!(trace.records()[0].samples.is_empty());
+    }
+
+    #[test]
+    fn test_nan_visible_from_injection() {
+        let input = (0..20).map(|x| x as f64).collect::<Vec<_>>();
+        let mut trace = Trace::new(16);
+        pipeline(&mut trace, &input, true);
+
+        let records = trace.records();
+        assert_eq!(records[0].nan_count, 0);
+        assert!(records[0].mean.is_finite());
+        assert_eq!(records[1].nan_count, 1);
+        assert_eq!(records[2].nan_count, 2);
+        assert!(records[1].mean.is_nan() && records[2].rms.is_nan());
+        assert_eq!((records[2].min, records[2].max), (1.0, 39.0));
+    }
+
+    #[test]
+    fn test_disabled_records_nothing() {
+        let input = (0..100).map(|x| x as f64).collect::<Vec<_>>();
+        let mut trace = Trace::Disabled;
+        let out = pipeline(&mut trace, &input, false);
+
+        assert!(trace.records().is_empty());
+        assert!(!trace.is_enabled());
+        assert_eq!(out, pipeline(&mut Trace::new(10), &input, false));
+    }
+
+    // Run with `cargo test --release -- --ignored bench_disabled_overhead --nocapture`. Both
+    // pipelines run on one thread, so the difference is the disabled trace's alone.
+    #[test]
+    #[ignore]
+    fn bench_disabled_overhead() {
+        let input = (0..1_000_000).map(|x| x as f64).collect::<Vec<_>>();
+
+        let (untraced, disabled) = YttriaConfig::new().with_num_threads(1).install(|| {
+            (
+                best_of(20, || {
+                    std::hint::black_box(input.multiply_const(2.0).add_const(1.0).repeat(2));
+                }),
+                best_of(20, || {
+                    std::hint::black_box(pipeline(&mut Trace::Disabled, &input, false));
+                }),
+            )
+        });
+        println!("untraced {untraced:?}, disabled {disabled:?}");
+        assert!(
+            disabled * 10 < untraced * 11,
+            "{disabled:?} vs {untraced:?}"
+        );
+    }
+}
@@ -1,15 +1,42 @@
 use num::integer::Roots;
 
 pub trait YttriaUnitSqrt<T> {
+    // Integer roots round down. Negative signed integers panic, while negative floats give NaN
+    // as their own `sqrt` does.
     fn sqrt(&self) -> T;
+    // `None` for negative inputs of any type.
+    fn checked_sqrt(&self) -> Option<T>;
 }
 
-macro_rules! implement_sqrt_roots {
+macro_rules! implement_sqrt_unsigned {
     ( $type_impl:ident ) => {
         impl YttriaUnitSqrt<$type_impl> for $type_impl {
             fn sqrt(&self) -> $type_impl {
                 Roots::sqrt(self)
             }
+
+            fn checked_sqrt(&self) -> Option<$type_impl> {
+                Some(Roots::sqrt(self))
+            }
+        }
+    };
+}
+
+macro_rules! implement_sqrt_signed {
+    ( $type_impl:ident ) => {
+        impl YttriaUnitSqrt<$type_impl> for $type_impl {
+            fn sqrt(&self) -> $type_impl {
+                self.checked_sqrt()
+                    .unwrap_or_else(|| panic!("Square root of negative integer {self}"))
+            }
+
+            fn checked_sqrt(&self) -> Option<$type_impl> {
+                if *self < 0 {
+                    None
+                } else {
+                    Some(Roots::sqrt(self))
+                }
+            }
         }
     };
 }
@@ -20,23 +47,77 @@ macro_rules! implement_sqrt_own {
             fn sqrt(&self) -> $type_impl {
                 $type_impl::sqrt(*self)
             }
+
+            fn checked_sqrt(&self) -> Option<$type_impl> {
+                if *self < 0.0 {
+                    None
+                } else {
+                    Some($type_impl::sqrt(*self))
+                }
+            }
         }
     };
 }
 
-implement_sqrt_roots!(u8);
-implement_sqrt_roots!(u16);
-implement_sqrt_roots!(u32);
-implement_sqrt_roots!(u64);
-implement_sqrt_roots!(u128);
-implement_sqrt_roots!(usize);
+implement_sqrt_unsigned!(u8);
+implement_sqrt_unsigned!(u16);
+implement_sqrt_unsigned!(u32);
+implement_sqrt_unsigned!(u64);
+implement_sqrt_unsigned!(u128);
+implement_sqrt_unsigned!(usize);
 
-implement_sqrt_roots!(i8);
-implement_sqrt_roots!(i16);
-implement_sqrt_roots!(i32);
-implement_sqrt_roots!(i64);
-implement_sqrt_roots!(i128);
-implement_sqrt_roots!(isize);
+implement_sqrt_signed!(i8);
+implement_sqrt_signed!(i16);
+implement_sqrt_signed!(i32);
+implement_sqrt_signed!(i64);
+implement_sqrt_signed!(i128);
+implement_sqrt_signed!(isize);
 
 implement_sqrt_own!(f32);
 implement_sqrt_own!(f64);
+
+// Half precision square roots are taken in f32 and rounded back.
+#[cfg(feature = "half")]
+impl YttriaUnitSqrt<half::f16> for half::f16 {
+    fn sqrt(&self) -> half::f16 {
+        half::f16::from_f32(self.to_f32().sqrt())
+    }
+
+    fn checked_sqrt(&self) -> Option<half::f16> {
+        self.to_f32().checked_sqrt().map(half::f16::from_f32)
+    }
+}
+
+#[cfg(feature = "half")]
+impl YttriaUnitSqrt<half::bf16> for half::bf16 {
+    fn sqrt(&self) -> half::bf16 {
+        half::bf16::from_f32(self.to_f32().sqrt())
+    }
+
+    fn checked_sqrt(&self) -> Option<half::bf16> {
+        self.to_f32().checked_sqrt().map(half::bf16::from_f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_sqrt() {
+        assert_eq!(17i32.checked_sqrt(), Some(4));
+        assert_eq!((-1i32).checked_sqrt(), None);
+        assert_eq!(i64::MIN.checked_sqrt(), None);
+        assert_eq!(0i8.checked_sqrt(), Some(0));
+        assert_eq!(u8::MAX.checked_sqrt(), Some(15));
+        assert_eq!(2.25f64.checked_sqrt(), Some(1.5));
+        assert_eq!((-2.25f32).checked_sqrt(), None);
+        assert!(YttriaUnitSqrt::sqrt(&-2.25f32).is_nan());
+    }
+
+    #[test]
+    #[should_panic(expected = "Square root of negative integer -4")]
+    fn test_negative_integer_sqrt() {
+        YttriaUnitSqrt::sqrt(&-4i16);
+    }
+}
@@ -3,12 +3,17 @@ use num::{cast::FromPrimitive, Num};
 use std::any::type_name;
 
 use crate::prelude::*;
+use crate::vector::MonotonicPolicy;
 use crate::windows;
 
 pub fn map<T: Num + Copy>(value: T, from_low: T, from_high: T, to_low: T, to_high: T) -> T {
     (value - from_low) * ((to_high - to_low) / (from_high - from_low)) + to_low
 }
 
+pub fn next_pow2(n: usize) -> usize {
+    n.next_power_of_two()
+}
+
 pub fn linspace<T: Num + FromPrimitive + Copy>(
     start: T,
     stop: T,
@@ -55,27 +60,16 @@ pub fn arange<T: Num + PartialOrd + Copy>(start: T, stop: T, step: T) -> Vec<T>
 }
 
 pub fn firwin2(numtaps: usize, freqs: &[f64], gains: &[f64], antisymmetric: bool) -> Vec<f64> {
-    let mut freqs = freqs.to_vec();
-
     let nyq = 1.0;
-    let nfreqs = 1 + usize::pow(2, f32::log2(numtaps as f32).ceil() as u32);
-
-    // These are sanity checks. I'm sane, so I can skip them for now... Right?
-    // let d = freqs.diff();
-    // let d2 = d
-    //     .iter()
-    //     .zip(d[1..].iter())
-    //     .map(|(one, two)| *one + *two)
-    //     .collect::<Vec<_>>();
-
-    for i in 0..(freqs.len() - 1) {
-        if freqs[i] == freqs[i + 1] {
-            freqs[i] -= f64::EPSILON;
-            freqs[i + 1] += f64::EPSILON;
-        }
-    }
+    let nfreqs = 1 + next_pow2(numtaps);
+
+    // A repeated frequency marks a step in the gain, which `interp` needs as two distinct
+    // points.
+    let freqs = freqs
+        .enforce_monotonic(MonotonicPolicy::NudgeByEpsilon)
+        .unwrap_or_else(|err| panic!("Invalid frequency points: {err}"));
 
-    let ftype = match (antisymmetric, numtaps % 2 == 0) {
+    let ftype = match (antisymmetric, numtaps.is_multiple_of(2)) {
         (false, false) => 1,
         (false, true) => {
             assert!(
@@ -157,9 +151,28 @@ mod tests {
         println!("{space:?}");
     }
 
+    #[test]
+    fn test_next_pow2() {
+        assert_eq!(next_pow2(1), 1);
+        assert_eq!(next_pow2(5), 8);
+        assert_eq!(next_pow2(8), 8);
+        assert_eq!(next_pow2(9), 16);
+    }
+
     #[test]
     fn test_firwin2() {
         let space = firwin2(10, &[0.0, 0.5, 0.5, 1.0], &[1.0, 1.0, 0.0, 0.0], false);
         println!("{space:?}");
     }
+
+    #[test]
+    fn test_firwin2_nudges_repeated_frequencies() {
+        // The nudged points `firwin2` has always used for a repeated band edge.
+        let gains = [1.0, 1.0, 0.0, 0.0];
+        let nudged = [0.0, 0.5 - f64::EPSILON, 0.5 + f64::EPSILON, 1.0];
+        assert_eq!(
+            firwin2(31, &[0.0, 0.5, 0.5, 1.0], &gains, false),
+            firwin2(31, &nudged, &gains, false)
+        );
+    }
 }
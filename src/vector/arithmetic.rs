@@ -3,11 +3,31 @@ use std::any::type_name;
 use num::{clamp, traits::Euclid, FromPrimitive, Num};
 use rayon::prelude::*;
 
+use super::assert_output_len;
+use crate::config::parallel_min_len;
 use crate::unit::YttriaUnitSqrt;
 
+// Full linear convolution length of an `n` and an `m` element vector, zero if either is empty.
+pub fn convolve_output_len(n: usize, m: usize) -> usize {
+    if n == 0 || m == 0 {
+        0
+    } else {
+        n + m - 1
+    }
+}
+
+// Methods without an `_output_len` companion return as many elements as `self` holds, and every
+// `_into` variant panics unless `out` has exactly the length the allocating method would return.
 pub trait YttriaVectorArithmetic<T> {
     fn sum(&self) -> T;
 
+    fn reduce<U, F>(&self, init: U, f: F) -> U
+    where
+        F: Fn(U, T) -> U;
+    fn par_reduce<F>(&self, identity: T, op: F) -> T
+    where
+        F: Fn(T, T) -> T + Send + Sync;
+
     fn add_into(&self, other: &[T], out: &mut [T]);
     fn add(&self, other: &[T]) -> Vec<T>;
     fn add_inplace(&mut self, other: &[T]) -> &mut Self;
@@ -44,6 +64,8 @@ pub trait YttriaVectorArithmetic<T> {
     fn powi(&mut self, power: u8) -> Vec<T>;
     fn powi_inplace(&mut self, power: u8) -> &mut Self;
 
+    // Negative signed integers panic, naming the offending value, and negative floats give NaN.
+    // `checked_sqrt` returns `None` instead if any element is negative.
     fn sqrt_into(&self, out: &mut [T])
     where
         T: YttriaUnitSqrt<T>;
@@ -51,13 +73,27 @@ pub trait YttriaVectorArithmetic<T> {
     where
         T: YttriaUnitSqrt<T>;
     fn sqrt_inplace(&mut self) -> &mut Self
+    where
+        T: YttriaUnitSqrt<T>;
+    fn checked_sqrt(&self) -> Option<Vec<T>>
     where
         T: YttriaUnitSqrt<T>;
 
+    // One element shorter than `self`, or empty.
+    fn diff_output_len(&self) -> usize;
     fn diff_into(&self, out: &mut [T]);
     fn diff(&self) -> Vec<T>;
     fn diff_in_place(&mut self) -> &mut Self;
 
+    // Same-length difference treating the vector as periodic, so the last element is
+    // `self[0] - self[len - 1]`. On `angle_unwrap` output of a closed phase trajectory the last
+    // element also absorbs the net winding of the loop, a whole number of periods; differencing
+    // the wrapped angles and then unwrapping keeps every step, including the wrap, within half a
+    // period.
+    fn diff_circular_into(&self, out: &mut [T]);
+    fn diff_circular(&self) -> Vec<T>;
+    fn diff_circular_in_place(&mut self) -> &mut Self;
+
     fn cumsum_into(&self, out: &mut [T]);
     fn cumsum(&self) -> Vec<T>;
     fn cumsum_in_place(&mut self) -> &mut Self;
@@ -69,13 +105,50 @@ pub trait YttriaVectorArithmetic<T> {
     where
         T: PartialOrd;
     fn clamp_in_place(&mut self, min: T, max: T) -> &mut Self
+    where
+        T: PartialOrd;
+    // `clamp` along with the number of samples it changed, for spotting clipping while gain
+    // staging. Samples equal to a bound, and NaN, pass through and are not counted.
+    fn clamp_counting_into(&self, out: &mut [T], min: T, max: T) -> usize
+    where
+        T: PartialOrd;
+    fn clamp_counting(&self, min: T, max: T) -> (Vec<T>, usize)
     where
         T: PartialOrd;
 
-    fn convolve_into(&self, out: &[T], out: &mut [T]);
+    // One-sided `clamp`, leaving the other side unbounded. NaN passes through untouched. Float
+    // slices use these too: the same names on `YttriaVectorFloat` would make every call on them
+    // ambiguous once the prelude brings both traits into scope.
+    fn clamp_min_into(&self, out: &mut [T], min: T)
+    where
+        T: PartialOrd;
+    fn clamp_min(&self, min: T) -> Vec<T>
+    where
+        T: PartialOrd;
+    fn clamp_min_in_place(&mut self, min: T) -> &mut Self
+    where
+        T: PartialOrd;
+    fn clamp_max_into(&self, out: &mut [T], max: T)
+    where
+        T: PartialOrd;
+    fn clamp_max(&self, max: T) -> Vec<T>
+    where
+        T: PartialOrd;
+    fn clamp_max_in_place(&mut self, max: T) -> &mut Self
+    where
+        T: PartialOrd;
+
+    // Full linear convolution, `convolve_output_len(len, other.len())` samples long. This used to
+    // stop at `len` samples and skip the last tap of `other`; callers that relied on that take
+    // `[..len]` of the full result, which now also includes the last tap.
+    fn convolve_into(&self, other: &[T], out: &mut [T]);
     fn convolve(&self, other: &[T]) -> Vec<T>;
 
+    // Trapezoidal integral with unit sample spacing.
     fn trapz(&self) -> T;
+    // Running trapezoidal integral with sample spacing `dx`, zero at the first sample.
+    fn cumtrapz_into(&self, dx: T, out: &mut [T]);
+    fn cumtrapz(&self, dx: T) -> Vec<T>;
 
     fn interp_into(&self, out: &mut [T], xp: &[T], fp: &[T])
     where
@@ -110,8 +183,34 @@ where
         accumulator
     }
 
+    // Folds serially from the front of the vector, so `f` does not need to be commutative.
+    fn reduce<U, F>(&self, init: U, f: F) -> U
+    where
+        F: Fn(U, T) -> U,
+    {
+        let mut accumulator = init;
+        for i in self {
+            accumulator = f(accumulator, *i);
+        }
+        accumulator
+    }
+
+    // `op` must be associative and `identity` must be its identity element, since rayon is free
+    // to group the elements however it likes.
+    fn par_reduce<F>(&self, identity: T, op: F) -> T
+    where
+        F: Fn(T, T) -> T + Send + Sync,
+    {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .copied()
+            .reduce(|| identity, &op)
+    }
+
     fn add_into(&self, other: &[T], out: &mut [T]) {
+        assert_output_len("add_into", self.len(), out.len());
         out.par_iter_mut()
+            .with_min_len(parallel_min_len())
             .zip(self)
             .zip(other)
             .for_each(|((out, own), other)| {
@@ -124,16 +223,23 @@ where
         out
     }
     fn add_inplace(&mut self, other: &[T]) -> &mut Self {
-        self.par_iter_mut().zip(other).for_each(|(out, other)| {
-            *out = *out + *other;
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(other)
+            .for_each(|(out, other)| {
+                *out = *out + *other;
+            });
         self
     }
 
     fn add_const_into(&self, addend: T, out: &mut [T]) {
-        out.par_iter_mut().zip(self).for_each(|(out, own)| {
-            *out = *own + addend;
-        });
+        assert_output_len("add_const_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| {
+                *out = *own + addend;
+            });
     }
 
     fn add_const(&self, addend: T) -> Vec<T> {
@@ -142,14 +248,18 @@ where
         out
     }
     fn add_const_inplace(&mut self, addend: T) -> &mut Self {
-        self.par_iter_mut().for_each(|out| {
-            *out = *out + addend;
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|out| {
+                *out = *out + addend;
+            });
         self
     }
 
     fn subtract_into(&self, other: &[T], out: &mut [T]) {
+        assert_output_len("subtract_into", self.len(), out.len());
         out.par_iter_mut()
+            .with_min_len(parallel_min_len())
             .zip(self)
             .zip(other)
             .for_each(|((out, own), other)| {
@@ -162,16 +272,23 @@ where
         out
     }
     fn subtract_inplace(&mut self, other: &[T]) -> &mut Self {
-        self.par_iter_mut().zip(other).for_each(|(out, other)| {
-            *out = *out - *other;
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(other)
+            .for_each(|(out, other)| {
+                *out = *out - *other;
+            });
         self
     }
 
     fn subtract_const_into(&self, subtrahend: T, out: &mut [T]) {
-        out.par_iter_mut().zip(self).for_each(|(out, own)| {
-            *out = *own - subtrahend;
-        });
+        assert_output_len("subtract_const_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| {
+                *out = *own - subtrahend;
+            });
     }
 
     fn subtract_const(&self, subtrahend: T) -> Vec<T> {
@@ -181,14 +298,18 @@ where
     }
 
     fn subtract_const_inplace(&mut self, subtrahend: T) -> &mut Self {
-        self.par_iter_mut().for_each(|out| {
-            *out = *out - subtrahend;
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|out| {
+                *out = *out - subtrahend;
+            });
         self
     }
 
     fn multiply_into(&self, other: &[T], out: &mut [T]) {
+        assert_output_len("multiply_into", self.len(), out.len());
         out.par_iter_mut()
+            .with_min_len(parallel_min_len())
             .zip(self)
             .zip(other)
             .for_each(|((out, own), other)| {
@@ -201,16 +322,23 @@ where
         out
     }
     fn multiply_inplace(&mut self, other: &[T]) -> &mut Self {
-        self.par_iter_mut().zip(other).for_each(|(out, other)| {
-            *out = *out * *other;
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(other)
+            .for_each(|(out, other)| {
+                *out = *out * *other;
+            });
         self
     }
 
     fn multiply_const_into(&self, multiplier: T, out: &mut [T]) {
-        out.par_iter_mut().zip(self).for_each(|(out, own)| {
-            *out = *own * multiplier;
-        });
+        assert_output_len("multiply_const_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| {
+                *out = *own * multiplier;
+            });
     }
     fn multiply_const(&self, multiplier: T) -> Vec<T> {
         let mut out = vec![T::zero(); self.len()];
@@ -218,14 +346,18 @@ where
         out
     }
     fn multiply_const_inplace(&mut self, multiplier: T) -> &mut Self {
-        self.par_iter_mut().for_each(|out| {
-            *out = *out * multiplier;
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|out| {
+                *out = *out * multiplier;
+            });
         self
     }
 
     fn divide_into(&self, other: &[T], out: &mut [T]) {
+        assert_output_len("divide_into", self.len(), out.len());
         out.par_iter_mut()
+            .with_min_len(parallel_min_len())
             .zip(self)
             .zip(other)
             .for_each(|((out, own), other)| {
@@ -238,16 +370,23 @@ where
         out
     }
     fn divide_inplace(&mut self, other: &[T]) -> &mut Self {
-        self.par_iter_mut().zip(other).for_each(|(out, other)| {
-            *out = *out / *other;
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(other)
+            .for_each(|(out, other)| {
+                *out = *out / *other;
+            });
         self
     }
 
     fn divide_const_into(&self, divisor: T, out: &mut [T]) {
-        out.par_iter_mut().zip(self).for_each(|(out, own)| {
-            *out = *own / divisor;
-        });
+        assert_output_len("divide_const_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| {
+                *out = *own / divisor;
+            });
     }
     fn divide_const(&self, divisor: T) -> Vec<T> {
         let mut out = vec![T::zero(); self.len()];
@@ -255,19 +394,25 @@ where
         out
     }
     fn divide_const_inplace(&mut self, divisor: T) -> &mut Self {
-        self.par_iter_mut().for_each(|out| {
-            *out = *out / divisor;
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|out| {
+                *out = *out / divisor;
+            });
         self
     }
 
     fn powi_into(&self, power: u8, out: &mut [T]) {
-        out.par_iter_mut().zip(self).for_each(|(out, own)| {
-            *out = T::one();
-            for _ in 0..power {
-                *out = *out * *own;
-            }
-        });
+        assert_output_len("powi_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| {
+                *out = T::one();
+                for _ in 0..power {
+                    *out = *out * *own;
+                }
+            });
     }
 
     fn powi(&mut self, power: u8) -> Vec<T> {
@@ -277,13 +422,15 @@ where
     }
 
     fn powi_inplace(&mut self, power: u8) -> &mut Self {
-        self.par_iter_mut().for_each(|own| {
-            let base = *own;
-            *own = T::one();
-            for _ in 0..power {
-                *own = *own * base;
-            }
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|own| {
+                let base = *own;
+                *own = T::one();
+                for _ in 0..power {
+                    *own = *own * base;
+                }
+            });
         self
     }
 
@@ -291,9 +438,13 @@ where
     where
         T: YttriaUnitSqrt<T>,
     {
-        out.par_iter_mut().zip(self).for_each(|(out, own)| {
-            *out = own.sqrt();
-        });
+        assert_output_len("sqrt_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| {
+                *out = own.sqrt();
+            });
     }
 
     fn sqrt(&self) -> Vec<T>
@@ -309,20 +460,40 @@ where
     where
         T: YttriaUnitSqrt<T>,
     {
-        self.par_iter_mut().for_each(|own| {
-            *own = own.sqrt();
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|own| {
+                *own = own.sqrt();
+            });
         self
     }
 
+    fn checked_sqrt(&self) -> Option<Vec<T>>
+    where
+        T: YttriaUnitSqrt<T>,
+    {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|own| own.checked_sqrt())
+            .collect()
+    }
+
+    fn diff_output_len(&self) -> usize {
+        self.len().saturating_sub(1)
+    }
+
     fn diff_into(&self, out: &mut [T]) {
-        out.par_iter_mut().enumerate().for_each(|(idx, out)| {
-            *out = self[idx + 1] - self[idx];
-        });
+        assert_output_len("diff_into", self.diff_output_len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .for_each(|(idx, out)| {
+                *out = self[idx + 1] - self[idx];
+            });
     }
 
     fn diff(&self) -> Vec<T> {
-        let mut out = vec![T::zero(); self.len() - 1];
+        let mut out = vec![T::zero(); self.diff_output_len()];
         self.diff_into(out.as_mut_slice());
         out
     }
@@ -334,7 +505,36 @@ where
         self
     }
 
+    fn diff_circular_into(&self, out: &mut [T]) {
+        assert_output_len("diff_circular_into", self.len(), out.len());
+        let len = self.len();
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .for_each(|(idx, out)| {
+                *out = self[(idx + 1) % len] - self[idx];
+            });
+    }
+
+    fn diff_circular(&self) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.diff_circular_into(out.as_mut_slice());
+        out
+    }
+
+    fn diff_circular_in_place(&mut self) -> &mut Self {
+        if let Some(&first) = self.first() {
+            let len = self.len();
+            for i in 0..(len - 1) {
+                self[i] = self[i + 1] - self[i];
+            }
+            self[len - 1] = first - self[len - 1];
+        }
+        self
+    }
+
     fn cumsum_into(&self, out: &mut [T]) {
+        assert_output_len("cumsum_into", self.len(), out.len());
         let mut sum = T::zero();
         for (out, next) in out.iter_mut().zip(self) {
             sum = sum + *next;
@@ -361,7 +561,9 @@ where
     where
         T: PartialOrd,
     {
+        assert_output_len("clamp_into", self.len(), out.len());
         out.par_iter_mut()
+            .with_min_len(parallel_min_len())
             .zip(self)
             .for_each(|(out, own)| *out = clamp(*own, min, max));
     }
@@ -379,24 +581,127 @@ where
     where
         T: PartialOrd,
     {
-        self.par_iter_mut().for_each(|own| {
-            *own = clamp(*own, min, max);
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|own| {
+                *own = clamp(*own, min, max);
+            });
+        self
+    }
+
+    fn clamp_counting_into(&self, out: &mut [T], min: T, max: T) -> usize
+    where
+        T: PartialOrd,
+    {
+        assert_output_len("clamp_counting_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .map(|(out, own)| {
+                *out = clamp(*own, min, max);
+                usize::from(*own < min || *own > max)
+            })
+            .sum()
+    }
+
+    fn clamp_counting(&self, min: T, max: T) -> (Vec<T>, usize)
+    where
+        T: PartialOrd,
+    {
+        let mut out = vec![T::zero(); self.len()];
+        let count = self.clamp_counting_into(out.as_mut_slice(), min, max);
+        (out, count)
+    }
+
+    fn clamp_min_into(&self, out: &mut [T], min: T)
+    where
+        T: PartialOrd,
+    {
+        assert_output_len("clamp_min_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| *out = if *own < min { min } else { *own });
+    }
+
+    fn clamp_min(&self, min: T) -> Vec<T>
+    where
+        T: PartialOrd,
+    {
+        let mut out = vec![T::zero(); self.len()];
+        self.clamp_min_into(out.as_mut_slice(), min);
+        out
+    }
+
+    fn clamp_min_in_place(&mut self, min: T) -> &mut Self
+    where
+        T: PartialOrd,
+    {
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|own| {
+                if *own < min {
+                    *own = min;
+                }
+            });
+        self
+    }
+
+    fn clamp_max_into(&self, out: &mut [T], max: T)
+    where
+        T: PartialOrd,
+    {
+        assert_output_len("clamp_max_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| *out = if *own > max { max } else { *own });
+    }
+
+    fn clamp_max(&self, max: T) -> Vec<T>
+    where
+        T: PartialOrd,
+    {
+        let mut out = vec![T::zero(); self.len()];
+        self.clamp_max_into(out.as_mut_slice(), max);
+        out
+    }
+
+    fn clamp_max_in_place(&mut self, max: T) -> &mut Self
+    where
+        T: PartialOrd,
+    {
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|own| {
+                if *own > max {
+                    *own = max;
+                }
+            });
         self
     }
 
     fn convolve_into(&self, other: &[T], out: &mut [T]) {
-        out.par_iter_mut().enumerate().for_each(|(idx_out, out)| {
-            let lower_bound = 0isize.max(idx_out as isize + 1 - self.len() as isize) as usize;
-            let upper_bound = other.len().min(idx_out);
-            for idx_n in lower_bound..upper_bound {
-                *out = *out + self[idx_out - idx_n] * other[idx_n];
-            }
-        });
+        assert_output_len(
+            "convolve_into",
+            convolve_output_len(self.len(), other.len()),
+            out.len(),
+        );
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .for_each(|(idx_out, out)| {
+                let lower_bound = 0isize.max(idx_out as isize + 1 - self.len() as isize) as usize;
+                let upper_bound = other.len().min(idx_out + 1);
+                *out = T::zero();
+                for idx_n in lower_bound..upper_bound {
+                    *out = *out + self[idx_out - idx_n] * other[idx_n];
+                }
+            });
     }
 
     fn convolve(&self, other: &[T]) -> Vec<T> {
-        let mut out = vec![T::zero(); self.len()];
+        let mut out = vec![T::zero(); convolve_output_len(self.len(), other.len())];
         self.convolve_into(other, &mut out);
         out
     }
@@ -405,10 +710,29 @@ where
         let mut out = T::zero();
         let two = T::one() + T::one();
 
-        for (a, b) in self.iter().zip(&self[1..]) {
-            out = out + (*a * *b) / two;
+        for (a, b) in self.iter().zip(self.iter().skip(1)) {
+            out = out + (*a + *b) / two;
+        }
+
+        out
+    }
+
+    fn cumtrapz_into(&self, dx: T, out: &mut [T]) {
+        assert_output_len("cumtrapz_into", self.len(), out.len());
+        if self.is_empty() {
+            return;
         }
 
+        let two = T::one() + T::one();
+        out[0] = T::zero();
+        for i in 1..self.len() {
+            out[i] = out[i - 1] + (self[i - 1] + self[i]) * dx / two;
+        }
+    }
+
+    fn cumtrapz(&self, dx: T) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.cumtrapz_into(dx, &mut out);
         out
     }
 
@@ -416,17 +740,21 @@ where
     where
         T: PartialOrd,
     {
-        out.par_iter_mut().zip(self).for_each(|(out, own)| {
-            let bin = xp.iter().position(|&pos| pos >= *own).unwrap_or(xp.len());
-            if bin == 0 {
-                *out = fp[0];
-            } else if bin == xp.len() {
-                *out = fp[fp.len() - 1];
-            } else {
-                let slope = (fp[bin] - fp[bin - 1]) / (xp[bin] - xp[bin - 1]);
-                *out = fp[bin - 1] + slope * (*own - xp[bin - 1])
-            }
-        });
+        assert_output_len("interp_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| {
+                let bin = xp.iter().position(|&pos| pos >= *own).unwrap_or(xp.len());
+                if bin == 0 {
+                    *out = fp[0];
+                } else if bin == xp.len() {
+                    *out = fp[fp.len() - 1];
+                } else {
+                    let slope = (fp[bin] - fp[bin - 1]) / (xp[bin] - xp[bin - 1]);
+                    *out = fp[bin - 1] + slope * (*own - xp[bin - 1])
+                }
+            });
     }
 
     fn interp(&self, xp: &[T], fp: &[T]) -> Vec<T>
@@ -442,17 +770,19 @@ where
     where
         T: PartialOrd,
     {
-        self.par_iter_mut().for_each(|out| {
-            let bin = xp.iter().position(|&pos| pos >= *out).unwrap_or(xp.len());
-            if bin == 0 {
-                *out = fp[0];
-            } else if bin == xp.len() {
-                *out = fp[fp.len() - 1];
-            } else {
-                let slope = (fp[bin] - fp[bin - 1]) / (xp[bin] - xp[bin - 1]);
-                *out = fp[bin - 1] + slope * (*out - xp[bin - 1])
-            }
-        });
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|out| {
+                let bin = xp.iter().position(|&pos| pos >= *out).unwrap_or(xp.len());
+                if bin == 0 {
+                    *out = fp[0];
+                } else if bin == xp.len() {
+                    *out = fp[fp.len() - 1];
+                } else {
+                    let slope = (fp[bin] - fp[bin - 1]) / (xp[bin] - xp[bin - 1]);
+                    *out = fp[bin - 1] + slope * (*out - xp[bin - 1])
+                }
+            });
         self
     }
 
@@ -460,6 +790,7 @@ where
     where
         T: FromPrimitive + Euclid,
     {
+        assert_output_len("angle_unwrap_into", self.len(), out.len());
         let period = period.unwrap_or_else(|| {
             T::from_f64(2.0 * std::f64::consts::PI).unwrap_or_else(|| {
                 panic!("Could not convert 2 * pi into type: '{}'", type_name::<T>())
@@ -478,7 +809,6 @@ where
         T: FromPrimitive + Euclid,
     {
         let mut out = vec![T::zero(); self.len()];
-        out[0] = T::zero();
         self.angle_unwrap_into(&mut out, period);
         out
     }
@@ -518,6 +848,17 @@ mod test {
         println!("{out:?}");
     }
 
+    #[test]
+    fn test_reduce_i64() {
+        let test = [3i64, -1, 4, 1, -5, 9, 2, 6];
+
+        assert_eq!(test.reduce(1, |acc, x| acc * x), 6480);
+        assert_eq!(test.par_reduce(1, |a, b| a * b), 6480);
+
+        assert_eq!(test.reduce(i64::MIN, |acc, x| acc.max(x)), 9);
+        assert_eq!(test.par_reduce(i64::MIN, |a, b| a.max(b)), 9);
+    }
+
     #[test]
     fn test_diff_i32() {
         let test = [0i32, 1, 5, 11];
@@ -532,6 +873,140 @@ mod test {
         println!("{interpd:?}");
     }
 
+    #[test]
+    fn test_diff_circular() {
+        let x = [1, 4, 9, 3];
+        assert_eq!(x.diff_circular(), [3, 5, -6, -2]);
+
+        let mut y = x;
+        y.diff_circular_in_place();
+        assert_eq!(y, [3, 5, -6, -2]);
+
+        // A phase ramp of one full turn: the wrap point jumps from 3/4 of a turn back to zero.
+        let quarter = std::f64::consts::FRAC_PI_2;
+        let phase = [0.0, quarter, 2.0 * quarter, 3.0 * quarter];
+        let steps = phase.diff_circular();
+        assert_eq!(steps[..3], [quarter; 3]);
+        assert_eq!(steps[3], -3.0 * quarter);
+        let pi = std::f64::consts::PI;
+        assert!(((steps[3] + pi).rem_euclid(2.0 * pi) - pi - quarter).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_convolve_i32() {
+        let test = [1i32, 2, 3];
+        let kernel = [0i32, 1, 1];
+        assert_eq!(test.convolve(&kernel), [0, 1, 3, 5, 3]);
+        // The old input-length output is the head of the full one.
+        assert_eq!(test.convolve(&kernel)[..test.len()], [0, 1, 3]);
+    }
+
+    #[test]
+    fn test_one_sided_clamp() {
+        let x = [
+            -1e300,
+            -3.0,
+            0.0,
+            2.5,
+            7.0,
+            1e300,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ];
+        assert_eq!(
+            x.clamp_min(0.0),
+            [0.0, 0.0, 0.0, 2.5, 7.0, 1e300, f64::INFINITY, 0.0]
+        );
+        assert_eq!(
+            x.clamp_max(2.5),
+            [-1e300, -3.0, 0.0, 2.5, 2.5, 2.5, 2.5, f64::NEG_INFINITY]
+        );
+        assert_eq!(x.clamp_min(0.0).clamp_max(2.5), x.clamp(0.0, 2.5));
+        assert!([f64::NAN].clamp_min(0.0)[0].is_nan());
+
+        let mut y = [5i32, -8, 3, i32::MIN, i32::MAX];
+        y.clamp_max_in_place(4);
+        assert_eq!(y, [4, -8, 3, i32::MIN, 4]);
+        y.clamp_min_in_place(-2);
+        assert_eq!(y, [4, -2, 3, -2, 4]);
+
+        let mut out = [0u8; 3];
+        [1u8, 9, 200].clamp_min_into(&mut out, 10);
+        assert_eq!(out, [10, 10, 200]);
+        [1u8, 9, 200].clamp_max_into(&mut out, 10);
+        assert_eq!(out, [1, 9, 10]);
+    }
+
+    #[test]
+    fn test_clamp_counting() {
+        let x = [
+            -5.0,
+            -1.0,
+            0.0,
+            0.5,
+            1.0,
+            1.5,
+            f64::NAN,
+            f64::INFINITY,
+            -0.0,
+        ];
+        let (clamped, count) = x.clamp_counting(-1.0, 1.0);
+        assert_eq!(count, 3);
+        assert_eq!(clamped[..6], [-1.0, -1.0, 0.0, 0.5, 1.0, 1.0]);
+        assert!(clamped[6].is_nan());
+        assert_eq!(clamped[7..], [1.0, -0.0]);
+        assert_eq!(clamped[..6], x.clamp(-1.0, 1.0)[..6]);
+
+        let samples = (-1000i32..1000).collect::<Vec<_>>();
+        let mut out = vec![0; samples.len()];
+        assert_eq!(samples.clamp_counting_into(&mut out, -900, 899), 200);
+        assert_eq!(out, samples.clamp_min(-900).clamp_max(899));
+        assert_eq!([0u8; 0].clamp_counting(1, 2), (vec![], 0));
+    }
+
+    #[test]
+    fn test_checked_sqrt() {
+        assert_eq!(
+            [0i32, 1, 15, 16, 1 << 30].checked_sqrt(),
+            Some(vec![0, 1, 3, 4, 1 << 15])
+        );
+        assert_eq!([4i32, 9, -1, 16].checked_sqrt(), None);
+        assert_eq!([4.0, -0.0].checked_sqrt(), Some(vec![2.0, -0.0]));
+        assert_eq!([4.0, -1e-300].checked_sqrt(), None);
+        assert!(YttriaVectorArithmetic::sqrt([-1.0f64].as_slice())[0].is_nan());
+    }
+
+    #[test]
+    #[should_panic(expected = "Square root of negative integer -9")]
+    fn test_sqrt_negative_integer() {
+        YttriaVectorArithmetic::sqrt([4i32, 9, -9, 16].as_slice());
+    }
+
+    #[test]
+    fn test_trapz() {
+        assert_eq!([1.0, 2.0, 4.0].trapz(), 4.5);
+        assert_eq!([3.0].trapz(), 0.0);
+        assert_eq!(Vec::<f64>::new().trapz(), 0.0);
+    }
+
+    #[test]
+    fn test_cumtrapz() {
+        let constant = [2.0; 6].cumtrapz(0.5);
+        assert_eq!(constant, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        // The integral of x is x^2 / 2, which the trapezoidal rule gets exactly.
+        let dx = 0.25;
+        let ramp = (0..41).map(|i| i as f64 * dx).collect::<Vec<_>>();
+        let parabola = ramp.cumtrapz(dx);
+        for (x, y) in ramp.iter().zip(&parabola) {
+            assert!((y - x * x / 2.0).abs() < 1e-12);
+        }
+        assert_eq!(*parabola.last().unwrap(), ramp.trapz() * dx);
+
+        assert_eq!([7i32, 9].cumtrapz(2), [0, 16]);
+        assert!(Vec::<f64>::new().cumtrapz(1.0).is_empty());
+    }
+
     #[test]
     fn test_interp_f32() {
         let test = [-1.0, -0.5, 0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0];
@@ -0,0 +1,209 @@
+use std::any::type_name;
+
+use num::{FromPrimitive, Num};
+use rayon::prelude::*;
+
+use super::statistics::undefined;
+
+// Batch operations apply the same operation to many channels at once. Parallelism is across
+// channels, and the work done on each individual channel is serial, which suits many short
+// channels better than parallelizing inside each one. The reductions only read the channels, so
+// they work on shared slices too.
+pub trait YttriaVectorBatch<T> {
+    // Mean of each channel, NaN or zero for an empty one as with `mean`.
+    fn batch_mean(&self) -> Vec<T>;
+    fn batch_reduce_mean(&self) -> Vec<T>;
+}
+
+pub trait YttriaVectorBatchMut<T> {
+    fn batch_apply_inplace<F>(&mut self, f: F)
+    where
+        F: Fn(&mut [T]) + Send + Sync;
+    fn batch_multiply_inplace(&mut self, common: &[T]);
+}
+
+fn assert_channel_len<T, C: AsRef<[T]>>(channels: &[C], len: usize) {
+    for (idx, channel) in channels.iter().enumerate() {
+        assert!(
+            channel.as_ref().len() == len,
+            "Channel {idx} has length {} but expected {len}",
+            channel.as_ref().len()
+        );
+    }
+}
+
+fn from_len<T: FromPrimitive>(len: usize) -> T {
+    T::from_usize(len).unwrap_or_else(|| {
+        panic!(
+            "Could not convert usize '{len}' into type: {}",
+            type_name::<T>()
+        )
+    })
+}
+
+impl<T, C> YttriaVectorBatchMut<T> for [C]
+where
+    T: Num + Send + Sync + Copy + Clone,
+    C: AsRef<[T]> + AsMut<[T]> + Send + Sync,
+{
+    fn batch_apply_inplace<F>(&mut self, f: F)
+    where
+        F: Fn(&mut [T]) + Send + Sync,
+    {
+        self.par_iter_mut().for_each(|channel| f(channel.as_mut()));
+    }
+
+    fn batch_multiply_inplace(&mut self, common: &[T]) {
+        assert_channel_len(self, common.len());
+
+        self.par_iter_mut().for_each(|channel| {
+            for (out, other) in channel.as_mut().iter_mut().zip(common) {
+                *out = *out * *other;
+            }
+        });
+    }
+}
+
+impl<T, C> YttriaVectorBatch<T> for [C]
+where
+    T: Num + FromPrimitive + Send + Sync + Copy + Clone,
+    C: AsRef<[T]> + Sync,
+{
+    fn batch_mean(&self) -> Vec<T> {
+        self.par_iter()
+            .map(|channel| {
+                let channel = channel.as_ref();
+                if channel.is_empty() {
+                    return undefined();
+                }
+                let mut sum = T::zero();
+                for i in channel {
+                    sum = sum + *i;
+                }
+                sum / from_len(channel.len())
+            })
+            .collect()
+    }
+
+    // Element-wise mean across channels (coherent averaging). The output is split into blocks
+    // that are processed in parallel, and every block sums the channels in the same order so the
+    // result is deterministic.
+    fn batch_reduce_mean(&self) -> Vec<T> {
+        let len = self.first().map(|x| x.as_ref().len()).unwrap_or(0);
+        assert_channel_len(self, len);
+
+        let count: T = from_len(self.len());
+        let mut out = vec![T::zero(); len];
+
+        out.par_chunks_mut(1024)
+            .enumerate()
+            .for_each(|(block, out)| {
+                let offset = block * 1024;
+                for channel in self {
+                    let channel = &channel.as_ref()[offset..(offset + out.len())];
+                    for (out, own) in out.iter_mut().zip(channel) {
+                        *out = *out + *own;
+                    }
+                }
+                for out in out.iter_mut() {
+                    *out = *out / count;
+                }
+            });
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::gaussian_noise;
+    use crate::vector::{YttriaVectorArithmetic, YttriaVectorStatistics};
+    use crate::windows;
+
+    fn noisy_channels(count: usize, len: usize, seed: u64) -> (Vec<f64>, Vec<Vec<f64>>) {
+        let clean = (0..len)
+            .map(|i| (2.0 * std::f64::consts::PI * 0.01 * i as f64).sin())
+            .collect::<Vec<_>>();
+        let channels = gaussian_noise(count * len, seed)
+            .chunks_exact(len)
+            .map(|noise| clean.iter().zip(noise).map(|(x, n)| x + n).collect())
+            .collect();
+        (clean, channels)
+    }
+
+    #[test]
+    fn test_batch_matches_per_channel() {
+        let (_, channels) = noisy_channels(8, 256, 1);
+        let window = windows::hann::<f64>(256);
+
+        let mut batch = channels.clone();
+        batch.batch_multiply_inplace(&window);
+
+        let mut applied = channels.clone();
+        applied.batch_apply_inplace(|x| {
+            x.multiply_inplace(&window);
+        });
+
+        for ((channel, batch), applied) in channels.iter().zip(&batch).zip(&applied) {
+            let expected = channel.multiply(&window);
+            assert_eq!(batch, &expected);
+            assert_eq!(applied, &expected);
+        }
+
+        let means = channels.batch_mean();
+        for (channel, mean) in channels.iter().zip(&means) {
+            assert!((channel.mean() - mean).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_batch_on_mutable_slices() {
+        let mut one = [1.0f32, 2.0, 3.0];
+        let mut two = [4.0f32, 5.0, 6.0];
+        let mut channels = [one.as_mut_slice(), two.as_mut_slice()];
+
+        channels.batch_multiply_inplace(&[2.0, 0.0, 1.0]);
+        assert_eq!(channels.batch_reduce_mean(), [5.0, 0.0, 4.5]);
+    }
+
+    #[test]
+    fn test_batch_on_shared_slices() {
+        let (one, two, empty) = ([1, 2, 6], [4, 5, 6], [0i32; 0]);
+        let channels = [one.as_slice(), two.as_slice(), empty.as_slice()];
+        assert_eq!(channels.batch_mean(), [3, 5, 0]);
+        assert_eq!(channels[..2].batch_reduce_mean(), [2, 3, 6]);
+
+        let floats = [vec![1.0f64, 2.0], vec![]];
+        let means = floats.batch_mean();
+        assert_eq!(means[0], 1.5);
+        assert!(means[1].is_nan());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_batch_unequal_lengths() {
+        let channels = [vec![0.0f32; 16], vec![0.0; 15]];
+        channels.batch_reduce_mean();
+    }
+
+    #[test]
+    fn test_coherent_averaging_snr() {
+        let count = 16;
+        let (clean, channels) = noisy_channels(count, 8192, 7);
+
+        let snr = |x: &[f64]| {
+            let noise = x.subtract(&clean);
+            let signal_power = clean.multiply(&clean).mean();
+            let noise_power = noise.multiply(&noise).mean();
+            10.0 * (signal_power / noise_power).log10()
+        };
+
+        let improvement = snr(&channels.batch_reduce_mean()) - snr(&channels[0]);
+        let expected = 10.0 * (count as f64).log10();
+        assert!(
+            (improvement - expected).abs() < 1.0,
+            "{improvement} dB vs {expected} dB"
+        );
+    }
+}
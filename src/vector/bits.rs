@@ -1,16 +1,208 @@
+use std::fmt;
 use std::mem::size_of;
 
 use num::{FromPrimitive, Integer};
 
+// How `bits_to_symbols` completes a final symbol when the bit count doesn't divide evenly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TailPadding {
+    Zeros,
+    Ones,
+    Truncate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverflowError;
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value does not fit in a u128")
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+// Manchester chip order for each bit. IEEE 802.3 sends a 0 as high then low, G. E. Thomas the
+// opposite, so every bit has a transition at its middle either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManchesterConvention {
+    Ieee,
+    Thomas,
+}
+
+impl ManchesterConvention {
+    fn chips(self, bit: u8) -> [u8; 2] {
+        match (self, bit & 1) {
+            (Self::Ieee, 0) | (Self::Thomas, 1) => [1, 0],
+            _ => [0, 1],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineCodeError {
+    // The chip pair starting at `index` has no mid-bit transition.
+    InvalidTransition { index: usize },
+    // Manchester chips come in pairs.
+    OddLength { len: usize },
+}
+
+impl fmt::Display for LineCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidTransition { index } => {
+                write!(f, "no mid-bit transition in the chips at {index}")
+            }
+            Self::OddLength { len } => write!(f, "{len} chips do not form whole bits"),
+        }
+    }
+}
+
+impl std::error::Error for LineCodeError {}
+
+// Inverse of `run_lengths`.
+pub fn from_run_lengths(runs: &[(u8, usize)]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(runs.iter().map(|(_, len)| len).sum());
+    for (value, len) in runs {
+        out.resize(out.len() + len, *value);
+    }
+    out
+}
+
+// A measured run converted to a whole number of bits. `residual` is how far the run was from
+// `bits` bit periods, in bits, and `clean` is false when that exceeds the tolerance or the run
+// is too short to hold a bit at all.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantizedRun {
+    pub value: u8,
+    pub bits: usize,
+    pub residual: f64,
+    pub clean: bool,
+}
+
+// Converts runs of sample decisions into bit counts, rounding each run to the nearest number
+// of bit periods and flagging runs more than `tolerance` bits away from one.
+pub fn bits_from_run_lengths_quantized(
+    runs: &[(u8, usize)],
+    samples_per_bit: f64,
+    tolerance: f64,
+) -> Vec<QuantizedRun> {
+    assert!(
+        samples_per_bit > 0.0,
+        "Samples per bit must be positive, got {samples_per_bit}"
+    );
+
+    runs.iter()
+        .map(|(value, len)| {
+            let periods = *len as f64 / samples_per_bit;
+            let bits = periods.round();
+            let residual = periods - bits;
+            QuantizedRun {
+                value: *value,
+                bits: bits as usize,
+                residual,
+                clean: bits > 0.0 && residual.abs() <= tolerance,
+            }
+        })
+        .collect()
+}
+
+fn assert_radix(radix: u32) {
+    assert!(
+        (2..=256).contains(&radix),
+        "Radix must lie within 2..=256, got {radix}"
+    );
+}
+
+// Splits `value` into exactly `digits` base `radix` digits, most significant first, keeping
+// leading zeros.
+pub fn unpack_radix_u128(value: u128, radix: u32, digits: usize) -> Vec<u8> {
+    assert_radix(radix);
+
+    let mut out = vec![0u8; digits];
+    let mut value = value;
+    for digit in out.iter_mut().rev() {
+        *digit = (value % radix as u128) as u8;
+        value /= radix as u128;
+    }
+    assert!(
+        value == 0,
+        "Value needs more than {digits} base {radix} digits"
+    );
+    out
+}
+
 pub trait YttriaVectorBitwise {
+    // A partial final byte is padded with zeros, so packing gives `len.div_ceil(8)` bytes.
+    fn packbits_output_len(&self) -> usize;
     fn packbits(&self) -> Vec<u8>;
+    fn unpackbits_output_len(&self) -> usize;
     fn unpackbits(&self) -> Vec<u8>;
+    // The first `count` bits of `unpackbits`, dropping the padding at the end of the last byte.
+    // Panics if the bytes hold fewer than `count` bits.
+    fn unpackbits_count(&self, count: usize) -> Vec<u8>;
+    // `unpackbits` as hard decisions, most significant bit first.
+    fn unpackbits_bool(&self) -> Vec<bool>;
     fn pack_into<T>(&self) -> T
     where
         T: Integer + FromPrimitive + std::ops::Shl<Output = T> + std::ops::BitOr<Output = T>;
+
+    // Groups of `bits_per_symbol` bits, most significant first, into symbol indices and back.
+    fn bits_to_symbols_output_len(&self, bits_per_symbol: usize, padding: TailPadding) -> usize;
+    fn bits_to_symbols(&self, bits_per_symbol: usize, padding: TailPadding) -> Vec<u8>;
+    fn symbols_to_bits_output_len(&self, bits_per_symbol: usize) -> usize;
+    fn symbols_to_bits(&self, bits_per_symbol: usize) -> Vec<u8>;
+
+    // Interprets `self` as base `radix` digits, most significant first.
+    fn pack_radix_u128(&self, radix: u32) -> Result<u128, OverflowError>;
+
+    // Consecutive equal values as (value, length) pairs, in order.
+    fn run_lengths(&self) -> Vec<(u8, usize)>;
+
+    // Two chips per bit, and back. Decoding fails on a chip pair without a transition.
+    fn manchester_encode(&self, convention: ManchesterConvention) -> Vec<u8>;
+    fn manchester_decode(&self, convention: ManchesterConvention)
+        -> Result<Vec<u8>, LineCodeError>;
+
+    // NRZ-M, where a 1 toggles the line level and a 0 holds it, starting from `initial_level`.
+    fn nrzi_encode(&self, initial_level: u8) -> Vec<u8>;
+    fn nrzi_decode(&self, initial_level: u8) -> Vec<u8>;
+}
+
+// Packing for hard decision streams held as `bool`, with the layout of the `[u8]` methods.
+pub trait YttriaVectorBoolBits {
+    fn packbits_output_len(&self) -> usize;
+    fn packbits(&self) -> Vec<u8>;
+}
+
+impl YttriaVectorBoolBits for [bool] {
+    fn packbits_output_len(&self) -> usize {
+        self.len().div_ceil(8)
+    }
+
+    fn packbits(&self) -> Vec<u8> {
+        self.chunks(8)
+            .map(|x| {
+                x.iter()
+                    .enumerate()
+                    .fold(0u8, |out, (i, bit)| out | ((*bit as u8) << (7 - i)))
+            })
+            .collect()
+    }
+}
+
+fn assert_bits_per_symbol(bits_per_symbol: usize) {
+    assert!(
+        (1..=8).contains(&bits_per_symbol),
+        "Bits per symbol must lie within 1..=8, got {bits_per_symbol}"
+    );
 }
 
 impl YttriaVectorBitwise for [u8] {
+    fn packbits_output_len(&self) -> usize {
+        self.len().div_ceil(8)
+    }
+
     fn packbits(&self) -> Vec<u8> {
         self.chunks(8)
             .map(|x| {
@@ -25,6 +217,10 @@ impl YttriaVectorBitwise for [u8] {
             .collect::<Vec<_>>()
     }
 
+    fn unpackbits_output_len(&self) -> usize {
+        8 * self.len()
+    }
+
     fn unpackbits(&self) -> Vec<u8> {
         self.iter()
             .flat_map(|x| {
@@ -42,6 +238,24 @@ impl YttriaVectorBitwise for [u8] {
             .collect::<Vec<_>>()
     }
 
+    fn unpackbits_count(&self, count: usize) -> Vec<u8> {
+        assert!(
+            count <= self.unpackbits_output_len(),
+            "Cannot unpack {count} bits from {} bytes",
+            self.len()
+        );
+
+        let mut bits = self[..count.div_ceil(8)].unpackbits();
+        bits.truncate(count);
+        bits
+    }
+
+    fn unpackbits_bool(&self) -> Vec<bool> {
+        self.iter()
+            .flat_map(|x| (0..8).rev().map(move |shift| (x >> shift) & 1 == 1))
+            .collect()
+    }
+
     fn pack_into<T>(&self) -> T
     where
         T: Integer + FromPrimitive + std::ops::Shl<Output = T> + std::ops::BitOr<Output = T>,
@@ -58,11 +272,227 @@ impl YttriaVectorBitwise for [u8] {
 
         sum
     }
+
+    fn bits_to_symbols_output_len(&self, bits_per_symbol: usize, padding: TailPadding) -> usize {
+        assert_bits_per_symbol(bits_per_symbol);
+        match padding {
+            TailPadding::Truncate => self.len() / bits_per_symbol,
+            _ => self.len().div_ceil(bits_per_symbol),
+        }
+    }
+
+    fn bits_to_symbols(&self, bits_per_symbol: usize, padding: TailPadding) -> Vec<u8> {
+        let count = self.bits_to_symbols_output_len(bits_per_symbol, padding);
+        let fill = match padding {
+            TailPadding::Zeros | TailPadding::Truncate => 0,
+            TailPadding::Ones => 1,
+        };
+
+        self.chunks(bits_per_symbol)
+            .take(count)
+            .map(|bits| {
+                let mut symbol = 0u8;
+                for i in 0..bits_per_symbol {
+                    let bit = bits.get(i).copied().unwrap_or(fill);
+                    assert!(bit <= 1, "Bits must be 0 or 1, got {bit}");
+                    symbol = (symbol << 1) | bit;
+                }
+                symbol
+            })
+            .collect()
+    }
+
+    fn symbols_to_bits_output_len(&self, bits_per_symbol: usize) -> usize {
+        assert_bits_per_symbol(bits_per_symbol);
+        bits_per_symbol * self.len()
+    }
+
+    fn symbols_to_bits(&self, bits_per_symbol: usize) -> Vec<u8> {
+        assert_bits_per_symbol(bits_per_symbol);
+
+        self.iter()
+            .flat_map(|symbol| {
+                assert!(
+                    (*symbol as u16) < 1 << bits_per_symbol,
+                    "Symbol {symbol} does not fit in {bits_per_symbol} bits"
+                );
+                (0..bits_per_symbol)
+                    .rev()
+                    .map(move |shift| (symbol >> shift) & 0x1)
+            })
+            .collect()
+    }
+
+    fn pack_radix_u128(&self, radix: u32) -> Result<u128, OverflowError> {
+        assert_radix(radix);
+
+        self.iter().try_fold(0u128, |value, digit| {
+            assert!(
+                (*digit as u32) < radix,
+                "Digit {digit} is out of range for radix {radix}"
+            );
+            value
+                .checked_mul(radix as u128)
+                .and_then(|value| value.checked_add(*digit as u128))
+                .ok_or(OverflowError)
+        })
+    }
+
+    fn run_lengths(&self) -> Vec<(u8, usize)> {
+        let mut runs: Vec<(u8, usize)> = Vec::new();
+        for value in self {
+            match runs.last_mut() {
+                Some((last, len)) if last == value => *len += 1,
+                _ => runs.push((*value, 1)),
+            }
+        }
+        runs
+    }
+
+    fn manchester_encode(&self, convention: ManchesterConvention) -> Vec<u8> {
+        self.iter().flat_map(|bit| convention.chips(*bit)).collect()
+    }
+
+    fn manchester_decode(
+        &self,
+        convention: ManchesterConvention,
+    ) -> Result<Vec<u8>, LineCodeError> {
+        if !self.len().is_multiple_of(2) {
+            return Err(LineCodeError::OddLength { len: self.len() });
+        }
+
+        self.chunks_exact(2)
+            .enumerate()
+            .map(|(i, pair)| match (pair[0] & 1, pair[1] & 1) {
+                (a, b) if a != b => Ok((convention.chips(1) == [a, b]) as u8),
+                _ => Err(LineCodeError::InvalidTransition { index: 2 * i }),
+            })
+            .collect()
+    }
+
+    fn nrzi_encode(&self, initial_level: u8) -> Vec<u8> {
+        let mut level = initial_level & 1;
+        self.iter()
+            .map(|bit| {
+                level ^= bit & 1;
+                level
+            })
+            .collect()
+    }
+
+    fn nrzi_decode(&self, initial_level: u8) -> Vec<u8> {
+        let mut previous = initial_level & 1;
+        self.iter()
+            .map(|level| {
+                let bit = (level & 1) ^ previous;
+                previous = level & 1;
+                bit
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::YttriaVectorBitwise;
+    use super::*;
+    use crate::rng::{Xoshiro256PlusPlus, YttriaRng};
+
+    fn random_bits(len: usize, seed: u64) -> Vec<u8> {
+        let mut rng = Xoshiro256PlusPlus::seed_from(seed);
+        (0..len).map(|_| (rng.next_u64() & 1) as u8).collect()
+    }
+
+    #[test]
+    fn test_run_lengths() {
+        let bits = [0u8, 0, 1, 1, 1, 0, 1, 1];
+        let runs = bits.run_lengths();
+        assert_eq!(runs, [(0, 2), (1, 3), (0, 1), (1, 2)]);
+        assert_eq!(from_run_lengths(&runs), bits);
+
+        let bits = random_bits(1000, 1);
+        assert_eq!(from_run_lengths(&bits.run_lengths()), bits);
+        assert!([0u8; 0].run_lengths().is_empty());
+        assert_eq!([1u8; 5].run_lengths(), [(1, 5)]);
+    }
+
+    #[test]
+    fn test_manchester() {
+        let bits = [0u8, 1, 1, 0];
+        assert_eq!(
+            bits.manchester_encode(ManchesterConvention::Ieee),
+            [1, 0, 0, 1, 0, 1, 1, 0]
+        );
+        assert_eq!(
+            bits.manchester_encode(ManchesterConvention::Thomas),
+            [0, 1, 1, 0, 1, 0, 0, 1]
+        );
+
+        let bits = random_bits(500, 2);
+        for convention in [ManchesterConvention::Ieee, ManchesterConvention::Thomas] {
+            let chips = bits.manchester_encode(convention);
+            assert_eq!(chips.manchester_decode(convention), Ok(bits.clone()));
+        }
+    }
+
+    #[test]
+    fn test_manchester_errors() {
+        let mut chips = [0u8, 1, 1, 0, 1, 0, 0, 1].manchester_encode(ManchesterConvention::Ieee);
+        chips[7] = chips[6];
+        assert_eq!(
+            chips.manchester_decode(ManchesterConvention::Ieee),
+            Err(LineCodeError::InvalidTransition { index: 6 })
+        );
+        assert_eq!(
+            [1u8, 0, 1].manchester_decode(ManchesterConvention::Thomas),
+            Err(LineCodeError::OddLength { len: 3 })
+        );
+    }
+
+    #[test]
+    fn test_nrzi() {
+        assert_eq!([1u8, 0, 1, 1, 0].nrzi_encode(0), [1, 1, 0, 1, 1]);
+        assert_eq!([1u8, 0, 1, 1, 0].nrzi_encode(1), [0, 0, 1, 0, 0]);
+
+        let bits = random_bits(500, 3);
+        for initial_level in [0, 1] {
+            assert_eq!(
+                bits.nrzi_encode(initial_level).nrzi_decode(initial_level),
+                bits
+            );
+        }
+    }
+
+    #[test]
+    fn test_quantized_runs() {
+        let samples_per_bit = 10.0;
+        let bits = random_bits(400, 4);
+        let expected = bits.run_lengths();
+
+        // Stretch or shrink every run by up to a fifth of a bit.
+        let mut rng = Xoshiro256PlusPlus::seed_from(5);
+        let mut runs = expected
+            .iter()
+            .map(|(value, len)| {
+                let jitter = (rng.next_f64() - 0.5) * 0.4 * samples_per_bit;
+                let samples = (*len as f64 * samples_per_bit + jitter).round() as usize;
+                (*value, samples)
+            })
+            .collect::<Vec<_>>();
+        // A glitch shorter than any bit.
+        runs.push((1, 2));
+
+        let quantized = bits_from_run_lengths_quantized(&runs, samples_per_bit, 0.25);
+        for (q, (value, len)) in quantized.iter().zip(&expected) {
+            assert_eq!((q.value, q.bits), (*value, *len));
+            assert!(q.clean && q.residual.abs() <= 0.2 + 0.05);
+        }
+        let glitch = quantized.last().unwrap();
+        assert_eq!(glitch.bits, 0);
+        assert!(!glitch.clean);
+
+        let half_way = bits_from_run_lengths_quantized(&[(0, 25)], samples_per_bit, 0.25);
+        assert!(!half_way[0].clean);
+    }
 
     #[test]
     fn test_unpack_bits() {
@@ -79,6 +509,45 @@ mod tests {
         assert!(data.iter().eq(recon_data.iter()));
     }
 
+    #[test]
+    fn test_unpack_bits_count() {
+        let data = [0xa5u8, 0x3c];
+        assert_eq!(
+            data.unpackbits_count(12),
+            [1, 0, 1, 0, 0, 1, 0, 1, 0, 0, 1, 1]
+        );
+        assert_eq!(data.unpackbits_count(12).packbits(), [0xa5, 0x30]);
+        assert_eq!(data.unpackbits_count(16), data.unpackbits());
+        assert_eq!(data.unpackbits_count(3), [1, 0, 1]);
+        assert!(data.unpackbits_count(0).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unpack_bits_count_too_many() {
+        [0xffu8].unpackbits_count(9);
+    }
+
+    #[test]
+    fn test_bool_round_trip() {
+        let data = [129u8, 15, 0xa5];
+        let bits = data.unpackbits_bool();
+        assert_eq!(
+            bits,
+            data.unpackbits()
+                .iter()
+                .map(|b| *b == 1)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(bits.packbits(), data);
+
+        // A partial final byte is zero padded, as for `[u8]` bits.
+        let partial = [true, true, false, true, true];
+        assert_eq!(partial.packbits_output_len(), 1);
+        assert_eq!(partial.packbits(), [0b1101_1000]);
+        assert_eq!(partial.packbits(), [1u8, 1, 0, 1, 1].packbits());
+    }
+
     #[test]
     fn test_pack_into_u64() {
         let data = [1u8, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 1, 1, 1];
@@ -94,4 +563,63 @@ mod tests {
 
         assert!(data.iter().eq(recon_data.iter()));
     }
+
+    #[test]
+    fn test_radix_round_trip() {
+        for (radix, digits) in [
+            (3, vec![0u8, 0, 2, 1, 0, 2]),
+            (10, vec![0, 4, 2, 9, 9]),
+            (36, vec![0, 0, 35, 17, 1, 0]),
+        ] {
+            let value = digits.pack_radix_u128(radix).unwrap();
+            assert_eq!(unpack_radix_u128(value, radix, digits.len()), digits);
+        }
+
+        assert_eq!([1u8, 2, 0].pack_radix_u128(3), Ok(15));
+        assert_eq!(unpack_radix_u128(15, 3, 5), [0, 0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn test_radix_overflow() {
+        let max = unpack_radix_u128(u128::MAX, 10, 39);
+        assert_eq!(max.pack_radix_u128(10), Ok(u128::MAX));
+
+        let mut over = max.clone();
+        over[38] += 1;
+        assert_eq!(over.pack_radix_u128(10), Err(OverflowError));
+        assert_eq!([1u8; 40].pack_radix_u128(10), Err(OverflowError));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_radix_digit_out_of_range() {
+        let _ = [0u8, 3].pack_radix_u128(3);
+    }
+
+    #[test]
+    fn test_symbols_match_packbits() {
+        let bits = [129u8, 15, 0xa5, 0x3c].unpackbits();
+        for bits_per_symbol in [1, 2, 4, 8] {
+            let symbols = bits.bits_to_symbols(bits_per_symbol, TailPadding::Zeros);
+            assert_eq!(symbols.len(), bits.len() / bits_per_symbol);
+            assert_eq!(symbols.symbols_to_bits(bits_per_symbol), bits);
+            assert_eq!(
+                symbols.symbols_to_bits(bits_per_symbol).packbits(),
+                bits.packbits()
+            );
+        }
+        assert_eq!(
+            bits.bits_to_symbols(8, TailPadding::Zeros),
+            [129, 15, 0xa5, 0x3c]
+        );
+    }
+
+    #[test]
+    fn test_tail_padding() {
+        let bits = [1u8, 0, 1, 1, 0, 1, 1];
+        assert_eq!(bits.bits_to_symbols(3, TailPadding::Zeros), [5, 5, 4]);
+        assert_eq!(bits.bits_to_symbols(3, TailPadding::Ones), [5, 5, 7]);
+        assert_eq!(bits.bits_to_symbols(3, TailPadding::Truncate), [5, 5]);
+        assert_eq!(bits.bits_to_symbols(7, TailPadding::Truncate), [91]);
+    }
 }
@@ -0,0 +1,230 @@
+use std::borrow::Cow;
+use std::mem::{align_of, size_of, size_of_val};
+
+use num::traits::{FromBytes, ToBytes};
+use num::{Complex, Num};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+pub trait YttriaVectorBytes<T> {
+    fn to_bytes_output_len(&self) -> usize;
+    fn to_bytes(&self, endian: Endianness) -> Vec<u8>;
+}
+
+impl<T> YttriaVectorBytes<T> for [T]
+where
+    T: ToBytes,
+{
+    fn to_bytes_output_len(&self) -> usize {
+        size_of_val(self)
+    }
+
+    fn to_bytes(&self, endian: Endianness) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.to_bytes_output_len());
+
+        for i in self {
+            match endian {
+                Endianness::Little => out.extend_from_slice(i.to_le_bytes().as_ref()),
+                Endianness::Big => out.extend_from_slice(i.to_be_bytes().as_ref()),
+            }
+        }
+
+        out
+    }
+}
+
+pub fn from_bytes<T>(bytes: &[u8], endian: Endianness) -> Vec<T>
+where
+    T: Num + FromBytes + ToBytes<Bytes = <T as FromBytes>::Bytes>,
+    <T as FromBytes>::Bytes: Sized,
+{
+    let width = size_of::<T>();
+    assert!(
+        bytes.len().is_multiple_of(width),
+        "Byte count {} is not a multiple of the element size {width}",
+        bytes.len()
+    );
+
+    let mut buffer = T::zero().to_ne_bytes();
+
+    bytes
+        .chunks_exact(width)
+        .map(|chunk| {
+            buffer.as_mut().copy_from_slice(chunk);
+            match endian {
+                Endianness::Little => T::from_le_bytes(&buffer),
+                Endianness::Big => T::from_be_bytes(&buffer),
+            }
+        })
+        .collect()
+}
+
+// Views interleaved `re, im` pairs as complex samples without copying. `Complex<T>` is
+// `#[repr(C)]` with exactly these two fields, so it has the layout and alignment of `[T; 2]`
+// and any aligned pair of `T` is a valid value. Panics on an odd length.
+pub fn complex_from_interleaved<T>(samples: &[T]) -> &[Complex<T>] {
+    assert!(
+        samples.len().is_multiple_of(2),
+        "Interleaved samples need an even length, got {}",
+        samples.len()
+    );
+    // SAFETY: the layout argument above; the length halves and the lifetime carries over.
+    unsafe { std::slice::from_raw_parts(samples.as_ptr().cast(), samples.len() / 2) }
+}
+
+pub fn complex_from_interleaved_mut<T>(samples: &mut [T]) -> &mut [Complex<T>] {
+    assert!(
+        samples.len().is_multiple_of(2),
+        "Interleaved samples need an even length, got {}",
+        samples.len()
+    );
+    // SAFETY: as for `complex_from_interleaved`, with the unique borrow moving to the view.
+    unsafe { std::slice::from_raw_parts_mut(samples.as_mut_ptr().cast(), samples.len() / 2) }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+// Primitive sample types for which every bit pattern is a valid value, so raw capture bytes can
+// be reinterpreted as them. Sealed, as implementing it for anything else would be unsound.
+pub trait PlainSample: sealed::Sealed + Copy {}
+
+macro_rules! plain_sample {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+            impl PlainSample for $t {}
+        )*
+    };
+}
+
+plain_sample!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+// Interleaved `re, im` samples stored as raw bytes, such as a memory mapped capture file. The
+// bytes are borrowed in place when they are in native byte order and aligned for `T`, which a
+// mapping at a page boundary is, and decoded into a new vector otherwise. Panics unless the byte
+// count is a whole number of complex samples.
+pub fn complex_from_interleaved_bytes<T>(bytes: &[u8], endian: Endianness) -> Cow<'_, [Complex<T>]>
+where
+    T: PlainSample + Num + FromBytes + ToBytes<Bytes = <T as FromBytes>::Bytes>,
+    <T as FromBytes>::Bytes: Sized,
+{
+    let width = 2 * size_of::<T>();
+    assert!(
+        bytes.len().is_multiple_of(width),
+        "Byte count {} is not a multiple of the complex sample size {width}",
+        bytes.len()
+    );
+
+    let native = match endian {
+        Endianness::Little => cfg!(target_endian = "little"),
+        Endianness::Big => cfg!(target_endian = "big"),
+    };
+    if native && bytes.as_ptr().align_offset(align_of::<T>()) == 0 {
+        // SAFETY: the pointer is aligned for `T` and every bit pattern is a valid `T`, so the
+        // bytes hold `len / width` valid `Complex<T>` laid out as `[T; 2]`.
+        Cow::Borrowed(unsafe {
+            std::slice::from_raw_parts(bytes.as_ptr().cast(), bytes.len() / width)
+        })
+    } else {
+        let values = from_bytes::<T>(bytes, endian);
+        Cow::Owned(
+            values
+                .chunks_exact(2)
+                .map(|pair| Complex::new(pair[0], pair[1]))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f32_round_trip() {
+        let data = [0.0f32, 1.5, -2.25, f32::MAX, f32::MIN_POSITIVE];
+
+        for endian in [Endianness::Little, Endianness::Big] {
+            let bytes = data.to_bytes(endian);
+            assert_eq!(bytes.len(), 4 * data.len());
+            assert_eq!(from_bytes::<f32>(&bytes, endian), data);
+        }
+
+        assert_eq!(data[1..2].to_bytes(Endianness::Big), [0x3f, 0xc0, 0, 0]);
+        assert_eq!(data[1..2].to_bytes(Endianness::Little), [0, 0, 0xc0, 0x3f]);
+    }
+
+    #[test]
+    fn test_i16_round_trip() {
+        let data = [0i16, 1, -1, 0x1234, i16::MIN, i16::MAX];
+
+        for endian in [Endianness::Little, Endianness::Big] {
+            let bytes = data.to_bytes(endian);
+            assert_eq!(from_bytes::<i16>(&bytes, endian), data);
+        }
+
+        assert_eq!(data[3..4].to_bytes(Endianness::Big), [0x12, 0x34]);
+        assert_eq!(data[3..4].to_bytes(Endianness::Little), [0x34, 0x12]);
+    }
+
+    fn copied<T: Copy>(samples: &[T]) -> Vec<Complex<T>> {
+        samples
+            .chunks_exact(2)
+            .map(|pair| Complex::new(pair[0], pair[1]))
+            .collect()
+    }
+
+    #[test]
+    fn test_interleaved_view() {
+        let mut samples = [1.0f32, -2.0, 0.5, 3.25, -0.125, 8.0];
+        let view = complex_from_interleaved(&samples);
+        assert_eq!(view, copied(&samples));
+        assert_eq!(view.as_ptr().cast::<f32>(), samples.as_ptr());
+
+        complex_from_interleaved_mut(&mut samples)[1] = Complex::new(7.0, -7.0);
+        assert_eq!(samples[2..4], [7.0, -7.0]);
+    }
+
+    #[test]
+    fn test_interleaved_bytes() {
+        let values = [1i16, -2, 0x1234, i16::MIN, 5, i16::MAX];
+        let expected = copied(&values);
+        let native = if cfg!(target_endian = "little") {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        };
+
+        for endian in [Endianness::Little, Endianness::Big] {
+            // One spare byte lets the samples start both on and off an aligned address.
+            let mut buffer = vec![0u8; values.len() * 2 + 1];
+            let aligned = buffer.as_ptr().align_offset(2);
+            for offset in [aligned, 1 - aligned] {
+                buffer[offset..offset + 12].copy_from_slice(&values.to_bytes(endian));
+                let bytes = &buffer[offset..offset + 12];
+                let view = complex_from_interleaved_bytes::<i16>(bytes, endian);
+                assert_eq!(*view, expected);
+                let borrowed = matches!(view, Cow::Borrowed(_));
+                assert_eq!(borrowed, endian == native && offset == aligned);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_interleaved_odd_length() {
+        complex_from_interleaved(&[1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_bytes_partial_element() {
+        from_bytes::<i16>(&[0, 1, 2], Endianness::Little);
+    }
+}
@@ -0,0 +1,254 @@
+use num::traits::Euclid;
+use num::{Complex, Float, FromPrimitive, Zero};
+use rustfft::FftNum;
+
+use super::{FftContext, YttriaVectorArithmetic, YttriaVectorComplexFft, YttriaVectorRealFft};
+
+// Magnitudes are floored this far below the spectral peak before taking the log, so exact
+// spectral nulls give a large negative log magnitude rather than -inf.
+const LOG_FLOOR: f64 = 1e-10;
+
+pub trait YttriaVectorCepstrum<T> {
+    // Inverse FFT of the log magnitude spectrum. An echo of delay `d` shows up as a peak at
+    // quefrency `d` and its multiples.
+    fn real_cepstrum(&self) -> Vec<T>;
+
+    // Inverse FFT of the log magnitude and unwrapped phase spectra. The linear phase term, a
+    // circular delay of a whole number of samples, is removed before the inverse and returned
+    // alongside, so a round trip through `inverse_complex_cepstrum` needs both.
+    fn complex_cepstrum(&self) -> (Vec<T>, isize);
+    fn inverse_complex_cepstrum(&self, delay: isize) -> Vec<T>;
+
+    // Homomorphic conversion of FIR taps to the minimum phase filter of the same length and
+    // magnitude response, folding the zeros outside the unit circle in. The response is sampled
+    // on a grid at least 100 times denser than the taps; within the passband of a typical
+    // lowpass the magnitude matches to within 0.1 dB, while deep stopband nulls are
+    // only approximated.
+    fn minimum_phase(&self) -> Vec<T>;
+}
+
+fn to_complex<T: Float>(x: &[T], len: usize) -> Vec<Complex<T>> {
+    let mut out = vec![Complex::zero(); len];
+    for (out, x) in out.iter_mut().zip(x) {
+        *out = Complex::new(*x, T::zero());
+    }
+    out
+}
+
+fn log_magnitude<T: Float + FromPrimitive>(spectrum: &[Complex<T>]) -> Vec<T> {
+    let peak = spectrum.iter().fold(T::zero(), |acc, x| acc.max(x.norm()));
+    let floor = peak * T::from_f64(LOG_FLOOR).expect("Could not convert f64 into type");
+    spectrum
+        .iter()
+        .map(|x| x.norm().max(floor).max(T::min_positive_value()).ln())
+        .collect()
+}
+
+// Phase slope of one whole sample of delay, per bin, as used by the linear phase removal.
+fn delay_slope<T: Float + FromPrimitive>(len: usize) -> T {
+    let pi = T::from_f64(std::f64::consts::PI).expect("Could not convert f64 into type");
+    pi / T::from_usize(len.div_ceil(2).max(1)).expect("Could not convert usize into type")
+}
+
+impl<T> YttriaVectorCepstrum<T> for [T]
+where
+    T: FftNum + Float + FromPrimitive + Euclid,
+{
+    // The log magnitude of a real signal is real and even, so only the non-negative bins are
+    // needed on the way in and the inverse is real.
+    fn real_cepstrum(&self) -> Vec<T> {
+        let log = log_magnitude(&self.rfft());
+        to_complex(&log, log.len()).irfft_n(self.len())
+    }
+
+    fn complex_cepstrum(&self) -> (Vec<T>, isize) {
+        let len = self.len();
+        let mut context = FftContext::new();
+        let mut spectrum = to_complex(self, len);
+        context.forward(&mut spectrum);
+
+        // `angle_unwrap` starts from zero, so the phase of the first bin is added back.
+        let phase = spectrum.iter().map(|x| x.arg()).collect::<Vec<_>>();
+        let start = phase.first().copied().unwrap_or_else(T::zero);
+        let phase = phase.angle_unwrap(None).add_const(start);
+
+        // A delay of `d` samples winds the phase down by `pi * d` between DC and the centre bin.
+        let pi = T::from_f64(std::f64::consts::PI).expect("Could not convert f64 into type");
+        let centre = phase.get(len.div_ceil(2)).copied().unwrap_or_else(T::zero);
+        let delay = (-centre / pi)
+            .round()
+            .to_isize()
+            .expect("Could not convert delay into isize");
+        let delay_t = T::from_isize(delay).expect("Could not convert isize into type");
+        let slope = delay_slope::<T>(len);
+
+        let mut cepstrum = log_magnitude(&spectrum)
+            .iter()
+            .zip(&phase)
+            .enumerate()
+            .map(|(k, (mag, phase))| {
+                let k = T::from_usize(k).expect("Could not convert usize into type");
+                Complex::new(*mag, *phase + slope * delay_t * k)
+            })
+            .collect::<Vec<_>>();
+        context.inverse(&mut cepstrum);
+
+        let scale = T::from_usize(len).expect("Could not convert usize into type");
+        (cepstrum.iter().map(|x| x.re / scale).collect(), delay)
+    }
+
+    fn inverse_complex_cepstrum(&self, delay: isize) -> Vec<T> {
+        let len = self.len();
+        let mut context = FftContext::new();
+        let mut spectrum = to_complex(self, len);
+        context.forward(&mut spectrum);
+
+        let slope = delay_slope::<T>(len);
+        let delay = T::from_isize(delay).expect("Could not convert isize into type");
+        spectrum.iter_mut().enumerate().for_each(|(k, x)| {
+            let k = T::from_usize(k).expect("Could not convert usize into type");
+            *x = Complex::from_polar(x.re.exp(), x.im - slope * delay * k);
+        });
+        context.inverse(&mut spectrum);
+
+        let scale = T::from_usize(len).expect("Could not convert usize into type");
+        spectrum.iter().map(|x| x.re / scale).collect()
+    }
+
+    fn minimum_phase(&self) -> Vec<T> {
+        let len = self.len();
+        if len < 2 {
+            return self.to_vec();
+        }
+
+        let n = (100 * len).next_power_of_two();
+        let scale = T::from_usize(n).expect("Could not convert usize into type");
+        let two = T::from_u8(2).unwrap();
+        let mut context = FftContext::new();
+
+        let mut spectrum = to_complex(self, n);
+        context.forward(&mut spectrum);
+        let mut cepstrum = to_complex(&log_magnitude(&spectrum), n);
+        context.inverse(&mut cepstrum);
+
+        // Folding the anticausal half of the cepstrum onto the causal half keeps the log
+        // magnitude and gives the matching minimum phase.
+        cepstrum.iter_mut().enumerate().for_each(|(i, x)| {
+            let weight = match i {
+                0 => T::one(),
+                i if i < n / 2 => two,
+                i if i == n / 2 => T::one(),
+                _ => T::zero(),
+            };
+            *x = Complex::new(x.re * weight / scale, T::zero());
+        });
+        context.forward(&mut cepstrum);
+        cepstrum.iter_mut().for_each(|x| *x = x.exp());
+        context.inverse(&mut cepstrum);
+
+        cepstrum[..len].iter().map(|x| x.re / scale).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{firwin_kaiser, freqz, group_delay};
+    use crate::rng::gaussian_noise;
+
+    #[test]
+    fn test_real_cepstrum_finds_echo() {
+        let noise = gaussian_noise(1024, 7);
+        let delay = 57;
+        let signal = (0..1024)
+            .map(|i| {
+                noise[i]
+                    + if i >= delay {
+                        0.6 * noise[i - delay]
+                    } else {
+                        0.0
+                    }
+            })
+            .collect::<Vec<_>>();
+
+        let cepstrum = signal.real_cepstrum();
+        let peak = (10..512)
+            .max_by(|&a, &b| cepstrum[a].partial_cmp(&cepstrum[b]).unwrap())
+            .unwrap();
+        assert_eq!(peak, delay);
+    }
+
+    #[test]
+    fn test_real_cepstrum_matches_full_transform() {
+        for len in [1, 2, 7, 64, 101] {
+            let signal = (0..len)
+                .map(|i| (0.37 * i as f64).sin() + if i == 0 { 1.0 } else { 0.0 })
+                .collect::<Vec<_>>();
+
+            let mut context = FftContext::new();
+            let mut spectrum = to_complex(&signal, len);
+            context.forward(&mut spectrum);
+            let mut expected = to_complex(&log_magnitude(&spectrum), len);
+            context.inverse(&mut expected);
+
+            let cepstrum = signal.real_cepstrum();
+            assert_eq!(cepstrum.len(), len);
+            for (c, e) in cepstrum.iter().zip(&expected) {
+                assert!((c - e.re / len as f64).abs() < 1e-12, "{len}");
+            }
+        }
+        assert!([0.0f64; 0].real_cepstrum().is_empty());
+    }
+
+    #[test]
+    fn test_complex_cepstrum_round_trip() {
+        let signal = (0..64)
+            .map(|i| 0.8f64.powi(i) * (0.3 * i as f64).cos())
+            .collect::<Vec<_>>();
+
+        let (cepstrum, delay) = signal.complex_cepstrum();
+        assert_eq!(delay, 0);
+        let restored = cepstrum.inverse_complex_cepstrum(delay);
+        for (a, b) in restored.iter().zip(&signal) {
+            assert!((a - b).abs() < 1e-6);
+        }
+
+        // A delayed copy reports the delay and still round trips.
+        let mut shifted = vec![0.0; 64];
+        shifted[5..].copy_from_slice(&signal[..59]);
+        let (cepstrum, delay) = shifted.complex_cepstrum();
+        assert_eq!(delay, 5);
+        let restored = cepstrum.inverse_complex_cepstrum(delay);
+        for (a, b) in restored.iter().zip(&shifted) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_minimum_phase_lowpass() {
+        let taps = firwin_kaiser(0.2, 0.4, 60.0);
+        let min_phase = taps.minimum_phase();
+        assert_eq!(min_phase.len(), taps.len());
+
+        let (freqs, original) = freqz(&taps, &[1.0], 512);
+        let (_, converted) = freqz(&min_phase, &[1.0], 512);
+        for ((f, a), b) in freqs.iter().zip(&original).zip(&converted) {
+            let (a, b) = (20.0 * a.norm().log10(), 20.0 * b.norm().log10());
+            if *f <= 0.2 {
+                assert!((a - b).abs() < 0.1, "{a} vs {b} dB at {f}");
+            } else if *f >= 0.4 {
+                assert!(b < -55.0, "{b} dB at {f}");
+            }
+        }
+
+        // With every zero on or inside the unit circle the energy arrives as early as possible,
+        // and the passband delay is well under the linear phase (N - 1) / 2.
+        let energy = |x: &[f64], k: usize| x[..k].iter().map(|x| x * x).sum::<f64>();
+        for k in 1..taps.len() {
+            assert!(energy(&min_phase, k) >= energy(&taps, k) - 1e-6);
+        }
+        let (_, delays) = group_delay(&min_phase, &[1.0], 512);
+        let linear = (taps.len() - 1) as f64 / 2.0;
+        assert!(delays[..100].iter().all(|d| *d < linear / 2.0));
+    }
+}
@@ -0,0 +1,438 @@
+use num::{Bounded, Num};
+use rayon::prelude::*;
+
+use super::assert_output_len;
+use crate::config::parallel_min_len;
+
+// Element-wise comparisons between two vectors or between a vector and a constant.
+//
+// `minimum` and `maximum` follow numpy's NaN propagation rule: if either element is NaN the
+// result is NaN. Comparisons producing masks follow IEEE semantics, so any comparison involving
+// NaN is false. `abs_diff` saturates at `T::max_value()` rather than overflowing, which matters
+// for signed integers near their minimum.
+pub trait YttriaVectorCompare<T> {
+    fn minimum_into(&self, other: &[T], out: &mut [T]);
+    fn minimum(&self, other: &[T]) -> Vec<T>;
+
+    fn maximum_into(&self, other: &[T], out: &mut [T]);
+    fn maximum(&self, other: &[T]) -> Vec<T>;
+
+    fn abs_diff_into(&self, other: &[T], out: &mut [T])
+    where
+        T: Bounded;
+    fn abs_diff(&self, other: &[T]) -> Vec<T>
+    where
+        T: Bounded;
+
+    fn greater_than_into(&self, other: &[T], out: &mut [bool]);
+    fn greater_than(&self, other: &[T]) -> Vec<bool>;
+
+    fn greater_than_const_into(&self, threshold: T, out: &mut [bool]);
+    fn greater_than_const(&self, threshold: T) -> Vec<bool>;
+
+    fn less_equal_into(&self, other: &[T], out: &mut [bool]);
+    fn less_equal(&self, other: &[T]) -> Vec<bool>;
+
+    fn less_equal_const_into(&self, threshold: T, out: &mut [bool]);
+    fn less_equal_const(&self, threshold: T) -> Vec<bool>;
+
+    fn mask_select(&self, mask: &[bool]) -> Vec<T>;
+    fn apply_mask_inplace(&mut self, mask: &[bool], fill: T) -> &mut Self;
+
+    // numpy's `where`: elements come from `self` where the mask is set and from `other` elsewhere.
+    fn where_mask_into(&self, mask: &[bool], other: &[T], out: &mut [T]);
+    fn where_mask(&self, mask: &[bool], other: &[T]) -> Vec<T>;
+    fn where_mask_const(&self, mask: &[bool], fill: T) -> Vec<T>;
+    fn blend_inplace(&mut self, mask: &[bool], other: &[T]) -> &mut Self;
+}
+
+pub fn select_where<T: Copy + Send + Sync>(mask: &[bool], a: &[T], b: &[T]) -> Vec<T> {
+    assert_same_len(a.len(), mask.len());
+    assert_same_len(a.len(), b.len());
+    let mut out = b.to_vec();
+    out.par_iter_mut()
+        .with_min_len(parallel_min_len())
+        .zip(a)
+        .zip(mask)
+        .for_each(|((out, a), mask)| {
+            if *mask {
+                *out = *a;
+            }
+        });
+    out
+}
+
+fn assert_same_len(own: usize, other: usize) {
+    assert!(own == other, "Vector lengths differ: {own} != {other}");
+}
+
+// NaN is the only value that isn't equal to itself.
+#[allow(clippy::eq_op)]
+fn is_nan<T: PartialEq>(x: T) -> bool {
+    x != x
+}
+
+fn nan_min<T: PartialOrd + Copy>(a: T, b: T) -> T {
+    if is_nan(a) || (!is_nan(b) && a <= b) {
+        a
+    } else {
+        b
+    }
+}
+
+fn nan_max<T: PartialOrd + Copy>(a: T, b: T) -> T {
+    if is_nan(a) || (!is_nan(b) && a >= b) {
+        a
+    } else {
+        b
+    }
+}
+
+impl<T> YttriaVectorCompare<T> for [T]
+where
+    T: Num + PartialOrd + Send + Sync + Copy + Clone,
+{
+    fn minimum_into(&self, other: &[T], out: &mut [T]) {
+        assert_output_len("minimum_into", self.len(), out.len());
+        assert_same_len(self.len(), other.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .zip(other)
+            .for_each(|((out, own), other)| *out = nan_min(*own, *other));
+    }
+
+    fn minimum(&self, other: &[T]) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.minimum_into(other, &mut out);
+        out
+    }
+
+    fn maximum_into(&self, other: &[T], out: &mut [T]) {
+        assert_output_len("maximum_into", self.len(), out.len());
+        assert_same_len(self.len(), other.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .zip(other)
+            .for_each(|((out, own), other)| *out = nan_max(*own, *other));
+    }
+
+    fn maximum(&self, other: &[T]) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.maximum_into(other, &mut out);
+        out
+    }
+
+    fn abs_diff_into(&self, other: &[T], out: &mut [T])
+    where
+        T: Bounded,
+    {
+        assert_output_len("abs_diff_into", self.len(), out.len());
+        assert_same_len(self.len(), other.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .zip(other)
+            .for_each(|((out, own), other)| {
+                let (high, low) = if *own > *other {
+                    (*own, *other)
+                } else {
+                    (*other, *own)
+                };
+
+                // `high - low` only overflows when `low` is negative and `high` is more than
+                // `max + low`, which can itself be computed without overflowing.
+                *out = if low < T::zero() && high > T::max_value() + low {
+                    T::max_value()
+                } else {
+                    high - low
+                };
+            });
+    }
+
+    fn abs_diff(&self, other: &[T]) -> Vec<T>
+    where
+        T: Bounded,
+    {
+        let mut out = vec![T::zero(); self.len()];
+        self.abs_diff_into(other, &mut out);
+        out
+    }
+
+    fn greater_than_into(&self, other: &[T], out: &mut [bool]) {
+        assert_output_len("greater_than_into", self.len(), out.len());
+        assert_same_len(self.len(), other.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .zip(other)
+            .for_each(|((out, own), other)| *out = *own > *other);
+    }
+
+    fn greater_than(&self, other: &[T]) -> Vec<bool> {
+        let mut out = vec![false; self.len()];
+        self.greater_than_into(other, &mut out);
+        out
+    }
+
+    fn greater_than_const_into(&self, threshold: T, out: &mut [bool]) {
+        assert_output_len("greater_than_const_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| *out = *own > threshold);
+    }
+
+    fn greater_than_const(&self, threshold: T) -> Vec<bool> {
+        let mut out = vec![false; self.len()];
+        self.greater_than_const_into(threshold, &mut out);
+        out
+    }
+
+    fn less_equal_into(&self, other: &[T], out: &mut [bool]) {
+        assert_output_len("less_equal_into", self.len(), out.len());
+        assert_same_len(self.len(), other.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .zip(other)
+            .for_each(|((out, own), other)| *out = *own <= *other);
+    }
+
+    fn less_equal(&self, other: &[T]) -> Vec<bool> {
+        let mut out = vec![false; self.len()];
+        self.less_equal_into(other, &mut out);
+        out
+    }
+
+    fn less_equal_const_into(&self, threshold: T, out: &mut [bool]) {
+        assert_output_len("less_equal_const_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| *out = *own <= threshold);
+    }
+
+    fn less_equal_const(&self, threshold: T) -> Vec<bool> {
+        let mut out = vec![false; self.len()];
+        self.less_equal_const_into(threshold, &mut out);
+        out
+    }
+
+    fn mask_select(&self, mask: &[bool]) -> Vec<T> {
+        assert_same_len(self.len(), mask.len());
+        self.iter()
+            .zip(mask)
+            .filter(|(_, mask)| **mask)
+            .map(|(own, _)| *own)
+            .collect()
+    }
+
+    // Elements where the mask is false are replaced by `fill`.
+    fn apply_mask_inplace(&mut self, mask: &[bool], fill: T) -> &mut Self {
+        assert_same_len(self.len(), mask.len());
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(mask)
+            .for_each(|(own, mask)| {
+                if !*mask {
+                    *own = fill;
+                }
+            });
+        self
+    }
+
+    fn where_mask_into(&self, mask: &[bool], other: &[T], out: &mut [T]) {
+        assert_same_len(self.len(), mask.len());
+        assert_same_len(self.len(), other.len());
+        assert_same_len(self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .zip(other)
+            .zip(mask)
+            .for_each(|(((out, own), other), mask)| *out = if *mask { *own } else { *other });
+    }
+    fn where_mask(&self, mask: &[bool], other: &[T]) -> Vec<T> {
+        select_where(mask, self, other)
+    }
+    fn where_mask_const(&self, mask: &[bool], fill: T) -> Vec<T> {
+        let mut out = self.to_vec();
+        out.apply_mask_inplace(mask, fill);
+        out
+    }
+    fn blend_inplace(&mut self, mask: &[bool], other: &[T]) -> &mut Self {
+        assert_same_len(self.len(), mask.len());
+        assert_same_len(self.len(), other.len());
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(other)
+            .zip(mask)
+            .for_each(|((own, other), mask)| {
+                if !*mask {
+                    *own = *other;
+                }
+            });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{Xoshiro256PlusPlus, YttriaRng};
+    use crate::vector::YttriaVectorStatistics;
+
+    #[test]
+    fn test_minimum_maximum_match_scalar() {
+        let a = [3i32, -7, 0, 12, 5];
+        let b = [4i32, -9, 0, 11, -5];
+
+        let expected_min = a.iter().zip(&b).map(|(a, b)| *a.min(b)).collect::<Vec<_>>();
+        let expected_max = a.iter().zip(&b).map(|(a, b)| *a.max(b)).collect::<Vec<_>>();
+
+        assert_eq!(a.minimum(&b), expected_min);
+        assert_eq!(a.maximum(&b), expected_max);
+    }
+
+    #[test]
+    fn test_nan_propagation() {
+        let a = [f64::NAN, 1.0, f64::NAN, 2.0];
+        let b = [1.0, f64::NAN, f64::NAN, 3.0];
+
+        let min = a.minimum(&b);
+        let max = a.maximum(&b);
+        for i in 0..3 {
+            assert!(min[i].is_nan());
+            assert!(max[i].is_nan());
+        }
+        assert_eq!(min[3], 2.0);
+        assert_eq!(max[3], 3.0);
+
+        assert_eq!(a.greater_than(&b), [false, false, false, false]);
+        assert_eq!(a.less_equal_const(2.0), [false, true, false, true]);
+    }
+
+    #[test]
+    fn test_abs_diff_saturates() {
+        let a = [i16::MIN, i16::MAX, -5, 3, i16::MIN];
+        let b = [i16::MAX, i16::MIN, 5, -3, i16::MIN];
+
+        assert_eq!(a.abs_diff(&b), [i16::MAX, i16::MAX, 10, 6, 0]);
+        assert_eq!([3u8, 250].abs_diff(&[250, 3]), [247, 247]);
+    }
+
+    #[test]
+    fn test_spectral_gate() {
+        let mut spectrum = (0..256)
+            .map(|i| 1.0 + 0.1 * ((i * 37 % 11) as f64))
+            .collect::<Vec<_>>();
+        spectrum[40] = 50.0;
+        spectrum[97] = 30.0;
+
+        let threshold = spectrum.mean() + 3.0 * spectrum.std();
+        let mask = spectrum.greater_than_const(threshold);
+
+        assert_eq!(spectrum.mask_select(&mask), [50.0, 30.0]);
+
+        spectrum.apply_mask_inplace(&mask, 0.0);
+        for (i, x) in spectrum.iter().enumerate() {
+            match i {
+                40 => assert_eq!(*x, 50.0),
+                97 => assert_eq!(*x, 30.0),
+                _ => assert_eq!(*x, 0.0),
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_length_mismatch() {
+        [1, 2, 3].minimum(&[1, 2]);
+    }
+
+    #[test]
+    fn test_where_truth_table() {
+        let mask = [true, false, true, false];
+        let a = [1, 2, 3, 4];
+        let b = [10, 20, 30, 40];
+
+        assert_eq!(select_where(&mask, &a, &b), [1, 20, 3, 40]);
+        assert_eq!(a.where_mask(&mask, &b), [1, 20, 3, 40]);
+        assert_eq!(b.where_mask(&mask, &a), [10, 2, 30, 4]);
+        assert_eq!(a.where_mask_const(&mask, 0), [1, 0, 3, 0]);
+
+        let mut out = [0; 4];
+        a.where_mask_into(&mask, &b, &mut out);
+        assert_eq!(out, [1, 20, 3, 40]);
+
+        let mut blended = a;
+        blended.blend_inplace(&mask, &b);
+        assert_eq!(blended, [1, 20, 3, 40]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_where_mask_length() {
+        [1, 2, 3].where_mask(&[true, false], &[4, 5, 6]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_where_other_length() {
+        [1, 2, 3].where_mask(&[true, false, true], &[4, 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_where_out_length() {
+        [1, 2, 3].where_mask_into(&[true, false, true], &[4, 5, 6], &mut [0; 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_blend_mask_length() {
+        [1, 2, 3].blend_inplace(&[true], &[4, 5, 6]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_where_const_mask_length() {
+        [1, 2, 3].where_mask_const(&[true], 0);
+    }
+
+    #[test]
+    fn test_where_matches_serial() {
+        let mut rng = Xoshiro256PlusPlus::seed_from(11);
+        let len = 100_000;
+        let a = (0..len).map(|_| rng.next_f64()).collect::<Vec<_>>();
+        let b = (0..len).map(|_| rng.next_f64()).collect::<Vec<_>>();
+        let mask = (0..len)
+            .map(|_| rng.next_u64() & 1 == 1)
+            .collect::<Vec<_>>();
+
+        let mut expected = vec![0.0; len];
+        for i in 0..len {
+            expected[i] = if mask[i] { a[i] } else { b[i] };
+        }
+
+        assert_eq!(a.where_mask(&mask, &b), expected);
+        let mut blended = a.clone();
+        blended.blend_inplace(&mask, &b);
+        assert_eq!(blended, expected);
+    }
+
+    #[test]
+    fn test_where_clamp() {
+        let x = [-3.0, -1.0, 0.0, 0.5, 2.0, 7.0];
+        let upper = x.where_mask_const(&x.less_equal_const(1.0), 1.0);
+        let clamped = upper.where_mask_const(&upper.greater_than_const(-1.0), -1.0);
+
+        let expected = x
+            .iter()
+            .map(|x: &f64| x.clamp(-1.0, 1.0))
+            .collect::<Vec<_>>();
+        assert_eq!(clamped, expected);
+    }
+}
@@ -1,16 +1,114 @@
 use num::{Complex, Float, Zero};
 use rayon::prelude::*;
+use std::any::type_name;
+
+use super::{assert_output_len, convolve_output_len, YttriaVectorArithmetic};
+use crate::config::parallel_min_len;
+
+// How `interp_complex` blends neighbouring samples. `Cartesian` interpolates the real and
+// imaginary parts independently, which is exact for band-limited I/Q but cuts the chord between
+// two phasors, so the magnitude dips between samples of a rotating signal. `Polar` interpolates
+// magnitude and unwrapped phase, following the arc instead: constant-envelope signals keep their
+// envelope, at the cost of bending straight trajectories, and the phase step between neighbouring
+// samples must stay below half a turn to unwrap unambiguously.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplexInterp {
+    Cartesian,
+    Polar,
+}
 
 pub trait YttriaVectorComplex<T> {
     fn real(&self) -> Vec<T>;
     fn imag(&self) -> Vec<T>;
 
+    // Casts the real and imaginary parts separately, as `YttriaVectorUtils::as_type` does for
+    // real samples.
+    fn as_complex_type<U: Float + Send + Sync>(&self) -> Vec<Complex<U>>;
+
+    fn magnitude_into(&self, out: &mut [T]);
+    fn magnitude(&self) -> Vec<T>;
+
     fn conj(&self) -> Vec<Complex<T>>;
     fn conj_inplace(&mut self);
+    fn conj_reverse(&self) -> Vec<Complex<T>>;
+
+    // Full cross-correlation, `convolve_output_len(len, other.len())` lags long.
+    fn correlate(&self, other: &[Complex<T>]) -> Vec<Complex<T>>;
 
     fn exp_into(&self, out: &mut [Complex<T>]);
     fn exp(&self) -> Vec<Complex<T>>;
     fn exp_inplace(&mut self);
+
+    // Principal branch, with imaginary parts in [-pi, pi]. The cut runs along the negative real
+    // axis and the sign of a zero imaginary part picks its side, so `-1 + 0i` gives `i pi` and
+    // `-1 - 0i` gives `-i pi`. The powers are `exp(exp * ln(x))` on the same branch.
+    fn ln_into(&self, out: &mut [Complex<T>]);
+    fn ln(&self) -> Vec<Complex<T>>;
+    fn ln_inplace(&mut self);
+    fn powf(&self, exp: T) -> Vec<Complex<T>>;
+    fn powc(&self, exp: Complex<T>) -> Vec<Complex<T>>;
+
+    fn to_polar(&self) -> (Vec<T>, Vec<T>);
+    fn set_magnitude_inplace(&mut self, mag: &[T]);
+    fn rotate_inplace(&mut self, phase: &[T]);
+
+    // Mixes the signal with a local oscillator of the same length, sample by sample. In the
+    // frequency domain this convolves the two spectra, so a complex LO tone at `f` moves every
+    // component up by `f` and its conjugate moves them down, while a real LO such as a cosine
+    // produces both the sum and the difference images. Panics if `lo` has a different length
+    // rather than tiling it, since a tiled LO is only phase continuous for whole cycles.
+    fn heterodyne_into(&self, lo: &[Complex<T>], out: &mut [Complex<T>]);
+    fn heterodyne(&self, lo: &[Complex<T>]) -> Vec<Complex<T>>;
+
+    // Per-sample Costas loop phase detector outputs, positive when the samples lead the nearest
+    // constellation point: `re * im` for BPSK and `sign(re) * im - sign(im) * re` for QPSK. Both
+    // scale with the signal power, so normalise first for a loop gain independent of level.
+    fn phase_error_bpsk(&self) -> Vec<T>;
+    fn phase_error_qpsk(&self) -> Vec<T>;
+
+    // Neumaier compensated sums of the real and imaginary parts, so the rounding error does not
+    // grow with the length as it does for the plain `sum` and `mean`.
+    fn sum_compensated(&self) -> Complex<T>;
+    fn mean_compensated(&self) -> Complex<T>;
+
+    // Scale the whole vector so its largest magnitude, or its RMS magnitude, equals the target.
+    // An all zero vector is left untouched.
+    fn normalize_peak_inplace(&mut self, target: T) -> &mut Self;
+    fn normalize_rms_inplace(&mut self, target_rms: T) -> &mut Self;
+
+    // Peak magnitude over RMS magnitude, and the same ratio as peak to average power in dB.
+    // Constant envelope signals give 1, or 0 dB. Panics on an empty vector; an all zero vector
+    // gives NaN.
+    fn crest_factor(&self) -> T;
+    fn papr_db(&self) -> T;
+
+    fn clamp_magnitude(&self, max_mag: T) -> Vec<Complex<T>>;
+    fn clamp_magnitude_inplace(&mut self, max_mag: T);
+    fn soft_clip_magnitude(&self, max_mag: T) -> Vec<Complex<T>>;
+    fn soft_clip_magnitude_inplace(&mut self, max_mag: T);
+
+    // Treats `self` as samples taken at the increasing positions `xp` and evaluates them at `x`,
+    // clamping to the end samples outside `xp` like the real `interp`.
+    fn interp_complex_into(&self, out: &mut [Complex<T>], x: &[T], xp: &[T], mode: ComplexInterp);
+    fn interp_complex(&self, x: &[T], xp: &[T], mode: ComplexInterp) -> Vec<Complex<T>>;
+}
+
+pub fn from_polar<T>(mag: &[T], phase: &[T]) -> Vec<Complex<T>>
+where
+    T: Float + Send + Sync + Copy + Clone,
+{
+    assert!(
+        mag.len() == phase.len(),
+        "Magnitude and phase lengths differ: {} != {}",
+        mag.len(),
+        phase.len()
+    );
+
+    mag.par_iter()
+        .with_min_len(parallel_min_len())
+        .zip(phase)
+        .map(|(mag, phase)| Complex::from_polar(*mag, *phase))
+        .collect()
 }
 
 impl<T> YttriaVectorComplex<T> for [Complex<T>]
@@ -25,18 +123,85 @@ where
         self.iter().map(|x| x.im).collect()
     }
 
+    fn as_complex_type<U: Float + Send + Sync>(&self) -> Vec<Complex<U>> {
+        let cast = |x: T| {
+            U::from(x).unwrap_or_else(|| {
+                panic!(
+                    "Could not cast type '{}' to '{}'",
+                    type_name::<T>(),
+                    type_name::<U>()
+                )
+            })
+        };
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|x| Complex::new(cast(x.re), cast(x.im)))
+            .collect()
+    }
+
+    fn magnitude_into(&self, out: &mut [T]) {
+        assert_output_len("magnitude_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| *out = own.norm());
+    }
+
+    fn magnitude(&self) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.magnitude_into(&mut out);
+        out
+    }
+
     fn conj(&self) -> Vec<Complex<T>> {
-        self.par_iter().map(|x| x.conj()).collect()
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|x| x.conj())
+            .collect()
     }
 
     fn conj_inplace(&mut self) {
-        self.par_iter_mut().for_each(|x| {
-            *x = x.conj();
-        })
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|x| {
+                *x = x.conj();
+            })
+    }
+
+    fn conj_reverse(&self) -> Vec<Complex<T>> {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .rev()
+            .map(|x| x.conj())
+            .collect()
+    }
+
+    // Full cross-correlation, matching `numpy.correlate(self, other, "full")`. Output index `k`
+    // corresponds to a lag of `k - (other.len() - 1)`.
+    fn correlate(&self, other: &[Complex<T>]) -> Vec<Complex<T>> {
+        let mut out = vec![Complex::<T>::zero(); convolve_output_len(self.len(), other.len())];
+        let offset = other.len() as isize - 1;
+
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .for_each(|(idx_out, out)| {
+                let lag = idx_out as isize - offset;
+                for (idx_n, other) in other.iter().enumerate() {
+                    let idx_own = idx_n as isize + lag;
+                    if idx_own >= 0 && (idx_own as usize) < self.len() {
+                        *out = *out + self[idx_own as usize] * other.conj();
+                    }
+                }
+            });
+
+        out
     }
 
     fn exp_into(&self, out: &mut [Complex<T>]) {
+        assert_output_len("exp_into", self.len(), out.len());
         out.par_iter_mut()
+            .with_min_len(parallel_min_len())
             .zip(self)
             .for_each(|(out, own)| *out = own.exp());
     }
@@ -48,7 +213,277 @@ where
     }
 
     fn exp_inplace(&mut self) {
-        todo!()
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|x| *x = x.exp());
+    }
+
+    fn ln_into(&self, out: &mut [Complex<T>]) {
+        assert_output_len("ln_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(self)
+            .for_each(|(out, own)| *out = own.ln());
+    }
+
+    fn ln(&self) -> Vec<Complex<T>> {
+        let mut out = vec![Complex::<T>::zero(); self.len()];
+        self.ln_into(out.as_mut_slice());
+        out
+    }
+
+    fn ln_inplace(&mut self) {
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|x| *x = x.ln());
+    }
+
+    fn powf(&self, exp: T) -> Vec<Complex<T>> {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|x| x.powf(exp))
+            .collect()
+    }
+
+    fn powc(&self, exp: Complex<T>) -> Vec<Complex<T>> {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|x| x.powc(exp))
+            .collect()
+    }
+
+    fn to_polar(&self) -> (Vec<T>, Vec<T>) {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|x| x.to_polar())
+            .unzip()
+    }
+
+    // Elements with zero magnitude have no phase to keep, so they are treated as having a phase
+    // of zero and become purely real.
+    fn set_magnitude_inplace(&mut self, mag: &[T]) {
+        assert!(
+            self.len() == mag.len(),
+            "Vector and magnitude lengths differ: {} != {}",
+            self.len(),
+            mag.len()
+        );
+
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(mag)
+            .for_each(|(own, mag)| {
+                let norm = own.norm();
+                *own = if norm.is_zero() {
+                    Complex::new(*mag, T::zero())
+                } else {
+                    own.scale(*mag / norm)
+                };
+            });
+    }
+
+    fn rotate_inplace(&mut self, phase: &[T]) {
+        assert!(
+            self.len() == phase.len(),
+            "Vector and phase lengths differ: {} != {}",
+            self.len(),
+            phase.len()
+        );
+
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(phase)
+            .for_each(|(own, phase)| {
+                let (sin, cos) = phase.sin_cos();
+                *own = *own * Complex::new(cos, sin);
+            });
+    }
+
+    fn heterodyne_into(&self, lo: &[Complex<T>], out: &mut [Complex<T>]) {
+        assert!(
+            self.len() == lo.len(),
+            "Signal and local oscillator lengths differ: {} != {}",
+            self.len(),
+            lo.len()
+        );
+        self.multiply_into(lo, out);
+    }
+
+    fn heterodyne(&self, lo: &[Complex<T>]) -> Vec<Complex<T>> {
+        let mut out = vec![Complex::zero(); self.len()];
+        self.heterodyne_into(lo, &mut out);
+        out
+    }
+
+    fn phase_error_bpsk(&self) -> Vec<T> {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|x| x.re * x.im)
+            .collect()
+    }
+
+    fn phase_error_qpsk(&self) -> Vec<T> {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|x| x.re.signum() * x.im - x.im.signum() * x.re)
+            .collect()
+    }
+
+    fn sum_compensated(&self) -> Complex<T> {
+        let mut sum = Complex::<T>::zero();
+        let mut compensation = Complex::<T>::zero();
+        for x in self {
+            for (sum, compensation, x) in [
+                (&mut sum.re, &mut compensation.re, x.re),
+                (&mut sum.im, &mut compensation.im, x.im),
+            ] {
+                let t = *sum + x;
+                *compensation = *compensation
+                    + if sum.abs() >= x.abs() {
+                        (*sum - t) + x
+                    } else {
+                        (x - t) + *sum
+                    };
+                *sum = t;
+            }
+        }
+        sum + compensation
+    }
+
+    fn mean_compensated(&self) -> Complex<T> {
+        assert!(!self.is_empty(), "Cannot take the mean of an empty vector");
+        self.sum_compensated() / T::from(self.len()).expect("Could not convert usize to type")
+    }
+
+    fn normalize_peak_inplace(&mut self, target: T) -> &mut Self {
+        let peak = self.iter().fold(T::zero(), |acc, x| acc.max(x.norm()));
+        if peak > T::zero() {
+            let gain = target / peak;
+            self.par_iter_mut()
+                .with_min_len(parallel_min_len())
+                .for_each(|x| *x = x.scale(gain));
+        }
+        self
+    }
+
+    fn normalize_rms_inplace(&mut self, target_rms: T) -> &mut Self {
+        let len = T::from(self.len()).expect("Could not convert usize to type");
+        let rms = (self.iter().fold(T::zero(), |acc, x| acc + x.norm_sqr()) / len).sqrt();
+        if rms > T::zero() {
+            let gain = target_rms / rms;
+            self.par_iter_mut()
+                .with_min_len(parallel_min_len())
+                .for_each(|x| *x = x.scale(gain));
+        }
+        self
+    }
+
+    fn crest_factor(&self) -> T {
+        assert!(
+            !self.is_empty(),
+            "Cannot take the crest factor of an empty vector"
+        );
+        let len = T::from(self.len()).expect("Could not convert usize to type");
+        let peak = self.iter().fold(T::zero(), |acc, x| acc.max(x.norm_sqr()));
+        let power = self.iter().fold(T::zero(), |acc, x| acc + x.norm_sqr()) / len;
+        (peak / power).sqrt()
+    }
+
+    fn papr_db(&self) -> T {
+        let twenty = T::from(20.0).expect("Could not convert f64 to type");
+        twenty * self.crest_factor().log10()
+    }
+
+    fn clamp_magnitude(&self, max_mag: T) -> Vec<Complex<T>> {
+        let mut out = self.to_vec();
+        out.clamp_magnitude_inplace(max_mag);
+        out
+    }
+
+    // Hard limiter: samples above `max_mag` are scaled down onto it, keeping their phase.
+    fn clamp_magnitude_inplace(&mut self, max_mag: T) {
+        assert!(
+            max_mag >= T::zero(),
+            "Maximum magnitude must not be negative"
+        );
+
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|own| {
+                let norm = own.norm();
+                if norm > max_mag {
+                    *own = own.scale(max_mag / norm);
+                }
+            });
+    }
+
+    fn soft_clip_magnitude(&self, max_mag: T) -> Vec<Complex<T>> {
+        let mut out = self.to_vec();
+        out.soft_clip_magnitude_inplace(max_mag);
+        out
+    }
+
+    // Smooth limiter mapping magnitude `r` to `max_mag * tanh(r / max_mag)`, which is linear for
+    // small signals and approaches `max_mag` without ever reaching it. Phase is kept.
+    fn soft_clip_magnitude_inplace(&mut self, max_mag: T) {
+        assert!(max_mag > T::zero(), "Maximum magnitude must be positive");
+
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|own| {
+                let norm = own.norm();
+                if !norm.is_zero() {
+                    *own = own.scale(max_mag * (norm / max_mag).tanh() / norm);
+                }
+            });
+    }
+
+    fn interp_complex_into(&self, out: &mut [Complex<T>], x: &[T], xp: &[T], mode: ComplexInterp) {
+        assert_output_len("interp_complex_into", x.len(), out.len());
+        assert!(
+            self.len() == xp.len(),
+            "Sample and position lengths differ: {} != {}",
+            self.len(),
+            xp.len()
+        );
+        assert!(
+            out.len() == x.len(),
+            "Output and query lengths differ: {} != {}",
+            out.len(),
+            x.len()
+        );
+
+        let (first, second) = match mode {
+            ComplexInterp::Cartesian => (x.interp(xp, &self.real()), x.interp(xp, &self.imag())),
+            ComplexInterp::Polar => {
+                let mut phase = vec![T::zero(); self.len()];
+                if let Some(first) = self.first() {
+                    phase[0] = first.arg();
+                }
+                for i in 1..self.len() {
+                    phase[i] = phase[i - 1] + (self[i] * self[i - 1].conj()).arg();
+                }
+                let mag = self.iter().map(|s| s.norm()).collect::<Vec<_>>();
+                (x.interp(xp, &mag), x.interp(xp, &phase))
+            }
+        };
+
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(first)
+            .zip(second)
+            .for_each(|((out, first), second)| {
+                *out = match mode {
+                    ComplexInterp::Cartesian => Complex::new(first, second),
+                    ComplexInterp::Polar => Complex::from_polar(first, second),
+                }
+            });
+    }
+
+    fn interp_complex(&self, x: &[T], xp: &[T], mode: ComplexInterp) -> Vec<Complex<T>> {
+        let mut out = vec![Complex::<T>::zero(); x.len()];
+        self.interp_complex_into(&mut out, x, xp, mode);
+        out
     }
 }
 
@@ -56,10 +491,48 @@ where
 mod tests {
     use super::*;
     use num::complex::Complex32;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_heterodyne_shifts_spectrum() {
+        use crate::vector::YttriaVectorComplexFft;
+
+        let n = 256;
+        let tone = |bin: f64| {
+            (0..n)
+                .map(|i| Complex::from_polar(1.0, 2.0 * PI * bin * i as f64 / n as f64))
+                .collect::<Vec<Complex<f64>>>()
+        };
+        let bins = |x: &[Complex<f64>]| {
+            x.fft()
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| x.norm() > 1e-6)
+                .map(|(k, x)| (k, (x.norm() * 1e6).round() / 1e6))
+                .collect::<Vec<_>>()
+        };
+        let signal = tone(10.0);
+        assert_eq!(bins(&signal.heterodyne(&tone(30.0))), [(40, 1.0)]);
+        assert_eq!(bins(&signal.heterodyne(&tone(-30.0))), [(236, 1.0)]);
+
+        // A real cosine LO gives the sum and difference at half amplitude each.
+        let cosine = tone(30.0)
+            .iter()
+            .map(|x| Complex::new(x.re, 0.0))
+            .collect::<Vec<_>>();
+        assert_eq!(bins(&signal.heterodyne(&cosine)), [(40, 0.5), (236, 0.5)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_heterodyne_length_mismatch() {
+        let x = [Complex::new(1.0, 0.0); 8];
+        x.heterodyne(&x[..4]);
+    }
 
     #[test]
     fn test_real() {
-        let test = vec![
+        let test = [
             Complex32 { re: 0.0, im: 0.0 },
             Complex32 { re: 1.0, im: 2.0 },
             Complex32 { re: 2.0, im: 5.0 },
@@ -69,4 +542,329 @@ mod tests {
 
         let _split = test.real();
     }
+
+    #[test]
+    fn test_as_complex_type_round_trip() {
+        let test = (0..1000)
+            .map(|i| Complex::from_polar(1.0 + i as f64 * 1e-3, 0.1 * i as f64))
+            .collect::<Vec<_>>();
+
+        let single = test.as_complex_type::<f32>();
+        assert_eq!(
+            single[1],
+            Complex32::new(test[1].re as f32, test[1].im as f32)
+        );
+        let double = single.as_complex_type::<f64>();
+        for (a, b) in double.iter().zip(&test) {
+            assert!((a - b).norm() <= 2.0 * f32::EPSILON as f64 * b.norm());
+        }
+    }
+
+    #[test]
+    fn test_conj_reverse_correlate() {
+        let x = (0..12)
+            .map(|i| Complex::new((i as f64).cos(), (i as f64 * 0.5).sin()))
+            .collect::<Vec<_>>();
+        let h = [
+            Complex::new(1.0, -1.0),
+            Complex::new(0.5, 2.0),
+            Complex::new(-0.25, 0.0),
+        ];
+
+        assert_eq!(h.conj_reverse(), [h[2].conj(), h[1].conj(), h[0].conj()]);
+
+        let via_convolve = x.convolve(&h.conj_reverse());
+        let correlated = x.correlate(&h);
+
+        assert_eq!(via_convolve.len(), correlated.len());
+        for (a, b) in via_convolve.iter().zip(&correlated) {
+            assert!((a - b).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_magnitude_into_matches_allocating() {
+        let test = (0..100)
+            .map(|i| Complex::new((i as f64 * 0.3).cos() * 2.0, (i as f64 * 0.7).sin() - 0.5))
+            .collect::<Vec<_>>();
+
+        let mut out = vec![-1.0; test.len()];
+        test.magnitude_into(&mut out);
+        assert_eq!(out, test.magnitude());
+        assert_eq!(out[3], test[3].norm());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_magnitude_into_length_mismatch() {
+        let test = [Complex::new(1.0, 1.0); 4];
+        test.magnitude_into(&mut [0.0; 3]);
+    }
+
+    #[test]
+    fn test_ln_inverts_exp() {
+        let x = [
+            Complex::new(0.0, 0.0),
+            Complex::new(1.5, -0.3),
+            Complex::new(-2.0, 3.1),
+            Complex::new(0.25, -3.1),
+        ];
+        for (a, b) in x.exp().ln().iter().zip(&x) {
+            assert!((a - b).norm() < 1e-12, "{a} != {b}");
+        }
+        let mut out = [Complex::zero(); 4];
+        x.exp().ln_into(&mut out);
+        assert_eq!(out.to_vec(), x.exp().ln());
+
+        let mut inplace = x;
+        inplace.exp_inplace();
+        x.exp_into(&mut out);
+        assert_eq!(inplace, out);
+        let exp = inplace;
+        inplace.ln_inplace();
+        exp.ln_into(&mut out);
+        assert_eq!(inplace, out);
+
+        // Outside the principal branch the imaginary part comes back wrapped.
+        let wrapped = [Complex::new(1.0, 4.0)].exp().ln()[0];
+        assert!((wrapped - Complex::new(1.0, 4.0 - 2.0 * PI)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_ln_branch_cut() {
+        let ln = [Complex::new(-1.0, 0.0), Complex::new(-1.0, -0.0)].ln();
+        assert_eq!(ln, [Complex::new(0.0, PI), Complex::new(0.0, -PI)]);
+        assert_eq!([Complex::new(0.0f64, 0.0)].ln()[0].re, f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_powers() {
+        let x = [
+            Complex::new(-4.0, 0.0),
+            Complex::new(0.0, 2.0),
+            Complex::new(3.0, -1.0),
+        ];
+        let roots = x.powf(0.5);
+        assert!((roots[0] - Complex::new(0.0, 2.0)).norm() < 1e-12);
+        assert!((roots[1] - Complex::new(1.0, 1.0)).norm() < 1e-12);
+        for (root, x) in roots.iter().zip(&x) {
+            assert!((root * root - x).norm() < 1e-12);
+        }
+
+        let squared = x.powc(Complex::new(2.0, 0.0));
+        for (a, b) in squared.iter().zip(&x) {
+            assert!((a - b * b).norm() < 1e-12);
+        }
+        // i^i is real.
+        let i_i = [Complex::new(0.0, 1.0)].powc(Complex::new(0.0, 1.0))[0];
+        assert!((i_i - Complex::new((-PI / 2.0).exp(), 0.0)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_polar_round_trip() {
+        let test = (0..64)
+            .map(|i| Complex::new((i as f64 * 0.3).cos() * 2.0, (i as f64 * 0.7).sin() - 0.5))
+            .collect::<Vec<_>>();
+
+        let (mag, phase) = test.to_polar();
+        let recon = from_polar(&mag, &phase);
+
+        for (a, b) in test.iter().zip(&recon) {
+            assert!((a - b).norm() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_set_magnitude_keeps_phase() {
+        let mut test = [
+            Complex::new(1.0f64, 1.0),
+            Complex::new(-3.0, 0.5),
+            Complex::new(0.0, -2.0),
+            Complex::new(0.0, 0.0),
+        ];
+        let phases = test.iter().map(|x| x.arg()).collect::<Vec<_>>();
+
+        test.set_magnitude_inplace(&[2.0, 0.5, 4.0, 3.0]);
+
+        for (x, (mag, phase)) in test[..3].iter().zip([2.0, 0.5, 4.0].iter().zip(&phases)) {
+            assert!((x.norm() - mag).abs() < 1e-12);
+            assert!((x.arg() - phase).abs() < 1e-12);
+        }
+        assert_eq!(test[3], Complex::new(3.0, 0.0));
+    }
+
+    #[test]
+    fn test_compensated_mean_of_phasors() {
+        let n = 100_003;
+        let phasors = (0..n)
+            .map(|k| Complex::from_polar(1.0f64, 2.0 * std::f64::consts::PI * k as f64 / n as f64))
+            .collect::<Vec<_>>();
+        assert!(phasors.mean_compensated().norm() < 1e-15);
+        assert!(phasors.sum_compensated().norm() < 1e-10);
+
+        // Tiny terms survive next to a large one.
+        let mut test = vec![Complex::new(1e16, -1e16)];
+        test.extend(vec![Complex::new(1.0, -1.0); 1000]);
+        test.push(Complex::new(-1e16, 1e16));
+        assert_eq!(test.sum_compensated(), Complex::new(1000.0, -1000.0));
+    }
+
+    #[test]
+    fn test_normalize_complex() {
+        let mut test = [Complex::new(3.0f64, 4.0), Complex::new(0.0, -1.0)];
+        test.normalize_peak_inplace(2.0);
+        for (a, b) in test
+            .iter()
+            .zip([Complex::new(1.2, 1.6), Complex::new(0.0, -0.4)])
+        {
+            assert!((a - b).norm() < 1e-15);
+        }
+
+        // Chains as the real versions do.
+        test.normalize_rms_inplace(1.0).normalize_peak_inplace(1.0);
+        assert!((test[0].norm() - 1.0).abs() < 1e-15);
+        test.normalize_rms_inplace(1.0);
+        let power = test.iter().map(|x| x.norm_sqr()).sum::<f64>() / 2.0;
+        assert!((10.0 * power.log10()).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_crest_factor_complex() {
+        let tone = (0..100)
+            .map(|i| Complex::from_polar(0.5f64, i as f64 * 0.3))
+            .collect::<Vec<_>>();
+        assert!((tone.crest_factor() - 1.0).abs() < 1e-12);
+        assert!(tone.papr_db().abs() < 1e-12);
+
+        // The real part alone is a real tone, with both spectral images.
+        let real = (0..1000)
+            .map(|i| Complex::new((2.0 * std::f64::consts::PI * i as f64 / 40.0).cos(), 0.0))
+            .collect::<Vec<_>>();
+        assert!((real.crest_factor() - 2f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_clamp_magnitude_keeps_phase() {
+        let test = (0..64)
+            .map(|i| Complex::from_polar(i as f64 * 0.05, i as f64 * 0.7 - 20.0))
+            .collect::<Vec<_>>();
+        let max_mag = 1.5;
+
+        let hard = test.clamp_magnitude(max_mag);
+        let soft = test.soft_clip_magnitude(max_mag);
+        for ((x, h), s) in test.iter().zip(&hard).zip(&soft) {
+            if x.norm() > max_mag {
+                assert!((h.norm() - max_mag).abs() < 1e-12);
+            } else {
+                assert_eq!(h, x);
+            }
+            assert!(s.norm() < max_mag && s.norm() <= x.norm());
+
+            if x.norm() > 0.0 {
+                assert!((h.arg() - x.arg()).abs() < 1e-12);
+                assert!((s.arg() - x.arg()).abs() < 1e-12);
+            }
+        }
+
+        // Small signals pass the soft clipper almost untouched.
+        let small = [Complex::new(0.01, -0.02)];
+        assert!((small.soft_clip_magnitude(max_mag)[0] - small[0]).norm() < 1e-5);
+    }
+
+    #[test]
+    fn test_phase_error_sign() {
+        let symbols = [1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0, -1.0];
+        for phase in [0.2, -0.2, 0.7, -0.7] {
+            let bpsk = symbols
+                .iter()
+                .map(|s| Complex::from_polar(*s, phase))
+                .collect::<Vec<Complex<f64>>>();
+            for error in bpsk.phase_error_bpsk() {
+                assert_eq!(error.signum(), phase.signum());
+                assert!((error - (2.0 * phase).sin() / 2.0).abs() < 1e-12);
+            }
+
+            // QPSK points at odd multiples of 45 degrees, rotated by less than a quarter turn.
+            let qpsk = (0..8)
+                .map(|k| {
+                    Complex::from_polar(1.0, PI / 4.0 * (2 * (k % 4) + 1) as f64 + phase / 2.0)
+                })
+                .collect::<Vec<Complex<f64>>>();
+            for error in qpsk.phase_error_qpsk() {
+                assert_eq!(error.signum(), phase.signum(), "{phase}");
+            }
+        }
+
+        let locked = [Complex::new(1.0, 0.0), Complex::new(-2.0, 0.0)];
+        assert_eq!(locked.phase_error_bpsk(), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_rotate_matches_multiply() {
+        let test = (0..32)
+            .map(|i| Complex::new(i as f64, 1.0 - i as f64 * 0.1))
+            .collect::<Vec<_>>();
+        let phase = (0..32).map(|i| i as f64 * 0.4 - 3.0).collect::<Vec<_>>();
+
+        let phasors = phase
+            .iter()
+            .map(|&x| Complex::new(0.0, x))
+            .collect::<Vec<_>>()
+            .exp();
+        let expected = test.multiply(&phasors);
+
+        let mut rotated = test.clone();
+        rotated.rotate_inplace(&phase);
+
+        for (a, b) in rotated.iter().zip(&expected) {
+            assert!((a - b).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_interp_rotating_phasor() {
+        let step = 0.7;
+        let xp = (0..16).map(|i| i as f64).collect::<Vec<_>>();
+        let samples = xp
+            .iter()
+            .map(|x| Complex::from_polar(2.0, step * x))
+            .collect::<Vec<_>>();
+        let x = (0..60).map(|i| i as f64 / 4.0).collect::<Vec<_>>();
+
+        let polar = samples.interp_complex(&x, &xp, ComplexInterp::Polar);
+        for (x, z) in x.iter().zip(&polar) {
+            assert!((z.norm() - 2.0).abs() < 1e-12);
+            assert!((z - Complex::from_polar(2.0, step * x)).norm() < 1e-12);
+        }
+
+        // Component-wise interpolation follows the chord, dipping to cos(step / 2) midway.
+        let cartesian = samples.interp_complex(&x, &xp, ComplexInterp::Cartesian);
+        for (i, z) in cartesian.iter().enumerate() {
+            if i % 4 == 0 {
+                assert!((z - samples[i / 4]).norm() < 1e-12);
+            } else if i % 4 == 2 {
+                assert!((z.norm() - 2.0 * (step / 2.0).cos()).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_polar_length_mismatch() {
+        from_polar(&[1.0f32, 2.0], &[0.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_magnitude_length_mismatch() {
+        let mut test = [Complex32::new(1.0, 0.0); 3];
+        test.set_magnitude_inplace(&[1.0, 2.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rotate_length_mismatch() {
+        let mut test = [Complex32::new(1.0, 0.0); 3];
+        test.rotate_inplace(&[1.0]);
+    }
 }
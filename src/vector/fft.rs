@@ -1,9 +1,23 @@
-use num::{Complex, Float, Zero};
-use rustfft::{FftNum, FftPlanner};
+use std::sync::Arc;
 
-use super::{YttriaVectorArithmetic, YttriaVectorComplex};
+use num::{Complex, Float, FromPrimitive, Zero};
+use rayon::prelude::*;
+use rustfft::{Fft, FftNum, FftPlanner};
+
+use super::{assert_output_len, YttriaVectorArithmetic, YttriaVectorComplex, YttriaVectorUtils};
+use crate::config::parallel_min_len;
+
+// The `_into` variants take caller-provided scratch, which must hold at least the matching
+// `_scratch_len` elements; depending on the length, rustfft can need more scratch than the
+// transform size itself. `out` must have exactly the length the allocating method returns.
+pub trait YttriaVectorComplexFft<T: FftNum> {
+    // `2 * (len - 1)` real samples, or none for fewer than two bins.
+    fn irfft_output_len(&self) -> usize;
+
+    fn fft_scratch_len(&self) -> usize;
+    fn ifft_scratch_len(&self) -> usize;
+    fn irfft_scratch_len(&self) -> usize;
 
-pub trait YttriaVectorComplexFft<T> {
     fn fft_into(&self, out: &mut [Complex<T>], scratch: &mut [Complex<T>]);
     fn fft(&self) -> Vec<Complex<T>>;
 
@@ -12,18 +26,130 @@ pub trait YttriaVectorComplexFft<T> {
 
     fn irfft_into(&self, out: &mut [T], scratch: &mut [Complex<T>]);
     fn irfft(&self) -> Vec<T>;
+    // `irfft` with exactly `n` real output samples, like `np.fft.irfft(x, n)`. Only the first
+    // `n / 2 + 1` bins are used, missing ones count as zero, and the imaginary part of the DC
+    // bin, and of the Nyquist bin when `n` is even, is ignored.
+    fn irfft_n(&self, n: usize) -> Vec<T>;
+
+    // `fft` of `gather_strided(start, stride)`, gathering straight into the transform buffer
+    // instead of copying through a separate vector first.
+    fn fft_strided(
+        &self,
+        start: usize,
+        stride: usize,
+        context: &mut FftContext<T>,
+    ) -> Vec<Complex<T>>;
+
+    // Whether the full length spectrum `self` is that of a real signal, with
+    // `|X[k] - conj(X[N - k])|` at most `tolerance` for every bin. The DC bin, and the Nyquist
+    // bin for even lengths, pair with themselves, so their imaginary parts must be within
+    // `tolerance / 2` of zero.
+    fn is_conjugate_symmetric(&self, tolerance: T) -> bool;
+    // Replaces each pair of bins by the average of `X[k]` and `conj(X[N - k])`, which zeroes the
+    // imaginary parts of the DC and Nyquist bins.
+    fn enforce_conjugate_symmetry_inplace(&mut self);
+    // Enforces symmetry on a copy and returns its inverse FFT, which is then real up to rounding.
+    fn make_real_via_symmetry(&self) -> Vec<T>;
+}
+
+fn assert_scratch_len<T>(method: &str, fft: &dyn Fft<T>, scratch: &[Complex<T>])
+where
+    T: FftNum,
+{
+    let needed = fft.get_inplace_scratch_len();
+    assert!(
+        scratch.len() >= needed,
+        "{method} needs {needed} scratch elements, got {}",
+        scratch.len()
+    );
+}
+
+// Inverse transform of the non-negative frequency bins `bins` into `out.len()` real samples. The
+// negative frequencies mirror bins `1..=(n - 1) / 2`, so an even length's Nyquist bin appears
+// once. Taking the real part of the result discards the imaginary parts of the self-paired DC
+// and Nyquist bins.
+fn irfft_with_len<T>(method: &str, bins: &[Complex<T>], out: &mut [T], scratch: &mut [Complex<T>])
+where
+    T: FftNum + Float,
+{
+    let n = out.len();
+    if n == 0 {
+        return;
+    }
+
+    let bin = |k: usize| bins.get(k).copied().unwrap_or_else(Complex::zero);
+    let mut hermitian = (0..n)
+        .map(|k| {
+            if k <= n / 2 {
+                bin(k)
+            } else {
+                bin(n - k).conj()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut planner = FftPlanner::<T>::new();
+    let ifft = planner.plan_fft_inverse(n);
+
+    assert_scratch_len(method, ifft.as_ref(), scratch);
+    ifft.process_with_scratch(hermitian.as_mut_slice(), scratch);
+
+    let scale = T::from_usize(n).expect("Could not convert array size to type");
+    for (out, x) in out.iter_mut().zip(&hermitian) {
+        *out = x.re / scale;
+    }
+}
+
+// Forward transform of `len` samples read from `sample`, each read once, straight into the
+// buffer the transform then runs in place on.
+fn fft_gathered<T, F>(len: usize, sample: F, context: &mut FftContext<T>) -> Vec<Complex<T>>
+where
+    T: FftNum + Float + Send + Sync,
+    F: Fn(usize) -> Complex<T> + Sync,
+{
+    let mut out = vec![Complex::<T>::zero(); len];
+    out.par_iter_mut()
+        .with_min_len(parallel_min_len())
+        .enumerate()
+        .for_each(|(idx, x)| *x = sample(idx));
+    context.forward(&mut out);
+    let len = T::from_usize(len).expect("Could not convert array size to type");
+    out.divide_const_inplace(Complex::new(len, T::zero()));
+    out
 }
 
 impl<T> YttriaVectorComplexFft<T> for [Complex<T>]
 where
     T: FftNum + Float + Send + Sync + Copy + Clone,
 {
+    fn irfft_output_len(&self) -> usize {
+        2 * self.len().saturating_sub(1)
+    }
+
+    fn fft_scratch_len(&self) -> usize {
+        FftPlanner::<T>::new()
+            .plan_fft_forward(self.len())
+            .get_inplace_scratch_len()
+    }
+    fn ifft_scratch_len(&self) -> usize {
+        FftPlanner::<T>::new()
+            .plan_fft_inverse(self.len())
+            .get_inplace_scratch_len()
+    }
+    fn irfft_scratch_len(&self) -> usize {
+        FftPlanner::<T>::new()
+            .plan_fft_inverse(self.irfft_output_len())
+            .get_inplace_scratch_len()
+    }
+
     fn fft_into(&self, out: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        assert_output_len("fft_into", self.len(), out.len());
         let mut planner = FftPlanner::<T>::new();
         let fft = planner.plan_fft_forward(self.len());
 
         out[0..(self.len())].clone_from_slice(self);
 
+        assert_scratch_len("fft_into", fft.as_ref(), scratch);
         fft.process_with_scratch(out, scratch);
         out.divide_const_inplace(Complex::<T>::new(
             T::from_usize(self.len()).expect("Could not convert array size to type"),
@@ -33,18 +159,20 @@ where
 
     fn fft(&self) -> Vec<Complex<T>> {
         let mut out = vec![Complex::<T>::zero(); self.len()];
-        let mut scratch = vec![Complex::<T>::zero(); self.len()];
+        let mut scratch = vec![Complex::<T>::zero(); self.fft_scratch_len()];
 
         self.fft_into(out.as_mut_slice(), scratch.as_mut_slice());
         out
     }
 
     fn ifft_into(&self, out: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        assert_output_len("ifft_into", self.len(), out.len());
         out[0..(self.len())].clone_from_slice(self);
 
         let mut planner = FftPlanner::<T>::new();
         let ifft = planner.plan_fft_inverse(self.len());
 
+        assert_scratch_len("ifft_into", ifft.as_ref(), scratch);
         ifft.process_with_scratch(out, scratch);
         out.divide_const_inplace(Complex::<T>::new(
             T::from_usize(self.len()).expect("Could not convert array size to type"),
@@ -54,59 +182,240 @@ where
 
     fn ifft(&self) -> Vec<Complex<T>> {
         let mut out = vec![Complex::<T>::zero(); self.len()];
-        let mut scratch = vec![Complex::<T>::zero(); self.len()];
+        let mut scratch = vec![Complex::<T>::zero(); self.ifft_scratch_len()];
 
         self.ifft_into(out.as_mut_slice(), scratch.as_mut_slice());
         out
     }
 
+    fn fft_strided(
+        &self,
+        start: usize,
+        stride: usize,
+        context: &mut FftContext<T>,
+    ) -> Vec<Complex<T>> {
+        fft_gathered(
+            self.strided_output_len(start, stride),
+            |idx| self[start + idx * stride],
+            context,
+        )
+    }
+
     fn irfft_into(&self, out: &mut [T], scratch: &mut [Complex<T>]) {
-        let out_len = 2 * (self.len() - 1);
-        let mut hermitian = vec![Complex::<T>::zero(); 2 * self.len() - 1];
-
-        hermitian[0..(self.len())].clone_from_slice(&self[0..(self.len())]);
-        hermitian.conj_inplace();
-        hermitian.reverse();
-        hermitian[0..(self.len())].clone_from_slice(&self[0..(self.len())]);
-
-        hermitian.resize(
-            out_len * 2,
-            Complex {
-                re: T::zero(),
-                im: T::zero(),
-            },
-        );
+        assert_output_len("irfft_into", self.irfft_output_len(), out.len());
+        irfft_with_len("irfft_into", self, out, scratch);
+    }
 
-        let mut planner = FftPlanner::<T>::new();
-        let ifft = planner.plan_fft_inverse(out.len());
+    fn irfft(&self) -> Vec<T> {
+        let mut out = vec![T::zero(); self.irfft_output_len()];
+        let mut scratch = vec![Complex::<T>::zero(); self.irfft_scratch_len()];
 
-        ifft.process_with_scratch(hermitian.as_mut_slice(), scratch);
-        hermitian.divide_const_inplace(Complex::<T>::new(
-            T::from_usize(out_len).expect("Could not convert array size to type"),
-            T::zero(),
-        ));
+        self.irfft_into(out.as_mut_slice(), scratch.as_mut_slice());
+        out
+    }
+
+    fn irfft_n(&self, n: usize) -> Vec<T> {
+        let mut out = vec![T::zero(); n];
+        let mut scratch = vec![
+            Complex::<T>::zero();
+            FftPlanner::<T>::new()
+                .plan_fft_inverse(n)
+                .get_inplace_scratch_len()
+        ];
 
-        out.clone_from_slice(&hermitian[0..(out.len())].real());
+        irfft_with_len("irfft_n", self, &mut out, &mut scratch);
+        out
     }
 
-    fn irfft(&self) -> Vec<T> {
-        let out_len = 2 * (self.len() - 1);
-        let mut out = vec![T::zero(); out_len];
-        let mut scratch = vec![Complex::<T>::zero(); out_len];
+    fn is_conjugate_symmetric(&self, tolerance: T) -> bool {
+        let n = self.len();
+        (0..n).all(|k| (self[k] - self[(n - k) % n].conj()).norm() <= tolerance)
+    }
 
-        self.irfft_into(out.as_mut_slice(), scratch.as_mut_slice());
+    fn enforce_conjugate_symmetry_inplace(&mut self) {
+        let n = self.len();
+        if n == 0 {
+            return;
+        }
+        let two = T::one() + T::one();
+        for k in 0..=(n / 2) {
+            let mirror = (n - k) % n;
+            let average = (self[k] + self[mirror].conj()) / two;
+            self[k] = average;
+            self[mirror] = average.conj();
+        }
+    }
+
+    fn make_real_via_symmetry(&self) -> Vec<T> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let mut spectrum = self.to_vec();
+        spectrum.enforce_conjugate_symmetry_inplace();
+        spectrum.ifft().real()
+    }
+}
+
+// Transforms of real signals. `rfft` follows numpy: it is unnormalized and keeps the
+// `len / 2 + 1` non-negative frequency bins.
+pub trait YttriaVectorRealFft<T: FftNum> {
+    // `len / 2 + 1` bins, or none for an empty input.
+    fn rfft_output_len(&self) -> usize;
+    fn rfft_into(&self, out: &mut [Complex<T>], context: &mut FftContext<T>);
+    fn rfft(&self) -> Vec<Complex<T>>;
+
+    // Magnitude spectrogram in dB of segments `window.len()` long, each overlapping the previous
+    // by `noverlap` samples. Returns segment centres in samples, bin frequencies in cycles per
+    // sample and a row-major matrix with one row per segment. Magnitudes are scaled by the window
+    // sum, so a tone of amplitude `a` reads `a / 2` in its bin, and values below `floor_db` are
+    // clamped to it.
+    fn spectrogram_db(
+        &self,
+        window: &[T],
+        noverlap: usize,
+        floor_db: T,
+    ) -> (Vec<T>, Vec<T>, Vec<T>);
+}
+
+impl<T> YttriaVectorRealFft<T> for [T]
+where
+    T: FftNum + Float + FromPrimitive,
+{
+    fn rfft_output_len(&self) -> usize {
+        if self.is_empty() {
+            0
+        } else {
+            self.len() / 2 + 1
+        }
+    }
+
+    fn rfft_into(&self, out: &mut [Complex<T>], context: &mut FftContext<T>) {
+        assert_output_len("rfft_into", self.rfft_output_len(), out.len());
+
+        let mut buffer = self
+            .iter()
+            .map(|x| Complex::new(*x, T::zero()))
+            .collect::<Vec<_>>();
+        context.forward(&mut buffer);
+        out.copy_from_slice(&buffer[..out.len()]);
+    }
+    fn rfft(&self) -> Vec<Complex<T>> {
+        let mut out = vec![Complex::<T>::zero(); self.rfft_output_len()];
+        self.rfft_into(&mut out, &mut FftContext::new());
         out
     }
+
+    fn spectrogram_db(
+        &self,
+        window: &[T],
+        noverlap: usize,
+        floor_db: T,
+    ) -> (Vec<T>, Vec<T>, Vec<T>) {
+        let nperseg = window.len();
+        assert!(nperseg > 0, "Window must not be empty");
+        assert!(
+            noverlap < nperseg,
+            "Overlap {noverlap} must be shorter than the {nperseg} sample window"
+        );
+
+        let from_usize = |x: usize| T::from_usize(x).expect("Could not convert usize to type");
+        let hop = nperseg - noverlap;
+        let segments = if self.len() < nperseg {
+            0
+        } else {
+            (self.len() - nperseg) / hop + 1
+        };
+        let bins = nperseg / 2 + 1;
+        let scale = window.iter().fold(T::zero(), |acc, w| acc + *w);
+        let twenty = T::from_f64(20.0).expect("Could not convert f64 into type");
+
+        let times = (0..segments)
+            .map(|s| from_usize(2 * s * hop + nperseg) / from_usize(2))
+            .collect();
+        let freqs = (0..bins)
+            .map(|k| from_usize(k) / from_usize(nperseg))
+            .collect();
+
+        let mut context = FftContext::new();
+        let mut segment = vec![T::zero(); nperseg];
+        let mut spectrum = vec![Complex::<T>::zero(); bins];
+        let mut matrix = Vec::with_capacity(segments * bins);
+        for s in 0..segments {
+            let start = s * hop;
+            for ((out, x), w) in segment
+                .iter_mut()
+                .zip(&self[start..(start + nperseg)])
+                .zip(window)
+            {
+                *out = *x * *w;
+            }
+            segment.rfft_into(&mut spectrum, &mut context);
+            matrix.extend(
+                spectrum
+                    .iter()
+                    .map(|x| (twenty * (x.norm() / scale).log10()).max(floor_db)),
+            );
+        }
+
+        (times, freqs, matrix)
+    }
+}
+
+// Caches FFT plans and scratch space across calls of any length. Transforms run in place and are
+// unnormalized in both directions, so a forward and inverse round trip scales by the length.
+pub struct FftContext<T: FftNum> {
+    planner: FftPlanner<T>,
+    scratch: Vec<Complex<T>>,
+}
+
+impl<T: FftNum> Default for FftContext<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: FftNum> FftContext<T> {
+    pub fn new() -> Self {
+        Self {
+            planner: FftPlanner::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    pub fn forward(&mut self, buffer: &mut [Complex<T>]) {
+        let fft = self.planner.plan_fft_forward(buffer.len());
+        self.process(fft.as_ref(), buffer);
+    }
+
+    pub fn inverse(&mut self, buffer: &mut [Complex<T>]) {
+        let fft = self.planner.plan_fft_inverse(buffer.len());
+        self.process(fft.as_ref(), buffer);
+    }
+
+    // A forward plan for transforms spread over threads, each bringing its own scratch.
+    pub(crate) fn plan_forward(&mut self, len: usize) -> Arc<dyn Fft<T>> {
+        self.planner.plan_fft_forward(len)
+    }
+
+    fn process(&mut self, fft: &dyn Fft<T>, buffer: &mut [Complex<T>]) {
+        let scratch_len = fft.get_inplace_scratch_len();
+        if self.scratch.len() < scratch_len {
+            self.scratch
+                .resize(scratch_len, Complex::new(T::zero(), T::zero()));
+        }
+        fft.process_with_scratch(buffer, &mut self.scratch[..scratch_len]);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use num::complex::Complex32;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_real() {
-        let test = vec![
+        let test = [
             Complex32 { re: 0.0, im: 0.0 },
             Complex32 { re: 1.0, im: 2.0 },
             Complex32 { re: 2.0, im: 5.0 },
@@ -119,7 +428,7 @@ mod tests {
 
     #[test]
     fn test_ifft() {
-        let test = vec![
+        let test = [
             Complex32 { re: 1.0, im: 0.0 },
             Complex32 { re: 0.0, im: -1.0 },
             Complex32 { re: -1.0, im: 0.0 },
@@ -131,7 +440,7 @@ mod tests {
 
     #[test]
     fn test_irfft() {
-        let test = vec![
+        let test = [
             Complex32 { re: 1.0, im: 0.0 },
             Complex32 { re: 0.0, im: -1.0 },
             Complex32 { re: -1.0, im: 0.0 },
@@ -142,4 +451,234 @@ mod tests {
         let fft = test.irfft();
         println!("{fft:?}");
     }
+
+    #[test]
+    fn test_irfft_n_even_and_odd() {
+        for n in [16, 15, 1] {
+            let x = (0..n)
+                .map(|t| (0.3 * t as f64).sin() + 0.1 * t as f64)
+                .collect::<Vec<_>>();
+            let recon = x.rfft().irfft_n(n);
+            assert_eq!(recon.len(), n);
+            for (r, x) in recon.iter().zip(&x) {
+                assert!((r - x).abs() < 1e-12, "{n}");
+            }
+        }
+
+        // The default length assumes an even signal, so an odd one cannot come back without `n`.
+        let x = [1.0, -2.0, 0.5, 3.0, 0.25];
+        assert_eq!(x.rfft().irfft().len(), 4);
+
+        // Extra bins are dropped and missing ones are zero padded.
+        let bins = [1.0, -2.0, 0.5, 3.0, 0.25].rfft();
+        assert_eq!(bins.irfft_n(8).len(), 8);
+        let (dc, nyquist) = (bins[0].re, bins[1].re);
+        let expected = [(dc + nyquist) / 2.0, (dc - nyquist) / 2.0];
+        for (t, e) in bins.irfft_n(2).iter().zip(expected) {
+            assert!((t - e).abs() < 1e-12);
+        }
+        assert!(bins.irfft_n(0).is_empty());
+    }
+
+    #[test]
+    fn test_conjugate_symmetry() {
+        for n in [1, 2, 15, 16] {
+            let x = (0..n)
+                .map(|i| Complex::new((0.7 * i as f64).sin() + 0.1 * i as f64, 0.0))
+                .collect::<Vec<_>>();
+            let spectrum = x.fft();
+            assert!(spectrum.is_conjugate_symmetric(1e-12), "{n}");
+
+            let mut flipped = spectrum.clone();
+            flipped[n / 3] += Complex::new(0.0, 1e-3);
+            assert!(!flipped.is_conjugate_symmetric(1e-6), "{n}");
+
+            flipped.enforce_conjugate_symmetry_inplace();
+            assert!(flipped.is_conjugate_symmetric(0.0), "{n}");
+            assert_eq!(flipped[0].im, 0.0);
+            if n % 2 == 0 {
+                assert_eq!(flipped[n / 2].im, 0.0);
+            }
+            assert!(flipped.ifft().iter().all(|x| x.im.abs() < 1e-12), "{n}");
+
+            let real = flipped.make_real_via_symmetry();
+            for (a, b) in real.iter().zip(flipped.ifft()) {
+                assert_eq!(*a, b.re);
+            }
+        }
+
+        // The DC and Nyquist bins keep their real parts and lose their imaginary ones, while a
+        // mismatched pair meets in the middle.
+        let mut spectrum = [
+            Complex::new(1.0, 0.5),
+            Complex::new(2.0, 1.0),
+            Complex::new(3.0, -2.0),
+            Complex::new(4.0, 0.0),
+        ];
+        assert!(!spectrum.is_conjugate_symmetric(0.9));
+        assert!(spectrum.is_conjugate_symmetric(4.0));
+        spectrum.enforce_conjugate_symmetry_inplace();
+        assert_eq!(
+            spectrum,
+            [
+                Complex::new(1.0, 0.0),
+                Complex::new(3.0, 0.5),
+                Complex::new(3.0, 0.0),
+                Complex::new(3.0, -0.5),
+            ]
+        );
+        assert_eq!(
+            [
+                Complex::new(0.0, 1.0),
+                Complex::new(2.0, 3.0),
+                Complex::new(2.0, -3.0)
+            ]
+            .make_real_via_symmetry()
+            .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_conjugate_symmetry_empty() {
+        let mut empty: [Complex<f64>; 0] = [];
+        assert!(empty.is_conjugate_symmetric(0.0));
+        empty.enforce_conjugate_symmetry_inplace();
+        assert!(empty.make_real_via_symmetry().is_empty());
+    }
+
+    #[test]
+    fn test_fft_context_round_trip() {
+        let mut context = FftContext::<f64>::new();
+        for len in [8, 12, 97] {
+            let input = (0..len)
+                .map(|i| Complex::new(i as f64, (i * i % 7) as f64))
+                .collect::<Vec<_>>();
+            let mut buffer = input.clone();
+
+            context.forward(&mut buffer);
+            let expected = input.fft();
+            for (a, b) in buffer.iter().zip(&expected) {
+                assert!((a / len as f64 - b).norm() < 1e-9);
+            }
+
+            context.inverse(&mut buffer);
+            for (a, b) in buffer.iter().zip(&input) {
+                assert!((a / len as f64 - b).norm() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rfft_matches_fft() {
+        let x = [1.0, -2.0, 3.5, 0.25, 4.0, -1.0, 0.5];
+        let complex = x.iter().map(|x| Complex::new(*x, 0.0)).collect::<Vec<_>>();
+        let spectrum = x.rfft();
+        assert_eq!(spectrum.len(), 4);
+        for (a, b) in spectrum.iter().zip(complex.fft()) {
+            assert!((a - b * 7.0).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_spectrogram_db() {
+        let window = crate::windows::hann::<f64>(64);
+        let tone = |amplitude: f64| {
+            (0..1024)
+                .map(|n| amplitude * (2.0 * std::f64::consts::PI * 0.125 * n as f64).cos())
+                .collect::<Vec<_>>()
+        };
+
+        let (times, freqs, quiet) = tone(0.01).spectrogram_db(&window, 32, -120.0);
+        assert_eq!(times.len(), 31);
+        assert_eq!(times[0], 32.0);
+        assert_eq!(times[1], 64.0);
+        assert_eq!(freqs.len(), 33);
+        assert_eq!(freqs[8], 0.125);
+        assert_eq!(quiet.len(), times.len() * freqs.len());
+
+        let (_, _, loud) = tone(1.0).spectrogram_db(&window, 32, -120.0);
+        for (q, l) in quiet.iter().zip(&loud) {
+            assert!(l >= q);
+        }
+        for row in loud.chunks(freqs.len()) {
+            assert!((row[8] - 20.0 * 0.5f64.log10()).abs() < 0.01);
+        }
+
+        let floor = -40.0;
+        let (_, _, floored) = tone(1.0).spectrogram_db(&window, 32, floor);
+        assert!(floored.iter().all(|x| *x >= floor));
+        assert!(floored.contains(&floor));
+
+        let (_, _, silent) = vec![0.0; 256].spectrogram_db(&window, 0, floor);
+        assert!(silent.iter().all(|x| *x == floor));
+    }
+
+    #[test]
+    fn test_scratch_larger_than_transform() {
+        // rustfft needs 2048 scratch elements for a 509 point transform.
+        let len = 509;
+        let x = (0..len)
+            .map(|i| Complex::new((i % 13) as f64, (i % 7) as f64))
+            .collect::<Vec<_>>();
+        assert!(x.fft_scratch_len() > len);
+
+        let round_trip = x.fft().ifft();
+        for (a, b) in round_trip.iter().zip(&x) {
+            assert!((a * len as f64 - b).norm() < 1e-9);
+        }
+
+        let half = vec![Complex::new(1.0, 0.0); len / 2 + 1];
+        assert_eq!(half.irfft().len(), 2 * (half.len() - 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "fft_into needs")]
+    fn test_scratch_too_small() {
+        let x = vec![Complex::new(1.0, 0.0); 509];
+        let mut out = vec![Complex::zero(); 509];
+        let mut scratch = vec![Complex::zero(); 509];
+        x.fft_into(&mut out, &mut scratch);
+    }
+
+    #[test]
+    fn test_fft_strided_matches_gather() {
+        let x = (0..1000)
+            .map(|i| Complex::new((i as f64 * 0.37).sin(), (i as f64 * 0.11).cos()))
+            .collect::<Vec<_>>();
+        let mut context = FftContext::new();
+        for (start, stride) in [(0, 1), (0, 4), (3, 4), (7, 3), (999, 8), (12, 1000)] {
+            let manual = x.gather_strided(start, stride).fft();
+            assert_eq!(x.fft_strided(start, stride, &mut context), manual);
+        }
+        assert!(x.fft_strided(1000, 2, &mut context).is_empty());
+    }
+
+    #[test]
+    fn test_fft_strided_single_pass() {
+        // Every sample is read from the source exactly once, with no copy between the gather and
+        // the transform.
+        let x = (0..(1 << 14))
+            .map(|i| Complex::new((i as f64 * 0.37).sin(), (i as f64 * 0.11).cos()))
+            .collect::<Vec<_>>();
+        let (start, stride) = (3, 4);
+        let visits = (0..x.len())
+            .map(|_| AtomicUsize::new(0))
+            .collect::<Vec<_>>();
+        let spectrum = fft_gathered(
+            x.strided_output_len(start, stride),
+            |idx| {
+                let i = start + idx * stride;
+                visits[i].fetch_add(1, Ordering::Relaxed);
+                x[i]
+            },
+            &mut FftContext::new(),
+        );
+
+        assert_eq!(spectrum, x.gather_strided(start, stride).fft());
+        for (i, visits) in visits.iter().enumerate() {
+            let expected = usize::from(i >= start && (i - start).is_multiple_of(stride));
+            assert_eq!(visits.load(Ordering::Relaxed), expected, "{i}");
+        }
+    }
 }
@@ -0,0 +1,206 @@
+use num::{Float, FromPrimitive};
+use rayon::prelude::*;
+
+use super::YttriaVectorArithmetic;
+use crate::config::parallel_min_len;
+use crate::windows::WindowKind;
+
+pub trait YttriaVectorFloat<T> {
+    // Multiplies by a window of the given kind, generated at the length of the vector.
+    fn apply_window(&self, window: WindowKind) -> Vec<T>;
+    fn apply_window_inplace(&mut self, window: WindowKind) -> &mut Self;
+
+    // Scale the whole vector so its largest magnitude, or its RMS, equals the target. An all
+    // zero vector is left untouched.
+    fn normalize_peak_inplace(&mut self, target: T) -> &mut Self;
+    fn normalize_rms_inplace(&mut self, target_rms: T) -> &mut Self;
+
+    // Stretches the samples onto `new_len` evenly spaced points with the first and last samples
+    // kept in place, interpolating linearly in between with `interp`. There is no filtering, so
+    // shrinking aliases anything above the new Nyquist frequency.
+    fn resample_linear(&self, new_len: usize) -> Vec<T>;
+
+    // Peak magnitude over RMS, and the same ratio as peak to average power in dB. A full scale
+    // tone gives sqrt(2), or about 3.01 dB. Panics on an empty vector; an all zero vector gives
+    // NaN.
+    fn crest_factor(&self) -> T;
+    fn papr_db(&self) -> T;
+}
+
+impl<T> YttriaVectorFloat<T> for [T]
+where
+    T: Float + FromPrimitive + Send + Sync,
+{
+    fn apply_window(&self, window: WindowKind) -> Vec<T> {
+        let mut out = self.to_vec();
+        out.apply_window_inplace(window);
+        out
+    }
+
+    fn apply_window_inplace(&mut self, window: WindowKind) -> &mut Self {
+        let window = window.generate::<T>(self.len());
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(window)
+            .for_each(|(own, w)| *own = *own * w);
+        self
+    }
+
+    fn normalize_peak_inplace(&mut self, target: T) -> &mut Self {
+        let peak = self.iter().fold(T::zero(), |acc, x| acc.max(x.abs()));
+        scale_inplace(self, target, peak)
+    }
+
+    fn normalize_rms_inplace(&mut self, target_rms: T) -> &mut Self {
+        let len = T::from_usize(self.len()).expect("Could not convert usize into type");
+        let rms = (self.iter().fold(T::zero(), |acc, x| acc + *x * *x) / len).sqrt();
+        scale_inplace(self, target_rms, rms)
+    }
+
+    fn resample_linear(&self, new_len: usize) -> Vec<T> {
+        assert!(
+            !self.is_empty() || new_len == 0,
+            "Cannot resample an empty vector to {new_len} samples"
+        );
+        if new_len < 2 || self.len() < 2 {
+            return self.iter().copied().cycle().take(new_len).collect();
+        }
+
+        // Output `k` lies `k * (len - 1) / (new_len - 1)` samples in, divided as integers
+        // first so the grid points land exactly.
+        let (span, steps) = (self.len() - 1, new_len - 1);
+        let steps_t = T::from_usize(steps).expect("Could not convert usize into type");
+        let index = |i: usize| T::from_usize(i).expect("Could not convert usize into type");
+        let positions = (0..new_len)
+            .map(|k| index((k * span) / steps) + index((k * span) % steps) / steps_t)
+            .collect::<Vec<_>>();
+        let xp = (0..self.len()).map(index).collect::<Vec<_>>();
+        positions.interp(&xp, self)
+    }
+
+    fn crest_factor(&self) -> T {
+        assert!(
+            !self.is_empty(),
+            "Cannot take the crest factor of an empty vector"
+        );
+        let len = T::from_usize(self.len()).expect("Could not convert usize into type");
+        let peak = self.iter().fold(T::zero(), |acc, x| acc.max(x.abs()));
+        let rms = (self.iter().fold(T::zero(), |acc, x| acc + *x * *x) / len).sqrt();
+        peak / rms
+    }
+
+    fn papr_db(&self) -> T {
+        let twenty = T::from_f64(20.0).expect("Could not convert f64 into type");
+        twenty * self.crest_factor().log10()
+    }
+}
+
+fn scale_inplace<T: Float + Send + Sync>(x: &mut [T], target: T, level: T) -> &mut [T] {
+    if level > T::zero() {
+        let gain = target / level;
+        x.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .for_each(|x| *x = *x * gain);
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::YttriaVectorArithmetic;
+    use crate::windows::{hann, kaiser};
+
+    // Both traits in scope, as with the prelude, still resolve the one-sided clamps.
+    #[test]
+    fn test_one_sided_clamp_with_float_trait() {
+        let mut x = [-1.0f32, 0.5, 2.0];
+        assert_eq!(x.clamp_min(0.0).clamp_max(1.0), [0.0, 0.5, 1.0]);
+        x.clamp_max_in_place(0.0);
+        assert_eq!(x, [-1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_apply_window_matches_multiply() {
+        let test = (0..33)
+            .map(|i| (i as f64 * 0.3).sin() + 2.0)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            test.apply_window(WindowKind::Hann),
+            test.multiply(&hann(33))
+        );
+        assert_eq!(
+            test.apply_window(WindowKind::Kaiser(8.6)),
+            test.multiply(&kaiser(33, 8.6))
+        );
+        assert_eq!(test.apply_window(WindowKind::Rectangular), test);
+
+        let mut in_place = test.clone();
+        in_place.apply_window_inplace(WindowKind::Blackman);
+        assert_eq!(in_place, test.apply_window(WindowKind::Blackman));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut test = vec![0.5, -2.0, 1.0, 0.25];
+        test.normalize_peak_inplace(0.8);
+        assert_eq!(test, [0.2, -0.8, 0.4, 0.1]);
+
+        let mut test = (0..1000)
+            .map(|i| (i as f64 * 0.01).sin())
+            .collect::<Vec<_>>();
+        test.normalize_rms_inplace(0.1);
+        let rms = (test.iter().map(|x| x * x).sum::<f64>() / 1000.0).sqrt();
+        assert!((20.0 * (rms / 0.1).log10()).abs() < 0.01);
+
+        let mut zeros = vec![0.0; 4];
+        zeros.normalize_peak_inplace(1.0).normalize_rms_inplace(1.0);
+        assert_eq!(zeros, [0.0; 4]);
+    }
+
+    #[test]
+    fn test_crest_factor() {
+        // Whole cycles of a tone, sampled at its peaks.
+        let tone = (0..1000)
+            .map(|i| (2.0 * std::f64::consts::PI * i as f64 / 40.0).sin())
+            .collect::<Vec<_>>();
+        assert!((tone.crest_factor() - 2f64.sqrt()).abs() < 1e-12);
+        assert!((tone.papr_db() - 3.0103).abs() < 1e-4);
+
+        let constant = [-0.7; 16];
+        assert!((constant.crest_factor() - 1.0).abs() < 1e-12);
+        assert!(constant.papr_db().abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_resample_linear_ramp() {
+        let ramp = (0..11).map(|i| 2.0 * i as f64 - 3.0).collect::<Vec<_>>();
+        for new_len in [11, 21, 37, 101] {
+            let out = ramp.resample_linear(new_len);
+            assert_eq!(out.len(), new_len);
+            assert_eq!((out[0], out[new_len - 1]), (-3.0, 17.0));
+            let step = 20.0 / (new_len - 1) as f64;
+            for (k, x) in out.iter().enumerate() {
+                assert!((x - (-3.0 + step * k as f64)).abs() < 1e-12);
+            }
+        }
+        // The original points come back when the new grid contains them.
+        assert_eq!(
+            ramp.resample_linear(21)
+                .iter()
+                .step_by(2)
+                .copied()
+                .collect::<Vec<_>>(),
+            ramp
+        );
+        assert_eq!(ramp.resample_linear(6), [-3.0, 1.0, 5.0, 9.0, 13.0, 17.0]);
+    }
+
+    #[test]
+    fn test_resample_linear_edges() {
+        assert_eq!([1.0f32, 3.0].resample_linear(5), [1.0, 1.5, 2.0, 2.5, 3.0]);
+        assert_eq!([4.0f32].resample_linear(3), [4.0; 3]);
+        assert_eq!([1.0f32, 3.0].resample_linear(1), [1.0]);
+        assert!([0.0f32; 0].resample_linear(0).is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+use num::{Complex, Float};
+use rayon::prelude::*;
+use std::fmt;
+
+use super::{InterpKind, Interpolant};
+
+// Samples per task. The gain is computed sample by sample as each chunk is scaled and never
+// stored, so memory use does not grow with the length of the capture.
+const CHUNK_LEN: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GainCurveError {
+    Empty,
+    // Point `index` is not strictly after the point before it.
+    Unsorted { index: usize },
+    // Point `index` lies past the end of the data.
+    OutOfRange { index: usize },
+}
+
+impl fmt::Display for GainCurveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "a gain curve needs at least one point"),
+            Self::Unsorted { index } => {
+                write!(f, "gain point {index} is not after the one before it")
+            }
+            Self::OutOfRange { index } => {
+                write!(f, "gain point {index} lies past the end of the data")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GainCurveError {}
+
+pub trait YttriaVectorGainCurve<T> {
+    // Applies the gain curve through the `(sample index, gain in dB)` control points, interpolated
+    // in dB. Samples before the first point or after the last take that point's gain. Complex
+    // samples are scaled, leaving their phase untouched.
+    fn apply_gain_curve_inplace(
+        &mut self,
+        points: &[(usize, T)],
+        interp: InterpKind,
+    ) -> Result<(), GainCurveError>;
+}
+
+fn gain_curve<T, S>(
+    x: &mut [S],
+    points: &[(usize, T)],
+    interp: InterpKind,
+    scale: impl Fn(S, T) -> S + Sync,
+) -> Result<(), GainCurveError>
+where
+    T: Float + Send + Sync,
+    S: Copy + Send,
+{
+    if points.is_empty() {
+        return Err(GainCurveError::Empty);
+    }
+    if let Some(index) = (1..points.len()).find(|&i| points[i].0 <= points[i - 1].0) {
+        return Err(GainCurveError::Unsorted { index });
+    }
+    if let Some(index) = points.iter().position(|(i, _)| *i >= x.len()) {
+        return Err(GainCurveError::OutOfRange { index });
+    }
+
+    let to_type = |i: usize| T::from(i).expect("Could not convert usize to type");
+    let times = points.iter().map(|(i, _)| to_type(*i)).collect::<Vec<_>>();
+    let gains = points.iter().map(|(_, g)| *g).collect::<Vec<_>>();
+    let interpolant = Interpolant::new(&times, &gains, interp);
+    let ten = to_type(10);
+    let twenty = to_type(20);
+
+    x.par_chunks_mut(CHUNK_LEN)
+        .enumerate()
+        .for_each(|(chunk, samples)| {
+            let start = chunk * CHUNK_LEN;
+            for (offset, x) in samples.iter_mut().enumerate() {
+                let gain = ten.powf(interpolant.at(to_type(start + offset)) / twenty);
+                *x = scale(*x, gain);
+            }
+        });
+    Ok(())
+}
+
+impl<T> YttriaVectorGainCurve<T> for [T]
+where
+    T: Float + Send + Sync,
+{
+    fn apply_gain_curve_inplace(
+        &mut self,
+        points: &[(usize, T)],
+        interp: InterpKind,
+    ) -> Result<(), GainCurveError> {
+        gain_curve(self, points, interp, |x, gain| x * gain)
+    }
+}
+
+impl<T> YttriaVectorGainCurve<T> for [Complex<T>]
+where
+    T: Float + Send + Sync,
+{
+    fn apply_gain_curve_inplace(
+        &mut self,
+        points: &[(usize, T)],
+        interp: InterpKind,
+    ) -> Result<(), GainCurveError> {
+        gain_curve(self, points, interp, |x, gain| x.scale(gain))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{OutOfRange, YttriaVectorArithmetic, YttriaVectorRegrid};
+
+    const KINDS: [InterpKind; 3] = [
+        InterpKind::Linear,
+        InterpKind::Nearest,
+        InterpKind::CubicSpline,
+    ];
+
+    fn signal(len: usize) -> Vec<f64> {
+        (0..len).map(|i| 1.0 + (i as f64 * 0.01).sin()).collect()
+    }
+
+    #[test]
+    fn test_two_point_ramp() {
+        let x = vec![1.0; 1001];
+        let mut ramp = x.clone();
+        ramp.apply_gain_curve_inplace(&[(100, -20.0), (900, 20.0)], InterpKind::Linear)
+            .unwrap();
+
+        // Edges extend flat, and in between the gain grows by 20 dB every 400 samples.
+        assert_eq!(ramp[0], ramp[100]);
+        assert_eq!(ramp[1000], ramp[900]);
+        for (i, expected) in [
+            (100, 0.1),
+            (300, 10f64.sqrt().recip()),
+            (500, 1.0),
+            (700, 10f64.sqrt()),
+            (900, 10.0),
+        ] {
+            assert!((ramp[i] - expected).abs() < 1e-12 * expected, "{i}");
+        }
+        for i in 100..900 {
+            let ratio = ramp[i + 1] / ramp[i];
+            assert!((ratio - 10f64.powf(40.0 / 800.0 / 20.0)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_invalid_points() {
+        let mut x = vec![1.0; 100];
+        assert_eq!(
+            x.apply_gain_curve_inplace(&[(0, 0.0), (100, 1.0)], InterpKind::Linear),
+            Err(GainCurveError::OutOfRange { index: 1 })
+        );
+        assert_eq!(
+            x.apply_gain_curve_inplace(&[(10, 0.0), (10, 1.0)], InterpKind::Linear),
+            Err(GainCurveError::Unsorted { index: 1 })
+        );
+        assert_eq!(
+            x.apply_gain_curve_inplace(&[], InterpKind::Linear),
+            Err(GainCurveError::Empty)
+        );
+        assert_eq!(
+            Vec::<f64>::new().apply_gain_curve_inplace(&[(0, 0.0)], InterpKind::Linear),
+            Err(GainCurveError::OutOfRange { index: 0 })
+        );
+        // Nothing is touched on error.
+        assert_eq!(x, vec![1.0; 100]);
+    }
+
+    #[test]
+    fn test_constant_curve() {
+        let x = signal(20_000);
+        for kind in KINDS {
+            for points in [
+                &[(5000, 6.0)][..],
+                &[(0, 6.0), (12_345, 6.0), (19_999, 6.0)],
+            ] {
+                let mut out = x.clone();
+                out.apply_gain_curve_inplace(points, kind).unwrap();
+                assert_eq!(out, x.multiply_const(10f64.powf(6.0 / 20.0)), "{kind:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_complex_keeps_phase() {
+        let x = (0..5000)
+            .map(|i| Complex::from_polar(1.0 + (i % 7) as f64, 0.3 * i as f64))
+            .collect::<Vec<_>>();
+        let points = [(0, 0.0), (2500, -12.0), (4999, 3.0)];
+        let mut out = x.clone();
+        out.apply_gain_curve_inplace(&points, InterpKind::Linear)
+            .unwrap();
+
+        let mut magnitudes = x.iter().map(|x| x.norm()).collect::<Vec<_>>();
+        magnitudes
+            .apply_gain_curve_inplace(&points, InterpKind::Linear)
+            .unwrap();
+        for ((a, b), m) in x.iter().zip(&out).zip(&magnitudes) {
+            assert!((a.arg() - b.arg()).abs() < 1e-12);
+            assert!((b.norm() - m).abs() < 1e-12 * m);
+        }
+    }
+
+    #[test]
+    fn test_matches_full_gain_vector() {
+        // Long enough for several chunks, with points on and away from chunk boundaries.
+        let len = 5 * CHUNK_LEN + 123;
+        let x = signal(len);
+        let points = [
+            (17, 3.0),
+            (CHUNK_LEN - 1, -6.0),
+            (CHUNK_LEN, -5.5),
+            (3 * CHUNK_LEN + 700, 10.0),
+            (len - 50, -1.0),
+        ];
+        let times = points.iter().map(|(i, _)| *i as f64).collect::<Vec<_>>();
+        let gains = points.iter().map(|(_, g)| *g).collect::<Vec<_>>();
+
+        for kind in KINDS {
+            let gain_db = gains
+                .regrid_uniform(&times, 0.0, 1.0, len, kind, OutOfRange::HoldEdges)
+                .unwrap();
+            let expected = x
+                .iter()
+                .zip(&gain_db)
+                .map(|(x, g)| x * 10f64.powf(g / 20.0))
+                .collect::<Vec<_>>();
+
+            let mut out = x.clone();
+            out.apply_gain_curve_inplace(&points, kind).unwrap();
+            assert_eq!(out, expected, "{kind:?}");
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use half::slice::HalfFloatSliceExt;
+use half::{bf16, f16};
+use rayon::prelude::*;
+
+// f16 and bf16 implement the num traits by widening to f32, computing there and rounding back
+// to half precision after every operation, so the generic arithmetic, comparison and statistics
+// traits accept them directly. Statistics accumulate in f64 regardless. Anything that needs
+// `FftNum`, such as the FFTs, wants the samples widened first.
+pub trait HalfSample: Copy + Default + Send + Sync {
+    const MAX_F32: f32;
+
+    fn widen_slice(src: &[Self], dst: &mut [f32]);
+    fn narrow_slice(src: &[f32], dst: &mut [Self]);
+}
+
+macro_rules! implement_half_sample {
+    ( $type_impl:ident ) => {
+        impl HalfSample for $type_impl {
+            const MAX_F32: f32 = $type_impl::MAX.to_f32_const();
+
+            fn widen_slice(src: &[Self], dst: &mut [f32]) {
+                src.convert_to_f32_slice(dst);
+            }
+
+            fn narrow_slice(src: &[f32], dst: &mut [Self]) {
+                dst.convert_from_f32_slice(src);
+            }
+        }
+    };
+}
+
+implement_half_sample!(f16);
+implement_half_sample!(bf16);
+
+// Samples per conversion task. The half crate converts each chunk with vector instructions
+// where the target supports them.
+const CHUNK_LEN: usize = 4096;
+
+pub trait YttriaVectorHalf<H> {
+    fn widen_to_f32(&self) -> Vec<f32>;
+}
+
+impl<H: HalfSample> YttriaVectorHalf<H> for [H] {
+    fn widen_to_f32(&self) -> Vec<f32> {
+        let mut out = vec![0.0f32; self.len()];
+        out.par_chunks_mut(CHUNK_LEN)
+            .zip(self.par_chunks(CHUNK_LEN))
+            .for_each(|(out, chunk)| H::widen_slice(chunk, out));
+        out
+    }
+}
+
+// Values beyond the largest finite half precision value saturate to it instead of becoming
+// infinite. Infinities saturate too, while NaN stays NaN.
+pub fn narrow_from_f32<H: HalfSample>(x: &[f32]) -> Vec<H> {
+    let mut out = vec![H::default(); x.len()];
+    out.par_chunks_mut(CHUNK_LEN)
+        .zip(x.par_chunks(CHUNK_LEN))
+        .for_each(|(out, chunk)| {
+            let mut clamped = [0.0f32; CHUNK_LEN];
+            let clamped = &mut clamped[..chunk.len()];
+            for (c, x) in clamped.iter_mut().zip(chunk) {
+                *c = x.clamp(-H::MAX_F32, H::MAX_F32);
+            }
+            H::narrow_slice(clamped, out);
+        });
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{YttriaVectorArithmetic, YttriaVectorStatistics, YttriaVectorUtils};
+
+    #[test]
+    fn test_round_trip_within_epsilon() {
+        let x = (0..10_000)
+            .map(|i| (i as f32 * 0.37).sin() * 1000.0)
+            .collect::<Vec<_>>();
+
+        let narrow = narrow_from_f32::<f16>(&x);
+        assert_eq!(x.as_type::<f16>(), narrow);
+        for (a, b) in narrow.widen_to_f32().iter().zip(&x) {
+            assert!((a - b).abs() <= b.abs() * f16::EPSILON.to_f32() / 2.0 + 1e-7);
+        }
+
+        let narrow = narrow_from_f32::<bf16>(&x);
+        for (a, b) in narrow.widen_to_f32().iter().zip(&x) {
+            assert!((a - b).abs() <= b.abs() * bf16::EPSILON.to_f32() / 2.0 + 1e-7);
+        }
+    }
+
+    #[test]
+    fn test_saturates_beyond_max() {
+        let x = [
+            1e5f32,
+            -1e6,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NAN,
+            1.5,
+        ];
+        let narrow = narrow_from_f32::<f16>(&x);
+
+        assert_eq!(narrow[..4], [f16::MAX, f16::MIN, f16::MAX, f16::MIN]);
+        assert!(narrow[4].is_nan());
+        assert_eq!(narrow[5], f16::from_f32(1.5));
+    }
+
+    #[test]
+    fn test_statistics_and_arithmetic() {
+        // Long enough that a running f16 sum would stop growing.
+        let x = (0..200_000)
+            .map(|i| 1.0 + 0.5 * (i as f32 * 0.01).sin())
+            .collect::<Vec<_>>();
+        let narrow = narrow_from_f32::<f16>(&x);
+        let reference = narrow.widen_to_f32();
+
+        let mean = narrow.mean().to_f32();
+        let expected = reference.iter().map(|&x| x as f64).sum::<f64>() / x.len() as f64;
+        assert!((mean as f64 - expected).abs() < 1e-3 * expected);
+
+        let var = narrow.var().to_f32() as f64;
+        let expected_var = reference
+            .iter()
+            .map(|&x| (x as f64 - expected).powi(2))
+            .sum::<f64>()
+            / x.len() as f64;
+        assert!((var - expected_var).abs() < 1e-2 * expected_var);
+
+        let (min, max) = narrow.extremes();
+        assert!(min.to_f32() >= 0.5 && max.to_f32() <= 1.5);
+
+        let doubled = narrow.multiply_const(f16::from_f32(2.0));
+        for (a, b) in doubled.iter().zip(&narrow) {
+            assert_eq!(a.to_f32(), 2.0 * b.to_f32());
+        }
+
+        assert!(<[f16]>::mean(&[]).is_nan());
+    }
+}
@@ -0,0 +1,376 @@
+use num::{Complex, Float, FromPrimitive, Zero};
+use rayon::prelude::*;
+
+use super::{FftContext, YttriaVectorArithmetic};
+use crate::config::parallel_min_len;
+use crate::rng::YttriaRng;
+
+// Half-length of the Hann windowed sinc used by `fractional_delay`.
+const DELAY_HALF_LEN: isize = 16;
+
+fn from_f64<T: FromPrimitive>(x: f64) -> T {
+    T::from_f64(x).expect("Could not convert f64 into type")
+}
+
+fn to_f64<T: Float>(x: T) -> f64 {
+    x.to_f64().expect("Could not convert type into f64")
+}
+
+// Channel and front-end impairments for building test signals. Frequencies are in cycles per
+// sample and phases in radians.
+pub trait YttriaVectorImpairments<T> {
+    // Rotates sample `n` by `2 pi cfo n`.
+    fn apply_cfo(&self, cfo: T) -> Vec<Complex<T>>;
+    fn apply_phase(&self, phase: T) -> Vec<Complex<T>>;
+
+    // Delays by `delay` samples, keeping the length. Fractional delays use a windowed sinc
+    // interpolator spanning 32 samples; whole sample delays are exact shifts.
+    fn fractional_delay(&self, delay: T) -> Vec<Complex<T>>;
+
+    // Receiver IQ imbalance: the quadrature branch has `gain_db` of extra gain and is skewed by
+    // `phase` from orthogonal, giving `re + j * g * (im * cos(phase) + re * sin(phase))`.
+    fn apply_iq_imbalance(&self, gain_db: T, phase: T) -> Vec<Complex<T>>;
+
+    // Rotates the samples by Gaussian phase noise whose single sideband spectrum `L(f)` follows
+    // the `(offset in Hz, dBc/Hz)` mask, interpolated linearly in dB against log frequency and
+    // held flat beyond its ends. The noise is shaped in the frequency domain over the whole
+    // vector, so it is periodic with the vector's length, and its RMS phase is the square root of
+    // the integral of `L(f)` over both sidebands.
+    fn add_phase_noise_inplace(
+        &mut self,
+        psd_dbc_hz: &[(T, T)],
+        sample_rate: T,
+        rng: &mut impl YttriaRng,
+    );
+
+    // Single sideband phase noise profile around the carrier in bin `carrier_bin` of `self`, a
+    // rectangular window spectrum such as `fft` returns: `(offset in Hz, dBc/Hz)` pairs, each the
+    // mean of `rbw_bins` bins on both sidebands. The carrier power is summed over the
+    // `rbw_bins / 2` bins either side of its own, so it should sit on a bin.
+    fn measure_phase_noise(
+        &self,
+        carrier_bin: usize,
+        rbw_bins: usize,
+        sample_rate: T,
+    ) -> Vec<(T, T)>;
+
+    // Adds circular complex white Gaussian noise at `snr_db` below the mean power of the whole
+    // vector, measured first. Seed the generator, as with `Xoshiro256PlusPlus::seed_from`, to
+    // reproduce the noise.
+    fn add_awgn(&self, snr_db: T, rng: &mut impl YttriaRng) -> Vec<Complex<T>>;
+}
+
+impl<T> YttriaVectorImpairments<T> for [Complex<T>]
+where
+    T: Float + FromPrimitive + Send + Sync,
+{
+    fn apply_cfo(&self, cfo: T) -> Vec<Complex<T>> {
+        let cfo = to_f64(cfo);
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .map(|(n, x)| {
+                let cycles = (cfo * n as f64).rem_euclid(1.0);
+                x * Complex::from_polar(T::one(), from_f64(2.0 * std::f64::consts::PI * cycles))
+            })
+            .collect()
+    }
+
+    fn apply_phase(&self, phase: T) -> Vec<Complex<T>> {
+        let rotation = Complex::from_polar(T::one(), phase);
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|x| x * rotation)
+            .collect()
+    }
+
+    fn fractional_delay(&self, delay: T) -> Vec<Complex<T>> {
+        let delay = to_f64(delay);
+        assert!(delay >= 0.0, "Delay must not be negative");
+
+        let whole = delay.floor();
+        let frac = delay - whole;
+        let whole = whole as isize;
+
+        if frac == 0.0 {
+            let mut out = vec![Complex::<T>::zero(); self.len()];
+            let shift = (whole as usize).min(self.len());
+            out[shift..].copy_from_slice(&self[..(self.len() - shift)]);
+            return out;
+        }
+
+        let taps = ((-DELAY_HALF_LEN + 1)..=DELAY_HALF_LEN)
+            .map(|k| {
+                let u = k as f64 - frac;
+                let sinc = (std::f64::consts::PI * u).sin() / (std::f64::consts::PI * u);
+                let window = 0.5 * (1.0 + (std::f64::consts::PI * u / DELAY_HALF_LEN as f64).cos());
+                (k, from_f64::<T>(sinc * window))
+            })
+            .collect::<Vec<_>>();
+
+        (0..self.len())
+            .into_par_iter()
+            .map(|n| {
+                let center = n as isize - whole;
+                taps.iter().fold(Complex::zero(), |acc, (k, tap)| {
+                    let idx = center - k;
+                    if idx >= 0 && (idx as usize) < self.len() {
+                        acc + self[idx as usize] * *tap
+                    } else {
+                        acc
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn apply_iq_imbalance(&self, gain_db: T, phase: T) -> Vec<Complex<T>> {
+        let gain = from_f64::<T>(10f64.powf(to_f64(gain_db) / 20.0));
+        let (sin, cos) = phase.sin_cos();
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|x| Complex::new(x.re, gain * (x.im * cos + x.re * sin)))
+            .collect()
+    }
+
+    fn add_phase_noise_inplace(
+        &mut self,
+        psd_dbc_hz: &[(T, T)],
+        sample_rate: T,
+        rng: &mut impl YttriaRng,
+    ) {
+        assert!(!psd_dbc_hz.is_empty(), "Phase noise mask must not be empty");
+        assert!(
+            psd_dbc_hz[0].0 > T::zero() && psd_dbc_hz.windows(2).all(|w| w[0].0 < w[1].0),
+            "Phase noise mask offsets must be positive and increasing"
+        );
+        let n = self.len();
+        if n < 2 {
+            return;
+        }
+
+        let fs = to_f64(sample_rate);
+        let log_offsets = psd_dbc_hz
+            .iter()
+            .map(|(f, _)| to_f64(*f).log10())
+            .collect::<Vec<_>>();
+        let levels = psd_dbc_hz
+            .iter()
+            .map(|(_, l)| to_f64(*l))
+            .collect::<Vec<_>>();
+        let log_freqs = (1..n)
+            .map(|k| (k.min(n - k) as f64 * fs / n as f64).log10())
+            .collect::<Vec<_>>();
+        let levels = log_freqs.interp(&log_offsets, &levels);
+
+        // White noise of unit variance has a density of `1 / fs` on both sides, so scaling bin
+        // `k` by `sqrt(L(f) * fs)` gives the phase a two sided density of `L(|f|)`. The shaping
+        // is symmetric in frequency, which keeps the phase real, and DC is left out.
+        let mut phase = (0..n)
+            .map(|_| Complex::new(rng.next_gaussian(), 0.0))
+            .collect::<Vec<_>>();
+        let mut context = FftContext::new();
+        context.forward(&mut phase);
+        phase[0] = Complex::zero();
+        for (bin, level) in phase[1..].iter_mut().zip(&levels) {
+            *bin *= (10f64.powf(level / 10.0) * fs).sqrt() / n as f64;
+        }
+        context.inverse(&mut phase);
+
+        self.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .zip(phase)
+            .for_each(|(x, phase)| *x = *x * Complex::from_polar(T::one(), from_f64(phase.re)));
+    }
+
+    fn measure_phase_noise(
+        &self,
+        carrier_bin: usize,
+        rbw_bins: usize,
+        sample_rate: T,
+    ) -> Vec<(T, T)> {
+        let n = self.len();
+        assert!(
+            carrier_bin < n,
+            "Carrier bin {carrier_bin} is outside the {n} bin spectrum"
+        );
+        assert!(
+            rbw_bins > 0,
+            "Resolution bandwidth must span at least one bin"
+        );
+
+        let power = |offset: isize| {
+            let bin = (carrier_bin as isize + offset).rem_euclid(n as isize) as usize;
+            to_f64(self[bin].norm_sqr())
+        };
+        let half = (rbw_bins / 2) as isize;
+        let carrier = (-half..=half).map(power).sum::<f64>();
+        let bin_hz = to_f64(sample_rate) / n as f64;
+
+        // Offsets stop short of where the two sidebands would meet.
+        let max_offset = (n - 1) / 2;
+        (0..)
+            .map(|g| half as usize + 1 + g * rbw_bins)
+            .take_while(|start| start + rbw_bins - 1 <= max_offset)
+            .map(|start| {
+                let noise = (start..start + rbw_bins)
+                    .map(|k| power(k as isize) + power(-(k as isize)))
+                    .sum::<f64>()
+                    / (2 * rbw_bins) as f64;
+                let offset = (start as f64 + (rbw_bins - 1) as f64 / 2.0) * bin_hz;
+                (
+                    from_f64(offset),
+                    from_f64(10.0 * (noise / carrier / bin_hz).log10()),
+                )
+            })
+            .collect()
+    }
+
+    fn add_awgn(&self, snr_db: T, rng: &mut impl YttriaRng) -> Vec<Complex<T>> {
+        let power = self.iter().map(|x| to_f64(x.norm_sqr())).sum::<f64>() / self.len() as f64;
+        let sigma = (power / 10f64.powf(to_f64(snr_db) / 10.0) / 2.0).sqrt();
+
+        self.iter()
+            .map(|x| {
+                let noise = Complex::new(
+                    from_f64::<T>(sigma * rng.next_gaussian()),
+                    from_f64::<T>(sigma * rng.next_gaussian()),
+                );
+                x + noise
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Xoshiro256PlusPlus;
+
+    fn tone(len: usize, freq: f64) -> Vec<Complex<f64>> {
+        (0..len)
+            .map(|n| Complex::from_polar(1.0, 2.0 * std::f64::consts::PI * freq * n as f64))
+            .collect()
+    }
+
+    #[test]
+    fn test_cfo_and_phase() {
+        let x = tone(100, 0.01);
+        let shifted = x.apply_cfo(0.02).apply_phase(0.5);
+        let expected = tone(100, 0.03).apply_phase(0.5);
+        for (a, b) in shifted.iter().zip(&expected) {
+            assert!((a - b).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_fractional_delay() {
+        let x = tone(400, 0.05);
+        let whole = x.fractional_delay(3.0);
+        assert_eq!(whole[3..], x[..397]);
+        assert!(whole[..3].iter().all(|x| x.is_zero()));
+
+        // Away from the edges a delayed tone is the tone with a phase lag.
+        let delayed = x.fractional_delay(2.7);
+        let lag = Complex::from_polar(1.0, -2.0 * std::f64::consts::PI * 0.05 * 2.7);
+        for n in 50..350 {
+            assert!((delayed[n] - x[n] * lag).norm() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_iq_imbalance() {
+        let x = [Complex::new(1.0, 0.0), Complex::new(0.0, 1.0)];
+        let y = x.apply_iq_imbalance(20.0 * 2f64.log10(), std::f64::consts::FRAC_PI_2);
+        assert!((y[0] - Complex::new(1.0, 2.0)).norm() < 1e-12);
+        assert!((y[1] - Complex::new(0.0, 0.0)).norm() < 1e-12);
+    }
+
+    // Bin centred tone at 1 MHz with phase noise from `mask`, and its spectrum.
+    fn noisy_carrier(mask: &[(f64, f64)], seed: u64) -> (Vec<Complex<f64>>, Vec<Complex<f64>>) {
+        use crate::vector::YttriaVectorComplexFft;
+
+        let clean = tone(1 << 16, 1000.0 / (1 << 16) as f64);
+        let mut noisy = clean.clone();
+        noisy.add_phase_noise_inplace(mask, 1e6, &mut Xoshiro256PlusPlus::seed_from(seed));
+        let spectrum = noisy.fft();
+        (noisy, spectrum)
+    }
+
+    #[test]
+    fn test_phase_noise_flat_mask() {
+        let mask = [(1.0, -80.0), (1e6, -80.0)];
+        let (_, spectrum) = noisy_carrier(&mask, 7);
+        let profile = spectrum.measure_phase_noise(1000, 64, 1e6);
+        assert_eq!(profile.len(), (((1 << 16) - 1) / 2 - 32) / 64);
+        assert!(profile[0].0 > 0.0 && profile.last().unwrap().0 < 5e5);
+        for (offset, level) in profile {
+            assert!((level + 80.0).abs() < 2.0, "{offset} Hz: {level} dBc/Hz");
+        }
+    }
+
+    #[test]
+    fn test_phase_noise_integrated_rms() {
+        // A falling mask, where the integral is taken over the same bins the synthesis uses.
+        let mask = [(1e3, -80.0), (1e5, -90.0), (5e5, -100.0)];
+        let n = 1 << 16;
+        let expected = (1..n)
+            .map(|k: usize| {
+                let f = (k.min(n - k) as f64 * 1e6 / n as f64).log10();
+                let level = [f].interp(&[3.0, 5.0, 5e5f64.log10()], &[-80.0, -90.0, -100.0])[0];
+                10f64.powf(level / 10.0) * 1e6 / n as f64
+            })
+            .sum::<f64>()
+            .sqrt();
+
+        let clean = tone(n, 1000.0 / n as f64);
+        let (noisy, _) = noisy_carrier(&mask, 11);
+        let rms = (noisy
+            .iter()
+            .zip(&clean)
+            .map(|(y, x)| (y / x).arg().powi(2))
+            .sum::<f64>()
+            / n as f64)
+            .sqrt();
+        assert!((rms / expected - 1.0).abs() < 0.1, "{rms} {expected}");
+    }
+
+    #[test]
+    fn test_clean_tone_phase_noise() {
+        use crate::vector::YttriaVectorComplexFft;
+
+        let spectrum = tone(1 << 16, 1000.0 / (1 << 16) as f64).fft();
+        let profile = spectrum.measure_phase_noise(1000, 64, 1e6);
+        assert!(profile.iter().all(|(_, level)| *level < -200.0));
+    }
+
+    #[test]
+    fn test_phase_noise_seeded() {
+        let mask = [(1e3, -70.0), (1e5, -100.0)];
+        assert_eq!(noisy_carrier(&mask, 3).0, noisy_carrier(&mask, 3).0);
+        assert_ne!(noisy_carrier(&mask, 3).0, noisy_carrier(&mask, 4).0);
+    }
+
+    #[test]
+    fn test_awgn_snr() {
+        let x = tone(200_000, 0.1);
+        for snr_db in [-5.0, 10.0, 30.0] {
+            let noisy = x.add_awgn(snr_db, &mut Xoshiro256PlusPlus::seed_from(1));
+            let noise = noisy
+                .iter()
+                .zip(&x)
+                .map(|(y, x)| (y - x).norm_sqr())
+                .sum::<f64>()
+                / x.len() as f64;
+            assert!((-10.0 * noise.log10() - snr_db).abs() < 0.05);
+        }
+
+        // The same seed gives the same noise, at any signal level.
+        let scaled = x.iter().map(|x| x * 4.0).collect::<Vec<_>>();
+        let a = x.add_awgn(10.0, &mut Xoshiro256PlusPlus::seed_from(2));
+        let b = scaled.add_awgn(10.0, &mut Xoshiro256PlusPlus::seed_from(2));
+        for (a, b) in a.iter().zip(&b) {
+            assert!((a * 4.0 - b).norm() < 1e-12);
+        }
+    }
+}
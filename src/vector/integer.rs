@@ -0,0 +1,272 @@
+use num::PrimInt;
+use rayon::prelude::*;
+
+use super::assert_output_len;
+use crate::config::parallel_min_len;
+
+// Overflow lands on the bound matching the sign the exact result would have had.
+fn saturate<T: PrimInt>(checked: Option<T>, negative: bool) -> T {
+    checked.unwrap_or(if negative {
+        T::min_value()
+    } else {
+        T::max_value()
+    })
+}
+
+fn add<T: PrimInt>(a: T, b: T) -> T {
+    saturate(a.checked_add(&b), b < T::zero())
+}
+
+fn subtract<T: PrimInt>(a: T, b: T) -> T {
+    saturate(a.checked_sub(&b), b > T::zero())
+}
+
+fn multiply<T: PrimInt>(a: T, b: T) -> T {
+    saturate(a.checked_mul(&b), (a < T::zero()) != (b < T::zero()))
+}
+
+// Fixed-point arithmetic that clamps to the range of the integer type on overflow, rather than
+// wrapping in release builds and panicking in debug ones, so a full scale sample stays at full
+// scale instead of flipping sign. The layout follows `YttriaVectorArithmetic`.
+pub trait YttriaVectorInteger<T> {
+    fn saturating_add_into(&self, other: &[T], out: &mut [T]);
+    fn saturating_add(&self, other: &[T]) -> Vec<T>;
+    fn saturating_add_inplace(&mut self, other: &[T]) -> &mut Self;
+
+    fn saturating_add_const_into(&self, addend: T, out: &mut [T]);
+    fn saturating_add_const(&self, addend: T) -> Vec<T>;
+    fn saturating_add_const_inplace(&mut self, addend: T) -> &mut Self;
+
+    fn saturating_subtract_into(&self, other: &[T], out: &mut [T]);
+    fn saturating_subtract(&self, other: &[T]) -> Vec<T>;
+    fn saturating_subtract_inplace(&mut self, other: &[T]) -> &mut Self;
+
+    fn saturating_subtract_const_into(&self, subtrahend: T, out: &mut [T]);
+    fn saturating_subtract_const(&self, subtrahend: T) -> Vec<T>;
+    fn saturating_subtract_const_inplace(&mut self, subtrahend: T) -> &mut Self;
+
+    fn saturating_multiply_into(&self, other: &[T], out: &mut [T]);
+    fn saturating_multiply(&self, other: &[T]) -> Vec<T>;
+    fn saturating_multiply_inplace(&mut self, other: &[T]) -> &mut Self;
+
+    fn saturating_multiply_const_into(&self, multiplier: T, out: &mut [T]);
+    fn saturating_multiply_const(&self, multiplier: T) -> Vec<T>;
+    fn saturating_multiply_const_inplace(&mut self, multiplier: T) -> &mut Self;
+}
+
+fn zip_into<T, F>(name: &str, own: &[T], other: &[T], out: &mut [T], op: F)
+where
+    T: PrimInt + Send + Sync,
+    F: Fn(T, T) -> T + Send + Sync,
+{
+    assert_output_len(name, own.len(), out.len());
+    out.par_iter_mut()
+        .with_min_len(parallel_min_len())
+        .zip(own)
+        .zip(other)
+        .for_each(|((out, own), other)| {
+            *out = op(*own, *other);
+        });
+}
+
+fn zip_inplace<T, F>(own: &mut [T], other: &[T], op: F)
+where
+    T: PrimInt + Send + Sync,
+    F: Fn(T, T) -> T + Send + Sync,
+{
+    own.par_iter_mut()
+        .with_min_len(parallel_min_len())
+        .zip(other)
+        .for_each(|(own, other)| {
+            *own = op(*own, *other);
+        });
+}
+
+fn const_into<T, F>(name: &str, own: &[T], constant: T, out: &mut [T], op: F)
+where
+    T: PrimInt + Send + Sync,
+    F: Fn(T, T) -> T + Send + Sync,
+{
+    assert_output_len(name, own.len(), out.len());
+    out.par_iter_mut()
+        .with_min_len(parallel_min_len())
+        .zip(own)
+        .for_each(|(out, own)| {
+            *out = op(*own, constant);
+        });
+}
+
+fn const_inplace<T, F>(own: &mut [T], constant: T, op: F)
+where
+    T: PrimInt + Send + Sync,
+    F: Fn(T, T) -> T + Send + Sync,
+{
+    own.par_iter_mut()
+        .with_min_len(parallel_min_len())
+        .for_each(|own| {
+            *own = op(*own, constant);
+        });
+}
+
+impl<T> YttriaVectorInteger<T> for [T]
+where
+    T: PrimInt + Send + Sync,
+{
+    fn saturating_add_into(&self, other: &[T], out: &mut [T]) {
+        zip_into("saturating_add_into", self, other, out, add);
+    }
+    fn saturating_add(&self, other: &[T]) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.saturating_add_into(other, out.as_mut_slice());
+        out
+    }
+    fn saturating_add_inplace(&mut self, other: &[T]) -> &mut Self {
+        zip_inplace(self, other, add);
+        self
+    }
+
+    fn saturating_add_const_into(&self, addend: T, out: &mut [T]) {
+        const_into("saturating_add_const_into", self, addend, out, add);
+    }
+    fn saturating_add_const(&self, addend: T) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.saturating_add_const_into(addend, out.as_mut_slice());
+        out
+    }
+    fn saturating_add_const_inplace(&mut self, addend: T) -> &mut Self {
+        const_inplace(self, addend, add);
+        self
+    }
+
+    fn saturating_subtract_into(&self, other: &[T], out: &mut [T]) {
+        zip_into("saturating_subtract_into", self, other, out, subtract);
+    }
+    fn saturating_subtract(&self, other: &[T]) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.saturating_subtract_into(other, out.as_mut_slice());
+        out
+    }
+    fn saturating_subtract_inplace(&mut self, other: &[T]) -> &mut Self {
+        zip_inplace(self, other, subtract);
+        self
+    }
+
+    fn saturating_subtract_const_into(&self, subtrahend: T, out: &mut [T]) {
+        const_into(
+            "saturating_subtract_const_into",
+            self,
+            subtrahend,
+            out,
+            subtract,
+        );
+    }
+    fn saturating_subtract_const(&self, subtrahend: T) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.saturating_subtract_const_into(subtrahend, out.as_mut_slice());
+        out
+    }
+    fn saturating_subtract_const_inplace(&mut self, subtrahend: T) -> &mut Self {
+        const_inplace(self, subtrahend, subtract);
+        self
+    }
+
+    fn saturating_multiply_into(&self, other: &[T], out: &mut [T]) {
+        zip_into("saturating_multiply_into", self, other, out, multiply);
+    }
+    fn saturating_multiply(&self, other: &[T]) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.saturating_multiply_into(other, out.as_mut_slice());
+        out
+    }
+    fn saturating_multiply_inplace(&mut self, other: &[T]) -> &mut Self {
+        zip_inplace(self, other, multiply);
+        self
+    }
+
+    fn saturating_multiply_const_into(&self, multiplier: T, out: &mut [T]) {
+        const_into(
+            "saturating_multiply_const_into",
+            self,
+            multiplier,
+            out,
+            multiply,
+        );
+    }
+    fn saturating_multiply_const(&self, multiplier: T) -> Vec<T> {
+        let mut out = vec![T::zero(); self.len()];
+        self.saturating_multiply_const_into(multiplier, out.as_mut_slice());
+        out
+    }
+    fn saturating_multiply_const_inplace(&mut self, multiplier: T) -> &mut Self {
+        const_inplace(self, multiplier, multiply);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: i16 = i16::MAX;
+    const MIN: i16 = i16::MIN;
+
+    #[test]
+    fn test_add_subtract_i16() {
+        let a = [MAX - 1, MAX, MIN + 1, MIN, 100, 0];
+        let b = [1, 1, -1, -1, MAX, MIN];
+        assert_eq!(a.saturating_add(&b), [MAX, MAX, MIN, MIN, MAX, MIN]);
+        assert_eq!(
+            a.saturating_subtract(&b),
+            [MAX - 2, MAX - 1, MIN + 2, MIN + 1, -32667, MAX]
+        );
+
+        // Exactly reaching the bound is not an overflow.
+        assert_eq!([MAX - 5, MIN + 5].saturating_add_const(5), [MAX, MIN + 10]);
+        assert_eq!([0, -1, 1].saturating_subtract_const(MIN), [MAX, MAX, MAX]);
+        assert_eq!([0, 1].saturating_subtract_const(MAX), [-MAX, -MAX + 1]);
+
+        let mut inplace = a;
+        inplace
+            .saturating_add_inplace(&b)
+            .saturating_subtract_const_inplace(-1);
+        assert_eq!(inplace, [MAX, MAX, MIN + 1, MIN + 1, MAX, MIN + 1]);
+    }
+
+    #[test]
+    fn test_multiply_i16() {
+        let a = [256, 256, -256, -256, MIN, MIN, 181, -1];
+        let b = [128, -128, 128, -128, -1, 1, 181, MIN];
+        assert_eq!(
+            a.saturating_multiply(&b),
+            [MAX, MIN, MIN, MAX, MAX, MIN, 32761, MAX]
+        );
+
+        // A gain of two on a full scale signal clips instead of wrapping.
+        let signal = [MAX, MAX / 2 + 1, 0, MIN / 2, MIN / 2 - 1, MIN];
+        let mut out = [0; 6];
+        signal.saturating_multiply_const_into(2, &mut out);
+        assert_eq!(out, [MAX, MAX, 0, MIN, MIN, MIN]);
+        assert_eq!(
+            signal.saturating_multiply_const(-1),
+            [-MAX, -MAX / 2 - 1, 0, 16384, 16385, MAX]
+        );
+
+        let mut inplace = signal;
+        inplace.saturating_multiply_inplace(&[0, 1, 1, 3, -2, 2]);
+        assert_eq!(inplace, [0, MAX / 2 + 1, 0, MIN, MAX, MIN]);
+    }
+
+    #[test]
+    fn test_unsigned() {
+        let a = [250u8, 5, 16];
+        assert_eq!(a.saturating_add(&[10, 10, 10]), [255, 15, 26]);
+        assert_eq!(a.saturating_subtract(&[10, 10, 10]), [240, 0, 6]);
+        assert_eq!(a.saturating_multiply_const(16), [255, 80, 255]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_output_len() {
+        [1i16, 2].saturating_add_into(&[1, 2], &mut [0; 3]);
+    }
+}
@@ -1,17 +1,72 @@
 mod arithmetic;
-pub use arithmetic::YttriaVectorArithmetic;
+pub use arithmetic::{convolve_output_len, YttriaVectorArithmetic};
+
+mod batch;
+pub use batch::{YttriaVectorBatch, YttriaVectorBatchMut};
 
 mod bits;
-pub use bits::YttriaVectorBitwise;
+pub use bits::{
+    bits_from_run_lengths_quantized, from_run_lengths, unpack_radix_u128, LineCodeError,
+    ManchesterConvention, OverflowError, QuantizedRun, TailPadding, YttriaVectorBitwise,
+    YttriaVectorBoolBits,
+};
+
+mod bytes;
+pub use bytes::{
+    complex_from_interleaved, complex_from_interleaved_bytes, complex_from_interleaved_mut,
+    from_bytes, Endianness, PlainSample, YttriaVectorBytes,
+};
+
+mod cepstrum;
+pub use cepstrum::YttriaVectorCepstrum;
+
+mod compare;
+pub use compare::{select_where, YttriaVectorCompare};
 
 mod complex;
-pub use complex::YttriaVectorComplex;
+pub use complex::{from_polar, ComplexInterp, YttriaVectorComplex};
 
 mod fft;
-pub use fft::YttriaVectorComplexFft;
+pub use fft::{FftContext, YttriaVectorComplexFft, YttriaVectorRealFft};
+
+mod float;
+pub use float::YttriaVectorFloat;
+
+#[cfg(feature = "half")]
+mod half_float;
+#[cfg(feature = "half")]
+pub use half_float::{narrow_from_f32, HalfSample, YttriaVectorHalf};
+
+mod gain;
+pub use gain::{GainCurveError, YttriaVectorGainCurve};
+
+mod impairments;
+pub use impairments::YttriaVectorImpairments;
+
+mod integer;
+pub use integer::YttriaVectorInteger;
+
+mod regrid;
+pub(crate) use regrid::Interpolant;
+pub use regrid::{InterpKind, OutOfRange, RegridError, YttriaVectorRegrid};
+
+mod scrambler;
+pub use scrambler::Scrambler;
+
+mod shape;
+pub(crate) use shape::assert_output_len;
+
+mod spectrum;
+pub use spectrum::{
+    db_power_sum, log_bin_edges, NoiseFloorConfig, NoiseFloorMethod, RebinError, RebinMode,
+    YttriaVectorSpectrum,
+};
 
 mod statistics;
-pub use statistics::YttriaVectorStatistics;
+pub use statistics::{FitQuality, PowerCcdf, YttriaVectorStatistics};
+
+mod transpose;
+pub use transpose::{TransposeError, YttriaVectorTranspose};
 
 mod utils;
-pub use utils::YttriaVectorUtils;
+pub use utils::{merge_axes, AxisError, MonotonicPolicy, YttriaVectorUtils};
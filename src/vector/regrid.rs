@@ -0,0 +1,442 @@
+use num::{Complex, Float};
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::config::parallel_min_len;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpKind {
+    Linear,
+    // The sample closest in time, the earlier one on a tie.
+    Nearest,
+    // Natural cubic spline through every sample, falling back to linear for two samples.
+    CubicSpline,
+}
+
+// What to produce for grid points before the first or after the last sample time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutOfRange<S> {
+    HoldEdges,
+    Fill(S),
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegridError {
+    Empty,
+    LengthMismatch { values: usize, times: usize },
+    // `times[index]` is not strictly greater than the time before it.
+    Unsorted { index: usize },
+    // Grid point `index` lies outside the sampled time range.
+    OutOfRange { index: usize },
+}
+
+impl fmt::Display for RegridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "cannot regrid an empty series"),
+            Self::LengthMismatch { values, times } => {
+                write!(f, "{values} values but {times} sample times")
+            }
+            Self::Unsorted { index } => {
+                write!(f, "sample time {index} is not after the one before it")
+            }
+            Self::OutOfRange { index } => {
+                write!(f, "grid point {index} lies outside the sampled times")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegridError {}
+
+pub trait YttriaVectorRegrid<T, S> {
+    // Interpolates the series `self` sampled at `times` onto the `len` point grid
+    // `start + i * step`. `times` must be strictly increasing.
+    fn regrid_uniform(
+        &self,
+        times: &[T],
+        start: T,
+        step: T,
+        len: usize,
+        kind: InterpKind,
+        out_of_range: OutOfRange<S>,
+    ) -> Result<Vec<S>, RegridError>;
+}
+
+// Second derivatives of the natural cubic spline through `(times, values)`.
+fn spline_curvature<T: Float>(times: &[T], values: &[T]) -> Vec<T> {
+    let n = times.len();
+    let two = T::one() + T::one();
+    let six = two * (two + T::one());
+
+    // Thomas algorithm on the tridiagonal system for the interior knots.
+    let mut diag = vec![T::zero(); n];
+    let mut rhs = vec![T::zero(); n];
+    for i in 1..(n - 1) {
+        let h0 = times[i] - times[i - 1];
+        let h1 = times[i + 1] - times[i];
+        diag[i] = two * (h0 + h1);
+        rhs[i] = six * ((values[i + 1] - values[i]) / h1 - (values[i] - values[i - 1]) / h0);
+        if i > 1 {
+            let w = h0 / diag[i - 1];
+            diag[i] = diag[i] - w * h0;
+            rhs[i] = rhs[i] - w * rhs[i - 1];
+        }
+    }
+
+    let mut curvature = vec![T::zero(); n];
+    for i in (1..(n - 1)).rev() {
+        let h1 = times[i + 1] - times[i];
+        curvature[i] = (rhs[i] - h1 * curvature[i + 1]) / diag[i];
+    }
+    curvature
+}
+
+// Evaluates the interpolation of `(times, values)` at arbitrary points, holding the edge values
+// outside `times`. `times` must be non-empty and strictly increasing.
+pub(crate) struct Interpolant<'a, T> {
+    times: &'a [T],
+    values: &'a [T],
+    kind: InterpKind,
+    curvature: Vec<T>,
+}
+
+impl<'a, T: Float> Interpolant<'a, T> {
+    pub(crate) fn new(times: &'a [T], values: &'a [T], kind: InterpKind) -> Self {
+        let kind = match kind {
+            InterpKind::CubicSpline if times.len() < 3 => InterpKind::Linear,
+            kind => kind,
+        };
+        let curvature = match kind {
+            InterpKind::CubicSpline => spline_curvature(times, values),
+            _ => Vec::new(),
+        };
+        Self {
+            times,
+            values,
+            kind,
+            curvature,
+        }
+    }
+
+    pub(crate) fn at(&self, t: T) -> T {
+        let (times, values) = (self.times, self.values);
+        if t < times[0] || times.len() == 1 {
+            return values[0];
+        }
+        if t > times[times.len() - 1] {
+            return values[values.len() - 1];
+        }
+
+        // `times[bin] <= t <= times[bin + 1]`
+        let bin = times.partition_point(|&x| x <= t).clamp(1, times.len() - 1) - 1;
+        let (t0, t1) = (times[bin], times[bin + 1]);
+        let h = t1 - t0;
+        let a = (t1 - t) / h;
+        let b = (t - t0) / h;
+        match self.kind {
+            InterpKind::Nearest => {
+                if b > T::from(0.5).unwrap() {
+                    values[bin + 1]
+                } else {
+                    values[bin]
+                }
+            }
+            // Written so that a flat segment reproduces its value exactly.
+            InterpKind::Linear => values[bin] + b * (values[bin + 1] - values[bin]),
+            InterpKind::CubicSpline => {
+                values[bin]
+                    + b * (values[bin + 1] - values[bin])
+                    + ((a * a * a - a) * self.curvature[bin]
+                        + (b * b * b - b) * self.curvature[bin + 1])
+                        * h
+                        * h
+                        / T::from(6.0).unwrap()
+            }
+        }
+    }
+}
+
+fn regrid<T: Float + Send + Sync>(
+    values: &[T],
+    times: &[T],
+    grid: impl Fn(usize) -> T + Sync,
+    len: usize,
+    kind: InterpKind,
+    out_of_range: OutOfRange<T>,
+) -> Result<Vec<T>, RegridError> {
+    if values.len() != times.len() {
+        return Err(RegridError::LengthMismatch {
+            values: values.len(),
+            times: times.len(),
+        });
+    }
+    if values.is_empty() {
+        return Err(RegridError::Empty);
+    }
+    if let Some(index) =
+        (1..times.len()).find(|&i| times[i].partial_cmp(&times[i - 1]) != Some(Ordering::Greater))
+    {
+        return Err(RegridError::Unsorted { index });
+    }
+
+    let (first, last) = (times[0], times[times.len() - 1]);
+    if out_of_range == OutOfRange::Error {
+        if let Some(index) = (0..len).find(|&i| !(grid(i) >= first && grid(i) <= last)) {
+            return Err(RegridError::OutOfRange { index });
+        }
+    }
+
+    let interpolant = Interpolant::new(times, values, kind);
+    let mut out = vec![T::zero(); len];
+    out.par_iter_mut()
+        .with_min_len(parallel_min_len())
+        .enumerate()
+        .for_each(|(i, out)| {
+            let t = grid(i);
+            *out = match out_of_range {
+                OutOfRange::Fill(fill) if t < first || t > last => fill,
+                _ => interpolant.at(t),
+            };
+        });
+    Ok(out)
+}
+
+impl<T> YttriaVectorRegrid<T, T> for [T]
+where
+    T: Float + Send + Sync,
+{
+    fn regrid_uniform(
+        &self,
+        times: &[T],
+        start: T,
+        step: T,
+        len: usize,
+        kind: InterpKind,
+        out_of_range: OutOfRange<T>,
+    ) -> Result<Vec<T>, RegridError> {
+        let grid = |i: usize| start + step * T::from(i).expect("Could not convert usize to type");
+        regrid(self, times, grid, len, kind, out_of_range)
+    }
+}
+
+// Real and imaginary parts are interpolated independently.
+impl<T> YttriaVectorRegrid<T, Complex<T>> for [Complex<T>]
+where
+    T: Float + Send + Sync,
+{
+    fn regrid_uniform(
+        &self,
+        times: &[T],
+        start: T,
+        step: T,
+        len: usize,
+        kind: InterpKind,
+        out_of_range: OutOfRange<Complex<T>>,
+    ) -> Result<Vec<Complex<T>>, RegridError> {
+        let (re_policy, im_policy) = match out_of_range {
+            OutOfRange::HoldEdges => (OutOfRange::HoldEdges, OutOfRange::HoldEdges),
+            OutOfRange::Fill(fill) => (OutOfRange::Fill(fill.re), OutOfRange::Fill(fill.im)),
+            OutOfRange::Error => (OutOfRange::Error, OutOfRange::Error),
+        };
+        let re = self.iter().map(|x| x.re).collect::<Vec<_>>();
+        let im = self.iter().map(|x| x.im).collect::<Vec<_>>();
+
+        let re = re.regrid_uniform(times, start, step, len, kind, re_policy)?;
+        let im = im.regrid_uniform(times, start, step, len, kind, im_policy)?;
+        Ok(re
+            .into_iter()
+            .zip(im)
+            .map(|(re, im)| Complex::new(re, im))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{gaussian_noise, Xoshiro256PlusPlus, YttriaRng};
+    use crate::vector::YttriaVectorRealFft;
+    use crate::windows::hann;
+    use std::f64::consts::PI;
+
+    const KINDS: [InterpKind; 3] = [
+        InterpKind::Linear,
+        InterpKind::Nearest,
+        InterpKind::CubicSpline,
+    ];
+
+    #[test]
+    fn test_uniform_identity() {
+        let values = gaussian_noise(64, 1);
+        let times = (0..64).map(|i| 2.0 + 0.5 * i as f64).collect::<Vec<_>>();
+
+        for kind in KINDS {
+            let out = values
+                .regrid_uniform(&times, 2.0, 0.5, 64, kind, OutOfRange::Error)
+                .unwrap();
+            for (a, b) in out.iter().zip(&values) {
+                assert!((a - b).abs() < 1e-12, "{kind:?}");
+            }
+        }
+
+        let iq = values
+            .iter()
+            .zip(values.iter().rev())
+            .map(|(re, im)| Complex::new(*re, *im))
+            .collect::<Vec<_>>();
+        let out = iq
+            .regrid_uniform(
+                &times,
+                2.0,
+                0.5,
+                64,
+                InterpKind::CubicSpline,
+                OutOfRange::Error,
+            )
+            .unwrap();
+        for (a, b) in out.iter().zip(&iq) {
+            assert!((a - b).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_jittered_sine() {
+        let n = 1024;
+        let freq = 50.0 / n as f64;
+        let mut rng = Xoshiro256PlusPlus::seed_from(2);
+        let times = (0..(n + 8))
+            .map(|i| i as f64 - 4.0 + 0.4 * (rng.next_f64() - 0.5))
+            .collect::<Vec<_>>();
+        let values = times
+            .iter()
+            .map(|t| (2.0 * PI * freq * t).sin())
+            .collect::<Vec<_>>();
+        let window = hann::<f64>(n);
+
+        // Level of the strongest bin away from the tone, relative to the tone.
+        let floor = |kind| {
+            let out = values
+                .regrid_uniform(&times, 0.0, 1.0, n, kind, OutOfRange::Error)
+                .unwrap();
+            let windowed = out
+                .iter()
+                .zip(&window)
+                .map(|(x, w)| x * w)
+                .collect::<Vec<_>>();
+            let spectrum = windowed.rfft().iter().map(|x| x.norm()).collect::<Vec<_>>();
+            let peak = (0..spectrum.len())
+                .max_by(|&a, &b| spectrum[a].partial_cmp(&spectrum[b]).unwrap())
+                .unwrap();
+            assert_eq!(peak, 50, "{kind:?}");
+            let spur = spectrum
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| k.abs_diff(peak) > 4)
+                .fold(0.0f64, |acc, (_, x)| acc.max(*x));
+            20.0 * (spur / spectrum[peak]).log10()
+        };
+
+        let nearest = floor(InterpKind::Nearest);
+        let linear = floor(InterpKind::Linear);
+        let cubic = floor(InterpKind::CubicSpline);
+        assert!(nearest < -30.0, "{nearest}");
+        assert!(linear < -50.0, "{linear}");
+        assert!(cubic < -80.0, "{cubic}");
+        assert!(cubic < linear && linear < nearest);
+    }
+
+    #[test]
+    fn test_out_of_range_policies() {
+        let times = [1.0, 2.0, 4.0];
+        let values = [10.0, 20.0, 40.0];
+
+        let held = values
+            .regrid_uniform(
+                &times,
+                0.0,
+                1.0,
+                6,
+                InterpKind::Linear,
+                OutOfRange::HoldEdges,
+            )
+            .unwrap();
+        assert_eq!(held, [10.0, 10.0, 20.0, 30.0, 40.0, 40.0]);
+
+        let filled = values
+            .regrid_uniform(
+                &times,
+                0.0,
+                1.0,
+                6,
+                InterpKind::Nearest,
+                OutOfRange::Fill(-1.0),
+            )
+            .unwrap();
+        assert_eq!(filled, [-1.0, 10.0, 20.0, 20.0, 40.0, -1.0]);
+
+        assert_eq!(
+            values.regrid_uniform(&times, 1.0, 1.0, 5, InterpKind::Linear, OutOfRange::Error),
+            Err(RegridError::OutOfRange { index: 4 })
+        );
+        assert!(values
+            .regrid_uniform(&times, 1.0, 1.0, 4, InterpKind::Linear, OutOfRange::Error)
+            .is_ok());
+
+        let iq = [Complex::new(1.0, -1.0), Complex::new(2.0, -2.0)];
+        let filled = iq
+            .regrid_uniform(
+                &[0.0, 1.0],
+                -1.0,
+                1.0,
+                3,
+                InterpKind::Linear,
+                OutOfRange::Fill(Complex::new(0.0, 5.0)),
+            )
+            .unwrap();
+        assert_eq!(filled, [Complex::new(0.0, 5.0), iq[0], iq[1]]);
+    }
+
+    #[test]
+    fn test_invalid_series() {
+        let values = [1.0, 2.0, 3.0];
+        assert_eq!(
+            values.regrid_uniform(
+                &[0.0, 2.0, 1.0],
+                0.0,
+                1.0,
+                3,
+                InterpKind::Linear,
+                OutOfRange::HoldEdges
+            ),
+            Err(RegridError::Unsorted { index: 2 })
+        );
+        assert_eq!(
+            values.regrid_uniform(
+                &[0.0, 1.0, 1.0],
+                0.0,
+                1.0,
+                3,
+                InterpKind::Linear,
+                OutOfRange::HoldEdges
+            ),
+            Err(RegridError::Unsorted { index: 2 })
+        );
+        assert_eq!(
+            values.regrid_uniform(
+                &[0.0, 1.0],
+                0.0,
+                1.0,
+                3,
+                InterpKind::Linear,
+                OutOfRange::HoldEdges
+            ),
+            Err(RegridError::LengthMismatch {
+                values: 3,
+                times: 2
+            })
+        );
+    }
+}
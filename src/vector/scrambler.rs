@@ -0,0 +1,338 @@
+// Linear feedback shift register scramblers over bits of value 0 or 1, most significant first in
+// the byte oriented methods like `unpackbits`.
+//
+// Polynomials are bit masks where bit `t` stands for the `x^t` term, and `x^t` taps the register
+// stage holding the bit from `t` steps earlier, so `1 + x^14 + x^15` is `0xc001`. The constant
+// term must be present and the degree lies within 1..=31. Register states use the same layout,
+// bit `t - 1` holding the bit from `t` steps earlier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    // The data is XORed with the register's own output, so both ends must start from the same
+    // state and stay aligned.
+    Additive,
+    // The register is fed the scrambled bits, so a descrambler recovers from any starting state
+    // or channel error once `degree` correct bits have passed.
+    Multiplicative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Scramble,
+    Descramble,
+}
+
+// Eight steps of the register are linear in the state and the input byte together, so they are
+// tabulated for each byte of the state and for the input, and combined by XOR.
+#[derive(Debug, Clone)]
+struct ByteTables {
+    state: Vec<[(u8, u32); 256]>,
+    input: [(u8, u32); 256],
+}
+
+#[derive(Debug, Clone)]
+pub struct Scrambler {
+    kind: Kind,
+    taps: u32,
+    mask: u32,
+    state: u32,
+    scramble_tables: ByteTables,
+    descramble_tables: ByteTables,
+}
+
+fn step(kind: Kind, direction: Direction, taps: u32, mask: u32, state: u32, bit: u8) -> (u8, u32) {
+    let feedback = ((state & taps).count_ones() & 1) as u8;
+    let (out, shifted) = match (kind, direction) {
+        (Kind::Additive, _) => (bit ^ feedback, feedback),
+        (Kind::Multiplicative, Direction::Scramble) => (bit ^ feedback, bit ^ feedback),
+        (Kind::Multiplicative, Direction::Descramble) => (bit ^ feedback, bit),
+    };
+    (out, ((state << 1) | shifted as u32) & mask)
+}
+
+fn step_byte(
+    kind: Kind,
+    direction: Direction,
+    taps: u32,
+    mask: u32,
+    mut state: u32,
+    byte: u8,
+) -> (u8, u32) {
+    let mut out = 0;
+    for shift in (0..8).rev() {
+        let bit;
+        (bit, state) = step(kind, direction, taps, mask, state, (byte >> shift) & 1);
+        out |= bit << shift;
+    }
+    (out, state)
+}
+
+impl ByteTables {
+    fn new(kind: Kind, direction: Direction, taps: u32, mask: u32) -> Self {
+        let table = |start: &dyn Fn(u8) -> (u32, u8)| {
+            let mut table = [(0, 0); 256];
+            for (value, entry) in table.iter_mut().enumerate() {
+                let (state, byte) = start(value as u8);
+                *entry = step_byte(kind, direction, taps, mask, state, byte);
+            }
+            table
+        };
+        let state_bytes = (32 - mask.leading_zeros()).div_ceil(8);
+        Self {
+            state: (0..state_bytes)
+                .map(|b| table(&|v| ((v as u32) << (8 * b), 0)))
+                .collect(),
+            input: table(&|v| (0, v)),
+        }
+    }
+
+    fn apply(&self, state: u32, byte: u8) -> (u8, u32) {
+        self.state
+            .iter()
+            .enumerate()
+            .fold(self.input[byte as usize], |(out, next), (b, table)| {
+                let (o, n) = table[((state >> (8 * b)) & 0xff) as usize];
+                (out ^ o, next ^ n)
+            })
+    }
+}
+
+impl Scrambler {
+    fn new(kind: Kind, polynomial: u32, init: u32) -> Self {
+        assert!(polynomial & 1 == 1, "Polynomial needs a constant term");
+        let degree = 31 - polynomial.leading_zeros();
+        assert!(
+            (1..=31).contains(&degree),
+            "Polynomial degree must lie within 1..=31, got {degree}"
+        );
+        let mask = (1u32 << degree) - 1;
+        assert!(
+            init & !mask == 0,
+            "Initial state {init:#x} does not fit in {degree} bits"
+        );
+
+        let taps = polynomial >> 1;
+        Self {
+            kind,
+            taps,
+            mask,
+            state: init,
+            scramble_tables: ByteTables::new(kind, Direction::Scramble, taps, mask),
+            descramble_tables: ByteTables::new(kind, Direction::Descramble, taps, mask),
+        }
+    }
+
+    // XORs the data with the register sequence, which makes descrambling the same operation.
+    pub fn additive(polynomial: u32, init: u32) -> Self {
+        assert!(
+            init != 0,
+            "An additive scrambler needs a nonzero initial state"
+        );
+        Self::new(Kind::Additive, polynomial, init)
+    }
+
+    // Self-synchronizing scrambler dividing the data by the polynomial.
+    pub fn multiplicative(polynomial: u32, init: u32) -> Self {
+        Self::new(Kind::Multiplicative, polynomial, init)
+    }
+
+    // CCSDS TM pseudo-randomizer, written `x^8 + x^7 + x^5 + x^3 + 1` in the standard, which
+    // counts stages the other way round. Its sequence starts `0xff, 0x48, 0x0e, 0xc0`.
+    pub fn ccsds() -> Self {
+        Self::additive(0x12b, 0x58)
+    }
+
+    // DVB energy dispersal, `1 + x^14 + x^15` loaded with `100101010000000`, whose sequence
+    // starts `0x03, 0xf6, 0x08, 0x34`. The register is reloaded every eight transport packets,
+    // which is left to the caller through `reset`.
+    pub fn dvb() -> Self {
+        Self::additive(0xc001, 0xa9)
+    }
+
+    // IEEE 802.11 data scrambler `x^7 + x^4 + 1`. Bit `i - 1` of the nonzero seed loads stage
+    // `x^i`, and an all ones seed gives the standard's 127 bit sequence starting `0x0e, 0xf2`.
+    pub fn ieee80211(seed: u8) -> Self {
+        Self::additive(0x91, seed as u32)
+    }
+
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+
+    pub fn reset(&mut self, state: u32) {
+        assert!(
+            state & !self.mask == 0,
+            "State {state:#x} does not fit in the register"
+        );
+        self.state = state;
+    }
+
+    fn process_bits(&mut self, direction: Direction, bits: &[u8]) -> Vec<u8> {
+        bits.iter()
+            .map(|bit| {
+                let out;
+                (out, self.state) = step(
+                    self.kind,
+                    direction,
+                    self.taps,
+                    self.mask,
+                    self.state,
+                    bit & 1,
+                );
+                out
+            })
+            .collect()
+    }
+
+    fn process_bytes(&mut self, direction: Direction, bytes: &[u8]) -> Vec<u8> {
+        let tables = match direction {
+            Direction::Scramble => &self.scramble_tables,
+            Direction::Descramble => &self.descramble_tables,
+        };
+        bytes
+            .iter()
+            .map(|byte| {
+                let out;
+                (out, self.state) = tables.apply(self.state, *byte);
+                out
+            })
+            .collect()
+    }
+
+    // The register carries over between calls, so a stream can be processed in pieces.
+    pub fn scramble(&mut self, bits: &[u8]) -> Vec<u8> {
+        self.process_bits(Direction::Scramble, bits)
+    }
+
+    pub fn descramble(&mut self, bits: &[u8]) -> Vec<u8> {
+        self.process_bits(Direction::Descramble, bits)
+    }
+
+    // Packed equivalents of `scramble` and `descramble`, eight bits per table lookup.
+    pub fn scramble_bytes(&mut self, bytes: &[u8]) -> Vec<u8> {
+        self.process_bytes(Direction::Scramble, bytes)
+    }
+
+    pub fn descramble_bytes(&mut self, bytes: &[u8]) -> Vec<u8> {
+        self.process_bytes(Direction::Descramble, bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{Xoshiro256PlusPlus, YttriaRng};
+    use crate::vector::YttriaVectorBitwise;
+
+    fn random_bytes(len: usize, seed: u64) -> Vec<u8> {
+        let mut rng = Xoshiro256PlusPlus::seed_from(seed);
+        (0..len).map(|_| rng.next_u64() as u8).collect()
+    }
+
+    fn presets() -> Vec<Scrambler> {
+        vec![
+            Scrambler::ccsds(),
+            Scrambler::dvb(),
+            Scrambler::ieee80211(0x5d),
+            Scrambler::multiplicative(0x21001, 0x1_5a5a),
+            Scrambler::multiplicative(0x8000_0003, 0x7fff_0001),
+        ]
+    }
+
+    #[test]
+    fn test_known_sequences() {
+        let zeros = [0u8; 8];
+        assert_eq!(
+            Scrambler::ccsds().scramble_bytes(&zeros),
+            [0xff, 0x48, 0x0e, 0xc0, 0x9a, 0x0d, 0x70, 0xbc]
+        );
+        assert_eq!(
+            Scrambler::dvb().scramble_bytes(&zeros),
+            [0x03, 0xf6, 0x08, 0x34, 0x30, 0xb8, 0xa3, 0x93]
+        );
+        assert_eq!(
+            Scrambler::ieee80211(0x7f).scramble_bytes(&zeros),
+            [0x0e, 0xf2, 0xc9, 0x02, 0x26, 0x2e, 0xb6, 0x0c]
+        );
+
+        // Maximal length sequences repeat after 2^degree - 1 bits.
+        let mut ccsds = Scrambler::ccsds();
+        let sequence = ccsds.scramble(&[0; 510]);
+        assert_eq!(sequence[..255], sequence[255..]);
+        assert_eq!(ccsds.state(), Scrambler::ccsds().state());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = random_bytes(1000, 1);
+        let bits = data.unpackbits();
+        for preset in presets() {
+            let scrambled = preset.clone().scramble(&bits);
+            assert_ne!(scrambled, bits);
+            assert_eq!(preset.clone().descramble(&scrambled), bits);
+
+            let scrambled_bytes = preset.clone().scramble_bytes(&data);
+            assert_eq!(scrambled_bytes.unpackbits(), scrambled);
+            assert_eq!(preset.clone().descramble_bytes(&scrambled_bytes), data);
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let data = random_bytes(600, 2);
+        let bits = data.unpackbits();
+        for preset in presets() {
+            let one_shot = preset.clone().scramble(&bits);
+
+            let mut streaming = preset.clone();
+            let mut rng = Xoshiro256PlusPlus::seed_from(3);
+            let mut pieces = Vec::new();
+            let mut rest = &bits[..];
+            while !rest.is_empty() {
+                let len = (rng.next_u64() as usize % 40 + 1).min(rest.len());
+                // Mix the bit and byte paths whenever a piece falls on a byte boundary.
+                if len.is_multiple_of(8) {
+                    pieces.extend(
+                        streaming
+                            .scramble_bytes(&rest[..len].packbits())
+                            .unpackbits(),
+                    );
+                } else {
+                    pieces.extend(streaming.scramble(&rest[..len]));
+                }
+                rest = &rest[len..];
+            }
+            assert_eq!(pieces, one_shot);
+        }
+    }
+
+    #[test]
+    fn test_multiplicative_resynchronizes() {
+        let polynomial = 0x21001;
+        let degree = 17;
+        let bits = random_bytes(200, 4).unpackbits();
+        let scrambled = Scrambler::multiplicative(polynomial, 0x1234).scramble(&bits);
+
+        // A descrambler starting from the wrong state, and a burst of channel errors later on.
+        let mut corrupted = scrambled.clone();
+        for bit in corrupted[500..530].iter_mut() {
+            *bit ^= 1;
+        }
+        let descrambled = Scrambler::multiplicative(polynomial, 0x1_ffff).descramble(&corrupted);
+
+        assert_eq!(descrambled[degree..500], bits[degree..500]);
+        assert_ne!(descrambled[500..530], bits[500..530]);
+        assert_eq!(descrambled[530 + degree..], bits[530 + degree..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_additive_state() {
+        Scrambler::additive(0x91, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_state_too_wide() {
+        Scrambler::multiplicative(0x91, 0x80);
+    }
+}
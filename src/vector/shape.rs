@@ -0,0 +1,221 @@
+// Panics unless the output handed to an `_into` method has exactly the length the matching
+// allocating method returns.
+pub(crate) fn assert_output_len(method: &str, expected: usize, actual: usize) {
+    assert!(
+        actual == expected,
+        "{method} needs an output of {expected} elements, got {actual}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use num::Complex;
+
+    use crate::filter::{DecimateConfig, YttriaVectorDecimate, YttriaVectorResample};
+    use crate::modulation::{Sideband, YttriaVectorSsb};
+    use crate::prelude::*;
+    use crate::vector::convolve_output_len;
+    use crate::windows::WindowKind;
+    use crate::{FftContext, TailPadding};
+
+    const SIZES: [usize; 6] = [0, 1, 2, 7, 8, 64];
+
+    fn real(n: usize) -> Vec<f64> {
+        (0..n).map(|i| 1.0 + (i as f64 * 0.7).sin()).collect()
+    }
+
+    fn complex(n: usize) -> Vec<Complex<f64>> {
+        (0..n)
+            .map(|i| Complex::from_polar(1.0 + 0.1 * i as f64, 0.3 * i as f64))
+            .collect()
+    }
+
+    fn bits(n: usize) -> Vec<u8> {
+        (0..n).map(|i| (i % 3 == 0) as u8).collect()
+    }
+
+    #[test]
+    fn test_same_length_methods() {
+        for n in SIZES {
+            let x = real(n);
+            let mask = x.iter().map(|v| *v > 1.0).collect::<Vec<_>>();
+            let xp = real(n.max(1)).cumsum();
+            let lens = [
+                x.add(&x).len(),
+                x.add_const(1.0).len(),
+                x.subtract(&x).len(),
+                x.subtract_const(1.0).len(),
+                x.multiply(&x).len(),
+                x.multiply_const(2.0).len(),
+                x.divide(&x).len(),
+                x.divide_const(2.0).len(),
+                x.clone().powi(3).len(),
+                YttriaVectorArithmetic::sqrt(x.as_slice()).len(),
+                x.diff_circular().len(),
+                x.cumsum().len(),
+                x.cumtrapz(0.5).len(),
+                YttriaVectorArithmetic::clamp(x.as_slice(), 0.5, 1.5).len(),
+                x.clamp_min(0.5).len(),
+                x.clamp_max(1.5).len(),
+                x.interp(&xp, &xp).len(),
+                x.angle_unwrap(None).len(),
+                x.minimum(&x).len(),
+                x.maximum(&x).len(),
+                x.abs_diff(&x).len(),
+                x.greater_than(&x).len(),
+                x.greater_than_const(1.0).len(),
+                x.less_equal(&x).len(),
+                x.less_equal_const(1.0).len(),
+                x.where_mask(&mask, &x).len(),
+                x.where_mask_const(&mask, 0.0).len(),
+                x.shift(2, 0.0).len(),
+                x.roll(3).len(),
+                x.fftshift().len(),
+                x.ifftshift().len(),
+                x.as_type::<f32>().len(),
+                x.apply_window(WindowKind::Hann).len(),
+                x.real_cepstrum().len(),
+                x.complex_cepstrum().0.len(),
+                x.inverse_complex_cepstrum(0).len(),
+                x.minimum_phase().len(),
+                x.ssb_modulate(0.1, Sideband::Upper).len(),
+                x.ssb_demodulate(0.1, Sideband::Lower).len(),
+            ];
+            assert!(lens.iter().all(|len| *len == n), "{n}: {lens:?}");
+            assert_eq!(
+                x.mask_select(&mask).len(),
+                mask.iter().filter(|m| **m).count()
+            );
+
+            let z = complex(n);
+            let lens = [
+                z.conj().len(),
+                z.conj_reverse().len(),
+                z.exp().len(),
+                z.real().len(),
+                z.imag().len(),
+                z.magnitude().len(),
+                z.phase_error_bpsk().len(),
+                z.phase_error_qpsk().len(),
+                z.as_complex_type::<f32>().len(),
+                z.to_polar().0.len(),
+                z.to_polar().1.len(),
+                z.clamp_magnitude(1.0).len(),
+                z.heterodyne(&z).len(),
+                z.soft_clip_magnitude(1.0).len(),
+                z.fft().len(),
+                z.ifft().len(),
+                z.apply_cfo(0.01).len(),
+                z.apply_phase(0.5).len(),
+                z.apply_iq_imbalance(0.5, 0.1).len(),
+                z.fractional_delay(0.25).len(),
+            ];
+            assert!(lens.iter().all(|len| *len == n), "{n}: {lens:?}");
+        }
+    }
+
+    #[test]
+    fn test_advertised_lengths() {
+        for n in SIZES {
+            let x = real(n);
+            assert_eq!(x.diff().len(), x.diff_output_len());
+            for m in SIZES {
+                let y = real(m);
+                assert_eq!(x.convolve(&y).len(), convolve_output_len(n, m));
+                assert_eq!(
+                    complex(n).correlate(&complex(m)).len(),
+                    convolve_output_len(n, m)
+                );
+                assert_eq!(x.concatenate(&y).len(), x.concatenate_output_len(&y));
+            }
+
+            for repeats in [0, 1, 3] {
+                assert_eq!(x.repeat(repeats).len(), x.repeat_output_len(repeats));
+                assert_eq!(x.tile(repeats).len(), x.tile_output_len(repeats));
+            }
+            assert_eq!(x.pad_to_pow2(0.0).len(), x.pad_to_pow2_output_len());
+            assert_eq!(x.zero_pad_center(n + 3).len(), n + 3);
+            for factor in [1, 2, 3, 8] {
+                assert_eq!(x.downsample(factor).len(), x.downsample_output_len(factor));
+                for offset in [0, 1, 5, 70] {
+                    assert_eq!(
+                        x.downsample_offset(factor, offset).len(),
+                        x.downsample_offset_output_len(factor, offset)
+                    );
+                }
+            }
+            for window in [1, 2, 7, 65] {
+                assert_eq!(
+                    x.rolling(window, |w| w.len()).len(),
+                    x.rolling_output_len(window)
+                );
+            }
+
+            assert_eq!(x.rfft().len(), x.rfft_output_len());
+            let z = complex(n);
+            assert_eq!(z.irfft().len(), z.irfft_output_len());
+            assert_eq!(
+                x.to_bytes(crate::Endianness::Big).len(),
+                x.to_bytes_output_len()
+            );
+
+            for (up, down) in [(1, 1), (2, 1), (1, 3), (3, 2)] {
+                assert_eq!(
+                    x.resample_poly(up, down).len(),
+                    x.resample_poly_output_len(up, down)
+                );
+            }
+            for factor in [1, 2, 6] {
+                assert_eq!(
+                    x.decimate_filtered(factor, DecimateConfig::new()).len(),
+                    x.decimate_filtered_output_len(factor)
+                );
+            }
+
+            let b = bits(n);
+            assert_eq!(b.packbits().len(), b.packbits_output_len());
+            assert_eq!(b.unpackbits().len(), b.unpackbits_output_len());
+            for bits_per_symbol in [1, 3, 8] {
+                for padding in [TailPadding::Zeros, TailPadding::Ones, TailPadding::Truncate] {
+                    assert_eq!(
+                        b.bits_to_symbols(bits_per_symbol, padding).len(),
+                        b.bits_to_symbols_output_len(bits_per_symbol, padding)
+                    );
+                }
+                let symbols = b.bits_to_symbols(bits_per_symbol, TailPadding::Zeros);
+                assert_eq!(
+                    symbols.symbols_to_bits(bits_per_symbol).len(),
+                    symbols.symbols_to_bits_output_len(bits_per_symbol)
+                );
+            }
+        }
+    }
+
+    fn panic_message<F: FnOnce()>(f: F) -> String {
+        *std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+            .expect_err("Expected a panic")
+            .downcast::<String>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_into_checks_output_len() {
+        let x = real(8);
+        let z = complex(5);
+
+        let message = panic_message(|| x.add_into(&x, &mut [0.0; 7]));
+        assert_eq!(message, "add_into needs an output of 8 elements, got 7");
+        let message = panic_message(|| x.diff_into(&mut [0.0; 8]));
+        assert_eq!(message, "diff_into needs an output of 7 elements, got 8");
+        let message = panic_message(|| x.convolve_into(&x, &mut [0.0; 8]));
+        assert_eq!(
+            message,
+            "convolve_into needs an output of 15 elements, got 8"
+        );
+        let message =
+            panic_message(|| x.rfft_into(&mut [Complex::new(0.0, 0.0); 4], &mut FftContext::new()));
+        assert_eq!(message, "rfft_into needs an output of 5 elements, got 4");
+        let message = panic_message(|| z.irfft_into(&mut [0.0; 4], &mut []));
+        assert_eq!(message, "irfft_into needs an output of 8 elements, got 4");
+    }
+}
@@ -0,0 +1,579 @@
+use num::Float;
+use std::fmt;
+
+// How `rebin_log` combines the input bins falling within an output bin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebinMode {
+    Sum,
+    Mean,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebinError {
+    NoBins,
+    // The spectrum needs at least a DC and a Nyquist bin.
+    TooShort,
+    // The frequency range is not within (0, 0.5] with its lower edge below the upper one.
+    InvalidRange,
+}
+
+impl fmt::Display for RebinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoBins => write!(f, "rebinning needs at least one output bin"),
+            Self::TooShort => write!(f, "the spectrum needs at least two bins"),
+            Self::InvalidRange => {
+                write!(f, "the frequency range must rise within (0, 0.5]")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RebinError {}
+
+fn to_f64<T: Float>(x: T) -> f64 {
+    x.to_f64().expect("Could not convert type into f64")
+}
+
+fn from_f64<T: Float>(x: f64) -> T {
+    T::from(x).expect("Could not convert f64 into type")
+}
+
+// The `n_out + 1` edges of logarithmically spaced bins from `f_min` to `f_max`, in cycles per
+// sample.
+pub fn log_bin_edges<T: Float>(n_out: usize, f_min: T, f_max: T) -> Result<Vec<T>, RebinError> {
+    if n_out == 0 {
+        return Err(RebinError::NoBins);
+    }
+    let (f_min, f_max) = (to_f64(f_min), to_f64(f_max));
+    // Written so that NaN edges are rejected too.
+    if !(f_min > 0.0 && f_min < f_max && f_max <= 0.5) {
+        return Err(RebinError::InvalidRange);
+    }
+
+    let ratio = f_max / f_min;
+    let mut edges = (0..=n_out)
+        .map(|i| from_f64(f_min * ratio.powf(i as f64 / n_out as f64)))
+        .collect::<Vec<T>>();
+    edges[n_out] = from_f64(f_max);
+    Ok(edges)
+}
+
+// Total power, in dB, of contributions given in dB, so two equal powers add 3 dB. The sum is
+// taken relative to the largest value, which keeps it finite for levels far outside the f64
+// range in linear terms. An empty slice has no power, giving negative infinity.
+pub fn db_power_sum<T: Float>(values_db: &[T]) -> T {
+    let max = values_db
+        .iter()
+        .map(|x| to_f64(*x))
+        .fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return from_f64(max);
+    }
+
+    let sum = values_db
+        .iter()
+        .map(|x| 10f64.powf((to_f64(*x) - max) / 10.0))
+        .sum::<f64>();
+    from_f64(max + 10.0 * sum.log10())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseFloorMethod {
+    // Repeatedly drops bins more than `k` standard deviations of the noise above the running
+    // floor estimate, correcting the mean of the remaining bins for the noise tail dropped with
+    // them, until the estimate settles or `max_iterations` passes have run.
+    SigmaClip { k: f64, max_iterations: usize },
+    // The given quantile of the bins, within (0, 1), scaled by the ratio of the mean to that
+    // quantile of the noise distribution. Low quantiles stay clear of signals.
+    Percentile(f64),
+}
+
+// Periodogram bins of noise with power `p` averaged over `averages` spectra follow
+// `p * chi^2(2 * averages) / (2 * averages)`, which both methods use to undo the bias of looking
+// at only part of the distribution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseFloorConfig {
+    method: NoiseFloorMethod,
+    averages: usize,
+}
+
+impl Default for NoiseFloorConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NoiseFloorConfig {
+    // Sigma clipping at 3 standard deviations for at most 50 passes, on a single periodogram.
+    pub fn new() -> Self {
+        Self {
+            method: NoiseFloorMethod::SigmaClip {
+                k: 3.0,
+                max_iterations: 50,
+            },
+            averages: 1,
+        }
+    }
+
+    pub fn with_method(mut self, method: NoiseFloorMethod) -> Self {
+        match method {
+            NoiseFloorMethod::SigmaClip { k, .. } => {
+                assert!(k > 0.0, "Clipping threshold must be positive")
+            }
+            NoiseFloorMethod::Percentile(q) => {
+                assert!(q > 0.0 && q < 1.0, "Quantile must lie within (0, 1)")
+            }
+        }
+        self.method = method;
+        self
+    }
+
+    // Number of periodograms averaged into the spectrum.
+    pub fn with_averages(mut self, averages: usize) -> Self {
+        assert!(averages > 0, "At least one spectrum must be averaged");
+        self.averages = averages;
+        self
+    }
+}
+
+// Regularized lower incomplete gamma function P(a, x) for integer `a`.
+fn gamma_cdf(a: usize, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let (mut term, mut sum) = (1.0, 1.0);
+    for j in 1..a {
+        term *= x / j as f64;
+        sum += term;
+    }
+    1.0 - (-x).exp() * sum
+}
+
+// Mean of unit mean noise bins averaged over `m` spectra, given they lie below `c`.
+fn truncated_mean(m: usize, c: f64) -> f64 {
+    let x = c * m as f64;
+    let (below, below_next) = (gamma_cdf(m, x), gamma_cdf(m + 1, x));
+    if below > 0.0 {
+        below_next / below
+    } else {
+        1.0
+    }
+}
+
+// Quantile `q` of unit mean noise bins averaged over `m` spectra.
+fn noise_quantile(m: usize, q: f64) -> f64 {
+    let (mut lo, mut hi) = (0.0, 1.0);
+    while gamma_cdf(m, hi * m as f64) < q {
+        hi *= 2.0;
+    }
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if gamma_cdf(m, mid * m as f64) < q {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+fn noise_floor(power: &[f64], config: &NoiseFloorConfig) -> f64 {
+    let m = config.averages;
+    match config.method {
+        NoiseFloorMethod::SigmaClip { k, max_iterations } => {
+            let c = 1.0 + k / (m as f64).sqrt();
+            let correction = truncated_mean(m, c);
+            let mut floor = power.iter().sum::<f64>() / power.len() as f64;
+            for _ in 0..max_iterations {
+                let threshold = c * floor;
+                let (sum, count) = power
+                    .iter()
+                    .filter(|p| **p <= threshold)
+                    .fold((0.0, 0usize), |(sum, count), p| (sum + p, count + 1));
+                if count == 0 {
+                    break;
+                }
+                let next = sum / count as f64 / correction;
+                let settled = (next - floor).abs() <= 1e-12 * floor;
+                floor = next;
+                if settled {
+                    break;
+                }
+            }
+            floor
+        }
+        NoiseFloorMethod::Percentile(q) => {
+            let mut sorted = power.to_vec();
+            let rank = ((q * power.len() as f64) as usize).min(power.len() - 1);
+            let (_, quantile, _) = sorted.select_nth_unstable_by(rank, f64::total_cmp);
+            *quantile / noise_quantile(m, q)
+        }
+    }
+}
+
+pub trait YttriaVectorSpectrum<T> {
+    // Refines the largest bin of a magnitude spectrum by fitting a parabola through it and its two
+    // neighbours, giving the fractional `(bin, magnitude)` of the vertex. A peak in the first or
+    // last bin has only one neighbour and is returned as is. The first of several equal maxima
+    // is used.
+    fn parabolic_peak(&self) -> (T, T);
+
+    // Rebins a one sided power spectrum, bin `k` centred on `k / (2 * (len - 1))` cycles per
+    // sample as `rfft` returns it, onto the `log_bin_edges` bins. Input bins straddling an edge
+    // are split in proportion to their overlap for `Sum` and `Mean`, and count fully for `Max`.
+    // At the low end an output bin can be narrower than an input bin and contain no input bin
+    // centre. Such bins are interpolated linearly between the two nearest input bins at the
+    // geometric centre of the output bin, scaled by its width in input bins for `Sum`, so the
+    // display stays smooth rather than stepped. Power is conserved exactly by `Sum` only as long
+    // as no bin is interpolated.
+    fn rebin_log(
+        &self,
+        n_out: usize,
+        f_min_norm: T,
+        f_max_norm: T,
+        mode: RebinMode,
+    ) -> Result<Vec<T>, RebinError>;
+
+    // Noise power per bin of a power spectrum that may also hold strong signals.
+    fn noise_floor_estimate(&self, config: NoiseFloorConfig) -> T;
+    // `noise_floor_estimate` with the default configuration over each consecutive
+    // `segment_bins` bins, for spectra whose floor is not flat. A shorter final segment gets its
+    // own estimate.
+    fn noise_floor_profile(&self, segment_bins: usize) -> Vec<T>;
+}
+
+impl<T: Float> YttriaVectorSpectrum<T> for [T] {
+    fn parabolic_peak(&self) -> (T, T) {
+        assert!(
+            !self.is_empty(),
+            "Cannot find the peak of an empty spectrum"
+        );
+
+        let peak = (1..self.len()).fold(0, |best, i| if self[i] > self[best] { i } else { best });
+        let bin = T::from(peak).expect("Could not convert usize to type");
+        if peak == 0 || peak == self.len() - 1 {
+            return (bin, self[peak]);
+        }
+
+        let (a, b, c) = (self[peak - 1], self[peak], self[peak + 1]);
+        let two = T::one() + T::one();
+        let curvature = a - two * b + c;
+        if curvature >= T::zero() {
+            // Flat top, nothing to refine.
+            return (bin, b);
+        }
+        let offset = (a - c) / (two * curvature);
+        (bin + offset, b - (a - c) * offset / (two + two))
+    }
+
+    fn rebin_log(
+        &self,
+        n_out: usize,
+        f_min_norm: T,
+        f_max_norm: T,
+        mode: RebinMode,
+    ) -> Result<Vec<T>, RebinError> {
+        let edges = log_bin_edges(n_out, f_min_norm, f_max_norm)?;
+        if self.len() < 2 {
+            return Err(RebinError::TooShort);
+        }
+
+        let last = self.len() - 1;
+        let power = |k: usize| to_f64(self[k]);
+        // Edges in units of input bins.
+        let scale = 2.0 * last as f64;
+        Ok(edges
+            .windows(2)
+            .map(|edge| {
+                let (lo, hi) = (to_f64(edge[0]) * scale, to_f64(edge[1]) * scale);
+                let value = if lo.ceil() >= hi {
+                    let centre = (lo * hi).sqrt();
+                    let k = (centre.floor() as usize).min(last - 1);
+                    let frac = centre - k as f64;
+                    let interpolated = power(k) * (1.0 - frac) + power(k + 1) * frac;
+                    match mode {
+                        RebinMode::Sum => interpolated * (hi - lo),
+                        _ => interpolated,
+                    }
+                } else {
+                    let first = (lo + 0.5).floor() as usize;
+                    let end = ((hi + 0.5).ceil() as usize).min(last + 1);
+                    let (mut sum, mut width, mut max) = (0.0, 0.0, f64::NEG_INFINITY);
+                    for k in first..end {
+                        let overlap = (hi.min(k as f64 + 0.5) - lo.max(k as f64 - 0.5)).max(0.0);
+                        if overlap > 0.0 {
+                            sum += overlap * power(k);
+                            width += overlap;
+                            max = max.max(power(k));
+                        }
+                    }
+                    match mode {
+                        RebinMode::Sum => sum,
+                        RebinMode::Mean => sum / width,
+                        RebinMode::Max => max,
+                    }
+                };
+                from_f64(value)
+            })
+            .collect())
+    }
+
+    fn noise_floor_estimate(&self, config: NoiseFloorConfig) -> T {
+        assert!(
+            !self.is_empty(),
+            "Cannot estimate the noise floor of an empty spectrum"
+        );
+        let power = self.iter().map(|x| to_f64(*x)).collect::<Vec<_>>();
+        from_f64(noise_floor(&power, &config))
+    }
+
+    fn noise_floor_profile(&self, segment_bins: usize) -> Vec<T> {
+        assert!(segment_bins > 0, "Segments need at least one bin");
+        self.chunks(segment_bins)
+            .map(|segment| segment.noise_floor_estimate(NoiseFloorConfig::new()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{Xoshiro256PlusPlus, YttriaRng};
+    use crate::vector::{YttriaVectorComplex, YttriaVectorRealFft};
+    use crate::windows::hann;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_tone_between_bins() {
+        let n = 1024;
+        let window = hann::<f64>(n);
+        for bin in [100.3, 100.5, 99.8, 250.1] {
+            let x = (0..n)
+                .map(|i| window[i] * (2.0 * PI * bin * i as f64 / n as f64).sin())
+                .collect::<Vec<_>>();
+            let spectrum = x.rfft().magnitude();
+
+            let (refined, magnitude) = spectrum.parabolic_peak();
+            let nearest = bin.round();
+            assert!((refined - bin).abs() < 0.1, "{bin} -> {refined}");
+            assert!((refined - bin).abs() < (nearest - bin).abs());
+            assert!(magnitude >= spectrum[nearest as usize]);
+        }
+    }
+
+    #[test]
+    fn test_log_bin_edges() {
+        let edges = log_bin_edges(3, 0.001, 0.5).unwrap();
+        assert_eq!(edges.len(), 4);
+        assert_eq!((edges[0], edges[3]), (0.001, 0.5));
+        assert!((edges[1] / edges[0] - edges[3] / edges[2]).abs() < 1e-12);
+
+        for (f_min, f_max) in [(0.1, 0.1), (0.2, 0.1), (0.0, 0.1), (-0.1, 0.1), (0.1, 0.6)] {
+            assert_eq!(
+                log_bin_edges(8, f_min, f_max),
+                Err(RebinError::InvalidRange),
+                "{f_min} {f_max}"
+            );
+            assert_eq!(
+                [1.0; 16].rebin_log(8, f_min, f_max, RebinMode::Sum),
+                Err(RebinError::InvalidRange)
+            );
+        }
+        assert_eq!(log_bin_edges(0, 0.1, 0.2), Err(RebinError::NoBins));
+        assert_eq!(
+            [1.0].rebin_log(4, 0.1, 0.2, RebinMode::Mean),
+            Err(RebinError::TooShort)
+        );
+    }
+
+    #[test]
+    fn test_rebin_log_conserves_power() {
+        let n = 1025;
+        let spectrum = (0..n)
+            .map(|k| 1.0 + ((k * 37) % 11) as f64)
+            .collect::<Vec<_>>();
+        // From the upper edge of bin 20 to the lower edge of the Nyquist bin, in bins that are
+        // all wider than an input bin.
+        let bin = 0.5 / (n - 1) as f64;
+        let out = spectrum
+            .rebin_log(40, 20.5 * bin, 0.5 - bin / 2.0, RebinMode::Sum)
+            .unwrap();
+        let expected = spectrum[21..(n - 1)].iter().sum::<f64>();
+        assert!((out.iter().sum::<f64>() - expected).abs() < 1e-9 * expected);
+
+        let mean = spectrum
+            .rebin_log(40, 20.5 * bin, 0.5 - bin / 2.0, RebinMode::Mean)
+            .unwrap();
+        let max = spectrum
+            .rebin_log(40, 20.5 * bin, 0.5 - bin / 2.0, RebinMode::Max)
+            .unwrap();
+        for (m, x) in mean.iter().zip(&max) {
+            assert!(*m >= 1.0 && m <= x && *x <= 11.0);
+        }
+    }
+
+    #[test]
+    fn test_rebin_log_single_tone() {
+        let n = 513;
+        let edges = log_bin_edges(16, 0.01, 0.5).unwrap();
+        for k in [100, 200, 301, 450] {
+            let mut spectrum = vec![0.0; n];
+            spectrum[k] = 2.0;
+            let out = spectrum.rebin_log(16, 0.01, 0.5, RebinMode::Sum).unwrap();
+
+            let occupied = out.iter().filter(|x| **x > 0.0).count();
+            assert!(occupied == 1 || occupied == 2, "{k}: {out:?}");
+            assert!((out.iter().sum::<f64>() - 2.0).abs() < 1e-12);
+            // The bin holding the tone's centre holds most of it.
+            let freq = k as f64 / (2 * (n - 1)) as f64;
+            let home = edges
+                .windows(2)
+                .position(|e| e[0] <= freq && freq < e[1])
+                .unwrap();
+            assert!(out[home] >= 1.0, "{k}: {out:?}");
+        }
+    }
+
+    #[test]
+    fn test_rebin_log_interpolates_narrow_bins() {
+        // Many log bins below the first few input bins of a rising spectrum.
+        let n = 65;
+        let spectrum = (0..n).map(|k| 1.0 + k as f64).collect::<Vec<_>>();
+        let bin = 0.5 / (n - 1) as f64;
+        for mode in [RebinMode::Sum, RebinMode::Mean, RebinMode::Max] {
+            let out = spectrum.rebin_log(64, 0.1 * bin, 0.5, mode).unwrap();
+            assert!(out.iter().all(|x| *x > 0.0), "{mode:?}");
+        }
+
+        let edges = log_bin_edges(64, 0.1 * bin, 0.5).unwrap();
+        let mean = spectrum
+            .rebin_log(64, 0.1 * bin, 0.5, RebinMode::Mean)
+            .unwrap();
+        let sum = spectrum
+            .rebin_log(64, 0.1 * bin, 0.5, RebinMode::Sum)
+            .unwrap();
+        // The lowest bins sit between the DC and first bins and follow the linear spectrum.
+        assert!(mean.windows(2).all(|w| w[1] >= w[0]));
+        for i in 0..8 {
+            let centre = (edges[i] * edges[i + 1]).sqrt() / bin;
+            assert!((mean[i] - (1.0 + centre)).abs() < 1e-12, "{i}");
+            let width = (edges[i + 1] - edges[i]) / bin;
+            assert!((sum[i] - mean[i] * width).abs() < 1e-12);
+        }
+    }
+
+    // Periodogram of noise with power `floor(k)` averaged over `averages` spectra, with every
+    // twentieth bin holding a tone 20 dB above the floor.
+    fn noisy_spectrum(n: usize, averages: usize, floor: impl Fn(usize) -> f64) -> Vec<f64> {
+        let mut rng = Xoshiro256PlusPlus::seed_from(9);
+        (0..n)
+            .map(|k| {
+                let noise = (0..averages)
+                    .map(|_| -(1.0 - rng.next_f64()).ln())
+                    .sum::<f64>()
+                    / averages as f64;
+                floor(k) * (noise + if k % 20 == 7 { 100.0 } else { 0.0 })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_db_power_sum() {
+        let two = db_power_sum(&[-3.0, -3.0]);
+        assert!((two - (-3.0 + 10.0 * 2f64.log10())).abs() < 1e-12);
+        assert!(two.abs() < 0.02);
+
+        // Doubling the count of equal contributions adds 3 dB each time.
+        for n in [1, 2, 4, 8, 10] {
+            let sum = db_power_sum(&vec![-120.0f32; n]);
+            assert!((sum - (-120.0 + 10.0 * (n as f32).log10())).abs() < 1e-4);
+        }
+
+        assert!((db_power_sum(&[0.0, -10.0, -20.0]) - 10.0 * 1.11f64.log10()).abs() < 1e-12);
+        assert!((db_power_sum(&[4000.0, 4000.0]) - 4003.0103).abs() < 1e-4);
+        assert_eq!(db_power_sum(&[-50.0, f64::NEG_INFINITY]), -50.0);
+        assert_eq!(db_power_sum::<f64>(&[]), f64::NEG_INFINITY);
+    }
+
+    fn db(x: f64) -> f64 {
+        10.0 * x.log10()
+    }
+
+    #[test]
+    fn test_noise_floor_with_tones() {
+        let floor = 2.0;
+        for averages in [1, 4, 16] {
+            let spectrum = noisy_spectrum(8192, averages, |_| floor);
+            let naive = spectrum.iter().sum::<f64>() / spectrum.len() as f64;
+            assert!(db(naive / floor) > 2.0);
+
+            let config = NoiseFloorConfig::new().with_averages(averages);
+            for method in [
+                NoiseFloorMethod::SigmaClip {
+                    k: 3.0,
+                    max_iterations: 50,
+                },
+                NoiseFloorMethod::Percentile(0.1),
+                NoiseFloorMethod::Percentile(0.5),
+            ] {
+                let estimate = spectrum.noise_floor_estimate(config.with_method(method));
+                assert!(
+                    db(estimate / floor).abs() < 0.5,
+                    "{averages} {method:?}: {} dB",
+                    db(estimate / floor)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_noise_floor_profile() {
+        // Floor rising by 20 dB across the spectrum.
+        let n = 8192;
+        let floor = |k: usize| 10f64.powf(2.0 * k as f64 / n as f64);
+        let spectrum = noisy_spectrum(n, 1, floor);
+
+        let profile = spectrum.noise_floor_profile(512);
+        assert_eq!(profile.len(), 16);
+        for (s, estimate) in profile.iter().enumerate() {
+            let truth = (s * 512..(s + 1) * 512).map(floor).sum::<f64>() / 512.0;
+            assert!(
+                db(estimate / truth).abs() < 0.75,
+                "{s}: {}",
+                db(estimate / truth)
+            );
+        }
+        assert!(profile.windows(2).all(|w| w[1] > w[0]));
+        assert_eq!(spectrum[..1000].noise_floor_profile(512).len(), 2);
+    }
+
+    #[test]
+    fn test_noise_floor_pathological() {
+        // Nothing but signals spread over twelve decades, then spectra with no power at all.
+        let signals = (0..1000)
+            .map(|k| 10f64.powf(12.0 * k as f64 / 1000.0))
+            .collect::<Vec<_>>();
+        let estimate = signals.noise_floor_estimate(NoiseFloorConfig::new());
+        assert!(estimate.is_finite() && estimate > 0.0);
+
+        assert_eq!([0.0; 64].noise_floor_estimate(NoiseFloorConfig::new()), 0.0);
+        let config = NoiseFloorConfig::new().with_method(NoiseFloorMethod::Percentile(0.2));
+        assert_eq!([0.0; 64].noise_floor_estimate(config), 0.0);
+        assert_eq!(
+            [5.0].noise_floor_estimate(NoiseFloorConfig::new()),
+            5.0 / truncated_mean(1, 4.0)
+        );
+    }
+
+    #[test]
+    fn test_edge_and_symmetric_peaks() {
+        assert_eq!([3.0, 2.0, 1.0].parabolic_peak(), (0.0, 3.0));
+        assert_eq!([1.0, 2.0, 3.0].parabolic_peak(), (2.0, 3.0));
+        assert_eq!([1.0].parabolic_peak(), (0.0, 1.0));
+        assert_eq!([1.0, 3.0, 1.0].parabolic_peak(), (1.0, 3.0));
+        // Samples of 4 - (x - 2.25)^2 give back its vertex exactly.
+        assert_eq!([0.0, 2.4375, 3.9375, 3.4375].parabolic_peak(), (2.25, 4.0));
+    }
+}
@@ -1,7 +1,10 @@
 use std::any::type_name;
+use std::mem::size_of;
 
-use num::{FromPrimitive, Num, ToPrimitive};
+use num::{Complex, Float, FromPrimitive, Num, ToPrimitive};
+use rayon::prelude::*;
 
+use crate::config::parallel_min_len;
 use crate::unit::YttriaUnitSqrt;
 
 pub trait YttriaVectorStatistics<T> {
@@ -9,9 +12,225 @@ pub trait YttriaVectorStatistics<T> {
     fn max(&self) -> T;
     fn extremes(&self) -> (T, T);
 
+    // An empty slice has a NaN mean for float types and a zero mean for integers.
     fn mean(&self) -> T;
+    // Population variance in two passes, summing squared deviations from the mean, which stays
+    // accurate however large the mean is next to the spread. Integer variances are exact,
+    // truncated towards zero, while the sums fit in i128. Like the mean, and all the variances
+    // below, it is NaN or zero over an empty slice.
     fn var(&self) -> T;
     fn std(&self) -> T;
+    // One pass variance from the sum and sum of squares, accumulated in f64 in parallel. It reads
+    // the data only once, but the two sums cancel once the mean dwarfs the spread, losing around
+    // `2 * log10(mean / std)` of the 16 significant digits; a result that rounds below zero is
+    // clamped to zero.
+    fn var_fast(&self) -> T;
+    fn std_fast(&self) -> T;
+
+    // Chunked versions of the reductions above, `chunk_len` samples per rayon task. Integer
+    // results equal the serial ones exactly. Float ones agree with them to rounding, as the
+    // chunks group the sums differently, and never depend on the number of threads.
+    fn sum_chunked(&self, chunk_len: Option<usize>) -> T;
+    fn mean_chunked(&self, chunk_len: Option<usize>) -> T;
+    fn var_chunked(&self, chunk_len: Option<usize>) -> T;
+    fn extremes_chunked(&self, chunk_len: Option<usize>) -> (T, T);
+
+    // Reductions along one axis of a row-major matrix `cols` wide, one value per row or per
+    // column. Panic unless the length is a whole number of rows.
+    fn row_sums(&self, cols: usize) -> Vec<T>;
+    fn col_sums(&self, cols: usize) -> Vec<T>;
+    fn row_means(&self, cols: usize) -> Vec<T>;
+    fn col_means(&self, cols: usize) -> Vec<T>;
+
+    // Least squares line through the samples against their index, as `(slope, intercept)`. The
+    // index and samples are centred on their means first, so large offsets do not cancel. Fewer
+    // than two samples give a NaN slope.
+    fn linear_fit(&self) -> (T, T)
+    where
+        T: Float;
+    // As `linear_fit`, against `x` instead of the index.
+    fn linear_fit_xy(&self, x: &[T]) -> (T, T)
+    where
+        T: Float;
+    // `linear_fit_xy`, or `linear_fit` without `x`, along with how well the line explains the
+    // samples.
+    fn linear_fit_quality(&self, x: Option<&[T]>) -> FitQuality<T>
+    where
+        T: Float;
+    // Samples minus the line `slope * index + intercept`.
+    fn residuals(&self, slope: T, intercept: T) -> Vec<T>
+    where
+        T: Float;
+    // Slope of each consecutive `segment_len` sample segment. A tail shorter than `segment_len`
+    // is left out, so there are `len / segment_len` slopes.
+    fn piecewise_slopes(&self, segment_len: usize) -> Vec<T>
+    where
+        T: Float;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FitQuality<T> {
+    pub slope: T,
+    pub intercept: T,
+    // Fraction of the sample variance explained by the line, one when the samples are constant
+    // and fitted exactly.
+    pub r_squared: T,
+    // Standard deviation of the residuals, with the two degrees of freedom of the line removed.
+    pub residual_std: T,
+}
+
+// Chunked reductions default to roughly 4 MiB of samples per rayon task. Each chunk produces a
+// partial result which is merged serially in chunk order, so the result does not depend on how
+// many threads happened to process the chunks.
+const DEFAULT_CHUNK_BYTES: usize = 4 * 1024 * 1024;
+
+fn resolve_chunk_len<T>(chunk_len: Option<usize>) -> usize {
+    chunk_len
+        .unwrap_or(DEFAULT_CHUNK_BYTES / size_of::<T>().max(1))
+        .max(1)
+}
+
+fn chunked_sum<T, S, F>(data: &[T], chunk_len: Option<usize>, f: F) -> S
+where
+    T: Sync,
+    S: Num + Copy + Send,
+    F: Fn(&T) -> S + Sync,
+{
+    let partials = data
+        .par_chunks(resolve_chunk_len::<T>(chunk_len))
+        .map(|chunk| {
+            let mut sum = S::zero();
+            for i in chunk {
+                sum = sum + f(i);
+            }
+            sum
+        })
+        .collect::<Vec<_>>();
+
+    let mut sum = S::zero();
+    for partial in partials {
+        sum = sum + partial;
+    }
+    sum
+}
+
+// Integer sums accumulate in i128, which holds the exact sum of any slice of 64 bit or narrower
+// integers; past 2^53 an f64 accumulator would round them.
+fn chunked_int_sum<T: ToPrimitive + Sync>(data: &[T], chunk_len: Option<usize>) -> i128 {
+    chunked_sum(data, chunk_len, |i| {
+        ToPrimitive::to_i128(i).expect("Could not convert value into i128")
+    })
+}
+
+// `(n * sum(x^2) - sum(x)^2) / n^2`, or `None` as soon as a sum leaves the range of i128.
+// Integer sums are exact however they are grouped, so any `chunk_len` gives the same result.
+fn int_var<T: ToPrimitive + Sync>(data: &[T], chunk_len: Option<usize>) -> Option<i128> {
+    let partials = data
+        .par_chunks(resolve_chunk_len::<T>(chunk_len))
+        .map(|chunk| {
+            let (mut sum, mut sum_sq) = (0i128, 0i128);
+            for x in chunk {
+                let x = ToPrimitive::to_i128(x)?;
+                sum = sum.checked_add(x)?;
+                sum_sq = sum_sq.checked_add(x.checked_mul(x)?)?;
+            }
+            Some((sum, sum_sq))
+        })
+        .collect::<Vec<_>>();
+
+    let (mut sum, mut sum_sq) = (0i128, 0i128);
+    for partial in partials {
+        let (partial_sum, partial_sq) = partial?;
+        sum = sum.checked_add(partial_sum)?;
+        sum_sq = sum_sq.checked_add(partial_sq)?;
+    }
+    let n = i128::try_from(data.len()).ok()?;
+    let spread = n.checked_mul(sum_sq)?.checked_sub(sum.checked_mul(sum)?)?;
+    Some(spread / n.checked_mul(n)?)
+}
+
+fn assert_rows(len: usize, cols: usize) -> usize {
+    assert!(cols > 0, "Matrix needs at least one column");
+    assert!(
+        len.is_multiple_of(cols),
+        "Length {len} is not a whole number of rows of {cols}"
+    );
+    len / cols
+}
+
+fn centred_fit<T: Float>(x: impl Fn(usize) -> T, y: &[T]) -> (T, T) {
+    let n = T::from(y.len()).expect("Could not convert usize to type");
+    let mean_x = (0..y.len()).fold(T::zero(), |acc, i| acc + x(i)) / n;
+    let mean_y = y.iter().fold(T::zero(), |acc, y| acc + *y) / n;
+
+    let (mut sxx, mut sxy) = (T::zero(), T::zero());
+    for (i, y) in y.iter().enumerate() {
+        let dx = x(i) - mean_x;
+        sxx = sxx + dx * dx;
+        sxy = sxy + dx * (*y - mean_y);
+    }
+    let slope = sxy / sxx;
+    (slope, mean_y - slope * mean_x)
+}
+
+fn index<T: Float>(i: usize) -> T {
+    T::from(i).expect("Could not convert usize to type")
+}
+
+// Rows are added in order within each parallel band of columns, so the result does not depend on
+// the thread count.
+fn banded_col_sums<T, S, F>(data: &[T], cols: usize, zero: S, f: F) -> Vec<S>
+where
+    T: Sync,
+    S: Copy + Send + std::ops::Add<Output = S>,
+    F: Fn(&T) -> S + Sync,
+{
+    let mut out = vec![zero; cols];
+    out.par_chunks_mut(1024)
+        .enumerate()
+        .for_each(|(block, out)| {
+            let offset = block * 1024;
+            for row in data.chunks_exact(cols) {
+                for (out, x) in out.iter_mut().zip(&row[offset..]) {
+                    *out = *out + f(x);
+                }
+            }
+        });
+    out
+}
+
+// Value of a statistic over no samples: NaN where the type has one, otherwise zero.
+pub(crate) fn undefined<T: Num + FromPrimitive>() -> T {
+    T::from_f64(f64::NAN).unwrap_or_else(T::zero)
+}
+
+// Only the float types have a NaN.
+fn is_integer<T: FromPrimitive>() -> bool {
+    T::from_f64(f64::NAN).is_none()
+}
+
+// Types narrower than 32 bits, f16 above all, accumulate in f64. Their own running sum would
+// overflow or stop growing long before the end of a capture.
+fn is_narrow<T>() -> bool {
+    size_of::<T>() < 4
+}
+
+fn wide_mean<T: ToPrimitive>(data: &[T]) -> f64 {
+    let mut sum = 0.0f64;
+    for i in data {
+        sum += ToPrimitive::to_f64(i).unwrap();
+    }
+    sum / data.len() as f64
+}
+
+fn wide_var<T: ToPrimitive>(data: &[T]) -> f64 {
+    let mean = wide_mean(data);
+    let mut sum = 0.0f64;
+    for i in data {
+        let detrended = ToPrimitive::to_f64(i).unwrap() - mean;
+        sum += detrended * detrended;
+    }
+    sum / data.len() as f64
 }
 
 impl<T> YttriaVectorStatistics<T> for [T]
@@ -58,6 +277,13 @@ where
     }
 
     fn mean(&self) -> T {
+        if self.is_empty() {
+            return undefined();
+        }
+        if is_narrow::<T>() {
+            return T::from_f64(wide_mean(self)).unwrap();
+        }
+
         if let Some(size) = T::from_usize(self.len()) {
             let mut sum = T::zero();
             for i in self {
@@ -83,40 +309,667 @@ where
     }
 
     fn var(&self) -> T {
-        if let Some(size) = T::from_usize(self.len()) {
-            let mut sum = T::zero();
-            let mean = self.mean();
-            for i in self {
-                let detrended = *i - mean;
-                sum = sum + detrended * detrended;
-            }
+        if self.is_empty() {
+            return undefined();
+        }
 
-            sum / size
-        } else {
-            let mut sum = 0.0f64;
-            let mean = ToPrimitive::to_f64(&self.mean()).unwrap();
-            for i in self {
-                let detrended = ToPrimitive::to_f64(i).unwrap() - mean;
-                sum += detrended * detrended;
+        if is_integer::<T>() {
+            if let Some(var) = int_var(self, None) {
+                return T::from_i128(var).unwrap_or_else(|| {
+                    panic!(
+                        "Variance is outside of representable range of type {}",
+                        type_name::<T>()
+                    )
+                });
             }
+        }
+
+        // A length the type cannot hold falls back to f64 as well, which outside the narrow
+        // types takes billions of samples, as do integers too large to square in i128.
+        match T::from_usize(self.len()) {
+            Some(size) if !is_narrow::<T>() && !is_integer::<T>() => {
+                let mut sum = T::zero();
+                let mean = self.mean();
+                for i in self {
+                    let detrended = *i - mean;
+                    sum = sum + detrended * detrended;
+                }
 
-            T::from_f64(sum).unwrap_or_else(|| {
+                sum / size
+            }
+            _ => T::from_f64(wide_var(self)).unwrap_or_else(|| {
                 panic!(
                     "Variance is outside of representable range of type {}",
                     type_name::<T>()
                 )
-            })
+            }),
         }
     }
 
     fn std(&self) -> T {
         self.var().sqrt()
     }
+
+    fn var_fast(&self) -> T {
+        if self.is_empty() {
+            return undefined();
+        }
+        let (sum, sum_sq) = self
+            .par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|i| {
+                let x = ToPrimitive::to_f64(i).unwrap();
+                (x, x * x)
+            })
+            .reduce(|| (0.0, 0.0), |a, b| (a.0 + b.0, a.1 + b.1));
+        let size = self.len() as f64;
+
+        T::from_f64(((sum_sq - sum * sum / size) / size).max(0.0)).unwrap_or_else(|| {
+            panic!(
+                "Variance is outside of representable range of type {}",
+                type_name::<T>()
+            )
+        })
+    }
+
+    fn std_fast(&self) -> T {
+        self.var_fast().sqrt()
+    }
+
+    fn sum_chunked(&self, chunk_len: Option<usize>) -> T {
+        let sum = if is_integer::<T>() {
+            T::from_i128(chunked_int_sum(self, chunk_len))
+        } else {
+            T::from_f64(chunked_sum(self, chunk_len, |i| {
+                ToPrimitive::to_f64(i).unwrap()
+            }))
+        };
+
+        sum.unwrap_or_else(|| {
+            panic!(
+                "Sum is outside of representable range of type {}",
+                type_name::<T>()
+            )
+        })
+    }
+
+    fn mean_chunked(&self, chunk_len: Option<usize>) -> T {
+        if self.is_empty() {
+            return undefined();
+        }
+        if is_integer::<T>() {
+            let sum = chunked_int_sum(self, chunk_len);
+            return T::from_i128(sum / self.len() as i128).unwrap();
+        }
+        let sum: f64 = chunked_sum(self, chunk_len, |i| ToPrimitive::to_f64(i).unwrap());
+
+        T::from_f64(sum / self.len() as f64).unwrap()
+    }
+
+    fn var_chunked(&self, chunk_len: Option<usize>) -> T {
+        if self.is_empty() {
+            return undefined();
+        }
+        if is_integer::<T>() {
+            if let Some(var) = int_var(self, chunk_len) {
+                return T::from_i128(var).unwrap_or_else(|| {
+                    panic!(
+                        "Variance is outside of representable range of type {}",
+                        type_name::<T>()
+                    )
+                });
+            }
+        }
+        // Deviations from the mean before rounding it back to `T`, as in `var`.
+        let sum: f64 = chunked_sum(self, chunk_len, |i| ToPrimitive::to_f64(i).unwrap());
+        let mean = sum / self.len() as f64;
+        let sum: f64 = chunked_sum(self, chunk_len, |i| {
+            let detrended = ToPrimitive::to_f64(i).unwrap() - mean;
+            detrended * detrended
+        });
+
+        T::from_f64(sum / self.len() as f64).unwrap_or_else(|| {
+            panic!(
+                "Variance is outside of representable range of type {}",
+                type_name::<T>()
+            )
+        })
+    }
+
+    fn extremes_chunked(&self, chunk_len: Option<usize>) -> (T, T) {
+        let partials = self
+            .par_chunks(resolve_chunk_len::<T>(chunk_len))
+            .map(|chunk| chunk.extremes())
+            .collect::<Vec<_>>();
+
+        let (mut min, mut max) = partials[0];
+        for (chunk_min, chunk_max) in &partials[1..] {
+            min = if *chunk_min < min { *chunk_min } else { min };
+            max = if *chunk_max > max { *chunk_max } else { max };
+        }
+
+        (min, max)
+    }
+
+    fn row_sums(&self, cols: usize) -> Vec<T> {
+        assert_rows(self.len(), cols);
+        self.par_chunks_exact(cols)
+            .map(|row| row.iter().fold(T::zero(), |acc, x| acc + *x))
+            .collect()
+    }
+
+    fn col_sums(&self, cols: usize) -> Vec<T> {
+        assert_rows(self.len(), cols);
+        if !is_narrow::<T>() {
+            return banded_col_sums(self, cols, T::zero(), |x| *x);
+        }
+
+        banded_col_sums(self, cols, 0.0f64, |x| ToPrimitive::to_f64(x).unwrap())
+            .into_iter()
+            .map(|sum| {
+                T::from_f64(sum).unwrap_or_else(|| {
+                    panic!(
+                        "Sum is outside of representable range of type {}",
+                        type_name::<T>()
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn row_means(&self, cols: usize) -> Vec<T> {
+        assert_rows(self.len(), cols);
+        self.par_chunks_exact(cols).map(|row| row.mean()).collect()
+    }
+
+    fn col_means(&self, cols: usize) -> Vec<T> {
+        let rows = assert_rows(self.len(), cols);
+        if rows == 0 {
+            return vec![undefined(); cols];
+        }
+        if is_narrow::<T>() {
+            return banded_col_sums(self, cols, 0.0f64, |x| ToPrimitive::to_f64(x).unwrap())
+                .into_iter()
+                .map(|sum| T::from_f64(sum / rows as f64).unwrap())
+                .collect();
+        }
+
+        let rows = T::from_usize(rows).expect("Could not convert usize to type");
+        self.col_sums(cols).into_iter().map(|x| x / rows).collect()
+    }
+
+    fn linear_fit(&self) -> (T, T)
+    where
+        T: Float,
+    {
+        centred_fit(index, self)
+    }
+
+    fn linear_fit_xy(&self, x: &[T]) -> (T, T)
+    where
+        T: Float,
+    {
+        assert!(
+            self.len() == x.len(),
+            "Sample and abscissa lengths differ: {} != {}",
+            self.len(),
+            x.len()
+        );
+        centred_fit(|i| x[i], self)
+    }
+
+    fn linear_fit_quality(&self, x: Option<&[T]>) -> FitQuality<T>
+    where
+        T: Float,
+    {
+        let (slope, intercept) = match x {
+            Some(x) => self.linear_fit_xy(x),
+            None => self.linear_fit(),
+        };
+        let at = |i: usize| x.map_or_else(|| index(i), |x| x[i]);
+
+        let mean = self.iter().fold(T::zero(), |acc, y| acc + *y) / index(self.len());
+        let (mut residual, mut total) = (T::zero(), T::zero());
+        for (i, y) in self.iter().enumerate() {
+            let error = *y - (slope * at(i) + intercept);
+            residual = residual + error * error;
+            total = total + (*y - mean) * (*y - mean);
+        }
+
+        FitQuality {
+            slope,
+            intercept,
+            r_squared: if total > T::zero() {
+                T::one() - residual / total
+            } else if residual == T::zero() {
+                T::one()
+            } else {
+                T::zero()
+            },
+            residual_std: if self.len() > 2 {
+                (residual / index(self.len() - 2)).sqrt()
+            } else {
+                T::zero()
+            },
+        }
+    }
+
+    fn residuals(&self, slope: T, intercept: T) -> Vec<T>
+    where
+        T: Float,
+    {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .map(|(i, y)| *y - (slope * index(i) + intercept))
+            .collect()
+    }
+
+    fn piecewise_slopes(&self, segment_len: usize) -> Vec<T>
+    where
+        T: Float,
+    {
+        assert!(segment_len >= 2, "Segments need at least two samples");
+        self.par_chunks_exact(segment_len)
+            .map(|segment| segment.linear_fit().0)
+            .collect()
+    }
+}
+
+// Distribution of instantaneous power for CCDF and PAPR measurements over streams too long to
+// keep. Samples are counted into bins of absolute power, `resolution_db` wide from `min_db` up to
+// `max_db` in dB relative to unit power, alongside the total power, and only converted to levels
+// relative to the average when queried. The average of a stream keeps moving until its end, so
+// binning against a running mean would leave early samples binned against the wrong reference.
+// Powers outside the range are still counted, with zero power below it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerCcdf<T> {
+    min_db: T,
+    resolution_db: T,
+    counts: Vec<u64>,
+    below: u64,
+    above: u64,
+    power_sum: f64,
+}
+
+// Samples per parallel task in `PowerCcdf::update`. Partial histograms are merged in chunk order,
+// so the power sum does not depend on the thread count.
+const CCDF_CHUNK_LEN: usize = 1 << 16;
+
+impl<T: Float + FromPrimitive + Send + Sync> PowerCcdf<T> {
+    pub fn new(min_db: T, max_db: T, resolution_db: T) -> Self {
+        assert!(resolution_db > T::zero(), "Bin resolution must be positive");
+        assert!(min_db < max_db, "Power range must not be empty");
+        let bins = ((max_db - min_db) / resolution_db)
+            .ceil()
+            .to_usize()
+            .expect("Could not convert bin count to usize");
+
+        Self {
+            min_db,
+            resolution_db,
+            counts: vec![0; bins],
+            below: 0,
+            above: 0,
+            power_sum: 0.0,
+        }
+    }
+
+    fn bin(&self, power: f64) -> Option<Result<usize, bool>> {
+        let db = 10.0 * power.log10();
+        let offset = (db - self.min_db.to_f64().unwrap()) / self.resolution_db.to_f64().unwrap();
+        if offset.is_nan() {
+            None
+        } else if offset < 0.0 {
+            Some(Err(false))
+        } else if offset >= self.counts.len() as f64 {
+            Some(Err(true))
+        } else {
+            Some(Ok(offset as usize))
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        for (own, other) in self.counts.iter_mut().zip(&other.counts) {
+            *own += other;
+        }
+        self.below += other.below;
+        self.above += other.above;
+        self.power_sum += other.power_sum;
+    }
+
+    // Counts every sample but NaNs, which leave both the histogram and the average untouched.
+    pub fn update(&mut self, samples: &[Complex<T>]) {
+        let empty = Self {
+            counts: vec![0; self.counts.len()],
+            below: 0,
+            above: 0,
+            power_sum: 0.0,
+            ..*self
+        };
+        let partials = samples
+            .par_chunks(CCDF_CHUNK_LEN)
+            .map(|chunk| {
+                let mut partial = empty.clone();
+                for x in chunk {
+                    let power = x.norm_sqr().to_f64().unwrap();
+                    match partial.bin(power) {
+                        Some(Ok(bin)) => partial.counts[bin] += 1,
+                        Some(Err(false)) => partial.below += 1,
+                        Some(Err(true)) => partial.above += 1,
+                        None => continue,
+                    }
+                    partial.power_sum += power;
+                }
+                partial
+            })
+            .collect::<Vec<_>>();
+        for partial in &partials {
+            self.add(partial);
+        }
+    }
+
+    // Combines the counts of an accumulator that saw other samples, such as another shard of the
+    // same capture. Panics unless both use the same bins.
+    pub fn merge(&mut self, other: &Self) {
+        assert!(
+            self.min_db == other.min_db
+                && self.resolution_db == other.resolution_db
+                && self.counts.len() == other.counts.len(),
+            "Accumulators must share their power bins"
+        );
+        self.add(other);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum::<u64>() + self.below + self.above
+    }
+
+    // Average power of everything counted so far, in dB.
+    pub fn average_power_db(&self) -> T {
+        T::from_f64(10.0 * (self.power_sum / self.count() as f64).log10()).unwrap()
+    }
+
+    // `(level, probability)` for the lower edge of every bin, from the bottom of the range up:
+    // the fraction of samples whose power lies at least `level` dB above the average.
+    pub fn ccdf(&self) -> Vec<(T, f64)> {
+        let total = self.count() as f64;
+        let average_db = self.average_power_db().to_f64().unwrap();
+        let (min_db, resolution_db) = (
+            self.min_db.to_f64().unwrap(),
+            self.resolution_db.to_f64().unwrap(),
+        );
+
+        let mut at_or_above = self.above;
+        let mut out = self
+            .counts
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(k, count)| {
+                at_or_above += count;
+                let level = min_db + k as f64 * resolution_db - average_db;
+                (T::from_f64(level).unwrap(), at_or_above as f64 / total)
+            })
+            .collect::<Vec<_>>();
+        out.reverse();
+        out
+    }
+
+    // Level above the average exceeded by a fraction `p` of the samples, interpolated between bin
+    // edges on a log probability scale. Infinite when more than `p` of them lie above the range.
+    pub fn papr_at_probability(&self, p: f64) -> T {
+        assert!(p > 0.0 && p < 1.0, "Probability must lie within (0, 1)");
+        let ccdf = self.ccdf();
+        match ccdf.iter().position(|(_, q)| *q <= p) {
+            None => T::infinity(),
+            Some(0) => ccdf[0].0,
+            Some(k) => {
+                let ((x0, p0), (x1, p1)) = (ccdf[k - 1], ccdf[k]);
+                if p1 == 0.0 {
+                    return x1;
+                }
+                let t = (p0.ln() - p.ln()) / (p0.ln() - p1.ln());
+                x0 + (x1 - x0) * T::from_f64(t).unwrap()
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::YttriaVectorStatistics;
+    use super::{PowerCcdf, YttriaVectorStatistics};
+    use crate::random::complex_normal;
+    use crate::rng::{gaussian_noise, Xoshiro256PlusPlus, YttriaRng};
+    use crate::vector::{YttriaVectorArithmetic, YttriaVectorComplexFft};
+    use num::Complex;
+
+    #[test]
+    fn test_linear_fit_exact() {
+        let line = (0..100).map(|i| 2.5 * i as f64 - 7.0).collect::<Vec<_>>();
+        let (slope, intercept) = line.linear_fit();
+        assert!((slope - 2.5).abs() < 1e-12 && (intercept + 7.0).abs() < 1e-12);
+        assert!(line
+            .residuals(slope, intercept)
+            .iter()
+            .all(|r| r.abs() < 1e-12));
+
+        let x = (0..50).map(|i| (i * i) as f64 * 0.1).collect::<Vec<_>>();
+        let y = x.iter().map(|x| -0.5 * x + 3.0).collect::<Vec<_>>();
+        let quality = y.linear_fit_quality(Some(&x));
+        assert!((quality.slope + 0.5).abs() < 1e-12 && (quality.intercept - 3.0).abs() < 1e-12);
+        assert!((quality.r_squared - 1.0).abs() < 1e-12);
+        assert!(quality.residual_std < 1e-12);
+
+        let flat = [4.0; 10].linear_fit_quality(None);
+        assert_eq!(
+            (flat.slope, flat.intercept, flat.r_squared),
+            (0.0, 4.0, 1.0)
+        );
+        assert!([1.0f64].linear_fit().0.is_nan());
+    }
+
+    #[test]
+    fn test_linear_fit_noise() {
+        // Slope variance of a least squares fit is 12 sigma^2 / (n (n^2 - 1)).
+        let (n, sigma, trials) = (64, 0.5, 2000);
+        let mut rng = Xoshiro256PlusPlus::seed_from(5);
+        let mut slopes = Vec::with_capacity(trials);
+        let (mut r_squared, mut residual_var) = (0.0, 0.0);
+        for _ in 0..trials {
+            let y = (0..n)
+                .map(|i| 0.1 * i as f64 + 1.0 + sigma * rng.next_gaussian())
+                .collect::<Vec<_>>();
+            let quality = y.linear_fit_quality(None);
+            slopes.push(quality.slope);
+            r_squared += quality.r_squared / trials as f64;
+            residual_var += quality.residual_std.powi(2) / trials as f64;
+        }
+
+        let expected = 12.0 * sigma * sigma / (n * (n * n - 1)) as f64;
+        assert!((slopes.mean() - 0.1).abs() < 3.0 * (expected / trials as f64).sqrt());
+        assert!(
+            (slopes.var() / expected - 1.0).abs() < 0.1,
+            "{}",
+            slopes.var() / expected
+        );
+        assert!(r_squared > 0.9 && r_squared < 1.0);
+        // With the two fitted degrees of freedom removed the residual variance is unbiased.
+        assert!((residual_var / (sigma * sigma) - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_linear_fit_large_offset() {
+        // Timestamps around 1e6, where the naive normal equations in f32 lose everything.
+        let x = (0..1000).map(|i| 1.0e6f32 + i as f32).collect::<Vec<_>>();
+        let y = x
+            .iter()
+            .enumerate()
+            .map(|(i, _)| 3.0e-3 * i as f32 + 0.25 * ((i * 7) % 5) as f32)
+            .collect::<Vec<_>>();
+
+        // Naive reference in f64.
+        let n = x.len() as f64;
+        let (sx, sy) = (
+            x.iter().map(|&x| x as f64).sum::<f64>(),
+            y.iter().map(|&y| y as f64).sum::<f64>(),
+        );
+        let sxx = x.iter().map(|&x| (x as f64).powi(2)).sum::<f64>();
+        let sxy = x
+            .iter()
+            .zip(&y)
+            .map(|(&x, &y)| x as f64 * y as f64)
+            .sum::<f64>();
+        let slope = (n * sxy - sx * sy) / (n * sxx - sx * sx);
+        let intercept = (sy - slope * sx) / n;
+
+        let (fit_slope, fit_intercept) = y.linear_fit_xy(&x);
+        assert!(
+            (fit_slope as f64 - slope).abs() < 1e-4 * slope.abs(),
+            "{fit_slope} {slope}"
+        );
+        assert!((fit_intercept as f64 - intercept).abs() < 1e-4 * intercept.abs());
+    }
+
+    #[test]
+    fn test_piecewise_slopes() {
+        // The derivative of i^2 / 2 is i, so segment slopes grow by the segment length.
+        let parabola = (0..1005).map(|i| (i * i) as f64 / 2.0).collect::<Vec<_>>();
+        let slopes = parabola.piecewise_slopes(100);
+        assert_eq!(slopes.len(), 10);
+        for (k, slope) in slopes.iter().enumerate() {
+            assert!(
+                (slope - (100 * k) as f64 - 49.5).abs() < 1e-9,
+                "{k}: {slope}"
+            );
+        }
+        assert_eq!(parabola[..99].piecewise_slopes(100), []);
+    }
+
+    #[test]
+    fn test_axis_reductions() {
+        // [[1, 2, 3],
+        //  [4, 5, 6]]
+        let m = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        assert_eq!(m.row_sums(3), [6.0, 15.0]);
+        assert_eq!(m.col_sums(3), [5.0, 7.0, 9.0]);
+        assert_eq!(m.row_means(3), [2.0, 5.0]);
+        assert_eq!(m.col_means(3), [2.5, 3.5, 4.5]);
+
+        // A single row or column reduces to the vector itself or its mean.
+        assert_eq!(m.row_means(1), m);
+        assert_eq!(m.col_means(6), m);
+        assert_eq!(m.col_means(1), [3.5]);
+        assert_eq!(m.row_means(6), [3.5]);
+        assert_eq!([2u32, 4, 6, 8].col_means(2), [4, 6]);
+
+        // Narrow types are summed in f64, so the column sums do not overflow on the way.
+        assert_eq!([200u8, 200].col_means(1), [200]);
+        assert_eq!([200u8, 10, 200, 20].col_means(2), [200, 15]);
+        assert_eq!([i16::MAX, 1, -2].col_sums(1), [i16::MAX - 1]);
+
+        // No rows at all.
+        assert_eq!(<[u8]>::col_means(&[], 2), [0, 0]);
+        assert!(<[f64]>::col_means(&[], 3).iter().all(|x| x.is_nan()));
+        assert_eq!(<[f64]>::col_sums(&[], 3), [0.0; 3]);
+
+        // Wider than one parallel band of columns.
+        let wide = (0..3 * 2500).map(|i| (i % 2500) as f64).collect::<Vec<_>>();
+        let expected = (0..2500).map(|i| i as f64).collect::<Vec<_>>();
+        assert_eq!(wide.col_means(2500), expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_axis_ragged() {
+        [1.0, 2.0, 3.0, 4.0, 5.0].row_means(2);
+    }
+
+    #[test]
+    fn test_ccdf_gaussian_noise() {
+        // Complex Gaussian noise has exponentially distributed power: P(power > x avg) = e^-x.
+        let noise = complex_normal::<f64>(1_000_000, &mut Xoshiro256PlusPlus::seed_from(11));
+        let mut ccdf = PowerCcdf::new(-60.0, 20.0, 0.1);
+        ccdf.update(&noise);
+        assert_eq!(ccdf.count(), 1_000_000);
+        assert!(ccdf.average_power_db().abs() < 0.01);
+
+        let mut checked = 0;
+        for (level, p) in ccdf.ccdf() {
+            let expected = (-(10f64.powf(level / 10.0))).exp();
+            if expected > 1e-4 {
+                let sigma = (expected * (1.0 - expected) / 1e6).sqrt();
+                assert!((p - expected).abs() < 5.0 * sigma + 1e-9, "{level}: {p}");
+                checked += 1;
+            }
+        }
+        assert!(checked > 500);
+        let papr = ccdf.papr_at_probability(1e-3);
+        assert!((papr - 10.0 * (1e3f64.ln()).log10()).abs() < 0.2, "{papr}");
+    }
+
+    #[test]
+    fn test_ccdf_constant_envelope() {
+        let tone = (0..10_000)
+            .map(|n| Complex::from_polar(2.0, 0.1 * n as f64))
+            .collect::<Vec<_>>();
+        let mut ccdf = PowerCcdf::new(-20.0, 20.0, 0.25);
+        ccdf.update(&tone);
+        // The whole distribution sits in the bin holding the average itself.
+        for (level, p) in ccdf.ccdf() {
+            if level <= -0.25 {
+                assert_eq!(p, 1.0, "{level}");
+            } else if level >= 0.25 {
+                assert_eq!(p, 0.0, "{level}");
+            }
+        }
+        assert!(ccdf.papr_at_probability(1e-4).abs() <= 0.25);
+    }
+
+    #[test]
+    fn test_ccdf_merge_matches_single_pass() {
+        let mut rng = Xoshiro256PlusPlus::seed_from(12);
+        // A level change halfway through moves the final average away from either half's own.
+        let mut samples = complex_normal::<f32>(150_000, &mut rng);
+        samples[..75_000].iter_mut().for_each(|x| *x *= 3.0);
+        samples[1] = Complex::new(0.0, 0.0);
+        samples[2] = Complex::new(1e6, 0.0);
+
+        let mut whole = PowerCcdf::new(-40.0f32, 30.0, 0.5);
+        whole.update(&samples);
+        let mut merged = PowerCcdf::new(-40.0f32, 30.0, 0.5);
+        for shard in samples.chunks(40_000) {
+            let mut partial = PowerCcdf::new(-40.0, 30.0, 0.5);
+            partial.update(shard);
+            merged.merge(&partial);
+        }
+
+        assert_eq!(merged.count(), whole.count());
+        for (a, b) in merged.ccdf().iter().zip(whole.ccdf()) {
+            assert!((a.0 - b.0).abs() < 1e-4);
+            assert_eq!(a.1, b.1);
+        }
+        // The outlier is counted above the range, so the top bin sees it.
+        assert_eq!(whole.ccdf().last().unwrap().1, 1.0 / 150_000.0);
+    }
+
+    #[test]
+    fn test_papr_of_multicarrier() {
+        // OFDM-like symbols of 52 QPSK subcarriers, 26 either side of DC, so the samples are
+        // close to Gaussian and exceed the average by about 9.6 dB once in 10^4.
+        let mut rng = Xoshiro256PlusPlus::seed_from(13);
+        let mut bit = move || (rng.next_u64() & 1) as f64 * 2.0 - 1.0;
+        let mut ccdf = PowerCcdf::new(-60.0, 20.0, 0.05);
+        let mut bins = vec![Complex::new(0.0, 0.0); 256];
+        for _ in 0..4000 {
+            for k in (1..=26).chain(230..256) {
+                bins[k] = Complex::new(bit(), bit());
+            }
+            ccdf.update(&bins.ifft());
+        }
+        let papr = ccdf.papr_at_probability(1e-4);
+        assert!((8.0..12.0).contains(&papr), "{papr}");
+    }
 
     #[test]
     fn test_mean_if32() {
@@ -124,4 +977,152 @@ mod test {
         let out = test.mean();
         println!("{out}");
     }
+
+    #[test]
+    fn test_mean_empty() {
+        assert!(<[f64]>::mean(&[]).is_nan());
+        assert!(<[f32]>::mean(&[]).is_nan());
+        assert_eq!(<[u8]>::mean(&[]), 0);
+        assert_eq!(<[i16]>::mean(&[]), 0);
+        assert_eq!(<[i64]>::mean(&[]), 0);
+    }
+
+    #[test]
+    fn test_var_fast_large_offset() {
+        // Unit variance noise on a 1e7 offset: the one pass sums reach 1e14 per sample, so the
+        // variance sits right at the edge of the digits they keep.
+        let noise = gaussian_noise(100_000, 5);
+        let reference = noise.var();
+        assert!((reference - 1.0).abs() < 0.02);
+        assert!((noise.var_fast() - reference).abs() < 1e-9);
+
+        let offset = noise.add_const(1e7);
+        assert!((offset.var() - reference).abs() < 1e-6);
+        assert!((offset.std() - reference.sqrt()).abs() < 1e-6);
+        assert!((offset.var_fast() - reference).abs() > 1e-3);
+
+        // Integers are summed in f64, so their squares do not overflow. Near 2^31 the squares
+        // need more than the 53 bits of an f64 mantissa though, and the cancellation eats a true
+        // variance of one, which the two pass version still finds.
+        assert_eq!([200u8, 202, 200, 202].var_fast(), 1);
+        let large = [i32::MAX as i64, i32::MAX as i64 - 2];
+        assert_eq!(large.var(), 1);
+        assert!(large.var_fast() < 1);
+    }
+
+    #[test]
+    fn test_var_empty_and_long_narrow() {
+        assert!(<[f64]>::var(&[]).is_nan());
+        assert!(<[f64]>::var_fast(&[]).is_nan());
+        assert!(<[f64]>::var_chunked(&[], None).is_nan());
+        assert!(<[f64]>::mean_chunked(&[], None).is_nan());
+        assert_eq!(<[i32]>::var(&[]), 0);
+        assert_eq!(<[i32]>::var_fast(&[]), 0);
+        assert_eq!(<[i32]>::var_chunked(&[], None), 0);
+        assert_eq!(<[u8]>::var(&[]), 0);
+
+        // Longer than the type can count: a variance of a quarter truncates to zero in u8, and
+        // one of exactly one survives in u16.
+        let bytes = (0..1001).map(|i| (i % 2) as u8).collect::<Vec<_>>();
+        assert_eq!(bytes.var(), 0);
+        let words = (0..70_000).map(|i| 2 * (i % 2) as u16).collect::<Vec<_>>();
+        assert_eq!(words.var(), 1);
+        assert_eq!(words.var(), words.var_chunked(None));
+    }
+
+    #[test]
+    fn test_chunked_matches_serial() {
+        let ints = (0..100_003)
+            .map(|x| (x * 7919 % 201) - 100)
+            .collect::<Vec<i32>>();
+        let shorts = ints.iter().map(|&x| x as i16).collect::<Vec<_>>();
+        let floats = ints.iter().map(|&x| x as f64).collect::<Vec<_>>();
+        let fractions = ints
+            .iter()
+            .map(|&x| 10.0 + x as f64 / 3.0)
+            .collect::<Vec<_>>();
+
+        for chunk_len in [Some(1), Some(17), Some(4096), None] {
+            assert_eq!(
+                ints.sum_chunked(chunk_len),
+                YttriaVectorArithmetic::sum(ints.as_slice())
+            );
+            assert_eq!(ints.mean_chunked(chunk_len), ints.mean());
+            assert_eq!(ints.var_chunked(chunk_len), ints.var());
+            assert_eq!(ints.extremes_chunked(chunk_len), ints.extremes());
+            assert_eq!(shorts.var_chunked(chunk_len), shorts.var());
+            // Deviations are from the exact mean of 5 / 3, not the truncated one.
+            assert_eq!([0i16, 0, 5].var_chunked(chunk_len), 5);
+            assert_eq!([0i32, 0, 5].var_chunked(chunk_len), 5);
+
+            assert_eq!(
+                floats.sum_chunked(chunk_len),
+                YttriaVectorArithmetic::sum(floats.as_slice())
+            );
+            assert_eq!(floats.mean_chunked(chunk_len), floats.mean());
+            assert_eq!(floats.extremes_chunked(chunk_len), floats.extremes());
+
+            // Squared deviations from a fractional mean are inexact, so grouping them into
+            // chunks only agrees to rounding.
+            for data in [&floats, &fractions] {
+                let (chunked, serial) = (data.var_chunked(chunk_len), data.var());
+                assert!(
+                    (chunked / serial - 1.0).abs() < 1e-12,
+                    "{chunked} vs {serial}"
+                );
+            }
+        }
+        assert_eq!([0i16, 0, 5].var(), 5);
+        assert_eq!([0i32, 0, 5].var(), 5);
+    }
+
+    #[test]
+    fn test_chunked_integer_sum_exact() {
+        // Both round to 2^63 in f64, which does not fit back into an i64.
+        let big = [i64::MAX - 10, 7, -1];
+        assert_eq!(big.sum_chunked(None), i64::MAX - 4);
+        assert_eq!(big.sum_chunked(Some(1)), i64::MAX - 4);
+
+        // Partial sums may leave the range of the type as long as the total does not.
+        let across = [i64::MAX, i64::MAX, -i64::MAX, 3 - i64::MAX];
+        assert_eq!(across.sum_chunked(Some(2)), 3);
+        assert_eq!(
+            [i64::MAX - 1, i64::MAX - 3].mean_chunked(Some(1)),
+            i64::MAX - 2
+        );
+        assert_eq!(
+            [u64::MAX - 1, u64::MAX - 3].mean_chunked(None),
+            u64::MAX - 2
+        );
+    }
+
+    #[test]
+    fn test_chunked_deterministic_across_threads() {
+        let data = (0..1_000_000)
+            .map(|x: u64| ((x.wrapping_mul(2_654_435_761) % 1_000_003) as f64).sin())
+            .collect::<Vec<_>>();
+
+        let results = [1, 2, 3, 8]
+            .iter()
+            .map(|&threads| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .unwrap()
+                    .install(|| {
+                        (
+                            data.sum_chunked(Some(1000)),
+                            data.mean_chunked(Some(1000)),
+                            data.var_chunked(Some(1000)),
+                        )
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        for result in &results[1..] {
+            assert_eq!(result.0.to_bits(), results[0].0.to_bits());
+            assert_eq!(result.1.to_bits(), results[0].1.to_bits());
+            assert_eq!(result.2.to_bits(), results[0].2.to_bits());
+        }
+    }
 }
@@ -0,0 +1,258 @@
+use std::fmt;
+
+use rayon::prelude::*;
+
+// Side of the square tiles the transposes work through. A tile of the source and one of the
+// destination then fit in L1 together for elements up to 16 bytes, so each cache line is
+// fetched once rather than once per element along the strided side.
+const TILE: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransposeError {
+    // The matrix holds `len` elements where its shape needs `expected`.
+    Shape { expected: usize, len: usize },
+    OutputLength { expected: usize, len: usize },
+    ColumnOutOfRange { col: usize, cols: usize },
+}
+
+impl fmt::Display for TransposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shape { expected, len } => {
+                write!(f, "matrix shape needs {expected} elements but got {len}")
+            }
+            Self::OutputLength { expected, len } => {
+                write!(f, "output needs {expected} elements but got {len}")
+            }
+            Self::ColumnOutOfRange { col, cols } => {
+                write!(f, "column {col} out of range for {cols} columns")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransposeError {}
+
+fn check_len(expected: usize, len: usize, output: bool) -> Result<(), TransposeError> {
+    match (expected == len, output) {
+        (true, _) => Ok(()),
+        (false, false) => Err(TransposeError::Shape { expected, len }),
+        (false, true) => Err(TransposeError::OutputLength { expected, len }),
+    }
+}
+
+// Transposes of row-major matrices held as flat slices, the corner turn between per-channel and
+// per-time layouts.
+pub trait YttriaVectorTranspose<T> {
+    // Writes the `cols x rows` transpose of the `rows x cols` matrix in `self`.
+    fn transpose_into(&self, rows: usize, cols: usize, out: &mut [T])
+        -> Result<(), TransposeError>;
+    fn transpose_square_inplace(&mut self, n: usize) -> Result<(), TransposeError>;
+    // Gathers column `col` into `out`, `rows` elements long.
+    fn copy_column_into(
+        &self,
+        rows: usize,
+        cols: usize,
+        col: usize,
+        out: &mut [T],
+    ) -> Result<(), TransposeError>;
+}
+
+impl<T> YttriaVectorTranspose<T> for [T]
+where
+    T: Copy + Send + Sync,
+{
+    fn transpose_into(
+        &self,
+        rows: usize,
+        cols: usize,
+        out: &mut [T],
+    ) -> Result<(), TransposeError> {
+        check_len(rows * cols, self.len(), false)?;
+        check_len(rows * cols, out.len(), true)?;
+        if rows == 0 || cols == 0 {
+            return Ok(());
+        }
+
+        // Each task fills one band of `TILE` output rows, reading the matching band of input
+        // columns a tile at a time.
+        out.par_chunks_mut(TILE * rows)
+            .enumerate()
+            .for_each(|(band, out)| {
+                let c0 = band * TILE;
+                let width = out.len() / rows;
+                for r0 in (0..rows).step_by(TILE) {
+                    for r in r0..(r0 + TILE).min(rows) {
+                        let src = &self[r * cols + c0..r * cols + c0 + width];
+                        for (c, x) in src.iter().enumerate() {
+                            out[c * rows + r] = *x;
+                        }
+                    }
+                }
+            });
+        Ok(())
+    }
+
+    fn transpose_square_inplace(&mut self, n: usize) -> Result<(), TransposeError> {
+        check_len(n * n, self.len(), false)?;
+        for r0 in (0..n).step_by(TILE) {
+            for c0 in (r0..n).step_by(TILE) {
+                for r in r0..(r0 + TILE).min(n) {
+                    // Diagonal tiles swap only their upper triangle.
+                    let first = if c0 == r0 { r + 1 } else { c0 };
+                    for c in first..(c0 + TILE).min(n) {
+                        self.swap(r * n + c, c * n + r);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn copy_column_into(
+        &self,
+        rows: usize,
+        cols: usize,
+        col: usize,
+        out: &mut [T],
+    ) -> Result<(), TransposeError> {
+        check_len(rows * cols, self.len(), false)?;
+        check_len(rows, out.len(), true)?;
+        if col >= cols {
+            return Err(TransposeError::ColumnOutOfRange { col, cols });
+        }
+        for (out, x) in out.iter_mut().zip(self[col..].iter().step_by(cols)) {
+            *out = *x;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{best_of, YttriaConfig};
+    use num::Complex;
+
+    fn naive_into<T: Copy>(x: &[T], rows: usize, cols: usize, out: &mut [T]) {
+        for r in 0..rows {
+            for c in 0..cols {
+                out[c * rows + r] = x[r * cols + c];
+            }
+        }
+    }
+
+    fn naive<T: Copy + Default>(x: &[T], rows: usize, cols: usize) -> Vec<T> {
+        let mut out = vec![T::default(); x.len()];
+        naive_into(x, rows, cols, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_round_trip_and_reference() {
+        for (rows, cols) in [
+            (1, 1),
+            (1, 77),
+            (77, 1),
+            (3, 5),
+            (32, 32),
+            (33, 65),
+            (100, 7),
+        ] {
+            let x = (0..rows * cols).collect::<Vec<_>>();
+            let mut t = vec![0; x.len()];
+            x.transpose_into(rows, cols, &mut t).unwrap();
+            assert_eq!(t, naive(&x, rows, cols), "{rows}x{cols}");
+
+            let mut back = vec![0; x.len()];
+            t.transpose_into(cols, rows, &mut back).unwrap();
+            assert_eq!(back, x, "{rows}x{cols}");
+        }
+        assert_eq!([0u8; 0].transpose_into(0, 5, &mut []), Ok(()));
+    }
+
+    #[test]
+    fn test_square_inplace() {
+        for n in [0, 1, 2, 31, 32, 33, 70] {
+            let x = (0..n * n).map(|i| i as f64).collect::<Vec<_>>();
+            let mut expected = vec![0.0; x.len()];
+            x.transpose_into(n, n, &mut expected).unwrap();
+            let mut in_place = x.clone();
+            in_place.transpose_square_inplace(n).unwrap();
+            assert_eq!(in_place, expected, "{n}");
+        }
+    }
+
+    #[test]
+    fn test_copy_column() {
+        let x = (0..12).collect::<Vec<_>>();
+        let mut col = [0; 3];
+        x.copy_column_into(3, 4, 2, &mut col).unwrap();
+        assert_eq!(col, [2, 6, 10]);
+
+        assert_eq!(
+            x.copy_column_into(3, 4, 4, &mut col),
+            Err(TransposeError::ColumnOutOfRange { col: 4, cols: 4 })
+        );
+        assert_eq!(
+            x.copy_column_into(4, 3, 0, &mut col),
+            Err(TransposeError::OutputLength {
+                expected: 4,
+                len: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_shape_errors() {
+        let x = [0; 12];
+        assert_eq!(
+            x.transpose_into(5, 3, &mut [0; 15]),
+            Err(TransposeError::Shape {
+                expected: 15,
+                len: 12
+            })
+        );
+        assert_eq!(
+            x.transpose_into(3, 4, &mut [0; 11]),
+            Err(TransposeError::OutputLength {
+                expected: 12,
+                len: 11
+            })
+        );
+        assert_eq!(
+            [0; 12].transpose_square_inplace(3),
+            Err(TransposeError::Shape {
+                expected: 9,
+                len: 12
+            })
+        );
+    }
+
+    // Run with `cargo test --release -- --ignored bench_transpose --nocapture`. The blocked
+    // transpose runs on one thread like the naive loop, so the margin is the tiling's alone.
+    #[test]
+    #[ignore]
+    fn bench_transpose() {
+        let (rows, cols, runs) = (4096, 2048, 5);
+        let x = (0..rows * cols)
+            .map(|i| Complex::new(i as f32, -(i as f32)))
+            .collect::<Vec<_>>();
+        let mut out = vec![Complex::default(); x.len()];
+
+        let (naive_time, blocked) = YttriaConfig::new().with_num_threads(1).install(|| {
+            let naive_time = best_of(runs, || {
+                naive_into(&x, rows, cols, &mut out);
+                std::hint::black_box(&out);
+            });
+            let blocked = best_of(runs, || {
+                x.transpose_into(rows, cols, &mut out).unwrap();
+                std::hint::black_box(&out);
+            });
+            (naive_time, blocked)
+        });
+        println!("naive: {naive_time:?}, blocked: {blocked:?}");
+        assert_eq!(out, naive(&x, rows, cols));
+        assert!(blocked * 2 < naive_time, "{blocked:?} {naive_time:?}");
+    }
+}
@@ -1,11 +1,114 @@
-use num::{Num, NumCast, ToPrimitive};
+use num::{Float, Num, NumCast, ToPrimitive};
 use rayon::prelude::*;
 use std::any::type_name;
+use std::fmt;
 
+use super::assert_output_len;
+use crate::config::parallel_min_len;
+use crate::next_pow2;
+
+// How `enforce_monotonic` repairs an axis that is not strictly increasing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonotonicPolicy {
+    // Drops every element not above the last one kept, and any NaN.
+    DropViolations,
+    // Moves each repeated point apart by machine epsilon, the earlier copy down and the later
+    // one up, so a repeated frequency can mark a step in a gain response. Further copies each
+    // sit another epsilon above the one before. The epsilon is
+    // absolute, which suits axes of order one such as normalized frequencies. Decreasing points
+    // are still an error.
+    NudgeByEpsilon,
+    // Returns the first violation.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxisError {
+    Nan { index: usize },
+    // Element `index` equals the one before it.
+    Duplicate { index: usize },
+    // Element `index` is below the one before it.
+    Decreasing { index: usize },
+}
+
+impl fmt::Display for AxisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nan { index } => write!(f, "axis point {index} is NaN"),
+            Self::Duplicate { index } => {
+                write!(f, "axis point {index} repeats the one before it")
+            }
+            Self::Decreasing { index } => {
+                write!(f, "axis point {index} is below the one before it")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AxisError {}
+
+fn first_violation<T: Float>(axis: &[T]) -> Option<AxisError> {
+    if let Some(index) = axis.iter().position(|x| x.is_nan()) {
+        return Some(AxisError::Nan { index });
+    }
+    axis.windows(2).enumerate().find_map(|(i, w)| {
+        if w[1] == w[0] {
+            Some(AxisError::Duplicate { index: i + 1 })
+        } else if w[1] < w[0] {
+            Some(AxisError::Decreasing { index: i + 1 })
+        } else {
+            None
+        }
+    })
+}
+
+// Sorted union of two axes, with points within `tolerance` of a smaller one dropped as in
+// `dedup_close`, for resampling two datasets onto a common grid. Panics on NaN.
+pub fn merge_axes<T>(a: &[T], b: &[T], tolerance: T) -> Vec<T>
+where
+    T: Float + Send + Sync,
+{
+    let mut merged = a.concatenate(b);
+    merged.sort_by(|x, y| x.partial_cmp(y).expect("Cannot merge axes containing NaN"));
+    merged.dedup_close(tolerance).0
+}
+
+// Unless a method takes its output length as an argument or has an `_output_len` companion, it
+// returns as many elements as `self` holds.
 pub trait YttriaVectorUtils<T> {
+    fn repeat_output_len(&self, repeats: usize) -> usize;
     fn repeat(&self, repeats: usize) -> Vec<T>;
+    fn tile_output_len(&self, repeats: usize) -> usize;
     fn tile(&self, repeats: usize) -> Vec<T>;
+    fn concatenate_output_len(&self, other: &[T]) -> usize;
     fn concatenate(&self, other: &[T]) -> Vec<T>;
+    // The next power of two at or above the length, so an empty vector pads to one element.
+    fn pad_to_pow2_output_len(&self) -> usize;
+    fn pad_to_pow2(&self, fill: T) -> Vec<T>;
+
+    // Centres the vector in `total_len` zeros. With an odd amount of padding the extra zero goes
+    // at the end.
+    fn zero_pad_center(&self, total_len: usize) -> Vec<T>;
+
+    // Every `factor`th element with no anti-alias filtering, starting at `offset`.
+    fn downsample_output_len(&self, factor: usize) -> usize;
+    fn downsample(&self, factor: usize) -> Vec<T>;
+    fn downsample_offset_output_len(&self, factor: usize, offset: usize) -> usize;
+    fn downsample_offset(&self, factor: usize, offset: usize) -> Vec<T>;
+
+    // Elements `start`, `start + stride`, ... up to the end, such as the pilots on every `stride`th
+    // subcarrier or one polyphase branch. There are `(len - start).div_ceil(stride)` of them, none
+    // when `start` is past the end. A zero stride panics.
+    fn strided_output_len(&self, start: usize, stride: usize) -> usize;
+    fn gather_strided_into(&self, start: usize, stride: usize, out: &mut [T]);
+    fn gather_strided(&self, start: usize, stride: usize) -> Vec<T>;
+    // Writes `values` back to the elements `gather_strided` would read, leaving the rest alone.
+    fn scatter_strided_inplace(&mut self, values: &[T], start: usize, stride: usize);
+
+    // Linear counterpart to `roll`: a positive `by` delays the vector, moving elements towards
+    // the end, and the vacated positions are filled with `fill` instead of wrapping around.
+    fn shift_into(&self, out: &mut [T], by: isize, fill: T);
+    fn shift(&self, by: isize, fill: T) -> Vec<T>;
 
     fn roll_into(&self, out: &mut [T], shift: usize);
     fn roll(&self, shift: usize) -> Vec<T>;
@@ -15,35 +118,101 @@ pub trait YttriaVectorUtils<T> {
     fn fftshift(&self) -> Vec<T>;
     fn fftshift_in_place(&mut self);
 
+    fn ifftshift_into(&self, out: &mut [T]);
+    fn ifftshift(&self) -> Vec<T>;
+    fn ifftshift_in_place(&mut self);
+
     fn as_type<U: NumCast + Send + Sync>(&self) -> Vec<U>;
+
+    // Casts like `as_type` and splits the result into rows of `cols`, for a flattened row-major
+    // matrix. Panics unless the length is a multiple of `cols`.
+    fn as_type_chunked<U: NumCast + Send + Sync>(&self, cols: usize) -> Vec<Vec<U>>;
+
+    // Rounds to the nearest integer, halfway cases away from zero, before casting.
+    fn as_type_rounded<U: NumCast + Send + Sync>(&self) -> Vec<U>
+    where
+        T: Float;
+
+    // Whether every element is at least, or strictly above, the one before it. Any NaN makes
+    // both false. Axes passed to `interp` or the spline regridding need the strict form.
+    fn is_monotonic_increasing(&self) -> bool
+    where
+        T: Float;
+    fn is_strictly_increasing(&self) -> bool
+    where
+        T: Float;
+
+    // Walks the vector in order and drops every element within `tolerance` of the last one kept.
+    // Returns the survivors and their indices, so values paired with the axis can be filtered to
+    // match.
+    fn dedup_close(&self, tolerance: T) -> (Vec<T>, Vec<usize>)
+    where
+        T: Float;
+
+    // A strictly increasing copy of the axis, repaired according to `policy`.
+    fn enforce_monotonic(&self, policy: MonotonicPolicy) -> Result<Vec<T>, AxisError>
+    where
+        T: Float;
+
+    fn all<P: Fn(T) -> bool + Send + Sync>(&self, pred: P) -> bool;
+    fn any<P: Fn(T) -> bool + Send + Sync>(&self, pred: P) -> bool;
+
+    fn all_finite(&self) -> bool
+    where
+        T: Float;
+    fn any_nan(&self) -> bool
+    where
+        T: Float;
+
+    // Applies `f` to every window of `window` consecutive elements, in order, giving
+    // `len - window + 1` results, or none when the vector is shorter than the window.
+    fn rolling_output_len(&self, window: usize) -> usize;
+    fn rolling<U, F>(&self, window: usize, f: F) -> Vec<U>
+    where
+        U: Send,
+        F: Fn(&[T]) -> U + Send + Sync;
 }
 
 impl<T> YttriaVectorUtils<T> for [T]
 where
     T: Num + ToPrimitive + Send + Sync + Copy + Clone,
 {
+    fn repeat_output_len(&self, repeats: usize) -> usize {
+        self.len() * repeats
+    }
+
     fn repeat(&self, repeats: usize) -> Vec<T> {
-        let mut out = vec![T::zero(); self.len() * repeats];
+        let mut out = vec![T::zero(); self.repeat_output_len(repeats)];
 
         out.par_iter_mut()
+            .with_min_len(parallel_min_len())
             .enumerate()
             .for_each(|(idx, x)| *x = self[idx / repeats]);
 
         out
     }
 
+    fn tile_output_len(&self, repeats: usize) -> usize {
+        self.len() * repeats
+    }
+
     fn tile(&self, repeats: usize) -> Vec<T> {
-        let mut out = vec![T::zero(); self.len() * repeats];
+        let mut out = vec![T::zero(); self.tile_output_len(repeats)];
 
         out.par_iter_mut()
+            .with_min_len(parallel_min_len())
             .enumerate()
             .for_each(|(idx, x)| *x = self[idx % self.len()]);
 
         out
     }
 
+    fn concatenate_output_len(&self, other: &[T]) -> usize {
+        self.len() + other.len()
+    }
+
     fn concatenate(&self, other: &[T]) -> Vec<T> {
-        let mut out = vec![T::zero(); self.len() + other.len()];
+        let mut out = vec![T::zero(); self.concatenate_output_len(other)];
 
         out[..(self.len())].copy_from_slice(self);
         out[(self.len())..].copy_from_slice(other);
@@ -51,10 +220,117 @@ where
         out
     }
 
-    fn roll_into(&self, other: &mut [T], shift: usize) {
-        other.par_iter_mut().enumerate().for_each(|(idx, out)| {
-            *out = self[(idx + shift) % self.len()];
-        });
+    fn pad_to_pow2_output_len(&self) -> usize {
+        next_pow2(self.len())
+    }
+
+    fn pad_to_pow2(&self, fill: T) -> Vec<T> {
+        let mut out = self.to_vec();
+        out.resize(self.pad_to_pow2_output_len(), fill);
+        out
+    }
+
+    fn zero_pad_center(&self, total_len: usize) -> Vec<T> {
+        assert!(
+            total_len >= self.len(),
+            "Padded length {total_len} is shorter than the vector ({})",
+            self.len()
+        );
+
+        let mut out = vec![T::zero(); total_len];
+        let start = (total_len - self.len()) / 2;
+        out[start..(start + self.len())].copy_from_slice(self);
+        out
+    }
+
+    fn downsample_output_len(&self, factor: usize) -> usize {
+        self.downsample_offset_output_len(factor, 0)
+    }
+
+    fn downsample(&self, factor: usize) -> Vec<T> {
+        self.downsample_offset(factor, 0)
+    }
+
+    fn downsample_offset_output_len(&self, factor: usize, offset: usize) -> usize {
+        assert!(factor > 0, "Downsampling factor must be at least 1");
+        self.strided_output_len(offset, factor)
+    }
+
+    fn downsample_offset(&self, factor: usize, offset: usize) -> Vec<T> {
+        assert!(factor > 0, "Downsampling factor must be at least 1");
+        self.gather_strided(offset, factor)
+    }
+
+    fn strided_output_len(&self, start: usize, stride: usize) -> usize {
+        assert!(stride > 0, "Stride must be at least 1");
+        self.len().saturating_sub(start).div_ceil(stride)
+    }
+
+    fn gather_strided_into(&self, start: usize, stride: usize, out: &mut [T]) {
+        assert_output_len(
+            "gather_strided_into",
+            self.strided_output_len(start, stride),
+            out.len(),
+        );
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .for_each(|(idx, x)| *x = self[start + idx * stride]);
+    }
+
+    fn gather_strided(&self, start: usize, stride: usize) -> Vec<T> {
+        let mut out = vec![T::zero(); self.strided_output_len(start, stride)];
+        self.gather_strided_into(start, stride, &mut out);
+        out
+    }
+
+    fn scatter_strided_inplace(&mut self, values: &[T], start: usize, stride: usize) {
+        let len = self.strided_output_len(start, stride);
+        assert!(
+            values.len() == len,
+            "Stride {stride} from {start} covers {len} elements but got {} values",
+            values.len()
+        );
+        if len == 0 {
+            return;
+        }
+        self[start..]
+            .par_chunks_mut(stride)
+            .with_min_len(parallel_min_len())
+            .zip(values)
+            .for_each(|(chunk, value)| chunk[0] = *value);
+    }
+
+    fn shift_into(&self, out: &mut [T], by: isize, fill: T) {
+        assert_output_len("shift_into", self.len(), out.len());
+
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .for_each(|(idx, out)| {
+                let src = if by >= 0 {
+                    idx.checked_sub(by.unsigned_abs())
+                } else {
+                    idx.checked_add(by.unsigned_abs())
+                };
+                *out = src.and_then(|src| self.get(src)).copied().unwrap_or(fill);
+            });
+    }
+
+    fn shift(&self, by: isize, fill: T) -> Vec<T> {
+        let mut out = vec![fill; self.len()];
+        self.shift_into(out.as_mut_slice(), by, fill);
+        out
+    }
+
+    fn roll_into(&self, out: &mut [T], shift: usize) {
+        assert_output_len("roll_into", self.len(), out.len());
+        out.par_iter_mut()
+            .with_min_len(parallel_min_len())
+            .enumerate()
+            .for_each(|(idx, out)| {
+                *out = self[(idx + shift) % self.len()];
+            });
     }
 
     fn roll(&self, shift: usize) -> Vec<T> {
@@ -64,13 +340,15 @@ where
     }
 
     fn roll_in_place(&mut self, shift: usize) {
-        for idx in 0..(self.len()) {
-            self[idx] = self[(idx + shift) % self.len()];
+        if !self.is_empty() {
+            let len = self.len();
+            self.rotate_left(shift % len);
         }
     }
 
-    fn fftshift_into(&self, other: &mut [T]) {
-        self.roll_into(other, self.len() / 2);
+    fn fftshift_into(&self, out: &mut [T]) {
+        assert_output_len("fftshift_into", self.len(), out.len());
+        self.roll_into(out, self.len() / 2);
     }
 
     fn fftshift(&self) -> Vec<T> {
@@ -81,8 +359,22 @@ where
         self.roll_in_place(self.len() / 2);
     }
 
+    fn ifftshift_into(&self, out: &mut [T]) {
+        assert_output_len("ifftshift_into", self.len(), out.len());
+        self.roll_into(out, self.len().div_ceil(2));
+    }
+
+    fn ifftshift(&self) -> Vec<T> {
+        self.roll(self.len().div_ceil(2))
+    }
+
+    fn ifftshift_in_place(&mut self) {
+        self.roll_in_place(self.len().div_ceil(2));
+    }
+
     fn as_type<U: NumCast + Send + Sync>(&self) -> Vec<U> {
         self.par_iter()
+            .with_min_len(parallel_min_len())
             .map(|&value| {
                 U::from(value).unwrap_or_else(|| {
                     panic!(
@@ -94,11 +386,170 @@ where
             })
             .collect()
     }
+
+    fn as_type_chunked<U: NumCast + Send + Sync>(&self, cols: usize) -> Vec<Vec<U>> {
+        assert!(cols > 0, "Rows must hold at least one column");
+        assert!(
+            self.len().is_multiple_of(cols),
+            "Cannot split {} elements into rows of {cols}",
+            self.len()
+        );
+        self.par_chunks(cols)
+            .with_min_len(parallel_min_len().div_ceil(cols))
+            .map(|row| row.as_type())
+            .collect()
+    }
+
+    fn as_type_rounded<U: NumCast + Send + Sync>(&self) -> Vec<U>
+    where
+        T: Float,
+    {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .map(|&value| {
+                U::from(value.round()).unwrap_or_else(|| {
+                    panic!(
+                        "Could not cast type '{}' to '{}'",
+                        type_name::<T>(),
+                        type_name::<U>()
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn all<P: Fn(T) -> bool + Send + Sync>(&self, pred: P) -> bool {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .all(|&x| pred(x))
+    }
+
+    fn any<P: Fn(T) -> bool + Send + Sync>(&self, pred: P) -> bool {
+        self.par_iter()
+            .with_min_len(parallel_min_len())
+            .any(|&x| pred(x))
+    }
+
+    fn is_monotonic_increasing(&self) -> bool
+    where
+        T: Float,
+    {
+        // A single element has no pair to compare, so its NaN is caught here.
+        if let [x] = self {
+            return !x.is_nan();
+        }
+        self.par_windows(2)
+            .with_min_len(parallel_min_len())
+            .all(|w| w[1] >= w[0])
+    }
+
+    fn is_strictly_increasing(&self) -> bool
+    where
+        T: Float,
+    {
+        if let [x] = self {
+            return !x.is_nan();
+        }
+        self.par_windows(2)
+            .with_min_len(parallel_min_len())
+            .all(|w| w[1] > w[0])
+    }
+
+    fn dedup_close(&self, tolerance: T) -> (Vec<T>, Vec<usize>)
+    where
+        T: Float,
+    {
+        assert!(tolerance >= T::zero(), "Tolerance must not be negative");
+        let (mut values, mut indices) = (Vec::new(), Vec::new());
+        for (i, x) in self.iter().enumerate() {
+            if values
+                .last()
+                .is_none_or(|last: &T| (*x - *last).abs() > tolerance)
+            {
+                values.push(*x);
+                indices.push(i);
+            }
+        }
+        (values, indices)
+    }
+
+    fn enforce_monotonic(&self, policy: MonotonicPolicy) -> Result<Vec<T>, AxisError>
+    where
+        T: Float,
+    {
+        match policy {
+            MonotonicPolicy::DropViolations => {
+                let mut out: Vec<T> = Vec::with_capacity(self.len());
+                for x in self.iter().filter(|x| !x.is_nan()) {
+                    if out.last().is_none_or(|last| x > last) {
+                        out.push(*x);
+                    }
+                }
+                Ok(out)
+            }
+            MonotonicPolicy::NudgeByEpsilon => {
+                let mut out = self.to_vec();
+                for i in 1..out.len() {
+                    if self[i] != self[i - 1] {
+                        continue;
+                    }
+                    // Only the first copy of a run moves down; later ones step up from the
+                    // copy before, as already nudged.
+                    if i == 1 || self[i - 2] != self[i - 1] {
+                        out[i - 1] = self[i - 1] - T::epsilon();
+                        out[i] = self[i] + T::epsilon();
+                    } else {
+                        out[i] = out[i - 1] + T::epsilon();
+                    }
+                }
+                match first_violation(&out) {
+                    Some(err) => Err(err),
+                    None => Ok(out),
+                }
+            }
+            MonotonicPolicy::Error => match first_violation(self) {
+                Some(err) => Err(err),
+                None => Ok(self.to_vec()),
+            },
+        }
+    }
+
+    fn all_finite(&self) -> bool
+    where
+        T: Float,
+    {
+        self.all(|x| x.is_finite())
+    }
+
+    fn any_nan(&self) -> bool
+    where
+        T: Float,
+    {
+        self.any(|x| x.is_nan())
+    }
+
+    fn rolling_output_len(&self, window: usize) -> usize {
+        assert!(window > 0, "Window must not be empty");
+        (self.len() + 1).saturating_sub(window)
+    }
+
+    fn rolling<U, F>(&self, window: usize, f: F) -> Vec<U>
+    where
+        U: Send,
+        F: Fn(&[T]) -> U + Send + Sync,
+    {
+        assert!(window > 0, "Window must not be empty");
+        self.par_windows(window)
+            .with_min_len(parallel_min_len())
+            .map(f)
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::YttriaVectorUtils;
+    use super::{merge_axes, AxisError, MonotonicPolicy, YttriaVectorUtils};
+    use crate::vector::{YttriaVectorArithmetic, YttriaVectorStatistics};
 
     #[test]
     fn test_fftshift() {
@@ -107,6 +558,114 @@ mod test {
         println!("{shifted:?}");
     }
 
+    #[test]
+    fn test_ifftshift_inverts_fftshift() {
+        let even = [0., 1., 2., 3., 4., -5., -4., -3., -2., -1.];
+        assert_eq!(even.fftshift().ifftshift(), even);
+
+        let odd = [0., 1., 2., 3., 4., -4., -3., -2., -1.];
+        assert_eq!(odd.fftshift().ifftshift(), odd);
+
+        let mut in_place = odd;
+        in_place.fftshift_in_place();
+        in_place.ifftshift_in_place();
+        assert_eq!(in_place, odd);
+    }
+
+    #[test]
+    fn test_pad_to_pow2() {
+        let five = [1, 2, 3, 4, 5];
+        assert_eq!(five.pad_to_pow2(0), [1, 2, 3, 4, 5, 0, 0, 0]);
+
+        let eight = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(eight.pad_to_pow2(0), eight);
+
+        let nine = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let padded = nine.pad_to_pow2(-1);
+        assert_eq!(padded.len(), 16);
+        assert_eq!(padded[..9], nine);
+        assert!(padded[9..].iter().all(|&x| x == -1));
+    }
+
+    #[test]
+    fn test_zero_pad_center() {
+        let test = [1, 2, 3];
+        assert_eq!(test.zero_pad_center(7), [0, 0, 1, 2, 3, 0, 0]);
+        assert_eq!(test.zero_pad_center(6), [0, 1, 2, 3, 0, 0]);
+        assert_eq!(test.zero_pad_center(3), test);
+
+        let even = [1, 2];
+        assert_eq!(even.zero_pad_center(6), [0, 0, 1, 2, 0, 0]);
+        assert_eq!(even.zero_pad_center(5), [0, 1, 2, 0, 0]);
+    }
+
+    #[test]
+    fn test_downsample() {
+        let test = (0..10).collect::<Vec<i32>>();
+        for factor in 1..12 {
+            assert_eq!(test.downsample(factor).len(), test.len().div_ceil(factor));
+        }
+        assert_eq!(test.downsample(3), [0, 3, 6, 9]);
+        assert_eq!(test.downsample_offset(3, 1), [1, 4, 7]);
+        assert_eq!(test.downsample_offset(4, 2), [2, 6]);
+        assert_eq!(test.downsample_offset(2, 9), [9]);
+        assert!(test.downsample_offset(2, 10).is_empty());
+    }
+
+    #[test]
+    fn test_strided_round_trip() {
+        let test = (0..23).collect::<Vec<i32>>();
+        for stride in 1..9 {
+            for start in 0..26 {
+                let len = test.strided_output_len(start, stride);
+                assert_eq!(len, (test.len().max(start) - start).div_ceil(stride));
+                let gathered = test.gather_strided(start, stride);
+                assert_eq!(gathered.len(), len);
+                // The last element gathered is the last one in range, and the next would not be.
+                if let Some(last) = gathered.last() {
+                    let last = *last as usize;
+                    assert!(last < test.len() && last + stride >= test.len());
+                }
+                assert!(gathered
+                    .iter()
+                    .enumerate()
+                    .all(|(i, x)| *x as usize == start + i * stride));
+
+                let mut scattered = vec![-1; test.len()];
+                scattered.scatter_strided_inplace(&gathered, start, stride);
+                for (i, x) in scattered.iter().enumerate() {
+                    let hit = i >= start && (i - start) % stride == 0;
+                    assert_eq!(*x, if hit { i as i32 } else { -1 });
+                }
+            }
+        }
+        assert_eq!(test.gather_strided(22, 5), [22]);
+        assert!(Vec::<f64>::new().gather_strided(0, 3).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_stride() {
+        [1, 2, 3].gather_strided(0, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_scatter_wrong_count() {
+        [1, 2, 3, 4].scatter_strided_inplace(&[0, 0, 0], 1, 2);
+    }
+
+    #[test]
+    fn test_shift_fills_vacated_end() {
+        let test = [1, 2, 3, 4, 5];
+        assert_eq!(test.shift(2, 0), [0, 0, 1, 2, 3]);
+        assert_eq!(test.shift(-2, -1), [3, 4, 5, -1, -1]);
+        assert_eq!(test.shift(0, 0), test);
+        assert_eq!(test.shift(5, 9), [9; 5]);
+        assert_eq!(test.shift(-7, 9), [9; 5]);
+        assert_eq!(test.shift(isize::MIN, 9), [9; 5]);
+    }
+
     #[test]
     fn test_u8_as_f32() {
         let test = [0u8, 5, 16, 32];
@@ -114,4 +673,176 @@ mod test {
 
         println!("{cast:?}");
     }
+
+    #[test]
+    fn test_u8_as_f32_chunked() {
+        let test = (0u8..12).collect::<Vec<_>>();
+        let rows = test.as_type_chunked::<f32>(4);
+        assert_eq!(
+            rows,
+            [
+                [0.0, 1.0, 2.0, 3.0],
+                [4.0, 5.0, 6.0, 7.0],
+                [8.0, 9.0, 10.0, 11.0]
+            ]
+        );
+        assert!([0u8; 0].as_type_chunked::<f32>(3).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_as_type_chunked_ragged() {
+        [0u8; 10].as_type_chunked::<f32>(4);
+    }
+
+    #[test]
+    fn test_f64_as_i32_rounded() {
+        let test = [0.4, 0.5, 0.6, -0.5, -1.4, 2.5];
+        assert_eq!(test.as_type::<i32>(), [0, 0, 0, 0, -1, 2]);
+        assert_eq!(test.as_type_rounded::<i32>(), [0, 1, 1, -1, -1, 3]);
+    }
+
+    #[test]
+    fn test_all_any() {
+        let clean = [0.0, -1.5, 2.0, 1e300];
+        assert!(clean.all_finite());
+        assert!(!clean.any_nan());
+        assert!(clean.all(|x| x > -2.0));
+        assert!(clean.any(|x| x < 0.0));
+        assert!(!clean.any(|x| x > 1e301));
+
+        let nan = [0.0f32, f32::NAN, 1.0];
+        assert!(!nan.all_finite());
+        assert!(nan.any_nan());
+
+        let inf = [0.0, f64::INFINITY];
+        assert!(!inf.all_finite());
+        assert!(!inf.any_nan());
+
+        let empty: [f64; 0] = [];
+        assert!(empty.all_finite());
+        assert!(!empty.any_nan());
+    }
+
+    #[test]
+    fn test_monotonic_checks() {
+        assert!([1.0, 2.0, 2.0, 3.0].is_monotonic_increasing());
+        assert!(![1.0, 2.0, 2.0, 3.0].is_strictly_increasing());
+        assert!([1.0, 2.0, 3.0].is_strictly_increasing());
+        assert!(![1.0, 3.0, 2.0].is_monotonic_increasing());
+        assert!(![1.0, f64::NAN, 2.0].is_monotonic_increasing());
+        assert!([0.0f64; 0].is_strictly_increasing());
+        assert!([1.0].is_strictly_increasing());
+        assert!(![f64::NAN].is_monotonic_increasing());
+        assert!(![f64::NAN].is_strictly_increasing());
+    }
+
+    #[test]
+    fn test_enforce_monotonic_policies() {
+        let duplicates = [0.0, 0.5, 0.5, 1.0];
+        let tiny = [0.0, 0.5, 0.5 - 1e-12, 1.0];
+        let large = [0.0, 0.5, 0.2, 0.6, 1.0];
+
+        use MonotonicPolicy::*;
+        assert_eq!(
+            duplicates.enforce_monotonic(DropViolations),
+            Ok(vec![0.0, 0.5, 1.0])
+        );
+        assert_eq!(
+            tiny.enforce_monotonic(DropViolations),
+            Ok(vec![0.0, 0.5, 1.0])
+        );
+        assert_eq!(
+            large.enforce_monotonic(DropViolations),
+            Ok(vec![0.0, 0.5, 0.6, 1.0])
+        );
+        assert_eq!(
+            [f64::NAN, 1.0, f64::NAN, 2.0].enforce_monotonic(DropViolations),
+            Ok(vec![1.0, 2.0])
+        );
+
+        assert_eq!(
+            duplicates.enforce_monotonic(NudgeByEpsilon),
+            Ok(vec![0.0, 0.5 - f64::EPSILON, 0.5 + f64::EPSILON, 1.0])
+        );
+        let run = [0.0, 0.5, 0.5, 0.5, 1.0]
+            .enforce_monotonic(NudgeByEpsilon)
+            .unwrap();
+        assert_eq!(
+            run,
+            [
+                0.0,
+                0.5 - f64::EPSILON,
+                0.5 + f64::EPSILON,
+                0.5 + 2.0 * f64::EPSILON,
+                1.0
+            ]
+        );
+        assert!(run.is_strictly_increasing());
+        assert_eq!(
+            tiny.enforce_monotonic(NudgeByEpsilon),
+            Err(AxisError::Decreasing { index: 2 })
+        );
+        assert_eq!(
+            large.enforce_monotonic(NudgeByEpsilon),
+            Err(AxisError::Decreasing { index: 2 })
+        );
+
+        assert_eq!(
+            duplicates.enforce_monotonic(Error),
+            Err(AxisError::Duplicate { index: 2 })
+        );
+        assert_eq!(
+            tiny.enforce_monotonic(Error),
+            Err(AxisError::Decreasing { index: 2 })
+        );
+        assert_eq!(
+            [0.0, 1.0, f64::NAN].enforce_monotonic(Error),
+            Err(AxisError::Nan { index: 2 })
+        );
+        assert_eq!([0.0, 1.0].enforce_monotonic(Error), Ok(vec![0.0, 1.0]));
+    }
+
+    #[test]
+    fn test_dedup_close_filters_paired_values() {
+        let freqs = [1.0, 1.0 + 1e-9, 2.0, 3.0, 3.0, 3.0 + 1e-10, 4.0];
+        let gains = [10.0, 11.0, 20.0, 30.0, 31.0, 32.0, 40.0];
+        let (axis, indices) = freqs.dedup_close(1e-6);
+        assert_eq!(axis, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(indices, [0, 2, 3, 6]);
+        let kept = indices.iter().map(|i| gains[*i]).collect::<Vec<_>>();
+        assert_eq!(kept, [10.0, 20.0, 30.0, 40.0]);
+        assert!(axis.is_strictly_increasing());
+    }
+
+    #[test]
+    fn test_merge_axes() {
+        let a = [0.0, 1.0, 2.0, 3.0];
+        let b = [2.5, 3.0 + 1e-9, 1.0 - 1e-10, 4.0];
+        assert_eq!(
+            merge_axes(&a, &b, 1e-6),
+            [0.0, 1.0 - 1e-10, 2.0, 2.5, 3.0, 4.0]
+        );
+        assert_eq!(merge_axes(&a, &[], 0.0), a);
+    }
+
+    #[test]
+    fn test_rolling_mean() {
+        let x = (0..1000)
+            .map(|i| (i as f64 * 0.1).sin() + 0.01 * i as f64)
+            .collect::<Vec<_>>();
+        let window = 25;
+        let rolling = x.rolling(window, |w| w.mean());
+        assert_eq!(rolling.len(), x.len() - window + 1);
+
+        // Moving average from differences of the running sum.
+        let cumsum = [0.0].concatenate(&x).cumsum();
+        for (i, mean) in rolling.iter().enumerate() {
+            let expected = (cumsum[i + window] - cumsum[i]) / window as f64;
+            assert!((mean - expected).abs() < 1e-9);
+        }
+
+        assert!(x[..10].rolling(11, |w| w.len()).is_empty());
+        assert_eq!(x[..10].rolling(10, |w| w.len()), [10]);
+    }
 }
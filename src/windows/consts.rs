@@ -0,0 +1,124 @@
+// Compile-time window and twiddle tables. Cosines come from a degree 18 Taylor polynomial on an
+// octant reduced in integer arithmetic, so every entry is within 1e-15 of the runtime f64
+// computation and the tables can initialize `const` and `static` items without allocating.
+
+const TAU: f64 = 2.0 * std::f64::consts::PI;
+
+// Taylor series of cos and sin on |x| <= pi / 4, where the truncation error is below 1e-19.
+const fn cos_sin_poly(x: f64) -> (f64, f64) {
+    let x2 = x * x;
+    let mut cos = 1.0;
+    let mut sin = x;
+    let mut cos_term = 1.0;
+    let mut sin_term = x;
+    let mut k = 1;
+    while k <= 9 {
+        cos_term = -cos_term * x2 / ((2 * k - 1) * (2 * k)) as f64;
+        sin_term = -sin_term * x2 / ((2 * k) * (2 * k + 1)) as f64;
+        cos += cos_term;
+        sin += sin_term;
+        k += 1;
+    }
+    (cos, sin)
+}
+
+// (cos, sin) of `2 pi num / den`.
+const fn cos_sin_turns(num: usize, den: usize) -> (f64, f64) {
+    let r = (num % den) as i128;
+    let den = den as i128;
+
+    // Nearest quarter turn, leaving a residual within an eighth of a turn.
+    let quarter = (8 * r + den) / (2 * den);
+    let residual = TAU * (4 * r - quarter * den) as f64 / (4 * den) as f64;
+    let (c, s) = cos_sin_poly(residual);
+
+    match quarter % 4 {
+        0 => (c, s),
+        1 => (-s, c),
+        2 => (-c, -s),
+        _ => (s, -c),
+    }
+}
+
+const fn const_cos_sum<const N: usize>(alpha: f64) -> [f64; N] {
+    let mut window = [1.0; N];
+    if N < 2 {
+        return window;
+    }
+
+    let mut i = 0;
+    while i < N {
+        window[i] = alpha - (1.0 - alpha) * cos_sin_turns(i, N - 1).0;
+        i += 1;
+    }
+    window
+}
+
+pub const fn const_hann<const N: usize>() -> [f64; N] {
+    const_cos_sum::<N>(0.5)
+}
+
+pub const fn const_hamming<const N: usize>() -> [f64; N] {
+    const_cos_sum::<N>(25.0 / 46.0)
+}
+
+pub const fn const_rectangular<const N: usize>() -> [f64; N] {
+    [1.0; N]
+}
+
+// (cos, sin) of `2 pi k / N` for every k, i.e. the N roots of unity counter-clockwise from 1.
+pub const fn const_twiddles<const N: usize>() -> [(f64, f64); N] {
+    let mut twiddles = [(1.0, 0.0); N];
+    let mut k = 0;
+    while k < N {
+        twiddles[k] = cos_sin_turns(k, N);
+        k += 1;
+    }
+    twiddles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::windows::{hamming, hann};
+
+    static HANN_64: [f64; 64] = const_hann::<64>();
+    const TWIDDLES_16: [(f64, f64); 16] = const_twiddles::<16>();
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1e-15, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_const_windows_match_runtime() {
+        assert_close(&HANN_64, &hann::<f64>(64));
+
+        assert_close(&const_hann::<16>(), &hann::<f64>(16));
+        assert_close(&const_hann::<256>(), &hann::<f64>(256));
+        assert_close(&const_hamming::<16>(), &hamming::<f64>(16));
+        assert_close(&const_hamming::<64>(), &hamming::<f64>(64));
+        assert_close(&const_hamming::<256>(), &hamming::<f64>(256));
+        assert_eq!(const_rectangular::<16>(), [1.0; 16]);
+    }
+
+    #[test]
+    fn test_const_twiddles() {
+        assert_eq!(TWIDDLES_16[0], (1.0, 0.0));
+        assert_eq!(TWIDDLES_16[4], (0.0, 1.0));
+
+        fn check<const N: usize>() {
+            for (k, (c, s)) in const_twiddles::<N>().iter().enumerate() {
+                let angle = TAU * k as f64 / N as f64;
+                assert!((c - angle.cos()).abs() < 1e-15);
+                assert!((s - angle.sin()).abs() < 1e-15);
+                assert!((c * c + s * s - 1.0).abs() < 1e-15);
+            }
+        }
+        check::<16>();
+        check::<64>();
+        check::<256>();
+    }
+}
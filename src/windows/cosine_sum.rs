@@ -26,6 +26,20 @@ pub fn hamming<T: Float + FromPrimitive>(n: usize) -> Vec<T> {
     )
 }
 
+pub fn blackman<T: Float + FromPrimitive>(n: usize) -> Vec<T> {
+    if n == 1 {
+        return vec![T::one()];
+    }
+
+    (0..n)
+        .map(|i| {
+            let x = 2.0 * std::f64::consts::PI * i as f64 / (n - 1) as f64;
+            T::from_f64(0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos())
+                .expect("Could not convert f64 into type")
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -35,4 +49,13 @@ mod tests {
         let test = hamming::<f64>(20);
         println!("{test:?}");
     }
+
+    #[test]
+    fn test_blackman() {
+        let test = blackman::<f64>(9);
+        assert!(test[0].abs() < 1e-12 && test[8].abs() < 1e-12);
+        assert!((test[4] - 1.0).abs() < 1e-12);
+        assert!((test[2] - 0.34).abs() < 1e-12);
+        assert_eq!(blackman::<f64>(1), [1.0]);
+    }
 }
@@ -0,0 +1,47 @@
+use num::{Float, FromPrimitive};
+
+// Zeroth order modified Bessel function of the first kind, by its power series.
+fn bessel_i0(x: f64) -> f64 {
+    let half = x / 2.0;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut k = 1.0;
+    while term > 1e-21 * sum {
+        term *= (half / k) * (half / k);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+pub fn kaiser<T: Float + FromPrimitive>(n: usize, beta: T) -> Vec<T> {
+    if n == 1 {
+        return vec![T::one()];
+    }
+
+    let beta = beta.to_f64().expect("Could not convert type into f64");
+    let denom = bessel_i0(beta);
+    (0..n)
+        .map(|i| {
+            let r = 2.0 * i as f64 / (n - 1) as f64 - 1.0;
+            T::from_f64(bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / denom)
+                .expect("Could not convert f64 into type")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kaiser() {
+        let expected = [
+            0.03671089, 0.32820196, 0.7753221, 1.0, 0.7753221, 0.32820196, 0.03671089,
+        ];
+        let test = kaiser::<f64>(7, 5.0);
+        for (a, b) in test.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-7, "{a} vs {b}");
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use num::{Float, FromPrimitive};
+
+use super::{blackman, hamming, hann, hann_poisson, kaiser, poisson};
+
+// Named window shapes, for APIs that generate a window of whatever length they need.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowKind {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+    Kaiser(f64),
+    // Decay constant in samples.
+    Poisson(f64),
+    HannPoisson(f64),
+}
+
+impl WindowKind {
+    pub fn generate<T: Float + FromPrimitive>(&self, n: usize) -> Vec<T> {
+        match self {
+            Self::Rectangular => vec![T::one(); n],
+            Self::Hann => hann(n),
+            Self::Hamming => hamming(n),
+            Self::Blackman => blackman(n),
+            Self::Kaiser(beta) => kaiser(
+                n,
+                T::from_f64(*beta).expect("Could not convert f64 into type"),
+            ),
+            Self::Poisson(tau) => poisson(
+                n,
+                T::from_f64(*tau).expect("Could not convert f64 into type"),
+            ),
+            Self::HannPoisson(tau) => hann_poisson(
+                n,
+                T::from_f64(*tau).expect("Could not convert f64 into type"),
+            ),
+        }
+    }
+}
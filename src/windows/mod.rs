@@ -1,2 +1,14 @@
+mod consts;
+pub use consts::{const_hamming, const_hann, const_rectangular, const_twiddles};
+
 mod cosine_sum;
-pub use cosine_sum::{cos_sum, hamming, hann};
+pub use cosine_sum::{blackman, cos_sum, hamming, hann};
+
+mod kaiser;
+pub use kaiser::kaiser;
+
+mod poisson;
+pub use poisson::{hann_poisson, poisson};
+
+mod kind;
+pub use kind::WindowKind;
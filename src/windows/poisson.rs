@@ -0,0 +1,77 @@
+use num::{Float, FromPrimitive};
+
+use super::hann;
+
+// Exponential decay away from the centre, falling by a factor of e every `tau` samples.
+pub fn poisson<T: Float + FromPrimitive>(n: usize, tau: T) -> Vec<T> {
+    assert!(tau > T::zero(), "Decay constant must be positive");
+
+    let tau = tau.to_f64().expect("Could not convert type into f64");
+    let centre = (n as f64 - 1.0) / 2.0;
+    (0..n)
+        .map(|i| {
+            T::from_f64((-(i as f64 - centre).abs() / tau).exp())
+                .expect("Could not convert f64 into type")
+        })
+        .collect()
+}
+
+// Product of the Hann and Poisson windows, which keeps the Poisson decay but reaches zero at
+// both ends.
+pub fn hann_poisson<T: Float + FromPrimitive>(n: usize, tau: T) -> Vec<T> {
+    if n == 1 {
+        return vec![T::one()];
+    }
+
+    hann::<T>(n)
+        .iter()
+        .zip(poisson(n, tau))
+        .map(|(h, p)| *h * p)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poisson() {
+        for n in [9, 10, 101] {
+            let test = poisson::<f64>(n, 4.0);
+            let peak = test.iter().cloned().fold(0.0, f64::max);
+            // Odd lengths peak on the centre sample, even ones on the two either side of it.
+            assert_eq!(test[n / 2], peak);
+            assert_eq!(test[(n - 1) / 2], peak);
+            for i in 0..n {
+                assert_eq!(test[i], test[n - 1 - i]);
+            }
+        }
+
+        let test = poisson::<f64>(101, 4.0);
+        assert_eq!(test[50], 1.0);
+        for i in 50..100 {
+            assert!((test[i] / test[i + 1] - 0.25f64.exp()).abs() < 1e-12);
+        }
+        assert!((test[54] - (-1.0f64).exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_hann_poisson() {
+        let n = 65;
+        let test = hann_poisson::<f64>(n, 8.0);
+        let peak = test.iter().enumerate().fold(
+            (0, 0.0),
+            |acc, (i, w)| if *w > acc.1 { (i, *w) } else { acc },
+        );
+        assert_eq!(peak, (32, 1.0));
+        assert!(test[0].abs() < 1e-12 && test[n - 1].abs() < 1e-12);
+
+        // Dividing out the Hann window leaves the exponential decay.
+        let hann = hann::<f64>(n);
+        for i in 33..(n - 2) {
+            let decay = (test[i] / hann[i]) / (test[i + 1] / hann[i + 1]);
+            assert!((decay - 0.125f64.exp()).abs() < 1e-9);
+        }
+        assert_eq!(hann_poisson::<f64>(1, 8.0), [1.0]);
+    }
+}
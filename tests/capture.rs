@@ -0,0 +1,71 @@
+use num::Complex;
+use std::f64::consts::PI;
+use yttria_math::signal::{process_capture, Averaging, CaptureConfig};
+use yttria_math::windows::WindowKind;
+use yttria_math::{Xoshiro256PlusPlus, YttriaRng};
+
+fn multitone(len: usize) -> Vec<Complex<f32>> {
+    let tones = [(0.05, 1.0), (-0.21, 0.3), (0.33, 0.05)];
+    (0..len)
+        .map(|i| {
+            let x = tones
+                .iter()
+                .map(|(f, a)| Complex::from_polar(*a, 2.0 * PI * f * i as f64))
+                .sum::<Complex<f64>>();
+            Complex::new(x.re as f32, x.im as f32)
+        })
+        .collect()
+}
+
+// Splits `capture` into chunks of pseudo-random length between 1 and `max_len`.
+fn random_chunks(capture: &[Complex<f32>], max_len: usize, seed: u64) -> Vec<Vec<Complex<f32>>> {
+    let mut rng = Xoshiro256PlusPlus::seed_from(seed);
+    let mut chunks = Vec::new();
+    let mut pos = 0;
+    while pos < capture.len() {
+        let end = (pos + 1 + (rng.next_u64() % max_len as u64) as usize).min(capture.len());
+        chunks.push(capture[pos..end].to_vec());
+        pos = end;
+    }
+    chunks
+}
+
+#[test]
+fn chunked_matches_single_shot() {
+    let capture = multitone(60_001);
+    let configs = [
+        CaptureConfig::new(1024),
+        CaptureConfig::new(512)
+            .with_hop(100)
+            .with_window(WindowKind::Blackman)
+            .with_averaging(Averaging::Exponential(0.1))
+            .with_batch_frames(7),
+        CaptureConfig::new(256)
+            .with_averaging(Averaging::PeakHold)
+            .with_decimation(3)
+            .with_peaks(30.0, 4),
+    ];
+
+    for config in configs {
+        let reference = process_capture([capture.clone()], config.clone());
+        assert!(reference.frames > 100);
+        assert_eq!(reference.samples, capture.len());
+        assert!(!reference.peaks.is_empty());
+
+        for (max_len, seed) in [(1, 1), (97, 2), (5000, 3), (100_000, 4)] {
+            let report = process_capture(random_chunks(&capture, max_len, seed), config.clone());
+            assert_eq!(report.spectrum, reference.spectrum);
+            assert_eq!(report.peaks, reference.peaks);
+            assert_eq!(report.frames, reference.frames);
+            assert_eq!(report.samples, reference.samples);
+            assert_eq!(report.stats, reference.stats);
+        }
+    }
+
+    // The strongest peaks sit on the tones, strongest first.
+    let report = process_capture([capture], CaptureConfig::new(1024).with_peaks(20.0, 2));
+    let freqs = report.peaks.iter().map(|p| p.frequency).collect::<Vec<_>>();
+    assert_eq!(freqs.len(), 2);
+    assert!((freqs[0] - 0.05).abs() < 1e-3, "{freqs:?}");
+    assert!((freqs[1] + 0.21).abs() < 1e-3, "{freqs:?}");
+}
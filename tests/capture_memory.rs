@@ -0,0 +1,60 @@
+use num::Complex;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use yttria_math::signal::{process_capture, CaptureConfig};
+
+// Tracks the bytes currently allocated and the most ever allocated at once.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(now, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// Peak bytes allocated while processing a capture of `chunks` chunks, generated on the fly.
+fn peak_usage(chunks: usize) -> usize {
+    let source = (0..chunks).map(|c| {
+        (0..10_000)
+            .map(|i| {
+                let phase = 0.3 * (c * 10_000 + i) as f32;
+                Complex::new(phase.cos(), phase.sin())
+            })
+            .collect::<Vec<_>>()
+    });
+    let config = CaptureConfig::new(1024).with_decimation(2);
+
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let report = process_capture(source, config);
+    assert_eq!(report.samples, chunks * 10_000);
+    PEAK.load(Ordering::SeqCst) - baseline
+}
+
+#[test]
+fn memory_does_not_grow_with_capture_length() {
+    // Let rayon start its pool before measuring anything.
+    peak_usage(2);
+
+    let short = peak_usage(10);
+    let long = peak_usage(100);
+    // 100 chunks are 8 MB of samples.
+    assert!(long < 4 * 1024 * 1024, "{long}");
+    assert!(long < 2 * short, "{short} -> {long}");
+}
@@ -0,0 +1,110 @@
+use num::Complex;
+use yttria_math::modulation::{BurstBuilder, Constellation};
+use yttria_math::prelude::*;
+use yttria_math::random;
+use yttria_math::signal::{Nco, PhaseWidth};
+use yttria_math::Xoshiro256PlusPlus;
+
+fn rng(seed: u64) -> Xoshiro256PlusPlus {
+    Xoshiro256PlusPlus::seed_from(seed)
+}
+
+fn tone(len: usize) -> Vec<Complex<f64>> {
+    (0..len)
+        .map(|n| Complex::from_polar(1.0, 0.01 * n as f64))
+        .collect()
+}
+
+// The mapping from seed to sequence is part of the public contract, so a change here needs a
+// major version bump.
+#[test]
+fn seed_sequence_is_stable() {
+    let mut r = rng(0);
+    let first = (0..4).map(|_| r.next_u64()).collect::<Vec<_>>();
+    assert_eq!(
+        first,
+        [
+            5987356902031041503,
+            7051070477665621255,
+            6633766593972829180,
+            211316841551650330
+        ]
+    );
+}
+
+// Runs every randomized API twice from the same seed and compares the outputs bit for bit.
+#[test]
+fn randomized_apis_are_deterministic() {
+    fn twice<T: PartialEq + std::fmt::Debug>(run: impl Fn(&mut Xoshiro256PlusPlus) -> T) {
+        let first = run(&mut rng(17));
+        assert_eq!(first, run(&mut rng(17)));
+        assert_ne!(first, run(&mut rng(18)));
+    }
+
+    twice(|r| (0..64).map(|_| r.next_u64()).collect::<Vec<_>>());
+    twice(|r| (0..64).map(|_| r.next_f64().to_bits()).collect::<Vec<_>>());
+    twice(|r| {
+        (0..64)
+            .map(|_| r.next_gaussian().to_bits())
+            .collect::<Vec<_>>()
+    });
+    twice(|r| {
+        let mut out = [0f32; 65];
+        r.fill_f32_slice_uniform(&mut out);
+        out.map(f32::to_bits)
+    });
+    twice(|r| {
+        let mut out = [0f32; 65];
+        r.fill_f32_slice_gaussian(&mut out);
+        out.map(f32::to_bits)
+    });
+    twice(|r| {
+        tone(1024)
+            .add_awgn(10.0, r)
+            .iter()
+            .map(|x| (x.re.to_bits(), x.im.to_bits()))
+            .collect::<Vec<_>>()
+    });
+    twice(|r| {
+        let mut x = tone(4096);
+        x.add_phase_noise_inplace(&[(1e3, -70.0), (1e5, -100.0)], 1e6, r);
+        x.iter()
+            .map(|x| (x.re.to_bits(), x.im.to_bits()))
+            .collect::<Vec<_>>()
+    });
+    twice(|r| {
+        let bits = (0..256)
+            .map(|_| (r.next_u64() & 1) as u8)
+            .collect::<Vec<_>>();
+        BurstBuilder::<f64>::new(Constellation::qpsk())
+            .bits(&bits)
+            .shaping_rrc(0.35, 4)
+            .awgn_snr_db(10.0)
+            .build(r)
+            .0
+            .iter()
+            .map(|x| (x.re.to_bits(), x.im.to_bits()))
+            .collect::<Vec<_>>()
+    });
+    twice(|r| {
+        let mut nco = Nco::<f64>::new(1024, PhaseWidth::Bits32)
+            .with_interpolation(false)
+            .with_dither(r.clone());
+        nco.set_frequency(0.0123);
+        let mut out = vec![Complex::default(); 256];
+        nco.fill_complex(&mut out);
+        out.iter()
+            .map(|x| (x.re.to_bits(), x.im.to_bits()))
+            .collect::<Vec<_>>()
+    });
+    twice(|r| {
+        let x = random::uniform(256, -1.0f64, 1.0, r);
+        let y = random::normal(256, 0.0f64, 2.0, r);
+        let z = random::complex_normal::<f64>(256, r);
+        x.iter()
+            .chain(&y)
+            .chain(z.iter().flat_map(|z| [&z.re, &z.im]))
+            .map(|x| x.to_bits())
+            .collect::<Vec<_>>()
+    });
+}